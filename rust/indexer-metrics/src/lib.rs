@@ -3,3 +3,4 @@
 
 pub mod metrics;
 pub mod util;
+pub mod verification;
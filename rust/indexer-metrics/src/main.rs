@@ -11,6 +11,7 @@ use indexer_metrics::{
         PFN_LEDGER_VERSION, TASK_FAILURE_COUNT,
     },
     util::{deserialize_from_string, fetch_processor_status_with_timeout, get_url_with_timeout},
+    verification::{start_verification, VerificationConfig},
 };
 use serde::{Deserialize, Serialize};
 use server_framework::{RunnableConfig, ServerArgs};
@@ -54,6 +55,8 @@ pub struct PostProcessorConfig {
     pub hasura_graphql_endpoint: Option<String>,
     pub fullnode_rest_api_endpoint: Option<String>,
     pub chain_name: String,
+    // Compares sampled balances with the fullnode, needs both endpoints
+    pub verification: Option<VerificationConfig>,
 }
 
 #[async_trait::async_trait]
@@ -64,6 +67,21 @@ impl RunnableConfig for PostProcessorConfig {
         let fullnode_rest_api_endpoint = self.fullnode_rest_api_endpoint.clone();
         let chain_name = self.chain_name.clone();
 
+        if let Some(verification) = self.verification.clone() {
+            let (Some(hasura), Some(fullnode)) =
+                (&hasura_graphql_endpoint, &fullnode_rest_api_endpoint)
+            else {
+                anyhow::bail!(
+                    "verification needs both hasura_graphql_endpoint and fullnode_rest_api_endpoint"
+                );
+            };
+            tasks.push(tokio::spawn(start_verification(
+                hasura.clone(),
+                fullnode.clone(),
+                chain_name.clone(),
+                verification,
+            )));
+        }
         if let Some(endpoint) = hasura_graphql_endpoint {
             tasks.push(tokio::spawn(start_processor_status_fetch(
                 endpoint,
@@ -70,3 +70,13 @@ pub static PFN_LEDGER_TIMESTAMP: Lazy<GaugeVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+/// Balances compared with the fullnode by the verification task, by outcome.
+pub static VERIFICATION_CHECK_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "indexer_metrics_verification_check_count",
+        "Indexed balances compared with the fullnode, by table and outcome (match, mismatch, skipped, error)",
+        &["table", "outcome", "chain_name"],
+    )
+    .unwrap()
+});
@@ -19,6 +19,29 @@ where
     s.parse::<T>().map_err(D::Error::custom)
 }
 
+/// Deserialize to type T from either a string or a number. Hasura returns numeric and bigint
+/// columns as either, depending on its settings
+pub fn deserialize_from_string_or_number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    <T as FromStr>::Err: std::fmt::Display,
+{
+    use serde::de::Error;
+
+    let s = match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => s,
+        serde_json::Value::Number(n) => n.to_string(),
+        other => {
+            return Err(D::Error::custom(format!(
+                "Expected a number, got {}",
+                other
+            )))
+        },
+    };
+    s.parse::<T>().map_err(D::Error::custom)
+}
+
 pub async fn fetch_processor_status_with_timeout(
     url: &str,
     timeout_ms: u64,
@@ -0,0 +1,487 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Cross-checks balances the indexer wrote against the chain. Every round samples the most
+//! recently changed rows of `current_fungible_asset_balances` and `current_delegator_balances`
+//! through Hasura and reads the same state from the fullnode REST API at the version the row was
+//! indexed at. Recent rows are sampled because fullnodes prune old versions.
+
+use crate::{
+    metrics::{TASK_FAILURE_COUNT, VERIFICATION_CHECK_COUNT},
+    util::deserialize_from_string_or_number,
+};
+use anyhow::{bail, Context, Result};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+const VERIFICATION_QUERY_TIMEOUT_MS: u64 = 5000;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerificationConfig {
+    // Number of rows of each table checked per round
+    #[serde(default = "VerificationConfig::default_sample_size")]
+    pub sample_size: u64,
+    #[serde(default = "VerificationConfig::default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+impl VerificationConfig {
+    pub const fn default_sample_size() -> u64 {
+        20
+    }
+
+    pub const fn default_interval_secs() -> u64 {
+        60
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FungibleAssetBalance {
+    storage_id: String,
+    owner_address: String,
+    asset_type: String,
+    token_standard: String,
+    #[serde(deserialize_with = "deserialize_from_string_or_number")]
+    amount: u128,
+    #[serde(deserialize_with = "deserialize_from_string_or_number")]
+    last_transaction_version: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct DelegatorBalance {
+    delegator_address: String,
+    pool_address: String,
+    table_handle: String,
+    #[serde(deserialize_with = "deserialize_from_string_or_number")]
+    shares: u128,
+    #[serde(deserialize_with = "deserialize_from_string_or_number")]
+    last_transaction_version: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SampleResponseInner {
+    #[serde(default)]
+    current_fungible_asset_balances: Vec<FungibleAssetBalance>,
+    #[serde(default)]
+    current_delegator_balances: Vec<DelegatorBalance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SampleResponse {
+    data: SampleResponseInner,
+}
+
+/// State read from the fullnode.
+#[derive(Debug, PartialEq)]
+enum ChainValue {
+    Found(u128),
+    /// The resource or table item doesn't exist, which means a balance of zero
+    Missing,
+    /// The fullnode no longer has the version
+    Pruned,
+}
+
+pub async fn start_verification(
+    hasura_url: String,
+    fullnode_url: String,
+    chain_name: String,
+    config: VerificationConfig,
+) {
+    let client = Client::builder()
+        .timeout(Duration::from_millis(VERIFICATION_QUERY_TIMEOUT_MS))
+        .build()
+        .expect("Failed to build HTTP client");
+    let fullnode_url = fullnode_url.trim_end_matches('/').to_string();
+    loop {
+        if let Err(err) = verify_sample(
+            &client,
+            &hasura_url,
+            &fullnode_url,
+            &chain_name,
+            config.sample_size,
+        )
+        .await
+        {
+            tracing::error!(error = ?err, "Verification round failed");
+            TASK_FAILURE_COUNT
+                .with_label_values(&["verification", &chain_name])
+                .inc();
+        }
+        tokio::time::sleep(Duration::from_secs(config.interval_secs)).await;
+    }
+}
+
+async fn verify_sample(
+    client: &Client,
+    hasura_url: &str,
+    fullnode_url: &str,
+    chain_name: &str,
+    sample_size: u64,
+) -> Result<()> {
+    let query = serde_json::json!({
+        "query": r#"
+            query Sample($limit: Int!) {
+                current_fungible_asset_balances(limit: $limit, order_by: {last_transaction_version: desc}) {
+                    storage_id
+                    owner_address
+                    asset_type
+                    token_standard
+                    amount
+                    last_transaction_version
+                }
+                current_delegator_balances(limit: $limit, order_by: {last_transaction_version: desc}) {
+                    delegator_address
+                    pool_address
+                    table_handle
+                    shares
+                    last_transaction_version
+                }
+            }
+        "#,
+        "variables": { "limit": sample_size },
+    });
+    let sample = client
+        .post(hasura_url)
+        .json(&query)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<SampleResponse>()
+        .await
+        .context("Failed to parse the sampled rows")?;
+
+    // A row that can't be checked is recorded as an error, the rest of the sample is still checked
+    for balance in sample.data.current_fungible_asset_balances {
+        let chain_value = fetch_fungible_asset_balance(client, fullnode_url, &balance).await;
+        record_outcome(
+            "current_fungible_asset_balances",
+            chain_name,
+            &balance.storage_id,
+            balance.last_transaction_version,
+            balance.amount,
+            chain_value,
+        );
+    }
+    for balance in sample.data.current_delegator_balances {
+        let chain_value = fetch_table_item_u128(
+            client,
+            fullnode_url,
+            &balance.table_handle,
+            "address",
+            &balance.delegator_address,
+            balance.last_transaction_version,
+        )
+        .await;
+        record_outcome(
+            "current_delegator_balances",
+            chain_name,
+            &format!("{}/{}", balance.pool_address, balance.delegator_address),
+            balance.last_transaction_version,
+            balance.shares,
+            chain_value,
+        );
+    }
+    Ok(())
+}
+
+fn record_outcome(
+    table: &str,
+    chain_name: &str,
+    key: &str,
+    version: u64,
+    indexed: u128,
+    chain_value: Result<ChainValue>,
+) {
+    let on_chain = match chain_value {
+        Ok(ChainValue::Found(value)) => value,
+        Ok(ChainValue::Missing) => 0,
+        Ok(ChainValue::Pruned) => {
+            VERIFICATION_CHECK_COUNT
+                .with_label_values(&[table, "skipped", chain_name])
+                .inc();
+            return;
+        },
+        Err(err) => {
+            tracing::warn!(
+                table = table,
+                key = key,
+                version = version,
+                error = ?err,
+                "Failed to read the balance from the fullnode"
+            );
+            VERIFICATION_CHECK_COUNT
+                .with_label_values(&[table, "error", chain_name])
+                .inc();
+            return;
+        },
+    };
+    if on_chain == indexed {
+        VERIFICATION_CHECK_COUNT
+            .with_label_values(&[table, "match", chain_name])
+            .inc();
+    } else {
+        tracing::error!(
+            table = table,
+            key = key,
+            version = version,
+            indexed = indexed.to_string(),
+            on_chain = on_chain.to_string(),
+            "Indexed balance doesn't match the chain"
+        );
+        VERIFICATION_CHECK_COUNT
+            .with_label_values(&[table, "mismatch", chain_name])
+            .inc();
+    }
+}
+
+/// Coin balances are read from the owner's `CoinStore`, fungible asset balances from the store
+/// object, which may keep its balance in a `ConcurrentFungibleBalance` instead.
+async fn fetch_fungible_asset_balance(
+    client: &Client,
+    fullnode_url: &str,
+    balance: &FungibleAssetBalance,
+) -> Result<ChainValue> {
+    let version = balance.last_transaction_version;
+    if balance.token_standard == "v1" {
+        let resource_type = format!("0x1::coin::CoinStore<{}>", balance.asset_type);
+        return fetch_resource_u128(
+            client,
+            fullnode_url,
+            &balance.owner_address,
+            &resource_type,
+            "/coin/value",
+            version,
+        )
+        .await;
+    }
+    let store_balance = fetch_resource_u128(
+        client,
+        fullnode_url,
+        &balance.storage_id,
+        "0x1::fungible_asset::FungibleStore",
+        "/balance",
+        version,
+    )
+    .await?;
+    if store_balance != ChainValue::Found(0) {
+        return Ok(store_balance);
+    }
+    match fetch_resource_u128(
+        client,
+        fullnode_url,
+        &balance.storage_id,
+        "0x1::fungible_asset::ConcurrentFungibleBalance",
+        "/balance/value",
+        version,
+    )
+    .await?
+    {
+        ChainValue::Missing => Ok(store_balance),
+        concurrent_balance => Ok(concurrent_balance),
+    }
+}
+
+async fn fetch_resource_u128(
+    client: &Client,
+    fullnode_url: &str,
+    address: &str,
+    resource_type: &str,
+    pointer: &str,
+    version: u64,
+) -> Result<ChainValue> {
+    let request = client.get(format!(
+        "{}/accounts/{}/resource/{}?ledger_version={}",
+        fullnode_url, address, resource_type, version
+    ));
+    read_u128(request, &format!("/data{}", pointer)).await
+}
+
+async fn fetch_table_item_u128(
+    client: &Client,
+    fullnode_url: &str,
+    table_handle: &str,
+    key_type: &str,
+    key: &str,
+    version: u64,
+) -> Result<ChainValue> {
+    let request = client
+        .post(format!(
+            "{}/tables/{}/item?ledger_version={}",
+            fullnode_url, table_handle, version
+        ))
+        .json(&serde_json::json!({
+            "key_type": key_type,
+            "value_type": "u128",
+            "key": key,
+        }));
+    read_u128(request, "").await
+}
+
+/// Sends a request to the fullnode and reads the u128 at `pointer` of the response, which the
+/// API encodes as a string.
+async fn read_u128(request: reqwest::RequestBuilder, pointer: &str) -> Result<ChainValue> {
+    let response = request.send().await?;
+    match response.status() {
+        StatusCode::NOT_FOUND => return Ok(ChainValue::Missing),
+        StatusCode::GONE => return Ok(ChainValue::Pruned),
+        status if !status.is_success() => bail!(
+            "Fullnode returned {}: {}",
+            status,
+            response.text().await.unwrap_or_default()
+        ),
+        _ => {},
+    }
+    let value = response.json::<Value>().await?;
+    let field = value
+        .pointer(pointer)
+        .with_context(|| format!("Fullnode response has no {}", pointer))?;
+    let parsed = match field {
+        Value::String(s) => s.parse::<u128>()?,
+        Value::Number(n) => n.to_string().parse::<u128>()?,
+        other => bail!("Expected a number at {}, got {}", pointer, other),
+    };
+    Ok(ChainValue::Found(parsed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use warp::{path::FullPath, Filter};
+
+    /// Serves the response of the first route whose prefix matches the request path, or a 404.
+    fn mock_server(routes: Vec<(&'static str, u16, Value)>) -> String {
+        let filter = warp::path::full().map(move |path: FullPath| {
+            let (status, body) = routes
+                .iter()
+                .find(|(prefix, ..)| path.as_str().starts_with(prefix))
+                .map(|(_, status, body)| (*status, body.clone()))
+                .unwrap_or((404, Value::Null));
+            warp::reply::with_status(
+                warp::reply::json(&body),
+                warp::http::StatusCode::from_u16(status).unwrap(),
+            )
+        });
+        let (address, server) = warp::serve(filter).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_read_u128() {
+        let url = mock_server(vec![
+            (
+                "/string",
+                200,
+                json!({"data": {"coin": {"value": u128::MAX.to_string()}}}),
+            ),
+            ("/number", 200, json!({"data": {"coin": {"value": 7}}})),
+            ("/pruned", 410, json!({"error_code": "version_pruned"})),
+            ("/failing", 500, json!({"message": "internal error"})),
+        ]);
+        let client = Client::new();
+        let read = |path: &str, pointer: &'static str| {
+            read_u128(client.get(format!("{}{}", url, path)), pointer)
+        };
+
+        assert_eq!(
+            read("/string", "/data/coin/value").await.unwrap(),
+            ChainValue::Found(u128::MAX)
+        );
+        assert_eq!(
+            read("/number", "/data/coin/value").await.unwrap(),
+            ChainValue::Found(7)
+        );
+        assert_eq!(
+            read("/missing", "/data/coin/value").await.unwrap(),
+            ChainValue::Missing
+        );
+        assert_eq!(
+            read("/pruned", "/data/coin/value").await.unwrap(),
+            ChainValue::Pruned
+        );
+        assert!(read("/failing", "/data/coin/value").await.is_err());
+        assert!(read("/string", "/data/coin/other").await.is_err());
+    }
+
+    fn coin_balance(owner_address: &str, amount: &str) -> Value {
+        json!({
+            "storage_id": format!("{}_store", owner_address),
+            "owner_address": owner_address,
+            "asset_type": "0x1::aptos_coin::AptosCoin",
+            "token_standard": "v1",
+            "amount": amount,
+            "last_transaction_version": 10,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_verify_sample_records_every_row() {
+        let url = mock_server(vec![
+            (
+                "/graphql",
+                200,
+                json!({"data": {
+                    "current_fungible_asset_balances": [
+                        coin_balance("0xa", "100"),
+                        coin_balance("0xb", "5"),
+                        coin_balance("0xc", "1"),
+                        coin_balance("0xd", "1"),
+                        coin_balance("0xe", "1"),
+                    ],
+                    "current_delegator_balances": [{
+                        "delegator_address": "0xf",
+                        "pool_address": "0x10",
+                        "table_handle": "0x11",
+                        "shares": "0",
+                        "last_transaction_version": 12,
+                    }],
+                }}),
+            ),
+            (
+                "/accounts/0xa/",
+                200,
+                json!({"data": {"coin": {"value": "100"}}}),
+            ),
+            (
+                "/accounts/0xb/",
+                200,
+                json!({"data": {"coin": {"value": "6"}}}),
+            ),
+            ("/accounts/0xc/", 410, json!({})),
+            // The failing row doesn't stop the rows after it from being checked
+            ("/accounts/0xd/", 500, json!({})),
+            (
+                "/accounts/0xe/",
+                200,
+                json!({"data": {"coin": {"value": "1"}}}),
+            ),
+        ]);
+        let chain_name = "verify_sample_test";
+
+        verify_sample(
+            &Client::new(),
+            &format!("{}/graphql", url),
+            &url,
+            chain_name,
+            10,
+        )
+        .await
+        .unwrap();
+
+        let count = |table: &str, outcome: &str| {
+            VERIFICATION_CHECK_COUNT
+                .with_label_values(&[table, outcome, chain_name])
+                .get()
+        };
+        assert_eq!(count("current_fungible_asset_balances", "match"), 2);
+        assert_eq!(count("current_fungible_asset_balances", "mismatch"), 1);
+        assert_eq!(count("current_fungible_asset_balances", "skipped"), 1);
+        assert_eq!(count("current_fungible_asset_balances", "error"), 1);
+        // The table item doesn't exist, which is a balance of zero
+        assert_eq!(count("current_delegator_balances", "match"), 1);
+    }
+}
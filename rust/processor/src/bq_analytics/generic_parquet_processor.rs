@@ -1,6 +1,6 @@
 use super::ParquetProcessingResult;
 use crate::{
-    bq_analytics::gcs_handler::upload_parquet_to_gcs,
+    bq_analytics::storage::{upload_parquet, ParquetStorage},
     gap_detectors::ProcessingResult,
     utils::{
         counters::{PARQUET_HANDLER_BUFFER_SIZE, PARQUET_STRUCT_SIZE},
@@ -10,7 +10,6 @@ use crate::{
 use ahash::AHashMap;
 use allocative::Allocative;
 use anyhow::{Context, Result};
-use google_cloud_storage::client::Client as GCSClient;
 use parquet::{
     file::{properties::WriterProperties, writer::SerializedFileWriter},
     record::RecordWriter,
@@ -65,7 +64,6 @@ where
     pub buffer_size_bytes: usize,
 
     pub transaction_version_to_struct_count: AHashMap<i64, i64>,
-    pub bucket_name: String,
     pub bucket_root: String,
     pub gap_detector_sender: kanal::AsyncSender<ProcessingResult>,
     pub upload_interval: Duration,
@@ -97,7 +95,6 @@ where
     }
 
     pub fn new(
-        bucket_name: String,
         bucket_root: String,
         gap_detector_sender: kanal::AsyncSender<ProcessingResult>,
         schema: Arc<Type>,
@@ -112,7 +109,6 @@ where
             buffer: Vec::new(),
             buffer_size_bytes: 0,
             transaction_version_to_struct_count: AHashMap::new(),
-            bucket_name,
             bucket_root,
             gap_detector_sender,
             schema,
@@ -124,7 +120,7 @@ where
 
     pub async fn handle(
         &mut self,
-        gcs_client: &GCSClient,
+        storage: &dyn ParquetStorage,
         changes: ParquetDataGeneric<ParquetType>,
     ) -> Result<()> {
         let parquet_structs = changes.data;
@@ -138,27 +134,27 @@ where
                 .set(size_of_struct as i64);
             self.buffer_size_bytes += size_of_struct;
             self.buffer.push(parquet_struct);
+        }
 
-            if self.buffer_size_bytes >= self.max_buffer_size {
-                info!("Max buffer size reached, uploading to GCS.");
-                if let Err(e) = self.upload_buffer(gcs_client).await {
-                    error!("Failed to upload buffer: {}", e);
-                    return Err(e);
-                }
-                self.last_upload_time = Instant::now();
+        // A batch always holds whole transactions, so only its end is a safe place to cut a file.
+        // Flushing in the middle of a batch could split a transaction over two files.
+        if self.buffer_size_bytes >= self.max_buffer_size {
+            info!("Max buffer size reached, uploading to storage.");
+            if let Err(e) = self.upload_buffer(storage).await {
+                error!("Failed to upload buffer: {}", e);
+                return Err(e);
             }
-
-            if self.last_upload_time.elapsed() >= self.upload_interval {
-                info!(
-                    "Time has elapsed more than {} since last upload.",
-                    self.upload_interval.as_secs()
-                );
-                if let Err(e) = self.upload_buffer(gcs_client).await {
-                    error!("Failed to upload buffer: {}", e);
-                    return Err(e);
-                }
-                self.last_upload_time = Instant::now();
+            self.last_upload_time = Instant::now();
+        } else if self.last_upload_time.elapsed() >= self.upload_interval {
+            info!(
+                "Time has elapsed more than {} since last upload.",
+                self.upload_interval.as_secs()
+            );
+            if let Err(e) = self.upload_buffer(storage).await {
+                error!("Failed to upload buffer: {}", e);
+                return Err(e);
             }
+            self.last_upload_time = Instant::now();
         }
 
         PARQUET_HANDLER_BUFFER_SIZE
@@ -167,7 +163,17 @@ where
         Ok(())
     }
 
-    async fn upload_buffer(&mut self, gcs_client: &GCSClient) -> Result<()> {
+    /// Uploads whatever is left in the buffer regardless of size or upload interval. Used on
+    /// shutdown so that buffered structs aren't lost.
+    pub async fn flush(&mut self, storage: &dyn ParquetStorage) -> Result<()> {
+        self.upload_buffer(storage).await?;
+        PARQUET_HANDLER_BUFFER_SIZE
+            .with_label_values(&[ParquetType::TABLE_NAME])
+            .set(0);
+        Ok(())
+    }
+
+    async fn upload_buffer(&mut self, storage: &dyn ParquetStorage) -> Result<()> {
         if self.buffer.is_empty() {
             return Ok(());
         }
@@ -183,6 +189,15 @@ where
         let end_version = last.version();
         let last_transaction_timestamp = naive_datetime_to_timestamp(last.get_timestamp());
 
+        // Rows are usually in version order, but the object name must cover every row in the file
+        let (min_version, max_version) = self
+            .buffer
+            .iter()
+            .map(|parquet_struct| parquet_struct.version())
+            .fold((start_version, end_version), |(min, max), version| {
+                (min.min(version), max.max(version))
+            });
+
         let txn_version_to_struct_count =
             process_struct_count_map(&self.buffer, &mut self.transaction_version_to_struct_count);
 
@@ -210,16 +225,16 @@ where
             table_name = ParquetType::TABLE_NAME,
             start_version = start_version,
             end_version = end_version,
-            "Max buffer size reached, uploading to GCS."
+            "Max buffer size reached, uploading to storage."
         );
         let bucket_root = PathBuf::from(&self.bucket_root);
-
-        upload_parquet_to_gcs(
-            gcs_client,
+        upload_parquet(
+            storage,
             upload_buffer,
             ParquetType::TABLE_NAME,
-            &self.bucket_name,
             &bucket_root,
+            min_version,
+            max_version,
         )
         .await?;
 
@@ -237,7 +252,7 @@ where
                 parquet_processing_result,
             ))
             .await
-            .expect("[Parser] Failed to send versions to gap detector");
+            .map_err(|_| anyhow::anyhow!("[Parser] Failed to send versions to gap detector"))?;
 
         Ok(())
     }
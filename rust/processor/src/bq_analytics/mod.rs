@@ -1,27 +1,23 @@
 pub mod gcs_handler;
 pub mod generic_parquet_processor;
+pub mod storage;
 
 use crate::{
     bq_analytics::generic_parquet_processor::{
         GetTimeStamp, HasParquetSchema, HasVersion, NamedTable, ParquetDataGeneric,
         ParquetHandler as GenericParquetHandler,
     },
+    bq_analytics::storage::ParquetStorageConfig,
     gap_detectors::ProcessingResult,
     worker::PROCESSOR_SERVICE_TYPE,
 };
 use ahash::AHashMap;
 use allocative::Allocative;
-use google_cloud_storage::{
-    client::{Client as GCSClient, ClientConfig as GcsClientConfig},
-    http::Error as StorageError,
-};
+use google_cloud_storage::http::Error as StorageError;
 use kanal::AsyncSender;
 use parquet::record::RecordWriter;
 use serde::{Deserialize, Serialize};
-use std::{
-    fmt::{Debug, Display, Formatter, Result as FormatResult},
-    sync::Arc,
-};
+use std::fmt::{Debug, Display, Formatter, Result as FormatResult};
 use tokio::{io, time::Duration};
 use tracing::{debug, error, info};
 
@@ -37,6 +33,7 @@ pub struct ParquetProcessingResult {
 pub enum ParquetProcessorError {
     ParquetError(parquet::errors::ParquetError),
     StorageError(StorageError),
+    HttpError(reqwest::Error),
     TimeoutError(tokio::time::error::Elapsed),
     IoError(io::Error),
     Other(String),
@@ -47,6 +44,7 @@ impl std::error::Error for ParquetProcessorError {
         match *self {
             ParquetProcessorError::ParquetError(ref err) => Some(err),
             ParquetProcessorError::StorageError(ref err) => Some(err),
+            ParquetProcessorError::HttpError(ref err) => Some(err),
             ParquetProcessorError::TimeoutError(ref err) => Some(err),
             ParquetProcessorError::IoError(ref err) => Some(err),
             ParquetProcessorError::Other(_) => None,
@@ -59,6 +57,7 @@ impl Display for ParquetProcessorError {
         match *self {
             ParquetProcessorError::ParquetError(ref err) => write!(f, "Parquet error: {}", err),
             ParquetProcessorError::StorageError(ref err) => write!(f, "Storage error: {}", err),
+            ParquetProcessorError::HttpError(ref err) => write!(f, "HTTP error: {}", err),
             ParquetProcessorError::TimeoutError(ref err) => write!(f, "Timeout error: {}", err),
             ParquetProcessorError::IoError(ref err) => write!(f, "IO error: {}", err),
             ParquetProcessorError::Other(ref desc) => write!(f, "Error: {}", desc),
@@ -72,6 +71,12 @@ impl From<std::io::Error> for ParquetProcessorError {
     }
 }
 
+impl From<reqwest::Error> for ParquetProcessorError {
+    fn from(err: reqwest::Error) -> Self {
+        ParquetProcessorError::HttpError(err)
+    }
+}
+
 impl From<anyhow::Error> for ParquetProcessorError {
     fn from(err: anyhow::Error) -> Self {
         ParquetProcessorError::Other(err.to_string())
@@ -89,6 +94,7 @@ pub fn create_parquet_handler_loop<ParquetType>(
     processor_name: &str,
     bucket_name: String,
     bucket_root: String,
+    storage_config: ParquetStorageConfig,
     parquet_handler_response_channel_size: usize,
     max_buffer_size: usize,
     upload_interval: Duration,
@@ -116,7 +122,6 @@ where
     );
 
     let mut parquet_manager = GenericParquetHandler::new(
-        bucket_name.clone(),
         bucket_root.clone(),
         new_gap_detector_sender.clone(),
         ParquetType::schema(),
@@ -126,44 +131,52 @@ where
     .expect("Failed to create parquet manager");
 
     tokio::spawn(async move {
-        let gcs_config = GcsClientConfig::default()
-            .with_auth()
-            .await
-            .expect("Failed to create GCS client config");
-        let gcs_client = Arc::new(GCSClient::new(gcs_config));
-
-        loop {
-            match parquet_receiver.recv().await {
-                Ok(txn_pb_res) => {
-                    let result = parquet_manager.handle(&gcs_client, txn_pb_res).await;
+        let result: anyhow::Result<()> = async {
+            let storage = storage_config.create_storage(bucket_name).await?;
+            loop {
+                match parquet_receiver.recv().await {
+                    Ok(txn_pb_res) => {
+                        parquet_manager.handle(storage.as_ref(), txn_pb_res).await?;
+                        info!(
+                            processor_name = processor_name.clone(),
+                            service_type = PROCESSOR_SERVICE_TYPE,
+                            "[Parquet Handler] Successfully processed structs to buffer",
+                        );
+                    },
+                    // The processor was dropped, e.g. on shutdown, so upload what's buffered and exit
+                    Err(e) => {
+                        info!(
+                            processor_name = processor_name.clone(),
+                            service_type = PROCESSOR_SERVICE_TYPE,
+                            "[Parquet Handler] Parquet channel closed, flushing buffer: {:?}",
+                            e
+                        );
+                        return parquet_manager.flush(storage.as_ref()).await;
+                    },
+                }
+            }
+        }
+        .await;
 
-                    match result {
-                        Ok(_) => {
-                            info!(
-                                processor_name = processor_name.clone(),
-                                service_type = PROCESSOR_SERVICE_TYPE,
-                                "[Parquet Handler] Successfully processed structs to buffer",
-                            );
-                        },
-                        Err(e) => {
-                            error!(
-                                processor_name = processor_name.clone(),
-                                service_type = PROCESSOR_SERVICE_TYPE,
-                                "[Parquet Handler] Error processing parquet files: {:?}",
-                                e
-                            );
-                            panic!("Error processing parquet files: {:?}", e);
-                        },
-                    }
-                },
-                Err(e) => {
-                    error!(
-                        processor_name = processor_name.clone(),
-                        service_type = PROCESSOR_SERVICE_TYPE,
-                        "[Parquet Handler] Error receiving parquet files: {:?}",
-                        e
-                    );
-                },
+        // Stopping drops the receiver, so the processor's next send fails. The error itself goes
+        // to the gap detector, which returns it to the worker's supervisor.
+        if let Err(e) = result {
+            error!(
+                processor_name = processor_name.clone(),
+                service_type = PROCESSOR_SERVICE_TYPE,
+                "[Parquet Handler] Error processing parquet files: {:?}",
+                e
+            );
+            if new_gap_detector_sender
+                .send(ProcessingResult::ParquetHandlerError(e))
+                .await
+                .is_err()
+            {
+                error!(
+                    processor_name = processor_name.clone(),
+                    service_type = PROCESSOR_SERVICE_TYPE,
+                    "[Parquet Handler] Gap detector channel closed, parquet handler error not reported",
+                );
             }
         }
     });
@@ -0,0 +1,145 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    reprocess::ReprocessArgs, rollback::RollbackArgs, verify_consistency::VerifyConsistencyArgs,
+    IndexerGrpcProcessorConfig,
+};
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use server_framework::{load, setup_logging, GenericConfig, ServerArgs};
+use std::path::PathBuf;
+use tokio::runtime::Handle;
+
+/// Runs the processor, or one of the maintenance commands against its database. Without a
+/// subcommand this behaves like `ServerArgs`, so existing deployments keep working.
+#[derive(Parser)]
+pub struct ProcessorArgs {
+    #[clap(short, long, value_parser)]
+    pub config_path: PathBuf,
+
+    #[clap(subcommand)]
+    pub command: Option<ProcessorCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum ProcessorCommand {
+    /// Run the processor. This is the default.
+    Run,
+    /// Rewind a processor to an earlier version, removing everything it wrote after it.
+    Rollback(RollbackArgs),
+    /// Rerun a processor on a list of transaction versions.
+    Reprocess(ReprocessArgs),
+    /// Check that current tables match the latest rows of their history tables.
+    VerifyConsistency(VerifyConsistencyArgs),
+}
+
+impl ProcessorArgs {
+    pub async fn run(&self, handle: Handle) -> Result<()> {
+        match &self.command {
+            None | Some(ProcessorCommand::Run) => {
+                let server_args = ServerArgs {
+                    config_path: self.config_path.clone(),
+                };
+                server_args.run::<IndexerGrpcProcessorConfig>(handle).await
+            },
+            Some(ProcessorCommand::Rollback(args)) => {
+                setup_logging();
+                args.run(&self.load_config()?).await
+            },
+            Some(ProcessorCommand::Reprocess(args)) => {
+                setup_logging();
+                args.run(&self.load_config()?).await
+            },
+            Some(ProcessorCommand::VerifyConsistency(args)) => {
+                setup_logging();
+                args.run(&self.load_config()?).await
+            },
+        }
+    }
+
+    fn load_config(&self) -> Result<IndexerGrpcProcessorConfig> {
+        Ok(load::<GenericConfig<IndexerGrpcProcessorConfig>>(&self.config_path)?.server_config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_without_subcommand() {
+        let args = ProcessorArgs::parse_from(["processor", "-c", "config.yaml"]);
+        assert_eq!(args.config_path, PathBuf::from("config.yaml"));
+        assert!(args.command.is_none());
+    }
+
+    #[test]
+    fn test_parse_rollback() {
+        let args = ProcessorArgs::parse_from([
+            "processor",
+            "-c",
+            "config.yaml",
+            "rollback",
+            "--processor",
+            "token_v2_processor",
+            "--target-version",
+            "100",
+        ]);
+        match args.command {
+            Some(ProcessorCommand::Rollback(rollback)) => {
+                assert_eq!(rollback.target_version, 100);
+                assert!(!rollback.allow_partial);
+            },
+            _ => panic!("Expected the rollback subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_parse_reprocess() {
+        let args = ProcessorArgs::parse_from([
+            "processor",
+            "-c",
+            "config.yaml",
+            "reprocess",
+            "--versions",
+            "5,3,9",
+        ]);
+        match args.command {
+            Some(ProcessorCommand::Reprocess(reprocess)) => {
+                assert_eq!(reprocess.versions, vec![5, 3, 9]);
+                assert!(reprocess.processor.is_none());
+                assert!(reprocess.versions_file.is_none());
+            },
+            _ => panic!("Expected the reprocess subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_parse_verify_consistency() {
+        let args = ProcessorArgs::parse_from([
+            "processor",
+            "-c",
+            "config.yaml",
+            "verify-consistency",
+            "--processor",
+            "coin_processor,token_v2_processor",
+            "--sample-percent",
+            "1",
+        ]);
+        match args.command {
+            Some(ProcessorCommand::VerifyConsistency(verify)) => {
+                assert_eq!(
+                    verify.processor,
+                    vec![
+                        crate::processors::ProcessorName::CoinProcessor,
+                        crate::processors::ProcessorName::TokenV2Processor
+                    ]
+                );
+                assert_eq!(verify.sample_percent, Some(1.0));
+                assert!(verify.tables.is_empty());
+            },
+            _ => panic!("Expected the verify-consistency subcommand"),
+        }
+    }
+}
@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    gap_detectors::DEFAULT_GAP_DETECTION_BATCH_SIZE, processors::ProcessorConfig,
-    transaction_filter::TransactionFilter, worker::Worker,
+    file_stream::LocalFileSourceConfig,
+    gap_detectors::DEFAULT_GAP_DETECTION_BATCH_SIZE,
+    processors::ProcessorConfig,
+    sharded_backfill::ShardedBackfill,
+    transaction_filter::TransactionFilter,
+    worker::{RetryPolicy, Worker},
 };
 use ahash::AHashMap;
 use anyhow::{Context, Result};
@@ -15,6 +19,8 @@ use url::Url;
 pub const QUERY_DEFAULT_RETRIES: u32 = 5;
 pub const QUERY_DEFAULT_RETRY_DELAY_MS: u64 = 500;
 pub const DEFAULT_SLEEP_TIME_BETWENN_REQUEST_MS: u64 = 10;
+pub const DEFAULT_PIPELINE_FAILURE_BUDGET: u32 = 5;
+pub const DEFAULT_PIPELINE_RETRY_BACKOFF_MS: u64 = 1000;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
@@ -58,12 +64,43 @@ pub struct IndexerGrpcProcessorConfig {
     pub deprecated_tables: HashSet<String>,
     #[serde(default = "IndexerGrpcProcessorConfig::default_sleep_time_between_request")]
     pub default_sleep_time_between_request: u64,
+    // Where to read transactions from. Defaults to the GRPC data service
+    #[serde(default)]
+    pub transaction_source: TransactionSourceConfig,
+    // Other processors to run off the same transaction stream. Each keeps its own status
+    #[serde(default)]
+    pub additional_processor_configs: Vec<ProcessorConfig>,
+    // Number of consecutive failures, without progress in between, that the fetcher or a
+    // processor task may hit before the worker gives up and exits
+    #[serde(default = "IndexerGrpcProcessorConfig::default_pipeline_failure_budget")]
+    pub pipeline_failure_budget: u32,
+    // Backoff before the first retry of a failed fetcher or batch, doubled for every retry after
+    #[serde(default = "IndexerGrpcProcessorConfig::default_pipeline_retry_backoff_ms")]
+    pub pipeline_retry_backoff_ms: u64,
+    // Process a bounded range into a subset of tables, tracking progress apart from processor_status
+    #[serde(default)]
+    pub backfill: Option<BackfillConfig>,
+    // Split a version range into shards that are processed concurrently, possibly by many pods
+    #[serde(default)]
+    pub sharded_backfill: Option<ShardedBackfillConfig>,
+    // Record transactions that fail to process in processor_errors and skip them instead of
+    // failing the batch
+    #[serde(default)]
+    pub enable_dead_letter: bool,
 }
 
 impl IndexerGrpcProcessorConfig {
     pub const fn default_sleep_time_between_request() -> u64 {
         DEFAULT_SLEEP_TIME_BETWENN_REQUEST_MS
     }
+    pub const fn default_pipeline_failure_budget() -> u32 {
+        DEFAULT_PIPELINE_FAILURE_BUDGET
+    }
+
+    pub const fn default_pipeline_retry_backoff_ms() -> u64 {
+        DEFAULT_PIPELINE_RETRY_BACKOFF_MS
+    }
+
     pub const fn default_gap_detection_batch_size() -> u64 {
         DEFAULT_GAP_DETECTION_BATCH_SIZE
     }
@@ -110,11 +147,25 @@ impl RunnableConfig for IndexerGrpcProcessorConfig {
             self.grpc_response_item_timeout_in_secs,
             self.deprecated_tables.clone(),
             self.default_sleep_time_between_request,
+            self.transaction_source.clone(),
+            self.additional_processor_configs.clone(),
+            RetryPolicy {
+                max_consecutive_failures: self.pipeline_failure_budget,
+                initial_backoff: Duration::from_millis(self.pipeline_retry_backoff_ms),
+            },
+            self.backfill.clone(),
+            self.enable_dead_letter,
         )
         .await
         .context("Failed to build worker")?;
-        worker.run().await;
-        Ok(())
+        match &self.sharded_backfill {
+            Some(sharded_backfill) => {
+                ShardedBackfill::new(sharded_backfill.clone(), worker)?
+                    .run()
+                    .await
+            },
+            None => worker.run().await,
+        }
     }
 
     fn get_server_name(&self) -> String {
@@ -129,6 +180,46 @@ impl RunnableConfig for IndexerGrpcProcessorConfig {
     }
 }
 
+/// Reprocesses `start_version..=end_version` into only the given tables. Progress is recorded in
+/// `backfill_processor_status` under `backfill_alias`, so a backfill can run next to the live
+/// processor and resumes where it left off when restarted with the same alias.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackfillConfig {
+    pub backfill_alias: String,
+    pub start_version: u64,
+    pub end_version: u64,
+    /// Table flag names to write, e.g. `CURRENT_TOKEN_ROYALTY_V1`. Every other table is skipped.
+    pub tables: HashSet<String>,
+}
+
+/// Processes `start_version..=end_version` as shards of `shard_size` versions. Shards are leased
+/// through the `backfill_shards` table, so any number of pods can run the same config, and
+/// `processor_status` only moves up to the highest version below which every shard is complete.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShardedBackfillConfig {
+    pub start_version: u64,
+    pub end_version: u64,
+    pub shard_size: u64,
+    /// Shards processed at the same time by this pod
+    #[serde(default = "ShardedBackfillConfig::default_concurrent_shards")]
+    pub concurrent_shards: usize,
+    /// A shard whose worker hasn't reported progress for this long is handed to another worker
+    #[serde(default = "ShardedBackfillConfig::default_lease_duration_secs")]
+    pub lease_duration_secs: u64,
+}
+
+impl ShardedBackfillConfig {
+    pub const fn default_concurrent_shards() -> usize {
+        4
+    }
+
+    pub const fn default_lease_duration_secs() -> u64 {
+        300
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
@@ -167,3 +258,14 @@ impl Default for IndexerGrpcHttp2Config {
         }
     }
 }
+
+/// Where the worker gets its transactions from.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TransactionSourceConfig {
+    /// Stream transactions from `indexer_grpc_data_service_address`.
+    #[default]
+    Grpc,
+    /// Read transactions from files in a local directory.
+    LocalFile(LocalFileSourceConfig),
+}
@@ -4,3 +4,6 @@
 pub mod ans_lookup;
 pub mod ans_lookup_v2;
 pub mod ans_utils;
+
+// parquet models
+pub mod parquet_ans_lookup_v2;
@@ -0,0 +1,116 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::ans_lookup_v2;
+use crate::bq_analytics::generic_parquet_processor::{GetTimeStamp, HasVersion, NamedTable};
+use allocative_derive::Allocative;
+use field_count::FieldCount;
+use parquet_derive::ParquetRecordWriter;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Allocative, Clone, Debug, Default, Deserialize, FieldCount, ParquetRecordWriter, Serialize,
+)]
+pub struct AnsLookupV2 {
+    pub txn_version: i64,
+    pub write_set_change_index: i64,
+    pub domain: String,
+    pub subdomain: String,
+    pub token_standard: String,
+    pub registered_address: Option<String>,
+    #[allocative(skip)]
+    pub expiration_timestamp: chrono::NaiveDateTime,
+    pub token_name: String,
+    pub is_deleted: bool,
+    pub subdomain_expiration_policy: Option<i64>,
+    #[allocative(skip)]
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl NamedTable for AnsLookupV2 {
+    const TABLE_NAME: &'static str = "ans_lookup_v2";
+}
+
+impl HasVersion for AnsLookupV2 {
+    fn version(&self) -> i64 {
+        self.txn_version
+    }
+}
+
+impl GetTimeStamp for AnsLookupV2 {
+    fn get_timestamp(&self) -> chrono::NaiveDateTime {
+        self.block_timestamp
+    }
+}
+
+impl AnsLookupV2 {
+    pub fn from_model(
+        ans_lookup: &ans_lookup_v2::AnsLookupV2,
+        block_timestamp: chrono::NaiveDateTime,
+    ) -> Self {
+        Self {
+            txn_version: ans_lookup.transaction_version,
+            write_set_change_index: ans_lookup.write_set_change_index,
+            domain: ans_lookup.domain.clone(),
+            subdomain: ans_lookup.subdomain.clone(),
+            token_standard: ans_lookup.token_standard.clone(),
+            registered_address: ans_lookup.registered_address.clone(),
+            expiration_timestamp: ans_lookup.expiration_timestamp,
+            token_name: ans_lookup.token_name.clone(),
+            is_deleted: ans_lookup.is_deleted,
+            subdomain_expiration_policy: ans_lookup.subdomain_expiration_policy,
+            block_timestamp,
+        }
+    }
+}
+
+#[derive(
+    Allocative, Clone, Debug, Default, Deserialize, FieldCount, ParquetRecordWriter, Serialize,
+)]
+pub struct AnsPrimaryNameV2 {
+    pub txn_version: i64,
+    pub write_set_change_index: i64,
+    pub registered_address: String,
+    pub token_standard: String,
+    pub domain: Option<String>,
+    pub subdomain: Option<String>,
+    pub token_name: Option<String>,
+    pub is_deleted: bool,
+    #[allocative(skip)]
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl NamedTable for AnsPrimaryNameV2 {
+    const TABLE_NAME: &'static str = "ans_primary_name_v2";
+}
+
+impl HasVersion for AnsPrimaryNameV2 {
+    fn version(&self) -> i64 {
+        self.txn_version
+    }
+}
+
+impl GetTimeStamp for AnsPrimaryNameV2 {
+    fn get_timestamp(&self) -> chrono::NaiveDateTime {
+        self.block_timestamp
+    }
+}
+
+impl AnsPrimaryNameV2 {
+    pub fn from_model(
+        primary_name: &ans_lookup_v2::AnsPrimaryNameV2,
+        block_timestamp: chrono::NaiveDateTime,
+    ) -> Self {
+        Self {
+            txn_version: primary_name.transaction_version,
+            write_set_change_index: primary_name.write_set_change_index,
+            registered_address: primary_name.registered_address.clone(),
+            token_standard: primary_name.token_standard.clone(),
+            domain: primary_name.domain.clone(),
+            subdomain: primary_name.subdomain.clone(),
+            token_name: primary_name.token_name.clone(),
+            is_deleted: primary_name.is_deleted,
+            block_timestamp,
+        }
+    }
+}
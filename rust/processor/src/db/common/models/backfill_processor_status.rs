@@ -0,0 +1,60 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(clippy::extra_unused_lifetimes)]
+
+use crate::{schema::backfill_processor_status, utils::database::DbPoolConnection};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl};
+use diesel_async::RunQueryDsl;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum BackfillStatus {
+    /// Not claimed by any worker yet, only used for backfill shards
+    Pending,
+    InProgress,
+    Complete,
+}
+
+#[derive(AsChangeset, Debug, Insertable)]
+#[diesel(table_name = backfill_processor_status)]
+/// Progress of a backfill, kept apart from `processor_status` so the live processor is unaffected
+pub struct BackfillProcessorStatus {
+    pub backfill_alias: String,
+    pub processor: String,
+    pub backfill_status: String,
+    pub last_success_version: i64,
+    pub last_transaction_timestamp: Option<chrono::NaiveDateTime>,
+    pub backfill_start_version: i64,
+    pub backfill_end_version: i64,
+}
+
+#[derive(AsChangeset, Debug, Queryable)]
+#[diesel(table_name = backfill_processor_status)]
+pub struct BackfillProcessorStatusQuery {
+    pub backfill_alias: String,
+    pub processor: String,
+    pub backfill_status: String,
+    pub last_success_version: i64,
+    pub last_updated: chrono::NaiveDateTime,
+    pub last_transaction_timestamp: Option<chrono::NaiveDateTime>,
+    pub backfill_start_version: i64,
+    pub backfill_end_version: i64,
+}
+
+impl BackfillProcessorStatusQuery {
+    pub async fn get_by_alias(
+        backfill_alias: &str,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Option<Self>> {
+        backfill_processor_status::table
+            .filter(backfill_processor_status::backfill_alias.eq(backfill_alias))
+            .first::<Self>(conn)
+            .await
+            .optional()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.backfill_status == BackfillStatus::Complete.to_string()
+    }
+}
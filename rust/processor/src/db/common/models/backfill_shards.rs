@@ -0,0 +1,139 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(clippy::extra_unused_lifetimes)]
+
+use super::backfill_processor_status::BackfillStatus;
+use crate::{
+    schema::backfill_shards,
+    utils::database::{DbPoolConnection, MyDbConnection},
+};
+use diesel::{
+    sql_query,
+    sql_types::{BigInt, Nullable, Text, Timestamp},
+    ExpressionMethods, OptionalExtension, QueryDsl,
+};
+use diesel_async::RunQueryDsl;
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = backfill_shards)]
+/// A version range of a sharded backfill, claimed by one worker at a time through a lease
+pub struct BackfillShard {
+    pub processor: String,
+    pub shard_start_version: i64,
+    pub shard_end_version: i64,
+    pub shard_status: String,
+}
+
+#[derive(Clone, Debug, Queryable, QueryableByName)]
+#[diesel(table_name = backfill_shards)]
+pub struct BackfillShardQuery {
+    pub processor: String,
+    pub shard_start_version: i64,
+    pub shard_end_version: i64,
+    pub shard_status: String,
+    pub last_success_version: Option<i64>,
+    pub last_transaction_timestamp: Option<chrono::NaiveDateTime>,
+    pub lease_owner: Option<String>,
+    pub lease_expires_at: Option<chrono::NaiveDateTime>,
+    pub last_updated: chrono::NaiveDateTime,
+}
+
+impl BackfillShardQuery {
+    /// All shards of the processor, ordered by version
+    pub async fn get_by_processor(
+        processor_name: &str,
+        conn: &mut MyDbConnection,
+    ) -> diesel::QueryResult<Vec<Self>> {
+        backfill_shards::table
+            .filter(backfill_shards::processor.eq(processor_name))
+            .order(backfill_shards::shard_start_version.asc())
+            .load::<Self>(conn)
+            .await
+    }
+
+    /// Leases the lowest shard that isn't complete and isn't leased by a live worker. A shard
+    /// whose lease expired, e.g. because its worker died, is handed out again.
+    pub async fn claim(
+        processor_name: &str,
+        lease_owner: &str,
+        lease_duration_secs: i64,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Option<Self>> {
+        sql_query(
+            "UPDATE backfill_shards SET shard_status = $3, lease_owner = $4, \
+             lease_expires_at = NOW() + $5 * INTERVAL '1 second', last_updated = NOW() \
+             WHERE (processor, shard_start_version) = ( \
+                 SELECT processor, shard_start_version FROM backfill_shards \
+                 WHERE processor = $1 AND shard_status <> $2 \
+                 AND (lease_expires_at IS NULL OR lease_expires_at < NOW()) \
+                 ORDER BY shard_start_version LIMIT 1 FOR UPDATE SKIP LOCKED \
+             ) RETURNING *",
+        )
+        .bind::<Text, _>(processor_name)
+        .bind::<Text, _>(BackfillStatus::Complete.to_string())
+        .bind::<Text, _>(BackfillStatus::InProgress.to_string())
+        .bind::<Text, _>(lease_owner)
+        .bind::<BigInt, _>(lease_duration_secs)
+        .get_result::<Self>(conn)
+        .await
+        .optional()
+    }
+
+    /// Records progress on a leased shard and extends the lease. Returns false if the lease has
+    /// been lost to another worker, in which case nothing is written.
+    pub async fn record_progress(
+        processor_name: &str,
+        shard_start_version: i64,
+        lease_owner: &str,
+        lease_duration_secs: i64,
+        last_success_version: i64,
+        last_transaction_timestamp: Option<chrono::NaiveDateTime>,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<bool> {
+        let updated = sql_query(
+            "UPDATE backfill_shards SET last_success_version = $4, \
+             last_transaction_timestamp = $5, \
+             shard_status = CASE WHEN $4 >= shard_end_version THEN $6 ELSE shard_status END, \
+             lease_expires_at = NOW() + $7 * INTERVAL '1 second', last_updated = NOW() \
+             WHERE processor = $1 AND shard_start_version = $2 AND lease_owner = $3 \
+             AND (last_success_version IS NULL OR last_success_version <= $4)",
+        )
+        .bind::<Text, _>(processor_name)
+        .bind::<BigInt, _>(shard_start_version)
+        .bind::<Text, _>(lease_owner)
+        .bind::<BigInt, _>(last_success_version)
+        .bind::<Nullable<Timestamp>, _>(last_transaction_timestamp)
+        .bind::<Text, _>(BackfillStatus::Complete.to_string())
+        .bind::<BigInt, _>(lease_duration_secs)
+        .execute(conn)
+        .await?;
+        Ok(updated > 0)
+    }
+
+    /// Extends the lease on a shard. Returns false if the lease has been lost to another worker.
+    pub async fn renew_lease(
+        processor_name: &str,
+        shard_start_version: i64,
+        lease_owner: &str,
+        lease_duration_secs: i64,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<bool> {
+        let updated = sql_query(
+            "UPDATE backfill_shards SET lease_expires_at = NOW() + $4 * INTERVAL '1 second', \
+             last_updated = NOW() \
+             WHERE processor = $1 AND shard_start_version = $2 AND lease_owner = $3",
+        )
+        .bind::<Text, _>(processor_name)
+        .bind::<BigInt, _>(shard_start_version)
+        .bind::<Text, _>(lease_owner)
+        .bind::<BigInt, _>(lease_duration_secs)
+        .execute(conn)
+        .await?;
+        Ok(updated > 0)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.shard_status == BackfillStatus::Complete.to_string()
+    }
+}
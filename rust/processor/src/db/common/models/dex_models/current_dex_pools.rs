@@ -0,0 +1,317 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+#![allow(clippy::unused_unit)]
+
+use super::{
+    dex_activities::DexActivity,
+    dex_utils::{normalize_amount, DexEvent, DexEventKind},
+};
+use crate::{schema::current_dex_pools, utils::database::DbPoolConnection};
+use ahash::AHashMap;
+use bigdecimal::{BigDecimal, Zero};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+// PK of current_dex_pools, i.e. (dex, pool_id)
+pub type CurrentDexPoolPK = (String, String);
+
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(dex, pool_id))]
+#[diesel(table_name = current_dex_pools)]
+pub struct CurrentDexPool {
+    pub dex: String,
+    pub pool_id: String,
+    pub contract_address: String,
+    pub token_x: Option<String>,
+    pub token_y: Option<String>,
+    pub token_x_decimals: Option<i32>,
+    pub token_y_decimals: Option<i32>,
+    pub reserve_x: BigDecimal,
+    pub reserve_y: BigDecimal,
+    pub reserve_x_normalized: Option<BigDecimal>,
+    pub reserve_y_normalized: Option<BigDecimal>,
+    pub volume_x: BigDecimal,
+    pub volume_y: BigDecimal,
+    pub volume_x_normalized: Option<BigDecimal>,
+    pub volume_y_normalized: Option<BigDecimal>,
+    pub swap_count: i64,
+    pub last_transaction_version: i64,
+    pub last_transaction_timestamp: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Identifiable, Queryable)]
+#[diesel(primary_key(dex, pool_id))]
+#[diesel(table_name = current_dex_pools)]
+pub struct CurrentDexPoolQuery {
+    pub dex: String,
+    pub pool_id: String,
+    pub contract_address: String,
+    pub token_x: Option<String>,
+    pub token_y: Option<String>,
+    pub token_x_decimals: Option<i32>,
+    pub token_y_decimals: Option<i32>,
+    pub reserve_x: BigDecimal,
+    pub reserve_y: BigDecimal,
+    pub reserve_x_normalized: Option<BigDecimal>,
+    pub reserve_y_normalized: Option<BigDecimal>,
+    pub volume_x: BigDecimal,
+    pub volume_y: BigDecimal,
+    pub volume_x_normalized: Option<BigDecimal>,
+    pub volume_y_normalized: Option<BigDecimal>,
+    pub swap_count: i64,
+    pub last_transaction_version: i64,
+    pub last_transaction_timestamp: chrono::NaiveDateTime,
+    pub inserted_at: chrono::NaiveDateTime,
+}
+
+impl CurrentDexPool {
+    pub fn get_pk(&self) -> CurrentDexPoolPK {
+        (self.dex.clone(), self.pool_id.clone())
+    }
+
+    fn new(activity: &DexActivity) -> Self {
+        Self {
+            dex: activity.dex.clone(),
+            pool_id: activity.pool_id.clone(),
+            contract_address: activity.contract_address.clone(),
+            token_x: None,
+            token_y: None,
+            token_x_decimals: None,
+            token_y_decimals: None,
+            reserve_x: BigDecimal::zero(),
+            reserve_y: BigDecimal::zero(),
+            reserve_x_normalized: None,
+            reserve_y_normalized: None,
+            volume_x: BigDecimal::zero(),
+            volume_y: BigDecimal::zero(),
+            volume_x_normalized: None,
+            volume_y_normalized: None,
+            swap_count: 0,
+            last_transaction_version: activity.transaction_version,
+            last_transaction_timestamp: activity.transaction_timestamp,
+        }
+    }
+
+    fn apply_event(&mut self, activity: &DexActivity, event: &DexEvent) {
+        if self.token_x.is_none() {
+            self.token_x.clone_from(&activity.token_x);
+        }
+        if self.token_y.is_none() {
+            self.token_y.clone_from(&activity.token_y);
+        }
+        self.reserve_x = match &event.reserve_x {
+            Some(reserve_x) => reserve_x.clone(),
+            None => &self.reserve_x + &event.amount_x,
+        };
+        self.reserve_y = match &event.reserve_y {
+            Some(reserve_y) => reserve_y.clone(),
+            None => &self.reserve_y + &event.amount_y,
+        };
+        if event.kind == DexEventKind::Swap {
+            self.volume_x += event.amount_x.abs();
+            self.volume_y += event.amount_y.abs();
+            self.swap_count += 1;
+        }
+        self.last_transaction_version = activity.transaction_version;
+        self.last_transaction_timestamp = activity.transaction_timestamp;
+    }
+
+    /// Applies the events of a batch, in order, to the pools they touch. `existing_pools` is what
+    /// is stored for them, and events at or below a stored pool's version are already counted in
+    /// it, so they're skipped. Activities without tokens take them from their pool.
+    pub fn from_dex_events(
+        activities_and_events: &mut [(DexActivity, DexEvent)],
+        mut existing_pools: AHashMap<CurrentDexPoolPK, CurrentDexPool>,
+    ) -> Vec<Self> {
+        let stored_versions: AHashMap<CurrentDexPoolPK, i64> = existing_pools
+            .iter()
+            .map(|(pk, pool)| (pk.clone(), pool.last_transaction_version))
+            .collect();
+        let mut pools: AHashMap<CurrentDexPoolPK, CurrentDexPool> = AHashMap::new();
+        for (activity, event) in activities_and_events.iter_mut() {
+            let pk = (activity.dex.clone(), activity.pool_id.clone());
+            let is_counted = stored_versions
+                .get(&pk)
+                .is_some_and(|version| activity.transaction_version <= *version);
+            let pool = pools.entry(pk).or_insert_with_key(|pk| {
+                existing_pools
+                    .remove(pk)
+                    .unwrap_or_else(|| Self::new(activity))
+            });
+            if !is_counted {
+                pool.apply_event(activity, event);
+            }
+            if activity.token_x.is_none() {
+                activity.token_x.clone_from(&pool.token_x);
+            }
+            if activity.token_y.is_none() {
+                activity.token_y.clone_from(&pool.token_y);
+            }
+        }
+        let mut pools = pools.into_values().collect::<Vec<Self>>();
+        // Sort by PK
+        pools.sort_by(|a, b| a.get_pk().cmp(&b.get_pk()));
+        pools
+    }
+
+    /// Loads the stored pools among `pks`
+    pub async fn get_by_pks(
+        conn: &mut DbPoolConnection<'_>,
+        pks: &[CurrentDexPoolPK],
+    ) -> diesel::QueryResult<AHashMap<CurrentDexPoolPK, Self>> {
+        let dexes: Vec<String> = pks.iter().map(|(dex, _)| dex.clone()).collect();
+        let pool_ids: Vec<String> = pks.iter().map(|(_, pool_id)| pool_id.clone()).collect();
+        let pools = current_dex_pools::table
+            .filter(current_dex_pools::dex.eq_any(dexes))
+            .filter(current_dex_pools::pool_id.eq_any(pool_ids))
+            .load::<CurrentDexPoolQuery>(conn)
+            .await?;
+        Ok(pools
+            .into_iter()
+            .map(Self::from)
+            .filter_map(|pool| {
+                let pk = pool.get_pk();
+                pks.contains(&pk).then_some((pk, pool))
+            })
+            .collect())
+    }
+
+    pub fn set_normalized_amounts(&mut self, decimals: &AHashMap<String, i32>) {
+        let get_decimals = |token: &Option<String>| token.as_ref().and_then(|t| decimals.get(t));
+        self.token_x_decimals = get_decimals(&self.token_x)
+            .copied()
+            .or(self.token_x_decimals);
+        self.token_y_decimals = get_decimals(&self.token_y)
+            .copied()
+            .or(self.token_y_decimals);
+        self.reserve_x_normalized = normalize_amount(&self.reserve_x, self.token_x_decimals);
+        self.reserve_y_normalized = normalize_amount(&self.reserve_y, self.token_y_decimals);
+        self.volume_x_normalized = normalize_amount(&self.volume_x, self.token_x_decimals);
+        self.volume_y_normalized = normalize_amount(&self.volume_y, self.token_y_decimals);
+    }
+}
+
+impl From<CurrentDexPoolQuery> for CurrentDexPool {
+    fn from(query: CurrentDexPoolQuery) -> Self {
+        Self {
+            dex: query.dex,
+            pool_id: query.pool_id,
+            contract_address: query.contract_address,
+            token_x: query.token_x,
+            token_y: query.token_y,
+            token_x_decimals: query.token_x_decimals,
+            token_y_decimals: query.token_y_decimals,
+            reserve_x: query.reserve_x,
+            reserve_y: query.reserve_y,
+            reserve_x_normalized: query.reserve_x_normalized,
+            reserve_y_normalized: query.reserve_y_normalized,
+            volume_x: query.volume_x,
+            volume_y: query.volume_y,
+            volume_x_normalized: query.volume_x_normalized,
+            volume_y_normalized: query.volume_y_normalized,
+            swap_count: query.swap_count,
+            last_transaction_version: query.last_transaction_version,
+            last_transaction_timestamp: query.last_transaction_timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn get_activity_and_event(
+        txn_version: i64,
+        kind: DexEventKind,
+        amount_x: i64,
+        amount_y: i64,
+    ) -> (DexActivity, DexEvent) {
+        let event = DexEvent {
+            kind,
+            pool_id: "0x1::aptos_coin::AptosCoin, 0xabc::usdc::USDC".to_string(),
+            token_x: (kind == DexEventKind::CreatePool)
+                .then(|| "0x1::aptos_coin::AptosCoin".to_string()),
+            token_y: (kind == DexEventKind::CreatePool).then(|| "0xabc::usdc::USDC".to_string()),
+            amount_x: BigDecimal::from(amount_x),
+            amount_y: BigDecimal::from(amount_y),
+            lp_amount: None,
+            reserve_x: None,
+            reserve_y: None,
+        };
+        let activity_type: &'static str = kind.into();
+        let activity = DexActivity {
+            transaction_version: txn_version,
+            event_index: 0,
+            dex: "liquidswap".to_string(),
+            contract_address: "0xabc".to_string(),
+            event_type: "0xabc::liquidity_pool::Event".to_string(),
+            activity_type: activity_type.to_string(),
+            pool_id: event.pool_id.clone(),
+            token_x: event.token_x.clone(),
+            token_y: event.token_y.clone(),
+            amount_x: event.amount_x.clone(),
+            amount_y: event.amount_y.clone(),
+            amount_x_normalized: None,
+            amount_y_normalized: None,
+            lp_amount: None,
+            reserve_x: event.reserve_x.clone(),
+            reserve_y: event.reserve_y.clone(),
+            sender: None,
+            entry_function_id_str: None,
+            transaction_timestamp: chrono::NaiveDateTime::default(),
+        };
+        (activity, event)
+    }
+
+    #[test]
+    fn test_from_dex_events() {
+        let mut activities_and_events = vec![
+            get_activity_and_event(1, DexEventKind::CreatePool, 0, 0),
+            get_activity_and_event(2, DexEventKind::AddLiquidity, 1000, 4000),
+            get_activity_and_event(3, DexEventKind::Swap, 100, -380),
+        ];
+        let pools = CurrentDexPool::from_dex_events(&mut activities_and_events, AHashMap::new());
+        assert_eq!(pools.len(), 1);
+        let pool = pools[0].clone();
+        assert_eq!(pool.reserve_x, BigDecimal::from(1100));
+        assert_eq!(pool.reserve_y, BigDecimal::from(3620));
+        assert_eq!(pool.volume_y, BigDecimal::from(380));
+        assert_eq!(pool.swap_count, 1);
+        assert_eq!(pool.last_transaction_version, 3);
+        // Later events learn the tokens from the pool creation
+        assert_eq!(
+            activities_and_events[2].0.token_y.as_deref(),
+            Some("0xabc::usdc::USDC")
+        );
+
+        // Reprocessing a batch doesn't count its events twice
+        let mut activities_and_events = vec![
+            get_activity_and_event(3, DexEventKind::Swap, 100, -380),
+            get_activity_and_event(4, DexEventKind::Swap, -50, 200),
+        ];
+        let pools = CurrentDexPool::from_dex_events(
+            &mut activities_and_events,
+            AHashMap::from_iter([(pool.get_pk(), pool)]),
+        );
+        assert_eq!(pools[0].reserve_x, BigDecimal::from(1050));
+        assert_eq!(pools[0].volume_x, BigDecimal::from(150));
+        assert_eq!(pools[0].swap_count, 2);
+
+        let mut pool = pools[0].clone();
+        pool.set_normalized_amounts(&AHashMap::from_iter([(
+            "0x1::aptos_coin::AptosCoin".to_string(),
+            2,
+        )]));
+        assert_eq!(
+            pool.reserve_x_normalized,
+            Some(BigDecimal::from_str("10.5").unwrap())
+        );
+        assert_eq!(pool.reserve_y_normalized, None);
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+#![allow(clippy::unused_unit)]
+
+use super::dex_utils::{normalize_amount, DexConfig, DexEvent};
+use crate::{schema::dex_activities, utils::util::standardize_address};
+use ahash::AHashMap;
+use bigdecimal::BigDecimal;
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(transaction_version, event_index))]
+#[diesel(table_name = dex_activities)]
+pub struct DexActivity {
+    pub transaction_version: i64,
+    pub event_index: i64,
+    pub dex: String,
+    pub contract_address: String,
+    pub event_type: String,
+    pub activity_type: String,
+    pub pool_id: String,
+    pub token_x: Option<String>,
+    pub token_y: Option<String>,
+    pub amount_x: BigDecimal,
+    pub amount_y: BigDecimal,
+    pub amount_x_normalized: Option<BigDecimal>,
+    pub amount_y_normalized: Option<BigDecimal>,
+    pub lp_amount: Option<BigDecimal>,
+    // Reserves the event reported, which the pool's reserves are set to
+    pub reserve_x: Option<BigDecimal>,
+    pub reserve_y: Option<BigDecimal>,
+    pub sender: Option<String>,
+    pub entry_function_id_str: Option<String>,
+    pub transaction_timestamp: chrono::NaiveDateTime,
+}
+
+impl DexActivity {
+    pub fn from_dex_event(
+        dex: &DexConfig,
+        event_type: &str,
+        event: &DexEvent,
+        txn_version: i64,
+        event_index: i64,
+        sender: &str,
+        entry_function_id_str: &Option<String>,
+        txn_timestamp: chrono::NaiveDateTime,
+    ) -> Self {
+        let activity_type: &'static str = event.kind.into();
+        Self {
+            transaction_version: txn_version,
+            event_index,
+            dex: dex.name.clone(),
+            contract_address: standardize_address(&dex.contract_address),
+            event_type: event_type.to_string(),
+            activity_type: activity_type.to_string(),
+            pool_id: event.pool_id.clone(),
+            token_x: event.token_x.clone(),
+            token_y: event.token_y.clone(),
+            amount_x: event.amount_x.clone(),
+            amount_y: event.amount_y.clone(),
+            amount_x_normalized: None,
+            amount_y_normalized: None,
+            lp_amount: event.lp_amount.clone(),
+            reserve_x: event.reserve_x.clone(),
+            reserve_y: event.reserve_y.clone(),
+            sender: Some(standardize_address(sender)),
+            entry_function_id_str: entry_function_id_str.clone(),
+            transaction_timestamp: txn_timestamp,
+        }
+    }
+
+    pub fn set_normalized_amounts(&mut self, decimals: &AHashMap<String, i32>) {
+        let get_decimals = |token: &Option<String>| token.as_ref().and_then(|t| decimals.get(t));
+        self.amount_x_normalized =
+            normalize_amount(&self.amount_x, get_decimals(&self.token_x).copied());
+        self.amount_y_normalized =
+            normalize_amount(&self.amount_y, get_decimals(&self.token_y).copied());
+    }
+}
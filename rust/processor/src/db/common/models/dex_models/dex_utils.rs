@@ -0,0 +1,384 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    schema::{coin_infos, fungible_asset_metadata},
+    utils::{
+        database::DbPoolConnection,
+        util::{get_json_path, standardize_address},
+    },
+};
+use ahash::AHashMap;
+use anyhow::Context;
+use bigdecimal::{BigDecimal, Zero};
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// A DEX contract whose pool events are indexed
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DexConfig {
+    /// Written to the dex column of every row, e.g. "liquidswap"
+    pub name: String,
+    pub contract_address: String,
+    /// Event types emitted by the contract, as `module::Struct`, mapped to what they mean
+    pub event_types: AHashMap<String, DexEventKind>,
+    #[serde(default)]
+    pub fields: DexEventFields,
+}
+
+impl DexConfig {
+    /// Returns what an event means if it was emitted by this contract and its type is mapped
+    pub fn get_event_kind(&self, event_type: &str) -> Option<DexEventKind> {
+        let (address, name) = event_type.split_once("::")?;
+        if standardize_address(address) != standardize_address(&self.contract_address) {
+            return None;
+        }
+        // Pools are usually generic over their tokens, so events are mapped by struct name alone
+        let name = name.split('<').next().unwrap_or(name);
+        self.event_types.get(name).copied()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, strum::IntoStaticStr)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum DexEventKind {
+    CreatePool,
+    Swap,
+    AddLiquidity,
+    RemoveLiquidity,
+}
+
+/// Paths of the event fields to read, dot separated for nested fields. The defaults match the
+/// events of Liquidswap, whose pools are identified by their type parameters.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DexEventFields {
+    /// Address of the pool. Events without it are attributed to the pool named by their type
+    /// parameters.
+    pub pool: String,
+    /// Coin type or fungible asset metadata of the tokens. Events without them take the first two
+    /// type parameters, or what an earlier event of the pool said.
+    pub token_x: String,
+    pub token_y: String,
+    pub x_in: String,
+    pub x_out: String,
+    pub y_in: String,
+    pub y_out: String,
+    pub added_x: String,
+    pub added_y: String,
+    pub lp_minted: String,
+    pub removed_x: String,
+    pub removed_y: String,
+    pub lp_burned: String,
+    /// Reserves after the event. Without them, reserves are summed from the amounts moved.
+    pub reserve_x: String,
+    pub reserve_y: String,
+}
+
+impl Default for DexEventFields {
+    fn default() -> Self {
+        Self {
+            pool: "pool".to_string(),
+            token_x: "token_x".to_string(),
+            token_y: "token_y".to_string(),
+            x_in: "x_in".to_string(),
+            x_out: "x_out".to_string(),
+            y_in: "y_in".to_string(),
+            y_out: "y_out".to_string(),
+            added_x: "added_x_val".to_string(),
+            added_y: "added_y_val".to_string(),
+            lp_minted: "lp_tokens_received".to_string(),
+            removed_x: "returned_x_val".to_string(),
+            removed_y: "returned_y_val".to_string(),
+            lp_burned: "lp_tokens_burned".to_string(),
+            reserve_x: "reserve_x".to_string(),
+            reserve_y: "reserve_y".to_string(),
+        }
+    }
+}
+
+/// A DEX event read with the configured field names
+#[derive(Clone, Debug, PartialEq)]
+pub struct DexEvent {
+    pub kind: DexEventKind,
+    pub pool_id: String,
+    pub token_x: Option<String>,
+    pub token_y: Option<String>,
+    /// Change of the pool's reserves, negative for what left the pool
+    pub amount_x: BigDecimal,
+    pub amount_y: BigDecimal,
+    pub lp_amount: Option<BigDecimal>,
+    pub reserve_x: Option<BigDecimal>,
+    pub reserve_y: Option<BigDecimal>,
+}
+
+impl DexEvent {
+    pub fn from_event_data(
+        kind: DexEventKind,
+        event_type: &str,
+        data: &str,
+        fields: &DexEventFields,
+        txn_version: i64,
+    ) -> anyhow::Result<Self> {
+        let data: Value = serde_json::from_str(data)
+            .with_context(|| format!("version {} failed! dex event isn't json", txn_version))?;
+        let type_params = get_type_params(event_type);
+        let pool_id = match get_json_path(&data, &fields.pool).and_then(get_address) {
+            Some(pool) => pool,
+            None if !type_params.is_empty() => type_params.join(", "),
+            None => anyhow::bail!(
+                "version {} failed! dex event has no {} and no type parameters",
+                txn_version,
+                fields.pool
+            ),
+        };
+        let get = |field: &str| {
+            get_amount(&data, field)
+                .with_context(|| format!("version {} failed! invalid dex event field", txn_version))
+        };
+        let (amount_x, amount_y, lp_amount) = match kind {
+            DexEventKind::CreatePool => (BigDecimal::zero(), BigDecimal::zero(), None),
+            DexEventKind::Swap => (
+                get(&fields.x_in)?.unwrap_or_default() - get(&fields.x_out)?.unwrap_or_default(),
+                get(&fields.y_in)?.unwrap_or_default() - get(&fields.y_out)?.unwrap_or_default(),
+                None,
+            ),
+            DexEventKind::AddLiquidity => (
+                get(&fields.added_x)?.unwrap_or_default(),
+                get(&fields.added_y)?.unwrap_or_default(),
+                get(&fields.lp_minted)?,
+            ),
+            DexEventKind::RemoveLiquidity => (
+                -get(&fields.removed_x)?.unwrap_or_default(),
+                -get(&fields.removed_y)?.unwrap_or_default(),
+                get(&fields.lp_burned)?,
+            ),
+        };
+        Ok(Self {
+            kind,
+            pool_id,
+            token_x: get_json_path(&data, &fields.token_x)
+                .and_then(get_asset_type)
+                .or_else(|| type_params.first().cloned()),
+            token_y: get_json_path(&data, &fields.token_y)
+                .and_then(get_asset_type)
+                .or_else(|| type_params.get(1).cloned()),
+            amount_x,
+            amount_y,
+            lp_amount,
+            reserve_x: get(&fields.reserve_x)?,
+            reserve_y: get(&fields.reserve_y)?,
+        })
+    }
+}
+
+/// Splits the type parameters of a type string, keeping nested ones with their parent
+fn get_type_params(type_str: &str) -> Vec<String> {
+    let Some(params) = type_str
+        .split_once('<')
+        .and_then(|(_, rest)| rest.strip_suffix('>'))
+    else {
+        return vec![];
+    };
+    let mut type_params = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in params.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                type_params.push(params[start..i].trim().to_string());
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    type_params.push(params[start..].trim().to_string());
+    type_params
+}
+
+/// Addresses are either plain or an object reference
+fn get_address(value: &Value) -> Option<String> {
+    match value {
+        Value::String(address) => Some(standardize_address(address)),
+        Value::Object(object) => object
+            .get("inner")
+            .and_then(|inner| inner.as_str())
+            .map(standardize_address),
+        _ => None,
+    }
+}
+
+/// Coin types are kept as they are written, fungible assets are identified by their metadata
+fn get_asset_type(value: &Value) -> Option<String> {
+    match value {
+        Value::String(coin_type) if coin_type.contains("::") => Some(coin_type.clone()),
+        _ => get_address(value),
+    }
+}
+
+/// u64 amounts are serialized as strings, but accept numbers too
+fn get_amount(data: &Value, path: &str) -> anyhow::Result<Option<BigDecimal>> {
+    let amount = match get_json_path(data, path) {
+        Some(Value::String(amount)) => amount.clone(),
+        Some(Value::Number(amount)) => amount.to_string(),
+        Some(Value::Null) | None => return Ok(None),
+        Some(other) => anyhow::bail!("field {} isn't an amount: {}", path, other),
+    };
+    Ok(Some(BigDecimal::from_str(&amount).with_context(|| {
+        format!("field {} isn't an amount: {}", path, amount)
+    })?))
+}
+
+/// Divides a raw amount by 10^decimals
+pub fn normalize_amount(amount: &BigDecimal, decimals: Option<i32>) -> Option<BigDecimal> {
+    let decimals = decimals?;
+    let (digits, scale) = amount.as_bigint_and_exponent();
+    Some(BigDecimal::new(digits, scale + decimals as i64))
+}
+
+/// Looks up the decimals of coins and fungible assets. Fungible asset metadata has both, and
+/// coin infos cover coins whose metadata hasn't been indexed. Unknown assets are left out.
+pub async fn get_decimals(
+    conn: &mut DbPoolConnection<'_>,
+    asset_types: &[String],
+) -> diesel::QueryResult<AHashMap<String, i32>> {
+    let mut decimals: AHashMap<String, i32> = fungible_asset_metadata::table
+        .select((
+            fungible_asset_metadata::asset_type,
+            fungible_asset_metadata::decimals,
+        ))
+        .filter(fungible_asset_metadata::asset_type.eq_any(asset_types))
+        .load::<(String, i32)>(conn)
+        .await?
+        .into_iter()
+        .collect();
+    let coin_types: Vec<String> = asset_types
+        .iter()
+        .filter(|asset_type| asset_type.contains("::") && !decimals.contains_key(*asset_type))
+        .cloned()
+        .collect();
+    if !coin_types.is_empty() {
+        decimals.extend(
+            coin_infos::table
+                .select((coin_infos::coin_type, coin_infos::decimals))
+                .filter(coin_infos::coin_type.eq_any(coin_types))
+                .load::<(String, i32)>(conn)
+                .await?,
+        );
+    }
+    Ok(decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTRACT: &str = "0x190d44266241744264b964a37b8f09863167a12d3e70cda39376cfb4e3561e12";
+    const POOL_TYPE: &str = "0x1::aptos_coin::AptosCoin, 0xabc::usdc::USDC, 0x190d44266241744264b964a37b8f09863167a12d3e70cda39376cfb4e3561e12::curves::Uncorrelated";
+
+    fn get_dex() -> DexConfig {
+        serde_yaml::from_str(&format!(
+            r#"
+name: liquidswap
+contract_address: "{}"
+event_types:
+  liquidity_pool::SwapEvent: swap
+  liquidity_pool::LiquidityRemovedEvent: remove_liquidity
+"#,
+            CONTRACT
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_get_event_kind() {
+        let dex = get_dex();
+        assert_eq!(
+            dex.get_event_kind(&format!(
+                "{}::liquidity_pool::SwapEvent<{}>",
+                CONTRACT, POOL_TYPE
+            )),
+            Some(DexEventKind::Swap)
+        );
+        assert_eq!(
+            dex.get_event_kind(&format!(
+                "{}::liquidity_pool::LiquidityAddedEvent<{}>",
+                CONTRACT, POOL_TYPE
+            )),
+            None
+        );
+        assert_eq!(dex.get_event_kind("0x1::liquidity_pool::SwapEvent"), None);
+    }
+
+    #[test]
+    fn test_get_type_params() {
+        assert_eq!(
+            get_type_params("0xabc::pool::SwapEvent<0x1::coin::Wrapped<u64, bool>, u8>"),
+            vec!["0x1::coin::Wrapped<u64, bool>", "u8"]
+        );
+        assert!(get_type_params("0xabc::pool::SwapEvent").is_empty());
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let dex = get_dex();
+        let event_type = format!("{}::liquidity_pool::SwapEvent<{}>", CONTRACT, POOL_TYPE);
+        let swap = DexEvent::from_event_data(
+            DexEventKind::Swap,
+            &event_type,
+            r#"{"x_in": "1000", "x_out": "0", "y_in": "0", "y_out": "250"}"#,
+            &dex.fields,
+            1,
+        )
+        .unwrap();
+        assert_eq!(swap.pool_id, POOL_TYPE);
+        assert_eq!(swap.token_x.as_deref(), Some("0x1::aptos_coin::AptosCoin"));
+        assert_eq!(swap.token_y.as_deref(), Some("0xabc::usdc::USDC"));
+        assert_eq!(swap.amount_x, BigDecimal::from(1000));
+        assert_eq!(swap.amount_y, BigDecimal::from(-250));
+
+        let remove = DexEvent::from_event_data(
+            DexEventKind::RemoveLiquidity,
+            "0xabc::pool::LiquidityRemovedEvent",
+            r#"{"pool": {"inner": "0x0def"}, "token_x": {"inner": "0xa"}, "returned_x_val": "10", "returned_y_val": 20, "lp_tokens_burned": "5"}"#,
+            &dex.fields,
+            1,
+        )
+        .unwrap();
+        assert_eq!(remove.pool_id, standardize_address("0xdef"));
+        assert_eq!(remove.token_x, Some(standardize_address("0xa")));
+        assert_eq!(remove.token_y, None);
+        assert_eq!(remove.amount_x, BigDecimal::from(-10));
+        assert_eq!(remove.amount_y, BigDecimal::from(-20));
+        assert_eq!(remove.lp_amount, Some(BigDecimal::from(5)));
+
+        assert!(DexEvent::from_event_data(
+            DexEventKind::Swap,
+            "0xabc::pool::SwapEvent",
+            r#"{"x_in": "1"}"#,
+            &dex.fields,
+            1,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_normalize_amount() {
+        assert_eq!(
+            normalize_amount(&BigDecimal::from(123_456_789), Some(8)),
+            Some(BigDecimal::from_str("1.23456789").unwrap())
+        );
+        assert_eq!(
+            normalize_amount(&BigDecimal::from(-5), Some(0)),
+            Some(BigDecimal::from(-5))
+        );
+        assert_eq!(normalize_amount(&BigDecimal::from(5), None), None);
+    }
+}
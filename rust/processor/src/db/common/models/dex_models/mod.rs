@@ -0,0 +1,6 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+pub mod current_dex_pools;
+pub mod dex_activities;
+pub mod dex_utils;
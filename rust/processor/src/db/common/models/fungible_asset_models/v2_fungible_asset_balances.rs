@@ -16,7 +16,7 @@ use crate::{
     },
     schema::{
         current_fungible_asset_balances, current_unified_fungible_asset_balances_to_be_renamed,
-        fungible_asset_balances,
+        daily_fungible_asset_balances, fungible_asset_balances,
     },
     utils::util::{
         hex_to_raw_bytes, sha3_256, standardize_address, APTOS_COIN_TYPE_STR,
@@ -34,6 +34,8 @@ use std::borrow::Borrow;
 pub type CurrentFungibleAssetBalancePK = String;
 pub type CurrentFungibleAssetMapping =
     AHashMap<CurrentFungibleAssetBalancePK, CurrentFungibleAssetBalance>;
+// Owner address, asset type and UTC date
+pub type DailyFungibleAssetBalancePK = (String, String, chrono::NaiveDate);
 
 #[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
 #[diesel(primary_key(transaction_version, write_set_change_index))]
@@ -86,6 +88,21 @@ pub struct CurrentUnifiedFungibleAssetBalance {
     pub last_transaction_timestamp_v2: Option<chrono::NaiveDateTime>,
 }
 
+/// Balance of a primary store at the end of a UTC day, from the day's last change
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(owner_address, asset_type, date))]
+#[diesel(table_name = daily_fungible_asset_balances)]
+pub struct DailyFungibleAssetBalance {
+    pub owner_address: String,
+    pub asset_type: String,
+    pub date: chrono::NaiveDate,
+    pub storage_id: String,
+    pub amount: BigDecimal,
+    pub token_standard: String,
+    pub last_transaction_version: i64,
+    pub last_transaction_timestamp: chrono::NaiveDateTime,
+}
+
 fn get_paired_metadata_address(coin_type_name: &str) -> String {
     if coin_type_name == APTOS_COIN_TYPE_STR {
         APT_METADATA_ADDRESS_HEX.clone()
@@ -146,6 +163,39 @@ impl From<&CurrentFungibleAssetBalance> for CurrentUnifiedFungibleAssetBalance {
     }
 }
 
+impl DailyFungibleAssetBalance {
+    pub fn get_pk(&self) -> DailyFungibleAssetBalancePK {
+        (
+            self.owner_address.clone(),
+            self.asset_type.clone(),
+            self.date,
+        )
+    }
+
+    /// Keeps the last balance of each day in the batch. Only primary stores are counted, so an
+    /// owner has a single balance per asset.
+    pub fn from_balances(balances: &[FungibleAssetBalance]) -> Vec<Self> {
+        let mut daily_balances: AHashMap<DailyFungibleAssetBalancePK, Self> = AHashMap::new();
+        for balance in balances.iter().filter(|balance| balance.is_primary) {
+            let daily_balance = Self {
+                owner_address: balance.owner_address.clone(),
+                asset_type: balance.asset_type.clone(),
+                date: balance.transaction_timestamp.date(),
+                storage_id: balance.storage_id.clone(),
+                amount: balance.amount.clone(),
+                token_standard: balance.token_standard.clone(),
+                last_transaction_version: balance.transaction_version,
+                last_transaction_timestamp: balance.transaction_timestamp,
+            };
+            daily_balances.insert(daily_balance.get_pk(), daily_balance);
+        }
+        let mut daily_balances = daily_balances.into_values().collect::<Vec<Self>>();
+        // Sort by PK
+        daily_balances.sort_by(|a, b| a.get_pk().cmp(&b.get_pk()));
+        daily_balances
+    }
+}
+
 impl FungibleAssetBalance {
     /// Basically just need to index FA Store, but we'll need to look up FA metadata
     pub async fn get_v2_from_write_resource(
@@ -381,4 +431,40 @@ mod tests {
         );
         assert_eq!(get_paired_metadata_address("0x66c34778730acbb120cefa57a3d98fd21e0c8b3a51e9baee530088b2e444e94c::moon_coin::MoonCoin"), "0xf772c28c069aa7e4417d85d771957eb3c5c11b5bf90b1965cda23b899ebc0384");
     }
+
+    #[test]
+    fn test_daily_balances() {
+        let balance =
+            |version: i64, timestamp: &str, is_primary: bool, amount: u64| FungibleAssetBalance {
+                transaction_version: version,
+                write_set_change_index: 0,
+                storage_id: "0x2".to_string(),
+                owner_address: "0x1".to_string(),
+                asset_type: "0x1::aptos_coin::AptosCoin".to_string(),
+                is_primary,
+                is_frozen: false,
+                amount: BigDecimal::from(amount),
+                transaction_timestamp: chrono::NaiveDateTime::parse_from_str(
+                    timestamp,
+                    "%Y-%m-%d %H:%M:%S",
+                )
+                .unwrap(),
+                token_standard: "v1".to_string(),
+            };
+        let daily_balances = DailyFungibleAssetBalance::from_balances(&[
+            balance(1, "2024-08-01 09:00:00", true, 100),
+            balance(2, "2024-08-01 23:59:59", true, 150),
+            balance(3, "2024-08-02 00:00:00", true, 120),
+            balance(4, "2024-08-02 10:00:00", false, 5),
+        ]);
+        assert_eq!(daily_balances.len(), 2);
+        assert_eq!(
+            daily_balances[0].date,
+            chrono::NaiveDate::from_ymd_opt(2024, 8, 1).unwrap()
+        );
+        assert_eq!(daily_balances[0].amount, BigDecimal::from(150));
+        assert_eq!(daily_balances[0].last_transaction_version, 2);
+        assert_eq!(daily_balances[1].amount, BigDecimal::from(120));
+        assert_eq!(daily_balances[1].last_transaction_version, 3);
+    }
 }
@@ -3,16 +3,23 @@
 
 pub mod account_transaction_models;
 pub mod ans_models;
+pub mod backfill_processor_status;
+pub mod backfill_shards;
 pub mod coin_models;
 pub mod default_models;
+pub mod dex_models;
 pub mod events_models;
 pub mod fungible_asset_models;
 pub mod ledger_info;
+pub mod nft_marketplace_models;
 pub mod object_models;
+pub mod processor_errors;
 pub mod processor_status;
 pub mod property_map;
 pub mod stake_models;
 pub mod token_models;
 pub mod token_v2_models;
+pub mod tracked_resource_models;
 pub mod transaction_metadata_model;
 pub mod user_transactions_models;
+pub mod webhook_deliveries;
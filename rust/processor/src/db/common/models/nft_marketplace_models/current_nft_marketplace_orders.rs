@@ -0,0 +1,191 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+#![allow(clippy::unused_unit)]
+
+use super::marketplace_utils::{MarketplaceConfig, MarketplaceEvent, MarketplaceEventKind};
+use crate::{
+    schema::{
+        current_nft_marketplace_collection_offers, current_nft_marketplace_listings,
+        current_nft_marketplace_token_offers,
+    },
+    utils::util::standardize_address,
+};
+use ahash::AHashSet;
+use bigdecimal::{BigDecimal, Zero};
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+// PK of current order tables, i.e. (contract_address, order_id)
+pub type CurrentNftMarketplaceOrderPK = (String, String);
+
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(contract_address, listing_id))]
+#[diesel(table_name = current_nft_marketplace_listings)]
+pub struct CurrentNftMarketplaceListing {
+    pub contract_address: String,
+    pub listing_id: String,
+    pub marketplace: String,
+    pub collection_id: String,
+    pub token_data_id: String,
+    pub token_standard: String,
+    pub price: BigDecimal,
+    pub token_amount: BigDecimal,
+    pub seller: Option<String>,
+    pub is_deleted: bool,
+    pub entry_function_id_str: Option<String>,
+    pub last_transaction_version: i64,
+    pub last_transaction_timestamp: chrono::NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(contract_address, offer_id))]
+#[diesel(table_name = current_nft_marketplace_token_offers)]
+pub struct CurrentNftMarketplaceTokenOffer {
+    pub contract_address: String,
+    pub offer_id: String,
+    pub marketplace: String,
+    pub collection_id: String,
+    pub token_data_id: String,
+    pub token_standard: String,
+    pub price: BigDecimal,
+    pub token_amount: BigDecimal,
+    pub buyer: Option<String>,
+    pub is_deleted: bool,
+    pub entry_function_id_str: Option<String>,
+    pub last_transaction_version: i64,
+    pub last_transaction_timestamp: chrono::NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(contract_address, collection_offer_id))]
+#[diesel(table_name = current_nft_marketplace_collection_offers)]
+pub struct CurrentNftMarketplaceCollectionOffer {
+    pub contract_address: String,
+    pub collection_offer_id: String,
+    pub marketplace: String,
+    pub collection_id: String,
+    pub token_standard: String,
+    pub item_price: BigDecimal,
+    pub remaining_token_amount: BigDecimal,
+    pub buyer: Option<String>,
+    pub is_deleted: bool,
+    pub entry_function_id_str: Option<String>,
+    pub last_transaction_version: i64,
+    pub last_transaction_timestamp: chrono::NaiveDateTime,
+}
+
+impl CurrentNftMarketplaceListing {
+    /// Listings are gone once canceled or filled
+    pub fn from_marketplace_event(
+        marketplace: &MarketplaceConfig,
+        event: &MarketplaceEvent,
+        txn_version: i64,
+        entry_function_id_str: &Option<String>,
+        txn_timestamp: chrono::NaiveDateTime,
+    ) -> Option<Self> {
+        Some(Self {
+            contract_address: standardize_address(&marketplace.contract_address),
+            listing_id: event.order_id.clone(),
+            marketplace: marketplace.name.clone(),
+            collection_id: event.token.collection_id.clone(),
+            token_data_id: event.token.token_data_id.clone()?,
+            token_standard: event.token.token_standard.to_string(),
+            price: event.price.clone(),
+            token_amount: event.token_amount.clone(),
+            seller: event.seller.clone(),
+            is_deleted: !event.kind.is_placed(),
+            entry_function_id_str: entry_function_id_str.clone(),
+            last_transaction_version: txn_version,
+            last_transaction_timestamp: txn_timestamp,
+        })
+    }
+
+    pub fn get_pk(&self) -> CurrentNftMarketplaceOrderPK {
+        (self.contract_address.clone(), self.listing_id.clone())
+    }
+}
+
+impl CurrentNftMarketplaceTokenOffer {
+    /// Token offers are gone once canceled or filled
+    pub fn from_marketplace_event(
+        marketplace: &MarketplaceConfig,
+        event: &MarketplaceEvent,
+        txn_version: i64,
+        entry_function_id_str: &Option<String>,
+        txn_timestamp: chrono::NaiveDateTime,
+    ) -> Option<Self> {
+        Some(Self {
+            contract_address: standardize_address(&marketplace.contract_address),
+            offer_id: event.order_id.clone(),
+            marketplace: marketplace.name.clone(),
+            collection_id: event.token.collection_id.clone(),
+            token_data_id: event.token.token_data_id.clone()?,
+            token_standard: event.token.token_standard.to_string(),
+            price: event.price.clone(),
+            token_amount: event.token_amount.clone(),
+            buyer: event.buyer.clone(),
+            is_deleted: !event.kind.is_placed(),
+            entry_function_id_str: entry_function_id_str.clone(),
+            last_transaction_version: txn_version,
+            last_transaction_timestamp: txn_timestamp,
+        })
+    }
+
+    pub fn get_pk(&self) -> CurrentNftMarketplaceOrderPK {
+        (self.contract_address.clone(), self.offer_id.clone())
+    }
+}
+
+impl CurrentNftMarketplaceCollectionOffer {
+    /// A collection offer stays open until canceled or until all of its tokens are bought. When
+    /// a fill doesn't say how many tokens are left, the offer is only known to be exhausted if
+    /// its object was deleted in the same transaction; otherwise the current row is left alone.
+    pub fn from_marketplace_event(
+        marketplace: &MarketplaceConfig,
+        event: &MarketplaceEvent,
+        txn_version: i64,
+        entry_function_id_str: &Option<String>,
+        txn_timestamp: chrono::NaiveDateTime,
+        deleted_object_addresses: &AHashSet<String>,
+    ) -> Option<Self> {
+        let (remaining_token_amount, is_deleted) = match event.kind {
+            MarketplaceEventKind::CollectionOfferPlaced => (event.token_amount.clone(), false),
+            MarketplaceEventKind::CollectionOfferCanceled => (event.token_amount.clone(), true),
+            _ => {
+                let remaining = match &event.remaining_token_amount {
+                    Some(remaining) => remaining.clone(),
+                    None if deleted_object_addresses.contains(&event.order_id) => {
+                        BigDecimal::zero()
+                    },
+                    None => return None,
+                };
+                let is_deleted = remaining.is_zero();
+                (remaining, is_deleted)
+            },
+        };
+        Some(Self {
+            contract_address: standardize_address(&marketplace.contract_address),
+            collection_offer_id: event.order_id.clone(),
+            marketplace: marketplace.name.clone(),
+            collection_id: event.token.collection_id.clone(),
+            token_standard: event.token.token_standard.to_string(),
+            item_price: event.price.clone(),
+            remaining_token_amount,
+            buyer: event.buyer.clone(),
+            is_deleted,
+            entry_function_id_str: entry_function_id_str.clone(),
+            last_transaction_version: txn_version,
+            last_transaction_timestamp: txn_timestamp,
+        })
+    }
+
+    pub fn get_pk(&self) -> CurrentNftMarketplaceOrderPK {
+        (
+            self.contract_address.clone(),
+            self.collection_offer_id.clone(),
+        )
+    }
+}
@@ -0,0 +1,494 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    db::common::models::{
+        token_models::token_utils::{CollectionDataIdType, TokenDataIdType, NAME_LENGTH},
+        token_v2_models::v2_token_utils::{ResourceReference, TokenStandard},
+    },
+    utils::util::{deserialize_from_string, standardize_address, truncate_str},
+};
+use ahash::AHashMap;
+use anyhow::Context;
+use bigdecimal::{BigDecimal, One};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// A marketplace contract whose events are indexed
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarketplaceConfig {
+    /// Written to the marketplace column of every row, e.g. "example_marketplace"
+    pub name: String,
+    pub contract_address: String,
+    /// Event types emitted by the contract, as `module::Struct`, mapped to what they mean
+    pub event_types: AHashMap<String, MarketplaceEventKind>,
+    #[serde(default)]
+    pub fields: MarketplaceEventFields,
+}
+
+impl MarketplaceConfig {
+    /// Returns what an event means if it was emitted by this contract and its type is mapped
+    pub fn get_event_kind(&self, event_type: &str) -> Option<MarketplaceEventKind> {
+        let (address, name) = event_type.split_once("::")?;
+        if standardize_address(address) != standardize_address(&self.contract_address) {
+            return None;
+        }
+        // Generic events are mapped by their struct name alone
+        let name = name.split('<').next().unwrap_or(name);
+        self.event_types.get(name).copied()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, strum::IntoStaticStr)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum MarketplaceEventKind {
+    ListingPlaced,
+    ListingCanceled,
+    ListingFilled,
+    TokenOfferPlaced,
+    TokenOfferCanceled,
+    TokenOfferFilled,
+    CollectionOfferPlaced,
+    CollectionOfferCanceled,
+    CollectionOfferFilled,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MarketplaceOrderType {
+    Listing,
+    TokenOffer,
+    CollectionOffer,
+}
+
+impl MarketplaceEventKind {
+    pub fn get_order_type(&self) -> MarketplaceOrderType {
+        match self {
+            Self::ListingPlaced | Self::ListingCanceled | Self::ListingFilled => {
+                MarketplaceOrderType::Listing
+            },
+            Self::TokenOfferPlaced | Self::TokenOfferCanceled | Self::TokenOfferFilled => {
+                MarketplaceOrderType::TokenOffer
+            },
+            Self::CollectionOfferPlaced
+            | Self::CollectionOfferCanceled
+            | Self::CollectionOfferFilled => MarketplaceOrderType::CollectionOffer,
+        }
+    }
+
+    pub fn is_placed(&self) -> bool {
+        matches!(
+            self,
+            Self::ListingPlaced | Self::TokenOfferPlaced | Self::CollectionOfferPlaced
+        )
+    }
+}
+
+/// Names of the event fields to read. The defaults match the events of the aptos-core example
+/// marketplace, which most marketplaces on Aptos follow.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarketplaceEventFields {
+    pub listing: String,
+    pub token_offer: String,
+    pub collection_offer: String,
+    pub seller: String,
+    pub buyer: String,
+    pub price: String,
+    pub token_amount: String,
+    pub remaining_token_amount: String,
+    pub commission: String,
+    pub royalties: String,
+    pub token_metadata: String,
+    pub collection_metadata: String,
+}
+
+impl Default for MarketplaceEventFields {
+    fn default() -> Self {
+        Self {
+            listing: "listing".to_string(),
+            token_offer: "token_offer".to_string(),
+            collection_offer: "collection_offer".to_string(),
+            seller: "seller".to_string(),
+            buyer: "purchaser".to_string(),
+            price: "price".to_string(),
+            token_amount: "token_amount".to_string(),
+            remaining_token_amount: "remaining_token_amount".to_string(),
+            commission: "commission".to_string(),
+            royalties: "royalties".to_string(),
+            token_metadata: "token_metadata".to_string(),
+            collection_metadata: "collection_metadata".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct OptionalResourceReference {
+    vec: Vec<ResourceReference>,
+}
+
+impl OptionalResourceReference {
+    fn get_reference_address(&self) -> Option<String> {
+        self.vec.first().map(|r| r.get_reference_address())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct OptionalBigDecimal {
+    vec: Vec<BigDecimalWrapper>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct BigDecimalWrapper(#[serde(deserialize_with = "deserialize_from_string")] pub BigDecimal);
+
+/// Identifies a token of either standard. v1 tokens only carry the property version.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TokenMetadata {
+    creator_address: String,
+    collection_name: String,
+    collection: OptionalResourceReference,
+    token_name: String,
+    token: OptionalResourceReference,
+    property_version: OptionalBigDecimal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CollectionMetadata {
+    creator_address: String,
+    collection_name: String,
+    collection: OptionalResourceReference,
+}
+
+/// The token or collection an order is for, with ids matching the token v2 tables
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarketplaceTokenIdentity {
+    pub collection_id: String,
+    pub token_data_id: Option<String>,
+    pub creator_address: String,
+    pub collection_name: String,
+    pub token_name: Option<String>,
+    pub property_version: Option<BigDecimal>,
+    pub token_standard: TokenStandard,
+}
+
+impl TokenMetadata {
+    pub fn get_identity(&self) -> MarketplaceTokenIdentity {
+        let (token_data_id, token_standard) = match self.token.get_reference_address() {
+            Some(token_address) => (token_address, TokenStandard::V2),
+            None => (
+                TokenDataIdType::new(
+                    self.creator_address.clone(),
+                    self.collection_name.clone(),
+                    self.token_name.clone(),
+                )
+                .to_id(),
+                TokenStandard::V1,
+            ),
+        };
+        MarketplaceTokenIdentity {
+            collection_id: get_collection_id(
+                &self.creator_address,
+                &self.collection_name,
+                &self.collection,
+            ),
+            token_data_id: Some(token_data_id),
+            creator_address: standardize_address(&self.creator_address),
+            collection_name: truncate_str(&self.collection_name, NAME_LENGTH),
+            token_name: Some(truncate_str(&self.token_name, NAME_LENGTH)),
+            property_version: self.property_version.vec.first().map(|v| v.0.clone()),
+            token_standard,
+        }
+    }
+}
+
+impl CollectionMetadata {
+    pub fn get_identity(&self) -> MarketplaceTokenIdentity {
+        let token_standard = match self.collection.get_reference_address() {
+            Some(_) => TokenStandard::V2,
+            None => TokenStandard::V1,
+        };
+        MarketplaceTokenIdentity {
+            collection_id: get_collection_id(
+                &self.creator_address,
+                &self.collection_name,
+                &self.collection,
+            ),
+            token_data_id: None,
+            creator_address: standardize_address(&self.creator_address),
+            collection_name: truncate_str(&self.collection_name, NAME_LENGTH),
+            token_name: None,
+            property_version: None,
+            token_standard,
+        }
+    }
+}
+
+/// v2 collections are objects, v1 collections are identified by the hash of creator and name
+fn get_collection_id(
+    creator_address: &str,
+    collection_name: &str,
+    collection: &OptionalResourceReference,
+) -> String {
+    collection.get_reference_address().unwrap_or_else(|| {
+        CollectionDataIdType::new(creator_address.to_string(), collection_name.to_string()).to_id()
+    })
+}
+
+/// A marketplace event read with the configured field names
+#[derive(Clone, Debug)]
+pub struct MarketplaceEvent {
+    pub kind: MarketplaceEventKind,
+    /// Address of the listing, token offer or collection offer
+    pub order_id: String,
+    pub token: MarketplaceTokenIdentity,
+    pub price: BigDecimal,
+    pub token_amount: BigDecimal,
+    pub remaining_token_amount: Option<BigDecimal>,
+    pub commission: Option<BigDecimal>,
+    pub royalties: Option<BigDecimal>,
+    pub seller: Option<String>,
+    pub buyer: Option<String>,
+}
+
+impl MarketplaceEvent {
+    pub fn from_event_data(
+        kind: MarketplaceEventKind,
+        data: &str,
+        fields: &MarketplaceEventFields,
+        txn_version: i64,
+    ) -> anyhow::Result<Self> {
+        let data: Value = serde_json::from_str(data).with_context(|| {
+            format!(
+                "version {} failed! marketplace event isn't json",
+                txn_version
+            )
+        })?;
+        let order_field = match kind.get_order_type() {
+            MarketplaceOrderType::Listing => &fields.listing,
+            MarketplaceOrderType::TokenOffer => &fields.token_offer,
+            MarketplaceOrderType::CollectionOffer => &fields.collection_offer,
+        };
+        let order_id = get_address(&data, order_field).with_context(|| {
+            format!(
+                "version {} failed! marketplace event has no {}",
+                txn_version, order_field
+            )
+        })?;
+        // Fills of collection offers are for a single token, so they carry token metadata
+        let token = if let Some(metadata) = data.get(&fields.token_metadata) {
+            serde_json::from_value::<TokenMetadata>(metadata.clone())
+                .map(|m| m.get_identity())
+                .with_context(|| {
+                    format!(
+                        "version {} failed! unable to parse token metadata",
+                        txn_version
+                    )
+                })?
+        } else if let Some(metadata) = data.get(&fields.collection_metadata) {
+            serde_json::from_value::<CollectionMetadata>(metadata.clone())
+                .map(|m| m.get_identity())
+                .with_context(|| {
+                    format!(
+                        "version {} failed! unable to parse collection metadata",
+                        txn_version
+                    )
+                })?
+        } else {
+            anyhow::bail!(
+                "version {} failed! marketplace event has neither {} nor {}",
+                txn_version,
+                fields.token_metadata,
+                fields.collection_metadata
+            );
+        };
+        let price = get_amount(&data, &fields.price)?.with_context(|| {
+            format!(
+                "version {} failed! marketplace event has no {}",
+                txn_version, fields.price
+            )
+        })?;
+        let remaining_token_amount = get_amount(&data, &fields.remaining_token_amount)?;
+        // Cancellations of collection offers only say how many tokens were still wanted
+        let token_amount = match get_amount(&data, &fields.token_amount)? {
+            Some(amount) => amount,
+            None if kind == MarketplaceEventKind::CollectionOfferCanceled => remaining_token_amount
+                .clone()
+                .unwrap_or_else(BigDecimal::one),
+            None => BigDecimal::one(),
+        };
+        Ok(Self {
+            kind,
+            order_id,
+            token,
+            price,
+            token_amount,
+            remaining_token_amount,
+            commission: get_amount(&data, &fields.commission)?,
+            royalties: get_amount(&data, &fields.royalties)?,
+            seller: get_address(&data, &fields.seller),
+            buyer: get_address(&data, &fields.buyer),
+        })
+    }
+}
+
+/// Addresses are either plain or an object reference
+fn get_address(data: &Value, field: &str) -> Option<String> {
+    match data.get(field)? {
+        Value::String(address) => Some(standardize_address(address)),
+        Value::Object(object) => object
+            .get("inner")
+            .and_then(|inner| inner.as_str())
+            .map(standardize_address),
+        _ => None,
+    }
+}
+
+/// u64 amounts are serialized as strings, but accept numbers too
+fn get_amount(data: &Value, field: &str) -> anyhow::Result<Option<BigDecimal>> {
+    let amount = match data.get(field) {
+        Some(Value::String(amount)) => amount,
+        Some(Value::Number(amount)) => return Ok(Some(BigDecimal::from_str(&amount.to_string())?)),
+        Some(Value::Null) | None => return Ok(None),
+        Some(other) => anyhow::bail!("field {} isn't an amount: {}", field, other),
+    };
+    Ok(Some(BigDecimal::from_str(amount).with_context(|| {
+        format!("field {} isn't an amount: {}", field, amount)
+    })?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTRACT: &str = "0x584b50b999c78ade62f8359c91b5165ff390338d45f8e55969a04e65d76258c9";
+
+    fn get_marketplace() -> MarketplaceConfig {
+        serde_yaml::from_str(&format!(
+            r#"
+name: example
+contract_address: "{}"
+event_types:
+  events::ListingPlacedEvent: listing_placed
+  events::CollectionOfferFilledEvent: collection_offer_filled
+"#,
+            CONTRACT
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_get_event_kind() {
+        let marketplace = get_marketplace();
+        assert_eq!(
+            marketplace.get_event_kind(&format!("{}::events::ListingPlacedEvent", CONTRACT)),
+            Some(MarketplaceEventKind::ListingPlaced)
+        );
+        assert_eq!(
+            marketplace.get_event_kind(&format!(
+                "{}::events::CollectionOfferFilledEvent<0x1::aptos_coin::AptosCoin>",
+                CONTRACT
+            )),
+            Some(MarketplaceEventKind::CollectionOfferFilled)
+        );
+        assert_eq!(
+            marketplace.get_event_kind(&format!("{}::events::ListingCanceledEvent", CONTRACT)),
+            None
+        );
+        assert_eq!(
+            marketplace.get_event_kind("0x1::events::ListingPlacedEvent"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_token_identity() {
+        let fields = MarketplaceEventFields::default();
+        let v2 = r#"{
+            "listing": "0xa1",
+            "seller": "0xb2",
+            "price": "1500",
+            "token_metadata": {
+                "creator_address": "0xc3",
+                "collection_name": "Collection",
+                "collection": {"vec": [{"inner": "0xd4"}]},
+                "token_name": "Token #1",
+                "token": {"vec": [{"inner": "0xe5"}]},
+                "property_version": {"vec": []}
+            }
+        }"#;
+        let event =
+            MarketplaceEvent::from_event_data(MarketplaceEventKind::ListingPlaced, v2, &fields, 1)
+                .unwrap();
+        assert_eq!(event.order_id, standardize_address("0xa1"));
+        assert_eq!(event.seller, Some(standardize_address("0xb2")));
+        assert_eq!(event.buyer, None);
+        assert_eq!(event.price, BigDecimal::from(1500));
+        assert_eq!(event.token_amount, BigDecimal::one());
+        assert_eq!(event.token.token_standard, TokenStandard::V2);
+        assert_eq!(event.token.collection_id, standardize_address("0xd4"));
+        assert_eq!(event.token.token_data_id, Some(standardize_address("0xe5")));
+
+        let v1 = r#"{
+            "listing": "0xa1",
+            "seller": "0xb2",
+            "price": "1500",
+            "token_metadata": {
+                "creator_address": "0xc3",
+                "collection_name": "Collection",
+                "collection": {"vec": []},
+                "token_name": "Token #1",
+                "token": {"vec": []},
+                "property_version": {"vec": ["0"]}
+            }
+        }"#;
+        let event =
+            MarketplaceEvent::from_event_data(MarketplaceEventKind::ListingPlaced, v1, &fields, 1)
+                .unwrap();
+        assert_eq!(event.token.token_standard, TokenStandard::V1);
+        assert_eq!(
+            event.token.token_data_id,
+            Some(
+                TokenDataIdType::new(
+                    "0xc3".to_string(),
+                    "Collection".to_string(),
+                    "Token #1".to_string()
+                )
+                .to_id()
+            )
+        );
+        assert_eq!(
+            event.token.collection_id,
+            CollectionDataIdType::new("0xc3".to_string(), "Collection".to_string()).to_id()
+        );
+        assert_eq!(event.token.property_version, Some(BigDecimal::from(0)));
+    }
+
+    #[test]
+    fn test_collection_offer_canceled_amount() {
+        let fields = MarketplaceEventFields::default();
+        let data = r#"{
+            "collection_offer": "0xa1",
+            "purchaser": "0xb2",
+            "price": "100",
+            "remaining_token_amount": "3",
+            "collection_metadata": {
+                "creator_address": "0xc3",
+                "collection_name": "Collection",
+                "collection": {"vec": [{"inner": "0xd4"}]}
+            }
+        }"#;
+        let event = MarketplaceEvent::from_event_data(
+            MarketplaceEventKind::CollectionOfferCanceled,
+            data,
+            &fields,
+            1,
+        )
+        .unwrap();
+        assert_eq!(event.buyer, Some(standardize_address("0xb2")));
+        assert_eq!(event.token_amount, BigDecimal::from(3));
+        assert_eq!(event.token.token_data_id, None);
+        assert_eq!(event.token.collection_id, standardize_address("0xd4"));
+    }
+}
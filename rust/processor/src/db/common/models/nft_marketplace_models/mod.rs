@@ -0,0 +1,6 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+pub mod current_nft_marketplace_orders;
+pub mod marketplace_utils;
+pub mod nft_marketplace_activities;
@@ -0,0 +1,78 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+#![allow(clippy::unused_unit)]
+
+use super::marketplace_utils::{MarketplaceConfig, MarketplaceEvent};
+use crate::{schema::nft_marketplace_activities, utils::util::standardize_address};
+use bigdecimal::BigDecimal;
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(transaction_version, event_index))]
+#[diesel(table_name = nft_marketplace_activities)]
+pub struct NftMarketplaceActivity {
+    pub transaction_version: i64,
+    pub event_index: i64,
+    pub marketplace: String,
+    pub contract_address: String,
+    pub event_type: String,
+    pub standard_event_type: String,
+    pub order_id: String,
+    pub collection_id: String,
+    pub token_data_id: Option<String>,
+    pub creator_address: String,
+    pub collection_name: String,
+    pub token_name: Option<String>,
+    pub property_version: Option<BigDecimal>,
+    pub token_standard: String,
+    pub price: BigDecimal,
+    pub token_amount: BigDecimal,
+    pub commission: Option<BigDecimal>,
+    pub royalties: Option<BigDecimal>,
+    pub seller: Option<String>,
+    pub buyer: Option<String>,
+    pub entry_function_id_str: Option<String>,
+    pub transaction_timestamp: chrono::NaiveDateTime,
+}
+
+impl NftMarketplaceActivity {
+    pub fn from_marketplace_event(
+        marketplace: &MarketplaceConfig,
+        event_type: &str,
+        event: &MarketplaceEvent,
+        txn_version: i64,
+        event_index: i64,
+        entry_function_id_str: &Option<String>,
+        txn_timestamp: chrono::NaiveDateTime,
+    ) -> Self {
+        let standard_event_type: &'static str = event.kind.into();
+        Self {
+            transaction_version: txn_version,
+            event_index,
+            marketplace: marketplace.name.clone(),
+            contract_address: standardize_address(&marketplace.contract_address),
+            event_type: event_type.to_string(),
+            standard_event_type: standard_event_type.to_string(),
+            order_id: event.order_id.clone(),
+            collection_id: event.token.collection_id.clone(),
+            token_data_id: event.token.token_data_id.clone(),
+            creator_address: event.token.creator_address.clone(),
+            collection_name: event.token.collection_name.clone(),
+            token_name: event.token.token_name.clone(),
+            property_version: event.token.property_version.clone(),
+            token_standard: event.token.token_standard.to_string(),
+            price: event.price.clone(),
+            token_amount: event.token_amount.clone(),
+            commission: event.commission.clone(),
+            royalties: event.royalties.clone(),
+            seller: event.seller.clone(),
+            buyer: event.buyer.clone(),
+            entry_function_id_str: entry_function_id_str.clone(),
+            transaction_timestamp: txn_timestamp,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::v2_objects;
+use crate::bq_analytics::generic_parquet_processor::{GetTimeStamp, HasVersion, NamedTable};
+use allocative_derive::Allocative;
+use field_count::FieldCount;
+use parquet_derive::ParquetRecordWriter;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Allocative, Clone, Debug, Default, Deserialize, FieldCount, ParquetRecordWriter, Serialize,
+)]
+pub struct Object {
+    pub txn_version: i64,
+    pub write_set_change_index: i64,
+    pub object_address: String,
+    pub owner_address: String,
+    pub state_key_hash: String,
+    pub guid_creation_num: String, // it is a string representation of the numeric
+    pub allow_ungated_transfer: bool,
+    pub is_deleted: bool,
+    pub untransferrable: bool,
+    #[allocative(skip)]
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl NamedTable for Object {
+    const TABLE_NAME: &'static str = "objects";
+}
+
+impl HasVersion for Object {
+    fn version(&self) -> i64 {
+        self.txn_version
+    }
+}
+
+impl GetTimeStamp for Object {
+    fn get_timestamp(&self) -> chrono::NaiveDateTime {
+        self.block_timestamp
+    }
+}
+
+impl Object {
+    pub fn from_model(object: &v2_objects::Object, block_timestamp: chrono::NaiveDateTime) -> Self {
+        Self {
+            txn_version: object.transaction_version,
+            write_set_change_index: object.write_set_change_index,
+            object_address: object.object_address.clone(),
+            owner_address: object.owner_address.clone(),
+            state_key_hash: object.state_key_hash.clone(),
+            guid_creation_num: object.guid_creation_num.to_string(),
+            allow_ungated_transfer: object.allow_ungated_transfer,
+            is_deleted: object.is_deleted,
+            untransferrable: object.untransferrable,
+            block_timestamp,
+        }
+    }
+}
@@ -0,0 +1,16 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(clippy::extra_unused_lifetimes)]
+
+use crate::schema::processor_errors;
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = processor_errors)]
+/// A transaction the processor skipped because it couldn't be processed, kept for reprocessing
+pub struct ProcessorError {
+    pub processor: String,
+    pub transaction_version: i64,
+    pub error_message: String,
+    pub raw_transaction: serde_json::Value,
+}
@@ -8,3 +8,9 @@ pub mod delegator_pools;
 pub mod proposal_votes;
 pub mod stake_utils;
 pub mod staking_pool_voter;
+
+// parquet models
+pub mod parquet_delegator_activities;
+pub mod parquet_delegator_balances;
+pub mod parquet_delegator_pools;
+pub mod parquet_proposal_votes;
@@ -0,0 +1,56 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::delegator_activities;
+use crate::bq_analytics::generic_parquet_processor::{GetTimeStamp, HasVersion, NamedTable};
+use allocative_derive::Allocative;
+use field_count::FieldCount;
+use parquet_derive::ParquetRecordWriter;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Allocative, Clone, Debug, Default, Deserialize, FieldCount, ParquetRecordWriter, Serialize,
+)]
+pub struct DelegatedStakingActivity {
+    pub txn_version: i64,
+    pub event_index: i64,
+    pub delegator_address: String,
+    pub pool_address: String,
+    pub event_type: String,
+    pub amount: String, // it is a string representation of the numeric
+    #[allocative(skip)]
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl NamedTable for DelegatedStakingActivity {
+    const TABLE_NAME: &'static str = "delegated_staking_activities";
+}
+
+impl HasVersion for DelegatedStakingActivity {
+    fn version(&self) -> i64 {
+        self.txn_version
+    }
+}
+
+impl GetTimeStamp for DelegatedStakingActivity {
+    fn get_timestamp(&self) -> chrono::NaiveDateTime {
+        self.block_timestamp
+    }
+}
+
+impl DelegatedStakingActivity {
+    pub fn from_model(
+        activity: &delegator_activities::DelegatedStakingActivity,
+        block_timestamp: chrono::NaiveDateTime,
+    ) -> Self {
+        Self {
+            txn_version: activity.transaction_version,
+            event_index: activity.event_index,
+            delegator_address: activity.delegator_address.clone(),
+            pool_address: activity.pool_address.clone(),
+            event_type: activity.event_type.clone(),
+            amount: activity.amount.to_string(),
+            block_timestamp,
+        }
+    }
+}
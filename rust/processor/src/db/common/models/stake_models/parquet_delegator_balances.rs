@@ -0,0 +1,60 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::delegator_balances;
+use crate::bq_analytics::generic_parquet_processor::{GetTimeStamp, HasVersion, NamedTable};
+use allocative_derive::Allocative;
+use field_count::FieldCount;
+use parquet_derive::ParquetRecordWriter;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Allocative, Clone, Debug, Default, Deserialize, FieldCount, ParquetRecordWriter, Serialize,
+)]
+pub struct DelegatorBalance {
+    pub txn_version: i64,
+    pub write_set_change_index: i64,
+    pub delegator_address: String,
+    pub pool_address: String,
+    pub pool_type: String,
+    pub table_handle: String,
+    pub shares: String, // it is a string representation of the numeric
+    pub parent_table_handle: String,
+    #[allocative(skip)]
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl NamedTable for DelegatorBalance {
+    const TABLE_NAME: &'static str = "delegator_balances";
+}
+
+impl HasVersion for DelegatorBalance {
+    fn version(&self) -> i64 {
+        self.txn_version
+    }
+}
+
+impl GetTimeStamp for DelegatorBalance {
+    fn get_timestamp(&self) -> chrono::NaiveDateTime {
+        self.block_timestamp
+    }
+}
+
+impl DelegatorBalance {
+    pub fn from_model(
+        balance: &delegator_balances::DelegatorBalance,
+        block_timestamp: chrono::NaiveDateTime,
+    ) -> Self {
+        Self {
+            txn_version: balance.transaction_version,
+            write_set_change_index: balance.write_set_change_index,
+            delegator_address: balance.delegator_address.clone(),
+            pool_address: balance.pool_address.clone(),
+            pool_type: balance.pool_type.clone(),
+            table_handle: balance.table_handle.clone(),
+            shares: balance.shares.to_string(),
+            parent_table_handle: balance.parent_table_handle.clone(),
+            block_timestamp,
+        }
+    }
+}
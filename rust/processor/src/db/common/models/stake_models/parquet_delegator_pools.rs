@@ -0,0 +1,58 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::delegator_pools;
+use crate::bq_analytics::generic_parquet_processor::{GetTimeStamp, HasVersion, NamedTable};
+use allocative_derive::Allocative;
+use field_count::FieldCount;
+use parquet_derive::ParquetRecordWriter;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Allocative, Clone, Debug, Default, Deserialize, FieldCount, ParquetRecordWriter, Serialize,
+)]
+pub struct DelegatorPoolBalance {
+    pub txn_version: i64,
+    pub staking_pool_address: String,
+    pub total_coins: String,  // it is a string representation of the numeric
+    pub total_shares: String, // it is a string representation of the numeric
+    pub operator_commission_percentage: String, // it is a string representation of the numeric
+    pub inactive_table_handle: String,
+    pub active_table_handle: String,
+    #[allocative(skip)]
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl NamedTable for DelegatorPoolBalance {
+    const TABLE_NAME: &'static str = "delegated_staking_pool_balances";
+}
+
+impl HasVersion for DelegatorPoolBalance {
+    fn version(&self) -> i64 {
+        self.txn_version
+    }
+}
+
+impl GetTimeStamp for DelegatorPoolBalance {
+    fn get_timestamp(&self) -> chrono::NaiveDateTime {
+        self.block_timestamp
+    }
+}
+
+impl DelegatorPoolBalance {
+    pub fn from_model(
+        pool_balance: &delegator_pools::DelegatorPoolBalance,
+        block_timestamp: chrono::NaiveDateTime,
+    ) -> Self {
+        Self {
+            txn_version: pool_balance.transaction_version,
+            staking_pool_address: pool_balance.staking_pool_address.clone(),
+            total_coins: pool_balance.total_coins.to_string(),
+            total_shares: pool_balance.total_shares.to_string(),
+            operator_commission_percentage: pool_balance.operator_commission_percentage.to_string(),
+            inactive_table_handle: pool_balance.inactive_table_handle.clone(),
+            active_table_handle: pool_balance.active_table_handle.clone(),
+            block_timestamp,
+        }
+    }
+}
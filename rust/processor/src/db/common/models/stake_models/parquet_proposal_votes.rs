@@ -0,0 +1,53 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::proposal_votes;
+use crate::bq_analytics::generic_parquet_processor::{GetTimeStamp, HasVersion, NamedTable};
+use allocative_derive::Allocative;
+use field_count::FieldCount;
+use parquet_derive::ParquetRecordWriter;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Allocative, Clone, Debug, Default, Deserialize, FieldCount, ParquetRecordWriter, Serialize,
+)]
+pub struct ProposalVote {
+    pub txn_version: i64,
+    pub proposal_id: i64,
+    pub voter_address: String,
+    pub staking_pool_address: String,
+    pub num_votes: String, // it is a string representation of the numeric
+    pub should_pass: bool,
+    #[allocative(skip)]
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl NamedTable for ProposalVote {
+    const TABLE_NAME: &'static str = "proposal_votes";
+}
+
+impl HasVersion for ProposalVote {
+    fn version(&self) -> i64 {
+        self.txn_version
+    }
+}
+
+impl GetTimeStamp for ProposalVote {
+    fn get_timestamp(&self) -> chrono::NaiveDateTime {
+        self.block_timestamp
+    }
+}
+
+impl From<&proposal_votes::ProposalVote> for ProposalVote {
+    fn from(vote: &proposal_votes::ProposalVote) -> Self {
+        Self {
+            txn_version: vote.transaction_version,
+            proposal_id: vote.proposal_id,
+            voter_address: vote.voter_address.clone(),
+            staking_pool_address: vote.staking_pool_address.clone(),
+            num_votes: vote.num_votes.to_string(),
+            should_pass: vote.should_pass,
+            block_timestamp: vote.transaction_timestamp,
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::v2_collections;
+use crate::bq_analytics::generic_parquet_processor::{GetTimeStamp, HasVersion, NamedTable};
+use allocative_derive::Allocative;
+use field_count::FieldCount;
+use parquet_derive::ParquetRecordWriter;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Allocative, Clone, Debug, Default, Deserialize, FieldCount, ParquetRecordWriter, Serialize,
+)]
+pub struct CollectionV2 {
+    pub txn_version: i64,
+    pub write_set_change_index: i64,
+    pub collection_id: String,
+    pub creator_address: String,
+    pub collection_name: String,
+    pub description: String,
+    pub uri: String,
+    pub current_supply: String, // it is a string representation of the numeric
+    pub max_supply: Option<String>, // it is a string representation of the numeric
+    pub total_minted_v2: Option<String>, // it is a string representation of the numeric
+    pub mutable_description: Option<bool>,
+    pub mutable_uri: Option<bool>,
+    pub table_handle_v1: Option<String>,
+    pub collection_properties: Option<String>, // it is a JSON string
+    pub token_standard: String,
+    #[allocative(skip)]
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl NamedTable for CollectionV2 {
+    const TABLE_NAME: &'static str = "collections_v2";
+}
+
+impl HasVersion for CollectionV2 {
+    fn version(&self) -> i64 {
+        self.txn_version
+    }
+}
+
+impl GetTimeStamp for CollectionV2 {
+    fn get_timestamp(&self) -> chrono::NaiveDateTime {
+        self.block_timestamp
+    }
+}
+
+impl From<&v2_collections::CollectionV2> for CollectionV2 {
+    fn from(collection: &v2_collections::CollectionV2) -> Self {
+        Self {
+            txn_version: collection.transaction_version,
+            write_set_change_index: collection.write_set_change_index,
+            collection_id: collection.collection_id.clone(),
+            creator_address: collection.creator_address.clone(),
+            collection_name: collection.collection_name.clone(),
+            description: collection.description.clone(),
+            uri: collection.uri.clone(),
+            current_supply: collection.current_supply.to_string(),
+            max_supply: collection.max_supply.as_ref().map(|v| v.to_string()),
+            total_minted_v2: collection.total_minted_v2.as_ref().map(|v| v.to_string()),
+            mutable_description: collection.mutable_description,
+            mutable_uri: collection.mutable_uri,
+            table_handle_v1: collection.table_handle_v1.clone(),
+            collection_properties: collection
+                .collection_properties
+                .as_ref()
+                .map(|v| v.to_string()),
+            token_standard: collection.token_standard.clone(),
+            block_timestamp: collection.transaction_timestamp,
+        }
+    }
+}
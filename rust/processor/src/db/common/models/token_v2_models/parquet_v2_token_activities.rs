@@ -0,0 +1,69 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::v2_token_activities;
+use crate::bq_analytics::generic_parquet_processor::{GetTimeStamp, HasVersion, NamedTable};
+use allocative_derive::Allocative;
+use field_count::FieldCount;
+use parquet_derive::ParquetRecordWriter;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Allocative, Clone, Debug, Default, Deserialize, FieldCount, ParquetRecordWriter, Serialize,
+)]
+pub struct TokenActivityV2 {
+    pub txn_version: i64,
+    pub event_index: i64,
+    pub event_account_address: String,
+    pub token_data_id: String,
+    pub property_version_v1: String, // it is a string representation of the numeric
+    pub type_: String,
+    pub from_address: Option<String>,
+    pub to_address: Option<String>,
+    pub token_amount: String, // it is a string representation of the numeric
+    pub before_value: Option<String>,
+    pub after_value: Option<String>,
+    pub entry_function_id_str: Option<String>,
+    pub token_standard: String,
+    pub is_fungible_v2: Option<bool>,
+    #[allocative(skip)]
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl NamedTable for TokenActivityV2 {
+    const TABLE_NAME: &'static str = "token_activities_v2";
+}
+
+impl HasVersion for TokenActivityV2 {
+    fn version(&self) -> i64 {
+        self.txn_version
+    }
+}
+
+impl GetTimeStamp for TokenActivityV2 {
+    fn get_timestamp(&self) -> chrono::NaiveDateTime {
+        self.block_timestamp
+    }
+}
+
+impl From<&v2_token_activities::TokenActivityV2> for TokenActivityV2 {
+    fn from(activity: &v2_token_activities::TokenActivityV2) -> Self {
+        Self {
+            txn_version: activity.transaction_version,
+            event_index: activity.event_index,
+            event_account_address: activity.event_account_address.clone(),
+            token_data_id: activity.token_data_id.clone(),
+            property_version_v1: activity.property_version_v1.to_string(),
+            type_: activity.type_.clone(),
+            from_address: activity.from_address.clone(),
+            to_address: activity.to_address.clone(),
+            token_amount: activity.token_amount.to_string(),
+            before_value: activity.before_value.clone(),
+            after_value: activity.after_value.clone(),
+            entry_function_id_str: activity.entry_function_id_str.clone(),
+            token_standard: activity.token_standard.clone(),
+            is_fungible_v2: activity.is_fungible_v2,
+            block_timestamp: activity.transaction_timestamp,
+        }
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::v2_token_datas;
+use crate::bq_analytics::generic_parquet_processor::{GetTimeStamp, HasVersion, NamedTable};
+use allocative_derive::Allocative;
+use field_count::FieldCount;
+use parquet_derive::ParquetRecordWriter;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Allocative, Clone, Debug, Default, Deserialize, FieldCount, ParquetRecordWriter, Serialize,
+)]
+pub struct TokenDataV2 {
+    pub txn_version: i64,
+    pub write_set_change_index: i64,
+    pub token_data_id: String,
+    pub collection_id: String,
+    pub token_name: String,
+    pub maximum: Option<String>, // it is a string representation of the numeric
+    pub supply: Option<String>,  // it is a string representation of the numeric
+    pub largest_property_version_v1: Option<String>, // it is a string representation of the numeric
+    pub token_uri: String,
+    pub token_properties: String, // it is a JSON string
+    pub description: String,
+    pub token_standard: String,
+    pub is_fungible_v2: Option<bool>,
+    #[allocative(skip)]
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl NamedTable for TokenDataV2 {
+    const TABLE_NAME: &'static str = "token_datas_v2";
+}
+
+impl HasVersion for TokenDataV2 {
+    fn version(&self) -> i64 {
+        self.txn_version
+    }
+}
+
+impl GetTimeStamp for TokenDataV2 {
+    fn get_timestamp(&self) -> chrono::NaiveDateTime {
+        self.block_timestamp
+    }
+}
+
+impl From<&v2_token_datas::TokenDataV2> for TokenDataV2 {
+    fn from(token_data: &v2_token_datas::TokenDataV2) -> Self {
+        Self {
+            txn_version: token_data.transaction_version,
+            write_set_change_index: token_data.write_set_change_index,
+            token_data_id: token_data.token_data_id.clone(),
+            collection_id: token_data.collection_id.clone(),
+            token_name: token_data.token_name.clone(),
+            maximum: token_data.maximum.as_ref().map(|v| v.to_string()),
+            supply: token_data.supply.as_ref().map(|v| v.to_string()),
+            largest_property_version_v1: token_data
+                .largest_property_version_v1
+                .as_ref()
+                .map(|v| v.to_string()),
+            token_uri: token_data.token_uri.clone(),
+            token_properties: token_data.token_properties.to_string(),
+            description: token_data.description.clone(),
+            token_standard: token_data.token_standard.clone(),
+            is_fungible_v2: token_data.is_fungible_v2,
+            block_timestamp: token_data.transaction_timestamp,
+        }
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::v2_token_ownerships;
+use crate::bq_analytics::generic_parquet_processor::{GetTimeStamp, HasVersion, NamedTable};
+use allocative_derive::Allocative;
+use field_count::FieldCount;
+use parquet_derive::ParquetRecordWriter;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Allocative, Clone, Debug, Default, Deserialize, FieldCount, ParquetRecordWriter, Serialize,
+)]
+pub struct TokenOwnershipV2 {
+    pub txn_version: i64,
+    pub write_set_change_index: i64,
+    pub token_data_id: String,
+    pub property_version_v1: String, // it is a string representation of the numeric
+    pub owner_address: Option<String>,
+    pub storage_id: String,
+    pub amount: String, // it is a string representation of the numeric
+    pub table_type_v1: Option<String>,
+    pub token_properties_mutated_v1: Option<String>, // it is a JSON string
+    pub is_soulbound_v2: Option<bool>,
+    pub token_standard: String,
+    pub is_fungible_v2: Option<bool>,
+    pub non_transferrable_by_owner: Option<bool>,
+    #[allocative(skip)]
+    pub block_timestamp: chrono::NaiveDateTime,
+}
+
+impl NamedTable for TokenOwnershipV2 {
+    const TABLE_NAME: &'static str = "token_ownerships_v2";
+}
+
+impl HasVersion for TokenOwnershipV2 {
+    fn version(&self) -> i64 {
+        self.txn_version
+    }
+}
+
+impl GetTimeStamp for TokenOwnershipV2 {
+    fn get_timestamp(&self) -> chrono::NaiveDateTime {
+        self.block_timestamp
+    }
+}
+
+impl From<&v2_token_ownerships::TokenOwnershipV2> for TokenOwnershipV2 {
+    fn from(ownership: &v2_token_ownerships::TokenOwnershipV2) -> Self {
+        Self {
+            txn_version: ownership.transaction_version,
+            write_set_change_index: ownership.write_set_change_index,
+            token_data_id: ownership.token_data_id.clone(),
+            property_version_v1: ownership.property_version_v1.to_string(),
+            owner_address: ownership.owner_address.clone(),
+            storage_id: ownership.storage_id.clone(),
+            amount: ownership.amount.to_string(),
+            table_type_v1: ownership.table_type_v1.clone(),
+            token_properties_mutated_v1: ownership
+                .token_properties_mutated_v1
+                .as_ref()
+                .map(|v| v.to_string()),
+            is_soulbound_v2: ownership.is_soulbound_v2,
+            token_standard: ownership.token_standard.clone(),
+            is_fungible_v2: ownership.is_fungible_v2,
+            non_transferrable_by_owner: ownership.non_transferrable_by_owner,
+            block_timestamp: ownership.transaction_timestamp,
+        }
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+// This is required because a diesel macro makes clippy sad
+#![allow(clippy::extra_unused_lifetimes)]
+#![allow(clippy::unused_unit)]
+
+use crate::{
+    db::common::models::default_models::move_resources::MoveResource,
+    schema::{current_tracked_resources, tracked_resources},
+    utils::util::standardize_address,
+};
+use anyhow::ensure;
+use aptos_protos::transaction::v1::MoveStructTag;
+use field_count::FieldCount;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+// PK of current_tracked_resources, i.e. (address, resource_type)
+pub type CurrentTrackedResourcePK = (String, String);
+
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(transaction_version, write_set_change_index))]
+#[diesel(table_name = tracked_resources)]
+pub struct TrackedResource {
+    pub transaction_version: i64,
+    pub write_set_change_index: i64,
+    pub address: String,
+    pub resource_type: String,
+    pub tracker: String,
+    pub fields: Option<serde_json::Value>,
+    pub is_deleted: bool,
+    pub transaction_timestamp: chrono::NaiveDateTime,
+}
+
+#[derive(Clone, Debug, Deserialize, FieldCount, Identifiable, Insertable, Serialize)]
+#[diesel(primary_key(address, resource_type))]
+#[diesel(table_name = current_tracked_resources)]
+pub struct CurrentTrackedResource {
+    pub address: String,
+    pub resource_type: String,
+    pub tracker: String,
+    pub fields: Option<serde_json::Value>,
+    pub is_deleted: bool,
+    pub last_transaction_version: i64,
+    pub last_transaction_timestamp: chrono::NaiveDateTime,
+}
+
+impl TrackedResource {
+    /// `fields` is what was extracted from the resource, and is ignored for deletions
+    pub fn from_move_resource(
+        resource: &MoveResource,
+        tracker: &str,
+        fields: Option<serde_json::Value>,
+        txn_timestamp: chrono::NaiveDateTime,
+    ) -> (Self, CurrentTrackedResource) {
+        let fields = if resource.is_deleted { None } else { fields };
+        (
+            Self {
+                transaction_version: resource.transaction_version,
+                write_set_change_index: resource.write_set_change_index,
+                address: resource.address.clone(),
+                resource_type: resource.type_.clone(),
+                tracker: tracker.to_string(),
+                fields: fields.clone(),
+                is_deleted: resource.is_deleted,
+                transaction_timestamp: txn_timestamp,
+            },
+            CurrentTrackedResource {
+                address: resource.address.clone(),
+                resource_type: resource.type_.clone(),
+                tracker: tracker.to_string(),
+                fields,
+                is_deleted: resource.is_deleted,
+                last_transaction_version: resource.transaction_version,
+                last_transaction_timestamp: txn_timestamp,
+            },
+        )
+    }
+}
+
+impl CurrentTrackedResource {
+    pub fn get_pk(&self) -> CurrentTrackedResourcePK {
+        (self.address.clone(), self.resource_type.clone())
+    }
+}
+
+/// Matches resource types by address, module and struct name. The struct name can be `*` to match
+/// every struct of the module. Without type parameters, all instantiations of a generic struct
+/// match; with them, they have to be written the way they appear in resource types.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceTypePattern {
+    address: String,
+    module: String,
+    name: Option<String>,
+    type_params: Option<String>,
+}
+
+impl ResourceTypePattern {
+    pub fn matches(&self, struct_tag: &MoveStructTag, type_str: &str) -> bool {
+        standardize_address(&struct_tag.address) == self.address
+            && struct_tag.module == self.module
+            && self
+                .name
+                .as_ref()
+                .map_or(true, |name| *name == struct_tag.name)
+            && self.type_params.as_ref().map_or(true, |type_params| {
+                type_str
+                    .split_once('<')
+                    .is_some_and(|(_, rest)| rest.strip_suffix('>') == Some(type_params.as_str()))
+            })
+    }
+}
+
+impl FromStr for ResourceTypePattern {
+    type Err = anyhow::Error;
+
+    fn from_str(pattern: &str) -> anyhow::Result<Self> {
+        let (struct_path, type_params) = match pattern.split_once('<') {
+            Some((struct_path, rest)) => match rest.strip_suffix('>') {
+                Some(type_params) => (struct_path, Some(type_params.to_string())),
+                None => anyhow::bail!("Unclosed type parameters in {}", pattern),
+            },
+            None => (pattern, None),
+        };
+        let parts: Vec<&str> = struct_path.split("::").collect();
+        ensure!(
+            parts.len() == 3 && parts.iter().all(|part| !part.is_empty()),
+            "Resource type {} isn't like 0x1::module::Struct",
+            pattern
+        );
+        let name = match parts[2] {
+            "*" => {
+                ensure!(
+                    type_params.is_none(),
+                    "Resource type {} can't have type parameters with a wildcard",
+                    pattern
+                );
+                None
+            },
+            name => Some(name.to_string()),
+        };
+        Ok(Self {
+            address: standardize_address(parts[0]),
+            module: parts[1].to_string(),
+            name,
+            type_params,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn struct_tag(address: &str, module: &str, name: &str) -> MoveStructTag {
+        MoveStructTag {
+            address: address.to_string(),
+            module: module.to_string(),
+            name: name.to_string(),
+            ..MoveStructTag::default()
+        }
+    }
+
+    #[test]
+    fn test_pattern_matches() {
+        let pool = struct_tag("0xabc", "pool", "Pool");
+        let pool_type = "0xabc::pool::Pool<0x1::aptos_coin::AptosCoin, 0xdef::usdc::USDC>";
+
+        let pattern: ResourceTypePattern = "0xabc::pool::Pool".parse().unwrap();
+        assert!(pattern.matches(&pool, pool_type));
+        assert!(!pattern.matches(&struct_tag("0xabc", "pool", "PoolConfig"), pool_type));
+        assert!(!pattern.matches(&struct_tag("0xabd", "pool", "Pool"), pool_type));
+
+        let pattern: ResourceTypePattern = "0x0abc::pool::*".parse().unwrap();
+        assert!(pattern.matches(&pool, pool_type));
+        assert!(pattern.matches(&struct_tag("0xabc", "pool", "PoolConfig"), pool_type));
+        assert!(!pattern.matches(&struct_tag("0xabc", "router", "Pool"), pool_type));
+
+        let pattern: ResourceTypePattern =
+            "0xabc::pool::Pool<0x1::aptos_coin::AptosCoin, 0xdef::usdc::USDC>"
+                .parse()
+                .unwrap();
+        assert!(pattern.matches(&pool, pool_type));
+        assert!(!pattern.matches(
+            &pool,
+            "0xabc::pool::Pool<0xdef::usdc::USDC, 0x1::aptos_coin::AptosCoin>"
+        ));
+    }
+
+    #[test]
+    fn test_pattern_parse_errors() {
+        assert!("0xabc::pool".parse::<ResourceTypePattern>().is_err());
+        assert!("0xabc::pool::Pool<u64"
+            .parse::<ResourceTypePattern>()
+            .is_err());
+        assert!("0xabc::pool::*<u64>"
+            .parse::<ResourceTypePattern>()
+            .is_err());
+        assert!("0xabc::::Pool".parse::<ResourceTypePattern>().is_err());
+    }
+}
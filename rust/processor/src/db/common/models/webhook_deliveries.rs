@@ -0,0 +1,40 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(clippy::extra_unused_lifetimes)]
+
+use crate::{schema::webhook_deliveries, utils::database::DbPoolConnection};
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+
+pub const DELIVERED: &str = "delivered";
+pub const FAILED: &str = "failed";
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = webhook_deliveries)]
+/// The last delivery attempt of a transaction's webhook notification
+pub struct WebhookDelivery {
+    pub transaction_version: i64,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub error_message: Option<String>,
+    pub last_attempted_at: chrono::NaiveDateTime,
+}
+
+impl WebhookDelivery {
+    /// Versions in the range whose notification was already delivered, e.g. before a restart.
+    pub async fn get_delivered_versions(
+        start_version: i64,
+        end_version: i64,
+        conn: &mut DbPoolConnection<'_>,
+    ) -> diesel::QueryResult<Vec<i64>> {
+        webhook_deliveries::table
+            .select(webhook_deliveries::transaction_version)
+            .filter(webhook_deliveries::transaction_version.between(start_version, end_version))
+            .filter(webhook_deliveries::status.eq(DELIVERED))
+            .load::<i64>(conn)
+            .await
+    }
+}
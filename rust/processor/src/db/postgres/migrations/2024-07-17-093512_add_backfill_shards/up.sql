@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS backfill_shards (
+  processor VARCHAR(50) NOT NULL,
+  shard_start_version BIGINT NOT NULL,
+  shard_end_version BIGINT NOT NULL,
+  shard_status VARCHAR(50) NOT NULL,
+  last_success_version BIGINT,
+  last_transaction_timestamp TIMESTAMP,
+  lease_owner VARCHAR(100),
+  lease_expires_at TIMESTAMP,
+  last_updated TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (processor, shard_start_version)
+);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS processor_errors (
+  processor VARCHAR(50) NOT NULL,
+  transaction_version BIGINT NOT NULL,
+  error_message TEXT NOT NULL,
+  raw_transaction JSONB NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (processor, transaction_version)
+);
+CREATE INDEX IF NOT EXISTS pe_insat_index ON processor_errors (inserted_at);
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+  transaction_version BIGINT NOT NULL,
+  payload JSONB NOT NULL,
+  -- delivered or failed
+  status VARCHAR(20) NOT NULL,
+  attempts INT NOT NULL,
+  response_status INT,
+  error_message TEXT,
+  last_attempted_at TIMESTAMP NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (transaction_version)
+);
+CREATE INDEX IF NOT EXISTS wd_status_index ON webhook_deliveries (status);
+CREATE INDEX IF NOT EXISTS wd_insat_index ON webhook_deliveries (inserted_at);
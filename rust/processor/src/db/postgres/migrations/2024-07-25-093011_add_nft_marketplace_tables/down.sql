@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS nft_marketplace_activities;
+DROP TABLE IF EXISTS current_nft_marketplace_listings;
+DROP TABLE IF EXISTS current_nft_marketplace_token_offers;
+DROP TABLE IF EXISTS current_nft_marketplace_collection_offers;
@@ -0,0 +1,100 @@
+-- Your SQL goes here
+-- Every listing, token offer and collection offer event of the configured marketplaces, including fills
+CREATE TABLE IF NOT EXISTS nft_marketplace_activities (
+  transaction_version BIGINT NOT NULL,
+  event_index BIGINT NOT NULL,
+  marketplace VARCHAR(100) NOT NULL,
+  contract_address VARCHAR(66) NOT NULL,
+  event_type TEXT NOT NULL,
+  -- e.g. listing_placed, token_offer_filled
+  standard_event_type VARCHAR(50) NOT NULL,
+  -- Address of the listing, token offer or collection offer
+  order_id VARCHAR(66) NOT NULL,
+  collection_id VARCHAR(66) NOT NULL,
+  token_data_id VARCHAR(66),
+  creator_address VARCHAR(66) NOT NULL,
+  collection_name VARCHAR(128) NOT NULL,
+  token_name VARCHAR(128),
+  property_version NUMERIC,
+  token_standard VARCHAR(10) NOT NULL,
+  price NUMERIC NOT NULL,
+  token_amount NUMERIC NOT NULL,
+  commission NUMERIC,
+  royalties NUMERIC,
+  seller VARCHAR(66),
+  buyer VARCHAR(66),
+  entry_function_id_str VARCHAR(1000),
+  transaction_timestamp TIMESTAMP NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (transaction_version, event_index)
+);
+CREATE INDEX IF NOT EXISTS nma_order_index ON nft_marketplace_activities (contract_address, order_id);
+CREATE INDEX IF NOT EXISTS nma_token_data_id_index ON nft_marketplace_activities (token_data_id);
+CREATE INDEX IF NOT EXISTS nma_collection_id_index ON nft_marketplace_activities (collection_id);
+CREATE INDEX IF NOT EXISTS nma_insat_index ON nft_marketplace_activities (inserted_at);
+
+CREATE TABLE IF NOT EXISTS current_nft_marketplace_listings (
+  contract_address VARCHAR(66) NOT NULL,
+  listing_id VARCHAR(66) NOT NULL,
+  marketplace VARCHAR(100) NOT NULL,
+  collection_id VARCHAR(66) NOT NULL,
+  token_data_id VARCHAR(66) NOT NULL,
+  token_standard VARCHAR(10) NOT NULL,
+  price NUMERIC NOT NULL,
+  token_amount NUMERIC NOT NULL,
+  seller VARCHAR(66),
+  -- Canceled or filled
+  is_deleted BOOLEAN NOT NULL,
+  entry_function_id_str VARCHAR(1000),
+  last_transaction_version BIGINT NOT NULL,
+  last_transaction_timestamp TIMESTAMP NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (contract_address, listing_id)
+);
+CREATE INDEX IF NOT EXISTS cnml_token_data_id_index ON current_nft_marketplace_listings (token_data_id);
+CREATE INDEX IF NOT EXISTS cnml_collection_price_index ON current_nft_marketplace_listings (collection_id, price);
+CREATE INDEX IF NOT EXISTS cnml_seller_index ON current_nft_marketplace_listings (seller);
+CREATE INDEX IF NOT EXISTS cnml_insat_index ON current_nft_marketplace_listings (inserted_at);
+
+CREATE TABLE IF NOT EXISTS current_nft_marketplace_token_offers (
+  contract_address VARCHAR(66) NOT NULL,
+  offer_id VARCHAR(66) NOT NULL,
+  marketplace VARCHAR(100) NOT NULL,
+  collection_id VARCHAR(66) NOT NULL,
+  token_data_id VARCHAR(66) NOT NULL,
+  token_standard VARCHAR(10) NOT NULL,
+  price NUMERIC NOT NULL,
+  token_amount NUMERIC NOT NULL,
+  buyer VARCHAR(66),
+  -- Canceled or filled
+  is_deleted BOOLEAN NOT NULL,
+  entry_function_id_str VARCHAR(1000),
+  last_transaction_version BIGINT NOT NULL,
+  last_transaction_timestamp TIMESTAMP NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (contract_address, offer_id)
+);
+CREATE INDEX IF NOT EXISTS cnmto_token_data_id_index ON current_nft_marketplace_token_offers (token_data_id);
+CREATE INDEX IF NOT EXISTS cnmto_buyer_index ON current_nft_marketplace_token_offers (buyer);
+CREATE INDEX IF NOT EXISTS cnmto_insat_index ON current_nft_marketplace_token_offers (inserted_at);
+
+CREATE TABLE IF NOT EXISTS current_nft_marketplace_collection_offers (
+  contract_address VARCHAR(66) NOT NULL,
+  collection_offer_id VARCHAR(66) NOT NULL,
+  marketplace VARCHAR(100) NOT NULL,
+  collection_id VARCHAR(66) NOT NULL,
+  token_standard VARCHAR(10) NOT NULL,
+  item_price NUMERIC NOT NULL,
+  remaining_token_amount NUMERIC NOT NULL,
+  buyer VARCHAR(66),
+  -- Canceled or filled completely
+  is_deleted BOOLEAN NOT NULL,
+  entry_function_id_str VARCHAR(1000),
+  last_transaction_version BIGINT NOT NULL,
+  last_transaction_timestamp TIMESTAMP NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (contract_address, collection_offer_id)
+);
+CREATE INDEX IF NOT EXISTS cnmco_collection_price_index ON current_nft_marketplace_collection_offers (collection_id, item_price);
+CREATE INDEX IF NOT EXISTS cnmco_buyer_index ON current_nft_marketplace_collection_offers (buyer);
+CREATE INDEX IF NOT EXISTS cnmco_insat_index ON current_nft_marketplace_collection_offers (inserted_at);
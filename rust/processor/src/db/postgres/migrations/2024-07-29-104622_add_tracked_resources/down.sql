@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS tracked_resources;
+DROP TABLE IF EXISTS current_tracked_resources;
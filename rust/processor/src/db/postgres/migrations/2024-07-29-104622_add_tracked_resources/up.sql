@@ -0,0 +1,33 @@
+-- Your SQL goes here
+-- Every change to a resource matched by the resource tracking processor
+CREATE TABLE IF NOT EXISTS tracked_resources (
+  transaction_version BIGINT NOT NULL,
+  write_set_change_index BIGINT NOT NULL,
+  address VARCHAR(66) NOT NULL,
+  resource_type TEXT NOT NULL,
+  -- Name of the config entry that matched the resource
+  tracker VARCHAR(100) NOT NULL,
+  -- Extracted fields, or the whole resource if no fields are configured. NULL when deleted.
+  fields JSONB,
+  is_deleted BOOLEAN NOT NULL,
+  transaction_timestamp TIMESTAMP NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (transaction_version, write_set_change_index)
+);
+CREATE INDEX IF NOT EXISTS tr_addr_type_index ON tracked_resources (address, resource_type);
+CREATE INDEX IF NOT EXISTS tr_tracker_index ON tracked_resources (tracker);
+CREATE INDEX IF NOT EXISTS tr_insat_index ON tracked_resources (inserted_at);
+
+CREATE TABLE IF NOT EXISTS current_tracked_resources (
+  address VARCHAR(66) NOT NULL,
+  resource_type TEXT NOT NULL,
+  tracker VARCHAR(100) NOT NULL,
+  fields JSONB,
+  is_deleted BOOLEAN NOT NULL,
+  last_transaction_version BIGINT NOT NULL,
+  last_transaction_timestamp TIMESTAMP NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (address, resource_type)
+);
+CREATE INDEX IF NOT EXISTS ctr_tracker_index ON current_tracked_resources (tracker);
+CREATE INDEX IF NOT EXISTS ctr_insat_index ON current_tracked_resources (inserted_at);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS dex_activities;
+DROP TABLE IF EXISTS current_dex_pools;
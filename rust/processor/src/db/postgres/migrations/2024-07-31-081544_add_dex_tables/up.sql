@@ -0,0 +1,57 @@
+-- Your SQL goes here
+-- Swaps and liquidity changes of the DEXes configured in the dex processor
+CREATE TABLE IF NOT EXISTS dex_activities (
+  transaction_version BIGINT NOT NULL,
+  event_index BIGINT NOT NULL,
+  dex VARCHAR(100) NOT NULL,
+  contract_address VARCHAR(66) NOT NULL,
+  event_type TEXT NOT NULL,
+  -- create_pool, swap, add_liquidity or remove_liquidity
+  activity_type VARCHAR(50) NOT NULL,
+  pool_id TEXT NOT NULL,
+  -- Coin types, or fungible asset metadata addresses
+  token_x TEXT,
+  token_y TEXT,
+  -- Change of the pool's reserves, so swaps have one positive and one negative amount
+  amount_x NUMERIC NOT NULL,
+  amount_y NUMERIC NOT NULL,
+  -- Divided by 10^decimals, NULL while the decimals of the token aren't indexed
+  amount_x_normalized NUMERIC,
+  amount_y_normalized NUMERIC,
+  lp_amount NUMERIC,
+  sender VARCHAR(66),
+  entry_function_id_str VARCHAR(1000),
+  transaction_timestamp TIMESTAMP NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (transaction_version, event_index)
+);
+CREATE INDEX IF NOT EXISTS da_dex_pool_index ON dex_activities (dex, pool_id);
+CREATE INDEX IF NOT EXISTS da_sender_index ON dex_activities (sender);
+CREATE INDEX IF NOT EXISTS da_insat_index ON dex_activities (inserted_at);
+
+CREATE TABLE IF NOT EXISTS current_dex_pools (
+  dex VARCHAR(100) NOT NULL,
+  pool_id TEXT NOT NULL,
+  contract_address VARCHAR(66) NOT NULL,
+  token_x TEXT,
+  token_y TEXT,
+  token_x_decimals INT,
+  token_y_decimals INT,
+  reserve_x NUMERIC NOT NULL,
+  reserve_y NUMERIC NOT NULL,
+  reserve_x_normalized NUMERIC,
+  reserve_y_normalized NUMERIC,
+  -- Swapped amounts, summed over both directions
+  volume_x NUMERIC NOT NULL,
+  volume_y NUMERIC NOT NULL,
+  volume_x_normalized NUMERIC,
+  volume_y_normalized NUMERIC,
+  swap_count BIGINT NOT NULL,
+  last_transaction_version BIGINT NOT NULL,
+  last_transaction_timestamp TIMESTAMP NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (dex, pool_id)
+);
+CREATE INDEX IF NOT EXISTS cdp_token_x_index ON current_dex_pools (token_x);
+CREATE INDEX IF NOT EXISTS cdp_token_y_index ON current_dex_pools (token_y);
+CREATE INDEX IF NOT EXISTS cdp_insat_index ON current_dex_pools (inserted_at);
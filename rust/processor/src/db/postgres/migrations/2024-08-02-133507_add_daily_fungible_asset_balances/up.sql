@@ -0,0 +1,17 @@
+-- Your SQL goes here
+-- Balance of each primary store at the end of every UTC day it changed
+CREATE TABLE IF NOT EXISTS daily_fungible_asset_balances (
+  owner_address VARCHAR(66) NOT NULL,
+  asset_type VARCHAR(1000) NOT NULL,
+  date DATE NOT NULL,
+  storage_id VARCHAR(66) NOT NULL,
+  amount NUMERIC NOT NULL,
+  token_standard VARCHAR(10) NOT NULL,
+  -- Last change of the day
+  last_transaction_version BIGINT NOT NULL,
+  last_transaction_timestamp TIMESTAMP NOT NULL,
+  inserted_at TIMESTAMP NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (owner_address, asset_type, date)
+);
+CREATE INDEX IF NOT EXISTS dfab_asset_type_date_index ON daily_fungible_asset_balances (asset_type, date);
+CREATE INDEX IF NOT EXISTS dfab_insat_index ON daily_fungible_asset_balances (inserted_at);
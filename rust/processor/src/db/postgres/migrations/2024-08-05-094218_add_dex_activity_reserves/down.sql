@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE dex_activities
+DROP COLUMN IF EXISTS reserve_x,
+DROP COLUMN IF EXISTS reserve_y;
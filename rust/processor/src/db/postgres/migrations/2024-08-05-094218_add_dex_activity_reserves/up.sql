@@ -0,0 +1,5 @@
+-- Your SQL goes here
+-- Reserves the event reported after it, NULL for events that don't report them
+ALTER TABLE dex_activities
+ADD COLUMN IF NOT EXISTS reserve_x NUMERIC,
+ADD COLUMN IF NOT EXISTS reserve_y NUMERIC;
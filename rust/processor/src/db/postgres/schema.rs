@@ -84,6 +84,39 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    backfill_processor_status (backfill_alias) {
+        #[max_length = 50]
+        backfill_alias -> Varchar,
+        #[max_length = 50]
+        processor -> Varchar,
+        #[max_length = 50]
+        backfill_status -> Varchar,
+        last_success_version -> Int8,
+        last_updated -> Timestamp,
+        last_transaction_timestamp -> Nullable<Timestamp>,
+        backfill_start_version -> Int8,
+        backfill_end_version -> Int8,
+    }
+}
+
+diesel::table! {
+    backfill_shards (processor, shard_start_version) {
+        #[max_length = 50]
+        processor -> Varchar,
+        shard_start_version -> Int8,
+        shard_end_version -> Int8,
+        #[max_length = 50]
+        shard_status -> Varchar,
+        last_success_version -> Nullable<Int8>,
+        last_transaction_timestamp -> Nullable<Timestamp>,
+        #[max_length = 100]
+        lease_owner -> Nullable<Varchar>,
+        lease_expires_at -> Nullable<Timestamp>,
+        last_updated -> Timestamp,
+    }
+}
+
 diesel::table! {
     block_metadata_transactions (version) {
         version -> Int8,
@@ -421,6 +454,32 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    current_dex_pools (dex, pool_id) {
+        #[max_length = 100]
+        dex -> Varchar,
+        pool_id -> Text,
+        #[max_length = 66]
+        contract_address -> Varchar,
+        token_x -> Nullable<Text>,
+        token_y -> Nullable<Text>,
+        token_x_decimals -> Nullable<Int4>,
+        token_y_decimals -> Nullable<Int4>,
+        reserve_x -> Numeric,
+        reserve_y -> Numeric,
+        reserve_x_normalized -> Nullable<Numeric>,
+        reserve_y_normalized -> Nullable<Numeric>,
+        volume_x -> Numeric,
+        volume_y -> Numeric,
+        volume_x_normalized -> Nullable<Numeric>,
+        volume_y_normalized -> Nullable<Numeric>,
+        swap_count -> Int8,
+        last_transaction_version -> Int8,
+        last_transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     current_fungible_asset_balances (storage_id) {
         #[max_length = 66]
@@ -440,6 +499,85 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    current_nft_marketplace_collection_offers (contract_address, collection_offer_id) {
+        #[max_length = 66]
+        contract_address -> Varchar,
+        #[max_length = 66]
+        collection_offer_id -> Varchar,
+        #[max_length = 100]
+        marketplace -> Varchar,
+        #[max_length = 66]
+        collection_id -> Varchar,
+        #[max_length = 10]
+        token_standard -> Varchar,
+        item_price -> Numeric,
+        remaining_token_amount -> Numeric,
+        #[max_length = 66]
+        buyer -> Nullable<Varchar>,
+        is_deleted -> Bool,
+        #[max_length = 1000]
+        entry_function_id_str -> Nullable<Varchar>,
+        last_transaction_version -> Int8,
+        last_transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    current_nft_marketplace_listings (contract_address, listing_id) {
+        #[max_length = 66]
+        contract_address -> Varchar,
+        #[max_length = 66]
+        listing_id -> Varchar,
+        #[max_length = 100]
+        marketplace -> Varchar,
+        #[max_length = 66]
+        collection_id -> Varchar,
+        #[max_length = 66]
+        token_data_id -> Varchar,
+        #[max_length = 10]
+        token_standard -> Varchar,
+        price -> Numeric,
+        token_amount -> Numeric,
+        #[max_length = 66]
+        seller -> Nullable<Varchar>,
+        is_deleted -> Bool,
+        #[max_length = 1000]
+        entry_function_id_str -> Nullable<Varchar>,
+        last_transaction_version -> Int8,
+        last_transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    current_nft_marketplace_token_offers (contract_address, offer_id) {
+        #[max_length = 66]
+        contract_address -> Varchar,
+        #[max_length = 66]
+        offer_id -> Varchar,
+        #[max_length = 100]
+        marketplace -> Varchar,
+        #[max_length = 66]
+        collection_id -> Varchar,
+        #[max_length = 66]
+        token_data_id -> Varchar,
+        #[max_length = 10]
+        token_standard -> Varchar,
+        price -> Numeric,
+        token_amount -> Numeric,
+        #[max_length = 66]
+        buyer -> Nullable<Varchar>,
+        is_deleted -> Bool,
+        #[max_length = 1000]
+        entry_function_id_str -> Nullable<Varchar>,
+        last_transaction_version -> Int8,
+        last_transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     current_objects (object_address) {
         #[max_length = 66]
@@ -651,6 +789,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    current_tracked_resources (address, resource_type) {
+        #[max_length = 66]
+        address -> Varchar,
+        resource_type -> Text,
+        #[max_length = 100]
+        tracker -> Varchar,
+        fields -> Nullable<Jsonb>,
+        is_deleted -> Bool,
+        last_transaction_version -> Int8,
+        last_transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     current_unified_fungible_asset_balances_to_be_renamed (storage_id) {
         #[max_length = 66]
@@ -680,6 +833,24 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    daily_fungible_asset_balances (owner_address, asset_type, date) {
+        #[max_length = 66]
+        owner_address -> Varchar,
+        #[max_length = 1000]
+        asset_type -> Varchar,
+        date -> Date,
+        #[max_length = 66]
+        storage_id -> Varchar,
+        amount -> Numeric,
+        #[max_length = 10]
+        token_standard -> Varchar,
+        last_transaction_version -> Int8,
+        last_transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     delegated_staking_activities (transaction_version, event_index) {
         transaction_version -> Int8,
@@ -738,6 +909,36 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    dex_activities (transaction_version, event_index) {
+        transaction_version -> Int8,
+        event_index -> Int8,
+        #[max_length = 100]
+        dex -> Varchar,
+        #[max_length = 66]
+        contract_address -> Varchar,
+        event_type -> Text,
+        #[max_length = 50]
+        activity_type -> Varchar,
+        pool_id -> Text,
+        token_x -> Nullable<Text>,
+        token_y -> Nullable<Text>,
+        amount_x -> Numeric,
+        amount_y -> Numeric,
+        amount_x_normalized -> Nullable<Numeric>,
+        amount_y_normalized -> Nullable<Numeric>,
+        lp_amount -> Nullable<Numeric>,
+        #[max_length = 66]
+        sender -> Nullable<Varchar>,
+        #[max_length = 1000]
+        entry_function_id_str -> Nullable<Varchar>,
+        transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+        reserve_x -> Nullable<Numeric>,
+        reserve_y -> Nullable<Numeric>,
+    }
+}
+
 diesel::table! {
     event_size_info (transaction_version, index) {
         transaction_version -> Int8,
@@ -896,6 +1097,47 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    nft_marketplace_activities (transaction_version, event_index) {
+        transaction_version -> Int8,
+        event_index -> Int8,
+        #[max_length = 100]
+        marketplace -> Varchar,
+        #[max_length = 66]
+        contract_address -> Varchar,
+        event_type -> Text,
+        #[max_length = 50]
+        standard_event_type -> Varchar,
+        #[max_length = 66]
+        order_id -> Varchar,
+        #[max_length = 66]
+        collection_id -> Varchar,
+        #[max_length = 66]
+        token_data_id -> Nullable<Varchar>,
+        #[max_length = 66]
+        creator_address -> Varchar,
+        #[max_length = 128]
+        collection_name -> Varchar,
+        #[max_length = 128]
+        token_name -> Nullable<Varchar>,
+        property_version -> Nullable<Numeric>,
+        #[max_length = 10]
+        token_standard -> Varchar,
+        price -> Numeric,
+        token_amount -> Numeric,
+        commission -> Nullable<Numeric>,
+        royalties -> Nullable<Numeric>,
+        #[max_length = 66]
+        seller -> Nullable<Varchar>,
+        #[max_length = 66]
+        buyer -> Nullable<Varchar>,
+        #[max_length = 1000]
+        entry_function_id_str -> Nullable<Varchar>,
+        transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     nft_points (transaction_version) {
         transaction_version -> Int8,
@@ -927,6 +1169,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    processor_errors (processor, transaction_version) {
+        #[max_length = 50]
+        processor -> Varchar,
+        transaction_version -> Int8,
+        error_message -> Text,
+        raw_transaction -> Jsonb,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     processor_status (processor) {
         #[max_length = 50]
@@ -1198,6 +1451,22 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    tracked_resources (transaction_version, write_set_change_index) {
+        transaction_version -> Int8,
+        write_set_change_index -> Int8,
+        #[max_length = 66]
+        address -> Varchar,
+        resource_type -> Text,
+        #[max_length = 100]
+        tracker -> Varchar,
+        fields -> Nullable<Jsonb>,
+        is_deleted -> Bool,
+        transaction_timestamp -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     transaction_size_info (transaction_version) {
         transaction_version -> Int8,
@@ -1255,6 +1524,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    webhook_deliveries (transaction_version) {
+        transaction_version -> Int8,
+        payload -> Jsonb,
+        #[max_length = 20]
+        status -> Varchar,
+        attempts -> Int4,
+        response_status -> Nullable<Int4>,
+        error_message -> Nullable<Text>,
+        last_attempted_at -> Timestamp,
+        inserted_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     write_set_changes (transaction_version, index) {
         transaction_version -> Int8,
@@ -1286,6 +1569,8 @@ diesel::allow_tables_to_appear_in_same_query!(
     ans_lookup_v2,
     ans_primary_name,
     ans_primary_name_v2,
+    backfill_processor_status,
+    backfill_shards,
     block_metadata_transactions,
     coin_activities,
     coin_balances,
@@ -1303,7 +1588,11 @@ diesel::allow_tables_to_appear_in_same_query!(
     current_delegated_staking_pool_balances,
     current_delegated_voter,
     current_delegator_balances,
+    current_dex_pools,
     current_fungible_asset_balances,
+    current_nft_marketplace_collection_offers,
+    current_nft_marketplace_listings,
+    current_nft_marketplace_token_offers,
     current_objects,
     current_staking_pool_voter,
     current_table_items,
@@ -1314,11 +1603,14 @@ diesel::allow_tables_to_appear_in_same_query!(
     current_token_pending_claims,
     current_token_royalty_v1,
     current_token_v2_metadata,
+    current_tracked_resources,
     current_unified_fungible_asset_balances_to_be_renamed,
+    daily_fungible_asset_balances,
     delegated_staking_activities,
     delegated_staking_pool_balances,
     delegated_staking_pools,
     delegator_balances,
+    dex_activities,
     event_size_info,
     events,
     fungible_asset_activities,
@@ -1328,8 +1620,10 @@ diesel::allow_tables_to_appear_in_same_query!(
     ledger_infos,
     move_modules,
     move_resources,
+    nft_marketplace_activities,
     nft_points,
     objects,
+    processor_errors,
     processor_status,
     proposal_votes,
     signatures,
@@ -1343,9 +1637,11 @@ diesel::allow_tables_to_appear_in_same_query!(
     token_ownerships,
     token_ownerships_v2,
     tokens,
+    tracked_resources,
     transaction_size_info,
     transactions,
     user_transactions,
+    webhook_deliveries,
     write_set_changes,
     write_set_size_info,
 );
@@ -0,0 +1,106 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::{Display, Formatter, Result as FormatResult};
+
+/// Errors from the fetch → process → gap detection pipeline. These are surfaced to the worker's
+/// supervisor, which decides whether to restart the failing component or give up.
+#[derive(Debug)]
+pub enum PipelineError {
+    /// Could not connect to, or open a stream from, the data service after all retries.
+    GrpcConnectionError(String),
+    /// The stream errored, timed out or ended and reconnecting didn't help.
+    StreamError(String),
+    /// The stream returned a batch that doesn't follow on from the previous one.
+    StreamGap {
+        expected_version: u64,
+        received_version: u64,
+    },
+    /// Reading transactions from a local source failed.
+    LocalSourceError(anyhow::Error),
+    /// The other end of a channel is gone, so there is nothing left to restart.
+    ChannelClosed(&'static str),
+    /// The stream switched to a different chain.
+    ChainIdMismatch { expected: u64, received: u64 },
+    /// Processing a batch kept failing until the failure budget ran out.
+    ProcessingError(anyhow::Error),
+    /// The gap detector received a result it couldn't handle.
+    GapDetectorError(anyhow::Error),
+    /// A parquet handler failed to write or upload a file and stopped.
+    ParquetHandlerError(anyhow::Error),
+    /// Another worker took over the backfill shard this worker was processing.
+    LeaseLost { shard_start_version: u64 },
+}
+
+impl PipelineError {
+    /// Whether restarting the failed component could help. Errors that indicate the rest of
+    /// the pipeline is gone, or that we're on the wrong chain, are not worth retrying.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            PipelineError::GrpcConnectionError(_)
+            | PipelineError::StreamError(_)
+            | PipelineError::StreamGap { .. }
+            | PipelineError::LocalSourceError(_)
+            | PipelineError::ProcessingError(_) => true,
+            PipelineError::ChannelClosed(_)
+            | PipelineError::ChainIdMismatch { .. }
+            | PipelineError::GapDetectorError(_)
+            | PipelineError::ParquetHandlerError(_)
+            | PipelineError::LeaseLost { .. } => false,
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            PipelineError::LocalSourceError(ref err)
+            | PipelineError::ProcessingError(ref err)
+            | PipelineError::GapDetectorError(ref err)
+            | PipelineError::ParquetHandlerError(ref err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl Display for PipelineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FormatResult {
+        match *self {
+            PipelineError::GrpcConnectionError(ref desc) => {
+                write!(f, "GRPC connection error: {}", desc)
+            },
+            PipelineError::StreamError(ref desc) => write!(f, "GRPC stream error: {}", desc),
+            PipelineError::StreamGap {
+                expected_version,
+                received_version,
+            } => write!(
+                f,
+                "Gap in transaction stream: expected version {} but received {}",
+                expected_version, received_version
+            ),
+            PipelineError::LocalSourceError(ref err) => {
+                write!(f, "Local transaction source error: {:?}", err)
+            },
+            PipelineError::ChannelClosed(channel) => write!(f, "{} channel closed", channel),
+            PipelineError::ChainIdMismatch { expected, received } => write!(
+                f,
+                "Chain id changed: expected {} but received {}",
+                expected, received
+            ),
+            PipelineError::ProcessingError(ref err) => write!(f, "Processing error: {:?}", err),
+            PipelineError::GapDetectorError(ref err) => {
+                write!(f, "Gap detector error: {:?}", err)
+            },
+            PipelineError::ParquetHandlerError(ref err) => {
+                write!(f, "Parquet handler error: {:?}", err)
+            },
+            PipelineError::LeaseLost {
+                shard_start_version,
+            } => write!(
+                f,
+                "Lost the lease on the shard starting at version {}",
+                shard_start_version
+            ),
+        }
+    }
+}
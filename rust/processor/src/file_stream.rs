@@ -0,0 +1,319 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Local file-based transaction source. This is an alternative to `grpc_stream` that reads
+//! transactions from a directory instead of the indexer data service, which is useful for
+//! replaying production ranges offline and for deterministic tests of processors.
+
+use crate::{
+    errors::PipelineError,
+    grpc_stream::TransactionsPBResponse,
+    transaction_filter::TransactionFilter,
+    utils::counters::{FETCHER_THREAD_CHANNEL_SIZE, NUM_TRANSACTIONS_FILTERED_OUT_COUNT},
+};
+use anyhow::{Context, Result};
+use aptos_protos::transaction::v1::Transaction;
+use kanal::AsyncSender;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tracing::{error, info};
+
+/// Maximum number of transactions sent to the channel in a single batch. This mirrors the
+/// rough batch size returned by the data service.
+pub const LOCAL_FILE_BATCH_SIZE: usize = 1000;
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocalFileFormat {
+    /// Each file is a sequence of length-delimited `Transaction` protobufs.
+    #[default]
+    Protobuf,
+    /// Each file is a JSON array of `Transaction`s.
+    Json,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LocalFileSourceConfig {
+    /// Directory containing the transaction files. Files are read in lexicographical order
+    /// of their names, so they should be named such that this matches version order.
+    pub path: PathBuf,
+    #[serde(default)]
+    pub format: LocalFileFormat,
+    /// There's no data service to ask, so the chain id the files belong to must be provided.
+    pub chain_id: u64,
+}
+
+/// Reads transactions from the configured directory and sends them to the channel in the same
+/// shape as `grpc_stream::create_fetcher_loop`. Transactions before `starting_version` are
+/// skipped and reading stops after `request_ending_version`. If the files are missing a version
+/// in between we return an error, the same way we would for a gap in the GRPC stream.
+pub async fn create_file_fetcher_loop(
+    txn_sender: AsyncSender<TransactionsPBResponse>,
+    config: LocalFileSourceConfig,
+    starting_version: u64,
+    request_ending_version: Option<u64>,
+    processor_name: String,
+    transaction_filter: TransactionFilter,
+    // The number of transactions per protobuf batch
+    pb_channel_txn_chunk_size: usize,
+    next_version_to_send: &AtomicU64,
+) -> Result<(), PipelineError> {
+    info!(
+        processor_name = processor_name,
+        service_type = crate::worker::PROCESSOR_SERVICE_TYPE,
+        path = config.path.display().to_string(),
+        start_version = starting_version,
+        end_version = request_ending_version,
+        "[Parser] Reading transactions from local files",
+    );
+
+    let files = match list_transaction_files(&config.path) {
+        Ok(files) => files,
+        Err(e) => {
+            error!(
+                processor_name = processor_name,
+                path = config.path.display().to_string(),
+                error = ?e,
+                "[Parser] Failed to list local transaction files"
+            );
+            return Err(PipelineError::LocalSourceError(e));
+        },
+    };
+
+    let batch_size = pb_channel_txn_chunk_size.clamp(1, LOCAL_FILE_BATCH_SIZE);
+    let mut next_version_to_fetch = starting_version;
+    let mut batch: Vec<Transaction> = Vec::with_capacity(batch_size);
+
+    'files: for file in files {
+        let transactions = match read_transactions_from_file(&file, &config.format).await {
+            Ok(transactions) => transactions,
+            Err(e) => {
+                error!(
+                    processor_name = processor_name,
+                    file = file.display().to_string(),
+                    error = ?e,
+                    "[Parser] Failed to read local transaction file"
+                );
+                return Err(PipelineError::LocalSourceError(e));
+            },
+        };
+
+        for txn in transactions {
+            // Either before the starting version or a duplicate across files
+            if txn.version < next_version_to_fetch {
+                continue;
+            }
+            if request_ending_version.is_some_and(|end| txn.version > end) {
+                break 'files;
+            }
+            if txn.version != next_version_to_fetch {
+                error!(
+                    processor_name = processor_name,
+                    file = file.display().to_string(),
+                    expected_version = next_version_to_fetch,
+                    current_fetched_version = txn.version,
+                    "[Parser] Local transaction files have a gap"
+                );
+                return Err(PipelineError::StreamGap {
+                    expected_version: next_version_to_fetch,
+                    received_version: txn.version,
+                });
+            }
+            next_version_to_fetch += 1;
+            batch.push(txn);
+
+            if batch.len() >= batch_size {
+                let txns = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+                send_batch(
+                    &txn_sender,
+                    txns,
+                    config.chain_id,
+                    &transaction_filter,
+                    &processor_name,
+                )
+                .await?;
+                next_version_to_send.store(next_version_to_fetch, Ordering::SeqCst);
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        send_batch(
+            &txn_sender,
+            batch,
+            config.chain_id,
+            &transaction_filter,
+            &processor_name,
+        )
+        .await?;
+        next_version_to_send.store(next_version_to_fetch, Ordering::SeqCst);
+    }
+
+    info!(
+        processor_name = processor_name,
+        service_type = crate::worker::PROCESSOR_SERVICE_TYPE,
+        ending_version = request_ending_version,
+        next_version_to_fetch,
+        "[Parser] Finished reading local transaction files."
+    );
+    Ok(())
+}
+
+async fn send_batch(
+    txn_sender: &AsyncSender<TransactionsPBResponse>,
+    mut transactions: Vec<Transaction>,
+    chain_id: u64,
+    transaction_filter: &TransactionFilter,
+    processor_name: &str,
+) -> Result<(), PipelineError> {
+    // Versions and timestamps are taken before filtering so the gap detector sees the full range
+    let start_version = transactions.first().map(|t| t.version).unwrap_or_default();
+    let end_version = transactions.last().map(|t| t.version).unwrap_or_default();
+    let start_txn_timestamp = transactions.first().and_then(|t| t.timestamp.clone());
+    let end_txn_timestamp = transactions.last().and_then(|t| t.timestamp.clone());
+    let size_in_bytes = transactions.iter().map(|t| t.encoded_len() as u64).sum();
+
+    let num_txns = transactions.len();
+    transactions.retain(|txn| transaction_filter.include(txn));
+    let num_filtered_txns = num_txns - transactions.len();
+
+    if let Err(e) = txn_sender
+        .send(TransactionsPBResponse {
+            transactions,
+            chain_id,
+            start_version,
+            end_version,
+            start_txn_timestamp,
+            end_txn_timestamp,
+            size_in_bytes,
+        })
+        .await
+    {
+        error!(
+            processor_name = processor_name,
+            error = ?e,
+            "[Parser] Error sending local transactions to channel."
+        );
+        return Err(PipelineError::ChannelClosed("Transaction"));
+    }
+
+    FETCHER_THREAD_CHANNEL_SIZE
+        .with_label_values(&[processor_name])
+        .set(txn_sender.len() as i64);
+    NUM_TRANSACTIONS_FILTERED_OUT_COUNT
+        .with_label_values(&[processor_name])
+        .inc_by(num_filtered_txns as u64);
+    Ok(())
+}
+
+/// Reads every transaction in the directory in file order. Unlike `create_file_fetcher_loop`
+/// this loads everything into memory, so it's meant for small fixture sets.
+pub async fn read_transactions_from_dir(
+    path: &Path,
+    format: &LocalFileFormat,
+) -> Result<Vec<Transaction>> {
+    let mut transactions = vec![];
+    for file in list_transaction_files(path)? {
+        transactions.extend(read_transactions_from_file(&file, format).await?);
+    }
+    Ok(transactions)
+}
+
+/// Lists all regular, non-hidden files in the directory sorted by file name.
+fn list_transaction_files(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in std::fs::read_dir(path)
+        .with_context(|| format!("Failed to read directory {}", path.display()))?
+    {
+        let entry = entry?;
+        let is_hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if entry.file_type()?.is_file() && !is_hidden {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+async fn read_transactions_from_file(
+    path: &Path,
+    format: &LocalFileFormat,
+) -> Result<Vec<Transaction>> {
+    let bytes = tokio::fs::read(path).await?;
+    match format {
+        LocalFileFormat::Protobuf => {
+            let mut buf = bytes.as_slice();
+            let mut transactions = vec![];
+            while !buf.is_empty() {
+                transactions.push(
+                    Transaction::decode_length_delimited(&mut buf)
+                        .context("Failed to decode length-delimited transaction")?,
+                );
+            }
+            Ok(transactions)
+        },
+        LocalFileFormat::Json => {
+            serde_json::from_slice(&bytes).context("Failed to parse transactions from JSON")
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_protobuf_file(path: &Path, versions: std::ops::RangeInclusive<u64>) {
+        let mut buf = vec![];
+        for version in versions {
+            Transaction {
+                version,
+                ..Transaction::default()
+            }
+            .encode_length_delimited(&mut buf)
+            .unwrap();
+        }
+        std::fs::write(path, buf).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_file_fetcher_honors_version_range() {
+        let dir = std::env::temp_dir().join(format!("file_stream_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_protobuf_file(&dir.join("00000.pb"), 0..=4);
+        write_protobuf_file(&dir.join("00001.pb"), 5..=9);
+
+        let (sender, receiver) = kanal::bounded_async::<TransactionsPBResponse>(10);
+        let next_version_to_send = AtomicU64::new(3);
+        create_file_fetcher_loop(
+            sender,
+            LocalFileSourceConfig {
+                path: dir.clone(),
+                format: LocalFileFormat::Protobuf,
+                chain_id: 27,
+            },
+            3,
+            Some(7),
+            "test".to_string(),
+            TransactionFilter::default(),
+            2,
+            &next_version_to_send,
+        )
+        .await
+        .unwrap();
+
+        let mut versions = vec![];
+        while let Ok(batch) = receiver.recv().await {
+            assert_eq!(batch.chain_id, 27);
+            assert!(batch.transactions.len() <= 2);
+            versions.extend(batch.transactions.iter().map(|t| t.version));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(versions, vec![3, 4, 5, 6, 7]);
+        assert_eq!(next_version_to_send.load(Ordering::SeqCst), 8);
+    }
+}
@@ -43,7 +43,7 @@ impl GapDetectorTrait for DefaultGapDetector {
                 ))
             },
             _ => {
-                panic!("Invalid result type");
+                anyhow::bail!("Invalid result type");
             },
         }
     }
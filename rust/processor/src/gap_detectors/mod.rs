@@ -1,11 +1,19 @@
 use crate::{
     bq_analytics::ParquetProcessingResult,
+    config::BackfillConfig,
+    db::common::models::backfill_shards::BackfillShardQuery,
+    errors::PipelineError,
     gap_detectors::{
         gap_detector::{DefaultGapDetector, DefaultGapDetectorResult},
         parquet_gap_detector::{ParquetFileGapDetector, ParquetFileGapDetectorResult},
     },
-    processors::{DefaultProcessingResult, Processor, ProcessorTrait},
-    utils::counters::{PARQUET_PROCESSOR_DATA_GAP_COUNT, PROCESSOR_DATA_GAP_COUNT},
+    processors::{write_backfill_status, write_processor_status, DefaultProcessingResult},
+    sharded_backfill::ShardLease,
+    utils::{
+        counters::{PARQUET_PROCESSOR_DATA_GAP_COUNT, PROCESSOR_DATA_GAP_COUNT},
+        database::ArcDbPool,
+        util::parse_timestamp,
+    },
     worker::PROCESSOR_SERVICE_TYPE,
 };
 use anyhow::Result;
@@ -51,15 +59,32 @@ pub trait GapDetectorTrait: Send {
 pub enum ProcessingResult {
     DefaultProcessingResult(DefaultProcessingResult),
     ParquetProcessingResult(ParquetProcessingResult),
+    /// A parquet handler stopped, so the versions it buffered will never be uploaded
+    ParquetHandlerError(anyhow::Error),
+}
+
+/// Where the gap detector records how far the processor has got.
+#[derive(Clone, Debug)]
+pub enum StatusTarget {
+    /// The processor's own row in `processor_status`
+    ProcessorStatus,
+    /// The backfill's row in `backfill_processor_status`
+    Backfill(BackfillConfig),
+    /// A leased shard in `backfill_shards`
+    Shard(ShardLease),
 }
 
+/// Tracks processing results, detects gaps and periodically writes the processor status. Returns
+/// once every sender is gone, after writing the latest status, or with an error if a result
+/// couldn't be handled.
 pub async fn create_gap_detector_status_tracker_loop(
     mut gap_detector: GapDetector,
     gap_detector_receiver: AsyncReceiver<ProcessingResult>,
-    processor: Processor,
+    processor_name: &'static str,
+    db_pool: ArcDbPool,
     gap_detection_batch_size: u64,
-) {
-    let processor_name = processor.name();
+    status_target: StatusTarget,
+) -> Result<(), PipelineError> {
     info!(
         processor_name = processor_name,
         service_type = PROCESSOR_SERVICE_TYPE,
@@ -67,127 +92,204 @@ pub async fn create_gap_detector_status_tracker_loop(
     );
 
     let mut last_update_time = std::time::Instant::now();
+    // Latest status not yet written, either because of the update throttle or a failed write
+    let mut pending_status_update = None;
     loop {
-        match gap_detector_receiver.recv().await {
-            Ok(ProcessingResult::DefaultProcessingResult(result)) => {
-                match gap_detector
-                    .process_versions(ProcessingResult::DefaultProcessingResult(result))
-                {
-                    Ok(res) => {
-                        match res {
-                            GapDetectorResult::DefaultGapDetectorResult(res) => {
-                                PROCESSOR_DATA_GAP_COUNT
-                                    .with_label_values(&[processor_name])
-                                    .set(res.num_gaps as i64);
-                                if res.num_gaps >= gap_detection_batch_size {
-                                    tracing::debug!(
-                                    processor_name,
-                                    gap_start_version = res.next_version_to_process,
-                                    num_gaps = res.num_gaps,
-                                    "[Parser] Processed {gap_detection_batch_size} batches with a gap",
-                                );
-                                    // We don't panic as everything downstream will panic if it doesn't work/receive
-                                }
-                                if let Some(res_last_success_batch) = res.last_success_batch {
-                                    if last_update_time.elapsed().as_secs()
-                                        >= UPDATE_PROCESSOR_STATUS_SECS
-                                    {
-                                        processor
-                                            .update_last_processed_version(
-                                                res_last_success_batch.end_version,
-                                                res_last_success_batch
-                                                    .last_transaction_timestamp
-                                                    .clone(),
-                                            )
-                                            .await
-                                            .unwrap();
-                                        last_update_time = std::time::Instant::now();
-                                    }
-                                }
-                            },
-                            _ => {
-                                panic!("Invalid result type");
-                            },
-                        }
-                    },
-                    Err(e) => {
-                        error!(
+        let result = match gap_detector_receiver.recv().await {
+            Ok(result) => result,
+            Err(e) => {
+                info!(
+                    processor_name,
+                    service_type = PROCESSOR_SERVICE_TYPE,
+                    error = ?e,
+                    "[Parser] Gap detector channel has been closed",
+                );
+                // All senders are gone, so this is the last chance to record progress
+                if let Some((version, last_transaction_timestamp)) = pending_status_update {
+                    if let Err(e) = update_status(
+                        &db_pool,
                         processor_name,
-                        service_type = PROCESSOR_SERVICE_TYPE,
-                        error = ?e,
-                        "[Parser] Gap detector task has panicked"
+                        &status_target,
+                        version,
+                        last_transaction_timestamp,
+                    )
+                    .await
+                    {
+                        error!(
+                            processor_name,
+                            service_type = PROCESSOR_SERVICE_TYPE,
+                            error = ?e,
+                            "[Parser] Failed to write final processor status"
                         );
-                        panic!("[Parser] Gap detector task has panicked: {:?}", e);
-                    },
+                        if let Some(e) = lease_lost(e) {
+                            return Err(e);
+                        }
+                    }
                 }
+                return Ok(());
             },
-            Ok(ProcessingResult::ParquetProcessingResult(result)) => {
+        };
+        match result {
+            ProcessingResult::ParquetProcessingResult(_) => {
                 info!(
                     processor_name,
                     service_type = PROCESSOR_SERVICE_TYPE,
                     "[ParquetGapDetector] received parquet gap detector task",
                 );
-                match gap_detector
-                    .process_versions(ProcessingResult::ParquetProcessingResult(result))
-                {
-                    Ok(res) => {
-                        match res {
-                            GapDetectorResult::ParquetFileGapDetectorResult(res) => {
-                                PARQUET_PROCESSOR_DATA_GAP_COUNT
-                                    .with_label_values(&[processor_name])
-                                    .set(res.num_gaps as i64);
-                                // we need a new gap detection batch size
-                                if res.num_gaps >= gap_detection_batch_size {
-                                    tracing::debug!(
-                                    processor_name,
-                                        gap_start_version = res.next_version_to_process,
-                                        num_gaps = res.num_gaps,
-                                        "[Parser] Processed {gap_detection_batch_size} batches with a gap",
-                                        );
-                                    // We don't panic as everything downstream will panic if it doesn't work/receive
-                                }
+            },
+            // Nothing after the failed upload can be recorded, so stop here and leave the status
+            // at the last version that was uploaded
+            ProcessingResult::ParquetHandlerError(e) => {
+                error!(
+                    processor_name,
+                    service_type = PROCESSOR_SERVICE_TYPE,
+                    error = ?e,
+                    "[ParquetGapDetector] Parquet handler has failed"
+                );
+                return Err(PipelineError::ParquetHandlerError(e));
+            },
+            ProcessingResult::DefaultProcessingResult(_) => {},
+        }
 
-                                if last_update_time.elapsed().as_secs()
-                                    >= UPDATE_PROCESSOR_STATUS_SECS
-                                {
-                                    tracing::info!("Updating last processed version");
-                                    processor
-                                        .update_last_processed_version(
-                                            res.start_version,
-                                            res.last_transaction_timestamp,
-                                        )
-                                        .await
-                                        .unwrap();
-                                    last_update_time = std::time::Instant::now();
-                                } else {
-                                    tracing::info!("Not Updating last processed version");
-                                }
-                            },
-                            _ => {
-                                panic!("Invalid result type");
-                            },
-                        }
+        let res = gap_detector.process_versions(result).map_err(|e| {
+            error!(
+                processor_name,
+                service_type = PROCESSOR_SERVICE_TYPE,
+                error = ?e,
+                "[Parser] Gap detector task has failed"
+            );
+            PipelineError::GapDetectorError(e)
+        })?;
+        match res {
+            GapDetectorResult::DefaultGapDetectorResult(res) => {
+                PROCESSOR_DATA_GAP_COUNT
+                    .with_label_values(&[processor_name])
+                    .set(res.num_gaps as i64);
+                if res.num_gaps >= gap_detection_batch_size {
+                    tracing::debug!(
+                        processor_name,
+                        gap_start_version = res.next_version_to_process,
+                        num_gaps = res.num_gaps,
+                        "[Parser] Processed {gap_detection_batch_size} batches with a gap",
+                    );
+                    // We don't panic as everything downstream will panic if it doesn't work/receive
+                }
+                if let Some(res_last_success_batch) = res.last_success_batch {
+                    pending_status_update = Some((
+                        res_last_success_batch.end_version,
+                        res_last_success_batch.last_transaction_timestamp,
+                    ));
+                }
+            },
+            GapDetectorResult::ParquetFileGapDetectorResult(res) => {
+                PARQUET_PROCESSOR_DATA_GAP_COUNT
+                    .with_label_values(&[processor_name])
+                    .set(res.num_gaps as i64);
+                // we need a new gap detection batch size
+                if res.num_gaps >= gap_detection_batch_size {
+                    tracing::debug!(
+                        processor_name,
+                        gap_start_version = res.next_version_to_process,
+                        num_gaps = res.num_gaps,
+                        "[Parser] Processed {gap_detection_batch_size} batches with a gap",
+                    );
+                    // We don't panic as everything downstream will panic if it doesn't work/receive
+                }
+                pending_status_update = Some((res.start_version, res.last_transaction_timestamp));
+            },
+        }
+
+        if last_update_time.elapsed().as_secs() >= UPDATE_PROCESSOR_STATUS_SECS {
+            if let Some((version, last_transaction_timestamp)) = pending_status_update.clone() {
+                // A failed write is retried with the next result, so it isn't fatal
+                match update_status(
+                    &db_pool,
+                    processor_name,
+                    &status_target,
+                    version,
+                    last_transaction_timestamp,
+                )
+                .await
+                {
+                    Ok(()) => {
+                        last_update_time = std::time::Instant::now();
+                        pending_status_update = None;
                     },
                     Err(e) => {
                         error!(
                             processor_name,
                             service_type = PROCESSOR_SERVICE_TYPE,
                             error = ?e,
-                            "[Parser] Gap detector task has panicked"
+                            "[Parser] Failed to update processor status"
                         );
-                        panic!("[Parser] Gap detector task has panicked: {:?}", e);
+                        // Another worker owns the shard now, so this one has to stop
+                        if let Some(e) = lease_lost(e) {
+                            return Err(e);
+                        }
                     },
                 }
-            },
-            Err(e) => {
-                info!(
-                    processor_name,
-                    service_type = PROCESSOR_SERVICE_TYPE,
-                    error = ?e,
-                    "[Parser] Gap detector channel has been closed",
-                );
-                return;
-            },
-        };
+            }
+        }
+    }
+}
+
+/// The lost lease behind a failed status write, if that's what failed.
+fn lease_lost(error: anyhow::Error) -> Option<PipelineError> {
+    match error.downcast::<PipelineError>() {
+        Ok(e @ PipelineError::LeaseLost { .. }) => Some(e),
+        _ => None,
+    }
+}
+
+/// Backfills record their progress in their own status rows so that they never move the live
+/// processor's `processor_status`.
+async fn update_status(
+    db_pool: &ArcDbPool,
+    processor_name: &'static str,
+    status_target: &StatusTarget,
+    version: u64,
+    last_transaction_timestamp: Option<aptos_protos::util::timestamp::Timestamp>,
+) -> Result<()> {
+    match status_target {
+        StatusTarget::ProcessorStatus => {
+            write_processor_status(
+                db_pool.clone(),
+                processor_name,
+                version,
+                last_transaction_timestamp,
+            )
+            .await
+        },
+        StatusTarget::Backfill(backfill) => {
+            write_backfill_status(
+                db_pool.clone(),
+                processor_name,
+                backfill,
+                version,
+                last_transaction_timestamp,
+            )
+            .await
+        },
+        StatusTarget::Shard(lease) => {
+            let timestamp = last_transaction_timestamp.map(|t| parse_timestamp(&t, version as i64));
+            let mut conn = db_pool.get().await?;
+            let recorded = BackfillShardQuery::record_progress(
+                processor_name,
+                lease.shard_start_version as i64,
+                &lease.lease_owner,
+                lease.lease_duration_secs as i64,
+                version as i64,
+                timestamp,
+                &mut conn,
+            )
+            .await?;
+            if !recorded {
+                return Err(PipelineError::LeaseLost {
+                    shard_start_version: lease.shard_start_version,
+                }
+                .into());
+            }
+            Ok(())
+        },
     }
 }
@@ -34,7 +34,7 @@ impl GapDetectorTrait for ParquetFileGapDetector {
         // Update counts of structures for each transaction version
         let result = match result {
             ProcessingResult::ParquetProcessingResult(r) => r,
-            _ => panic!("Invalid result type"),
+            _ => anyhow::bail!("Invalid result type"),
         };
         for (version, count) in result.txn_version_to_struct_count.iter() {
             if !self.version_counters.contains_key(version) {
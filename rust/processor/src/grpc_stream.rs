@@ -1,10 +1,13 @@
-use crate::utils::{
-    counters::{
-        ProcessorStep, FETCHER_THREAD_CHANNEL_SIZE, LATEST_PROCESSED_VERSION,
-        NUM_TRANSACTIONS_FILTERED_OUT_COUNT, NUM_TRANSACTIONS_PROCESSED_COUNT,
-        PROCESSED_BYTES_COUNT, TRANSACTION_UNIX_TIMESTAMP,
+use crate::{
+    errors::PipelineError,
+    utils::{
+        counters::{
+            ProcessorStep, FETCHER_THREAD_CHANNEL_SIZE, LATEST_PROCESSED_VERSION,
+            NUM_TRANSACTIONS_FILTERED_OUT_COUNT, NUM_TRANSACTIONS_PROCESSED_COUNT,
+            PROCESSED_BYTES_COUNT, TRANSACTION_UNIX_TIMESTAMP,
+        },
+        util::{timestamp_to_iso, timestamp_to_unixtime},
     },
-    util::{timestamp_to_iso, timestamp_to_unixtime},
 };
 use aptos_moving_average::MovingAverage;
 use aptos_protos::{
@@ -17,7 +20,10 @@ use futures_util::StreamExt;
 use itertools::Itertools;
 use kanal::AsyncSender;
 use prost::Message;
-use std::time::Duration;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
 use tokio::time::timeout;
 use tonic::{Response, Streaming};
 use tracing::{debug, error, info};
@@ -79,7 +85,7 @@ pub async fn get_stream(
     ending_version: Option<u64>,
     auth_token: String,
     processor_name: String,
-) -> Response<Streaming<TransactionsResponse>> {
+) -> Result<Response<Streaming<TransactionsResponse>>, PipelineError> {
     info!(
         processor_name = processor_name,
         service_type = crate::worker::PROCESSOR_SERVICE_TYPE,
@@ -146,7 +152,9 @@ pub async fn get_stream(
             },
         }
     }
-    .expect("[Parser] Timeout connecting to GRPC server");
+    .map_err(|e| {
+        PipelineError::GrpcConnectionError(format!("Timeout connecting to GRPC server: {}", e))
+    })?;
 
     let mut rpc_client = match connect_res {
         Ok(client) => client
@@ -165,7 +173,10 @@ pub async fn get_stream(
                 error = ?e,
                 "[Parser] Error connecting to GRPC client"
             );
-            panic!("[Parser] Error connecting to GRPC client");
+            return Err(PipelineError::GrpcConnectionError(format!(
+                "Error connecting to GRPC client: {}",
+                e
+            )));
         },
     };
     let count = ending_version.map(|v| (v as i64 - starting_version as i64 + 1) as u64);
@@ -213,10 +224,15 @@ pub async fn get_stream(
             },
         }
     }
-    .expect("[Parser] Timed out making grpc request after max retries.");
+    .map_err(|e| {
+        PipelineError::GrpcConnectionError(format!(
+            "Timed out making grpc request after max retries: {}",
+            e
+        ))
+    })?;
 
     match stream_res {
-        Ok(stream) => stream,
+        Ok(stream) => Ok(stream),
         Err(e) => {
             error!(
                 processor_name = processor_name,
@@ -227,7 +243,10 @@ pub async fn get_stream(
                 error = ?e,
                 "[Parser] Failed to get grpc response. Is the server running?"
             );
-            panic!("[Parser] Failed to get grpc response. Is the server running?");
+            Err(PipelineError::GrpcConnectionError(format!(
+                "Failed to get grpc response: {}",
+                e
+            )))
         },
     }
 }
@@ -240,7 +259,7 @@ pub async fn get_chain_id(
     auth_token: String,
     processor_name: String,
     starting_version_from_db: u64,
-) -> u64 {
+) -> Result<u64, PipelineError> {
     info!(
         processor_name = processor_name,
         service_type = crate::worker::PROCESSOR_SERVICE_TYPE,
@@ -257,7 +276,7 @@ pub async fn get_chain_id(
         auth_token.clone(),
         processor_name.to_string(),
     )
-    .await;
+    .await?;
     let connection_id = match response.metadata().get(GRPC_CONNECTION_ID) {
         Some(connection_id) => connection_id.to_str().unwrap().to_string(),
         None => "".to_string(),
@@ -272,7 +291,9 @@ pub async fn get_chain_id(
     );
 
     match resp_stream.next().await {
-        Some(Ok(r)) => r.chain_id.expect("[Parser] Chain Id doesn't exist."),
+        Some(Ok(r)) => r
+            .chain_id
+            .ok_or_else(|| PipelineError::StreamError("Chain id doesn't exist".to_string())),
         Some(Err(rpc_error)) => {
             error!(
                 processor_name = processor_name,
@@ -282,7 +303,10 @@ pub async fn get_chain_id(
                 error = ?rpc_error,
                 "[Parser] Error receiving datastream response for chain id"
             );
-            panic!("[Parser] Error receiving datastream response for chain id");
+            Err(PipelineError::StreamError(format!(
+                "Error receiving datastream response for chain id: {}",
+                rpc_error
+            )))
         },
         None => {
             error!(
@@ -292,7 +316,9 @@ pub async fn get_chain_id(
                 connection_id,
                 "[Parser] Stream ended before getting response fo for chain id"
             );
-            panic!("[Parser] Stream ended before getting response fo for chain id");
+            Err(PipelineError::StreamError(
+                "Stream ended before getting response for chain id".to_string(),
+            ))
         },
     }
 }
@@ -300,9 +326,11 @@ pub async fn get_chain_id(
 /// Gets a batch of transactions from the stream. Batch size is set in the grpc server.
 /// The number of batches depends on our config
 /// There could be several special scenarios:
-/// 1. If we lose the connection, we will try reconnecting X times within Y seconds before crashing.
+/// 1. If we lose the connection, we will try reconnecting X times within Y seconds before returning an error.
 /// 2. If we specified an end version and we hit that, we will stop fetching, but we will make sure that
 ///    all existing transactions are processed
+/// `next_version_to_send` is advanced after every batch sent to the channel, so that a supervisor
+/// can restart the loop from where it stopped.
 pub async fn create_fetcher_loop(
     txn_sender: AsyncSender<TransactionsPBResponse>,
     indexer_grpc_data_service_address: Url,
@@ -318,7 +346,8 @@ pub async fn create_fetcher_loop(
     // The number of transactions per protobuf batch
     pb_channel_txn_chunk_size: usize,
     sleep_time_between_request: u64,
-) {
+    next_version_to_send: &AtomicU64,
+) -> Result<(), PipelineError> {
     info!(
         processor_name = processor_name,
         service_type = crate::worker::PROCESSOR_SERVICE_TYPE,
@@ -337,7 +366,7 @@ pub async fn create_fetcher_loop(
         auth_token.clone(),
         processor_name.to_string(),
     )
-    .await;
+    .await?;
     let mut connection_id = match response.metadata().get(GRPC_CONNECTION_ID) {
         Some(connection_id) => connection_id.to_str().unwrap().to_string(),
         None => "".to_string(),
@@ -390,7 +419,9 @@ pub async fn create_fetcher_loop(
                         next_version_to_fetch = end_version + 1;
 
                         let size_in_bytes = r.encoded_len() as u64;
-                        let chain_id: u64 = r.chain_id.expect("[Parser] Chain Id doesn't exist.");
+                        let chain_id: u64 = r.chain_id.ok_or_else(|| {
+                            PipelineError::StreamError("Chain id doesn't exist".to_string())
+                        })?;
                         let num_txns = r.transactions.len();
                         let duration_in_secs = grpc_channel_recv_latency.elapsed().as_secs_f64();
                         fetch_ma.tick_now(num_txns as u64);
@@ -439,7 +470,10 @@ pub async fn create_fetcher_loop(
                                 current_fetched_version = start_version,
                                 "[Parser] Received batch with gap from GRPC stream"
                             );
-                            return;
+                            return Err(PipelineError::StreamGap {
+                                expected_version: (last_fetched_version + 1) as u64,
+                                received_version: start_version,
+                            });
                         }
                         last_fetched_version = end_version as i64;
 
@@ -486,7 +520,7 @@ pub async fn create_fetcher_loop(
                                         error = ?e,
                                         "[Parser] Error sending GRPC response to channel."
                                     );
-                                    return;
+                                    return Err(PipelineError::ChannelClosed("Transaction"));
                                 },
                             }
                         } else {
@@ -523,12 +557,14 @@ pub async fn create_fetcher_loop(
                                             error = ?e,
                                             "[Parser] Error sending GRPC response to channel."
                                         );
-                                        return;
+                                        return Err(PipelineError::ChannelClosed("Transaction"));
                                     },
                                 }
                             }
                         }
 
+                        next_version_to_send.store(end_version + 1, Ordering::SeqCst);
+
                         let duration_in_secs = txn_channel_send_latency.elapsed().as_secs_f64();
                         send_ma.tick_now(num_txns as u64);
                         let tps = send_ma.avg().ceil() as u64;
@@ -661,7 +697,10 @@ pub async fn create_fetcher_loop(
                     stream_address = indexer_grpc_data_service_address.to_string(),
                     "[Parser] Reconnected more than {RECONNECTION_MAX_RETRIES} times. Will not retry.",
                 );
-                return;
+                return Err(PipelineError::StreamError(format!(
+                    "Reconnected more than {} times",
+                    RECONNECTION_MAX_RETRIES
+                )));
             }
             reconnection_retries += 1;
             info!(
@@ -683,7 +722,7 @@ pub async fn create_fetcher_loop(
                 auth_token.clone(),
                 processor_name.to_string(),
             )
-            .await;
+            .await?;
             connection_id = match response.metadata().get(GRPC_CONNECTION_ID) {
                 Some(connection_id) => connection_id.to_str().unwrap().to_string(),
                 None => "".to_string(),
@@ -701,4 +740,5 @@ pub async fn create_fetcher_loop(
             );
         }
     }
+    Ok(())
 }
@@ -16,16 +16,26 @@ extern crate canonical_json;
 extern crate parquet;
 extern crate parquet_derive;
 
-pub use config::IndexerGrpcProcessorConfig;
+pub use config::{
+    BackfillConfig, IndexerGrpcProcessorConfig, ShardedBackfillConfig, TransactionSourceConfig,
+};
 
 pub mod bq_analytics;
+pub mod cli;
 mod config;
 mod db;
+pub mod errors;
+pub mod file_stream;
 pub mod gap_detectors;
 pub mod grpc_stream;
 pub mod processors;
+pub mod reprocess;
+pub mod rollback;
 #[path = "db/postgres/schema.rs"]
 pub mod schema;
+pub mod sharded_backfill;
+pub mod sinks;
 pub mod transaction_filter;
 pub mod utils;
+pub mod verify_consistency;
 pub mod worker;
@@ -3,8 +3,7 @@
 
 use anyhow::Result;
 use clap::Parser;
-use processor::IndexerGrpcProcessorConfig;
-use server_framework::ServerArgs;
+use processor::cli::ProcessorArgs;
 
 #[cfg(unix)]
 #[global_allocator]
@@ -28,8 +27,7 @@ fn main() -> Result<()> {
         .build()
         .unwrap()
         .block_on(async {
-            let args = ServerArgs::parse();
-            args.run::<IndexerGrpcProcessorConfig>(tokio::runtime::Handle::current())
-                .await
+            let args = ProcessorArgs::parse();
+            args.run(tokio::runtime::Handle::current()).await
         })
 }
@@ -380,9 +380,9 @@ impl ProcessorTrait for AnsProcessor {
             mut all_ans_lookups,
             mut all_current_ans_primary_names,
             mut all_ans_primary_names,
-            all_current_ans_lookups_v2,
-            all_ans_lookups_v2,
-            all_current_ans_primary_names_v2,
+            mut all_current_ans_lookups_v2,
+            mut all_ans_lookups_v2,
+            mut all_current_ans_primary_names_v2,
             mut all_ans_primary_names_v2,
         ) = parse_ans(
             &transactions,
@@ -421,6 +421,21 @@ impl ProcessorTrait for AnsProcessor {
         {
             all_current_ans_primary_names.clear();
         }
+        if self.deprecated_tables.contains(TableFlags::ANS_LOOKUP_V2) {
+            all_ans_lookups_v2.clear();
+        }
+        if self
+            .deprecated_tables
+            .contains(TableFlags::CURRENT_ANS_LOOKUP_V2)
+        {
+            all_current_ans_lookups_v2.clear();
+        }
+        if self
+            .deprecated_tables
+            .contains(TableFlags::CURRENT_ANS_PRIMARY_NAME_V2)
+        {
+            all_current_ans_primary_names_v2.clear();
+        }
 
         // Insert values to db
         let tx_result = insert_to_db(
@@ -470,7 +485,7 @@ impl ProcessorTrait for AnsProcessor {
     }
 }
 
-fn parse_ans(
+pub fn parse_ans(
     transactions: &[Transaction],
     ans_v1_primary_names_table_handle: String,
     ans_v1_name_records_table_handle: String,
@@ -13,6 +13,7 @@ use crate::{
     },
     gap_detectors::ProcessingResult,
     schema,
+    sinks::{write_to_sinks, ProcessorOutput, Sink, SinkConfig, TableRows},
     utils::database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
 };
 use ahash::AHashMap;
@@ -24,19 +25,28 @@ use diesel::{
     query_builder::QueryFragment,
     ExpressionMethods,
 };
-use std::fmt::Debug;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, sync::Arc};
 use tracing::error;
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CoinProcessorConfig {
+    // Where rows are written, Postgres if empty
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
+}
+
 pub struct CoinProcessor {
     connection_pool: ArcDbPool,
-    per_table_chunk_sizes: AHashMap<String, usize>,
+    sinks: Arc<Vec<Sink>>,
 }
 
 impl CoinProcessor {
-    pub fn new(connection_pool: ArcDbPool, per_table_chunk_sizes: AHashMap<String, usize>) -> Self {
+    pub fn new(connection_pool: ArcDbPool, sinks: Arc<Vec<Sink>>) -> Self {
         Self {
             connection_pool,
-            per_table_chunk_sizes,
+            sinks,
         }
     }
 }
@@ -52,57 +62,80 @@ impl Debug for CoinProcessor {
     }
 }
 
-async fn insert_to_db(
-    conn: ArcDbPool,
-    name: &'static str,
-    start_version: u64,
-    end_version: u64,
-    coin_activities: &[CoinActivity],
-    coin_infos: &[CoinInfo],
-    coin_balances: &[CoinBalance],
-    current_coin_balances: &[CurrentCoinBalance],
-    per_table_chunk_sizes: &AHashMap<String, usize>,
-) -> Result<(), diesel::result::Error> {
-    tracing::trace!(
-        name = name,
-        start_version = start_version,
-        end_version = end_version,
-        "Inserting to db",
-    );
+pub struct CoinProcessorOutput {
+    pub coin_activities: Vec<CoinActivity>,
+    pub coin_infos: Vec<CoinInfo>,
+    pub coin_balances: Vec<CoinBalance>,
+    pub current_coin_balances: Vec<CurrentCoinBalance>,
+}
 
-    let ca = execute_in_chunks(
-        conn.clone(),
-        insert_coin_activities_query,
-        coin_activities,
-        get_config_table_chunk_size::<CoinActivity>("coin_activities", per_table_chunk_sizes),
-    );
-    let ci = execute_in_chunks(
-        conn.clone(),
-        insert_coin_infos_query,
-        coin_infos,
-        get_config_table_chunk_size::<CoinInfo>("coin_infos", per_table_chunk_sizes),
-    );
-    let cb = execute_in_chunks(
-        conn.clone(),
-        insert_coin_balances_query,
-        coin_balances,
-        get_config_table_chunk_size::<CoinBalance>("coin_balances", per_table_chunk_sizes),
-    );
-    let ccb = execute_in_chunks(
-        conn.clone(),
-        insert_current_coin_balances_query,
-        current_coin_balances,
-        get_config_table_chunk_size::<CurrentCoinBalance>(
-            "current_coin_balances",
-            per_table_chunk_sizes,
-        ),
-    );
+#[async_trait]
+impl ProcessorOutput for CoinProcessorOutput {
+    async fn insert_to_db(
+        &self,
+        conn: ArcDbPool,
+        per_table_chunk_sizes: &AHashMap<String, usize>,
+    ) -> Result<(), diesel::result::Error> {
+        let ca = execute_in_chunks(
+            conn.clone(),
+            insert_coin_activities_query,
+            &self.coin_activities,
+            get_config_table_chunk_size::<CoinActivity>("coin_activities", per_table_chunk_sizes),
+        );
+        let ci = execute_in_chunks(
+            conn.clone(),
+            insert_coin_infos_query,
+            &self.coin_infos,
+            get_config_table_chunk_size::<CoinInfo>("coin_infos", per_table_chunk_sizes),
+        );
+        let cb = execute_in_chunks(
+            conn.clone(),
+            insert_coin_balances_query,
+            &self.coin_balances,
+            get_config_table_chunk_size::<CoinBalance>("coin_balances", per_table_chunk_sizes),
+        );
+        let ccb = execute_in_chunks(
+            conn.clone(),
+            insert_current_coin_balances_query,
+            &self.current_coin_balances,
+            get_config_table_chunk_size::<CurrentCoinBalance>(
+                "current_coin_balances",
+                per_table_chunk_sizes,
+            ),
+        );
 
-    let (ca_res, ci_res, cb_res, ccb_res) = tokio::join!(ca, ci, cb, ccb);
-    for res in [ca_res, ci_res, cb_res, ccb_res] {
-        res?;
+        let (ca_res, ci_res, cb_res, ccb_res) = tokio::join!(ca, ci, cb, ccb);
+        for res in [ca_res, ci_res, cb_res, ccb_res] {
+            res?;
+        }
+        Ok(())
+    }
+
+    fn table_rows(&self) -> anyhow::Result<Vec<TableRows>> {
+        Ok(vec![
+            TableRows::new(
+                "coin_activities",
+                &[
+                    "transaction_version",
+                    "event_account_address",
+                    "event_creation_number",
+                    "event_sequence_number",
+                ],
+                &self.coin_activities,
+            )?,
+            TableRows::new("coin_infos", &["coin_type_hash"], &self.coin_infos)?,
+            TableRows::new(
+                "coin_balances",
+                &["transaction_version", "owner_address", "coin_type_hash"],
+                &self.coin_balances,
+            )?,
+            TableRows::new(
+                "current_coin_balances",
+                &["owner_address", "coin_type_hash"],
+                &self.current_coin_balances,
+            )?,
+        ])
     }
-    Ok(())
 }
 
 fn insert_coin_activities_query(
@@ -262,18 +295,19 @@ impl ProcessorTrait for CoinProcessor {
         let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
         let db_insertion_start = std::time::Instant::now();
 
-        let tx_result = insert_to_db(
-            self.get_pool(),
-            self.name(),
-            start_version,
-            end_version,
-            &all_coin_activities,
-            &all_coin_infos,
-            &all_coin_balances,
-            &all_current_coin_balances,
-            &self.per_table_chunk_sizes,
-        )
-        .await;
+        tracing::trace!(
+            name = self.name(),
+            start_version = start_version,
+            end_version = end_version,
+            "Writing to sinks",
+        );
+        let output = CoinProcessorOutput {
+            coin_activities: all_coin_activities,
+            coin_infos: all_coin_infos,
+            coin_balances: all_coin_balances,
+            current_coin_balances: all_current_coin_balances,
+        };
+        let tx_result = write_to_sinks(&self.sinks, &output, start_version, end_version).await;
 
         let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
 
@@ -0,0 +1,640 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{DefaultProcessingResult, ProcessorName, ProcessorTrait};
+use crate::{
+    gap_detectors::ProcessingResult,
+    utils::{
+        database::{ArcDbPool, DbPoolConnection},
+        util::{get_json_path, parse_timestamp, standardize_address, standardize_event_type},
+    },
+};
+use ahash::AHashSet;
+use anyhow::{bail, ensure, Context};
+use aptos_protos::transaction::v1::{transaction::TxnData, Event, Transaction};
+use async_trait::async_trait;
+use bigdecimal::BigDecimal;
+use diesel::{
+    sql_query,
+    sql_types::{Jsonb, Text},
+    QueryableByName,
+};
+use diesel_async::RunQueryDsl;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{fmt::Debug, str::FromStr};
+use tracing::{error, info};
+
+/// Columns every custom event table has besides the configured ones
+const BASE_COLUMNS: [&str; 4] = [
+    "transaction_version",
+    "event_index",
+    "transaction_timestamp",
+    "inserted_at",
+];
+
+/// Postgres truncates longer identifiers
+const MAX_IDENTIFIER_LENGTH: usize = 63;
+
+// Lowercase so quoted and unquoted references name the same table, and short enough that
+// Postgres doesn't truncate it
+static IDENTIFIER: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-z_][a-z0-9_]{0,62}$").unwrap());
+
+/// Tables of the migrations, read from the diesel schema so that new tables are covered as well.
+/// `CREATE TABLE IF NOT EXISTS` would silently reuse them.
+static SCHEMA_TABLES: Lazy<AHashSet<&'static str>> = Lazy::new(|| {
+    let schema = include_str!("../db/postgres/schema.rs");
+    let mut tables: AHashSet<&str> = schema
+        .split("diesel::table! {")
+        .skip(1)
+        .filter_map(|table| table.split_whitespace().next())
+        .collect();
+    tables.insert("__diesel_schema_migrations");
+    tables
+});
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomEventProcessorConfig {
+    pub tables: Vec<EventTableConfig>,
+}
+
+/// A table holding one row per event of a type
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventTableConfig {
+    pub name: String,
+    // A full type, or a type without generic parameters to store all instantiations
+    pub event_type: String,
+    pub columns: Vec<EventColumnConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventColumnConfig {
+    pub name: String,
+    // Dot separated fields of the event data, with numbers indexing into arrays, e.g.
+    // `metadata.inner` or `amounts.0`. Missing fields are stored as NULL.
+    pub path: String,
+    #[serde(rename = "type")]
+    pub column_type: EventColumnType,
+    #[serde(default)]
+    pub index: bool,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventColumnType {
+    /// Standardized to 0x followed by 64 hex characters. Object references are unwrapped.
+    Address,
+    Bigint,
+    Boolean,
+    Jsonb,
+    /// For u64 and larger integers
+    Numeric,
+    /// Strings as they are, anything else as its JSON
+    Text,
+}
+
+impl EventColumnType {
+    fn sql_type(&self) -> &'static str {
+        match self {
+            EventColumnType::Address => "VARCHAR(66)",
+            EventColumnType::Bigint => "BIGINT",
+            EventColumnType::Boolean => "BOOLEAN",
+            EventColumnType::Jsonb => "JSONB",
+            EventColumnType::Numeric => "NUMERIC",
+            EventColumnType::Text => "TEXT",
+        }
+    }
+
+    /// Converts a field of the event data to the JSON that `jsonb_to_recordset` reads the column
+    /// from, failing if the field doesn't fit the column.
+    fn convert(&self, value: &Value) -> anyhow::Result<Value> {
+        Ok(match (self, value) {
+            (_, Value::Null) => Value::Null,
+            (EventColumnType::Address, Value::String(address)) => {
+                Value::String(standardize_address(address))
+            },
+            (EventColumnType::Address, Value::Object(object)) => match object.get("inner") {
+                Some(Value::String(address)) => Value::String(standardize_address(address)),
+                _ => bail!("{} isn't an address", value),
+            },
+            (EventColumnType::Bigint, Value::String(number)) => Value::from(
+                number
+                    .parse::<i64>()
+                    .with_context(|| format!("{} isn't a bigint", value))?,
+            ),
+            (EventColumnType::Bigint, Value::Number(number)) if number.is_i64() => value.clone(),
+            (EventColumnType::Boolean, Value::Bool(_)) => value.clone(),
+            (EventColumnType::Boolean, Value::String(boolean)) => Value::Bool(
+                boolean
+                    .parse::<bool>()
+                    .with_context(|| format!("{} isn't a boolean", value))?,
+            ),
+            (EventColumnType::Jsonb, _) => value.clone(),
+            // Kept as a string, since u128 and u256 values don't fit a JSON number
+            (EventColumnType::Numeric, Value::String(number)) => Value::String(
+                BigDecimal::from_str(number)
+                    .with_context(|| format!("{} isn't numeric", value))?
+                    .to_string(),
+            ),
+            (EventColumnType::Numeric, Value::Number(number)) => Value::String(number.to_string()),
+            (EventColumnType::Text, Value::String(_)) => value.clone(),
+            (EventColumnType::Text, _) => Value::String(value.to_string()),
+            (column_type, _) => bail!("{} can't be stored as {:?}", value, column_type),
+        })
+    }
+}
+
+impl CustomEventProcessorConfig {
+    /// Table and column names end up in SQL, so only plain identifiers are allowed
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            !self.tables.is_empty(),
+            "Custom event processor needs at least one table"
+        );
+        let mut table_names = AHashSet::new();
+        for table in &self.tables {
+            ensure!(
+                IDENTIFIER.is_match(&table.name),
+                "Table name {} must be lowercase letters, digits and underscores",
+                table.name
+            );
+            ensure!(
+                table_names.insert(table.name.as_str()),
+                "Table {} is configured more than once",
+                table.name
+            );
+            ensure!(
+                !SCHEMA_TABLES.contains(table.name.as_str()),
+                "Table {} is already a table of the processors",
+                table.name
+            );
+            ensure!(
+                table.event_type.contains("::"),
+                "Table {} needs an event type like 0x1::module::Event, got {}",
+                table.name,
+                table.event_type
+            );
+            ensure!(
+                !table.columns.is_empty(),
+                "Table {} needs at least one column",
+                table.name
+            );
+            let mut column_names = AHashSet::from(BASE_COLUMNS);
+            for column in &table.columns {
+                ensure!(
+                    IDENTIFIER.is_match(&column.name),
+                    "Column name {} of table {} must be lowercase letters, digits and underscores",
+                    column.name,
+                    table.name
+                );
+                ensure!(
+                    column_names.insert(column.name.as_str()),
+                    "Column {} of table {} is reserved or configured more than once",
+                    column.name,
+                    table.name
+                );
+                ensure!(
+                    !column.path.is_empty(),
+                    "Column {} of table {} needs a path",
+                    column.name,
+                    table.name
+                );
+                ensure!(
+                    !column.index || table.index_name(column).len() <= MAX_IDENTIFIER_LENGTH,
+                    "Index {} is longer than {} characters, shorten the table or column name",
+                    table.index_name(column),
+                    MAX_IDENTIFIER_LENGTH
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the configured tables that don't exist yet. Existing tables are never altered,
+    /// so columns added to the config later have to be added to the table by hand.
+    pub async fn create_tables(&self, pool: &ArcDbPool) -> anyhow::Result<()> {
+        let mut conn = pool.get().await?;
+        for table in &self.tables {
+            for statement in table.create_statements() {
+                sql_query(statement).execute(&mut conn).await?;
+            }
+            let existing_columns: AHashSet<String> = get_table_columns(&table.name, &mut conn)
+                .await?
+                .into_iter()
+                .collect();
+            let missing_columns: Vec<&str> = BASE_COLUMNS
+                .into_iter()
+                .chain(table.columns.iter().map(|column| column.name.as_str()))
+                .filter(|column| !existing_columns.contains(*column))
+                .collect();
+            ensure!(
+                missing_columns.is_empty(),
+                "Table {} already exists without columns {}",
+                table.name,
+                missing_columns.join(", ")
+            );
+            info!(
+                table = table.name.as_str(),
+                event_type = table.event_type.as_str(),
+                "[Custom Event] Table is ready"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(QueryableByName)]
+struct ColumnName {
+    #[diesel(sql_type = Text)]
+    column_name: String,
+}
+
+async fn get_table_columns(
+    table_name: &str,
+    conn: &mut DbPoolConnection<'_>,
+) -> diesel::QueryResult<Vec<String>> {
+    let columns: Vec<ColumnName> = sql_query(
+        "SELECT column_name::TEXT AS column_name FROM information_schema.columns \
+         WHERE table_schema = current_schema() AND table_name = $1",
+    )
+    .bind::<Text, _>(table_name)
+    .load(conn)
+    .await?;
+    Ok(columns.into_iter().map(|c| c.column_name).collect())
+}
+
+impl EventTableConfig {
+    fn create_statements(&self) -> Vec<String> {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| format!("\"{}\" {}", column.name, column.column_type.sql_type()))
+            .collect();
+        let mut statements = vec![format!(
+            "CREATE TABLE IF NOT EXISTS \"{}\" (\
+             transaction_version BIGINT NOT NULL, \
+             event_index BIGINT NOT NULL, \
+             {}, \
+             transaction_timestamp TIMESTAMP NOT NULL, \
+             inserted_at TIMESTAMP NOT NULL DEFAULT NOW(), \
+             PRIMARY KEY (transaction_version, event_index))",
+            self.name,
+            columns.join(", ")
+        )];
+        for column in self.columns.iter().filter(|column| column.index) {
+            statements.push(format!(
+                "CREATE INDEX IF NOT EXISTS \"{}\" ON \"{}\" (\"{}\")",
+                self.index_name(column),
+                self.name,
+                column.name
+            ));
+        }
+        statements
+    }
+
+    fn index_name(&self, column: &EventColumnConfig) -> String {
+        format!("{}_{}_index", self.name, column.name)
+    }
+
+    /// Inserts rows given as a JSON array in one statement, whatever the columns are. Rows that
+    /// are already there are skipped, so reprocessing a batch is harmless.
+    fn insert_statement(&self) -> String {
+        let names: Vec<String> = [
+            "transaction_version",
+            "event_index",
+            "transaction_timestamp",
+        ]
+        .into_iter()
+        .map(String::from)
+        .chain(
+            self.columns
+                .iter()
+                .map(|column| format!("\"{}\"", column.name)),
+        )
+        .collect();
+        let types: Vec<String> = [
+            "transaction_version BIGINT",
+            "event_index BIGINT",
+            "transaction_timestamp TIMESTAMP",
+        ]
+        .into_iter()
+        .map(String::from)
+        .chain(
+            self.columns
+                .iter()
+                .map(|column| format!("\"{}\" {}", column.name, column.column_type.sql_type())),
+        )
+        .collect();
+        format!(
+            "INSERT INTO \"{}\" ({}) SELECT {} FROM jsonb_to_recordset($1) AS event_rows({}) \
+             ON CONFLICT (transaction_version, event_index) DO NOTHING",
+            self.name,
+            names.join(", "),
+            names.join(", "),
+            types.join(", ")
+        )
+    }
+
+    /// Expects the configured event type to be standardized already
+    fn matches(&self, event_type: &str) -> bool {
+        let event_type = standardize_event_type(event_type);
+        event_type == self.event_type
+            || event_type
+                .strip_prefix(self.event_type.as_str())
+                .is_some_and(|rest| rest.starts_with('<'))
+    }
+
+    fn build_row(
+        &self,
+        event: &Event,
+        txn_version: i64,
+        event_index: i64,
+        txn_timestamp: chrono::NaiveDateTime,
+    ) -> anyhow::Result<Value> {
+        let data: Value = serde_json::from_str(&event.data)?;
+        let mut row = Map::new();
+        row.insert("transaction_version".to_string(), Value::from(txn_version));
+        row.insert("event_index".to_string(), Value::from(event_index));
+        row.insert(
+            "transaction_timestamp".to_string(),
+            serde_json::to_value(txn_timestamp)?,
+        );
+        for column in &self.columns {
+            let value = match get_json_path(&data, &column.path) {
+                Some(value) => column
+                    .column_type
+                    .convert(value)
+                    .with_context(|| format!("column {}", column.name))?,
+                None => Value::Null,
+            };
+            row.insert(column.name.clone(), value);
+        }
+        Ok(Value::Object(row))
+    }
+}
+
+pub struct CustomEventProcessor {
+    connection_pool: ArcDbPool,
+    config: CustomEventProcessorConfig,
+}
+
+impl CustomEventProcessor {
+    pub fn new(connection_pool: ArcDbPool, mut config: CustomEventProcessorConfig) -> Self {
+        for table in config.tables.iter_mut() {
+            table.event_type = standardize_event_type(&table.event_type);
+        }
+        Self {
+            connection_pool,
+            config,
+        }
+    }
+}
+
+impl Debug for CustomEventProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = &self.connection_pool.state();
+        write!(
+            f,
+            "CustomEventProcessor {{ connections: {:?}  idle_connections: {:?} }}",
+            state.connections, state.idle_connections
+        )
+    }
+}
+
+#[async_trait]
+impl ProcessorTrait for CustomEventProcessor {
+    fn name(&self) -> &'static str {
+        ProcessorName::CustomEventProcessor.into()
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+        _: Option<u64>,
+    ) -> anyhow::Result<ProcessingResult> {
+        let processing_start = std::time::Instant::now();
+        let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
+
+        let mut rows: Vec<Vec<Value>> = vec![vec![]; self.config.tables.len()];
+        for txn in &transactions {
+            let txn_version = txn.version as i64;
+            let events = match txn.txn_data.as_ref() {
+                Some(TxnData::User(inner)) => &inner.events,
+                Some(TxnData::Genesis(inner)) => &inner.events,
+                Some(TxnData::BlockMetadata(inner)) => &inner.events,
+                _ => continue,
+            };
+            let txn_timestamp = parse_timestamp(txn.timestamp.as_ref().unwrap(), txn_version);
+            for (index, event) in events.iter().enumerate() {
+                for (table, table_rows) in self.config.tables.iter().zip(rows.iter_mut()) {
+                    if !table.matches(&event.type_str) {
+                        continue;
+                    }
+                    let row = table
+                        .build_row(event, txn_version, index as i64, txn_timestamp)
+                        .with_context(|| {
+                            format!(
+                                "Failed to map event {} of version {} to table {}",
+                                index, txn_version, table.name
+                            )
+                        })?;
+                    table_rows.push(row);
+                }
+            }
+        }
+
+        let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
+        let db_insertion_start = std::time::Instant::now();
+
+        let mut conn = self.get_conn().await;
+        for (table, table_rows) in self.config.tables.iter().zip(rows) {
+            if table_rows.is_empty() {
+                continue;
+            }
+            if let Err(e) = sql_query(table.insert_statement())
+                .bind::<Jsonb, _>(Value::Array(table_rows))
+                .execute(&mut conn)
+                .await
+            {
+                error!(
+                    start_version = start_version,
+                    end_version = end_version,
+                    processor_name = self.name(),
+                    table = table.name.as_str(),
+                    error = ?e,
+                    "[Parser] Error inserting transactions to db",
+                );
+                bail!(e)
+            }
+        }
+        let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
+
+        Ok(ProcessingResult::DefaultProcessingResult(
+            DefaultProcessingResult {
+                start_version,
+                end_version,
+                processing_duration_in_secs,
+                db_insertion_duration_in_secs,
+                last_transaction_timestamp,
+            },
+        ))
+    }
+
+    fn connection_pool(&self) -> &ArcDbPool {
+        &self.connection_pool
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swaps_table() -> EventTableConfig {
+        serde_yaml::from_str(
+            r#"
+name: swaps
+event_type: "0xabc::pool::SwapEvent"
+columns:
+  - name: pool
+    path: pool.inner
+    type: address
+    index: true
+  - name: amount_in
+    path: amounts.0
+    type: numeric
+  - name: from
+    path: from
+    type: text
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = CustomEventProcessorConfig {
+            tables: vec![swaps_table()],
+        };
+        assert!(config.validate().is_ok());
+
+        let mut table = swaps_table();
+        table.name = "swaps; DROP TABLE events".to_string();
+        let config = CustomEventProcessorConfig {
+            tables: vec![table],
+        };
+        assert!(config.validate().is_err());
+
+        let mut table = swaps_table();
+        table.columns[0].name = "event_index".to_string();
+        let config = CustomEventProcessorConfig {
+            tables: vec![table],
+        };
+        assert!(config.validate().is_err());
+
+        for name in ["events", "processor_status", "__diesel_schema_migrations"] {
+            let mut table = swaps_table();
+            table.name = name.to_string();
+            let config = CustomEventProcessorConfig {
+                tables: vec![table],
+            };
+            assert!(config.validate().is_err());
+        }
+
+        // Postgres would truncate the index name
+        let mut table = swaps_table();
+        table.name = "a".repeat(40);
+        table.columns[0].name = "b".repeat(20);
+        let config = CustomEventProcessorConfig {
+            tables: vec![table.clone()],
+        };
+        assert!(config.validate().is_err());
+        table.columns[0].index = false;
+        let config = CustomEventProcessorConfig {
+            tables: vec![table],
+        };
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_statements() {
+        let table = swaps_table();
+        assert_eq!(
+            table.create_statements(),
+            vec![
+                "CREATE TABLE IF NOT EXISTS \"swaps\" (transaction_version BIGINT NOT NULL, \
+             event_index BIGINT NOT NULL, \"pool\" VARCHAR(66), \"amount_in\" NUMERIC, \
+             \"from\" TEXT, transaction_timestamp TIMESTAMP NOT NULL, \
+             inserted_at TIMESTAMP NOT NULL DEFAULT NOW(), \
+             PRIMARY KEY (transaction_version, event_index))"
+                    .to_string(),
+                "CREATE INDEX IF NOT EXISTS \"swaps_pool_index\" ON \"swaps\" (\"pool\")"
+                    .to_string(),
+            ]
+        );
+        assert_eq!(
+            table.insert_statement(),
+            "INSERT INTO \"swaps\" (transaction_version, event_index, transaction_timestamp, \
+             \"pool\", \"amount_in\", \"from\") \
+             SELECT transaction_version, event_index, transaction_timestamp, \
+             \"pool\", \"amount_in\", \"from\" FROM jsonb_to_recordset($1) AS event_rows(\
+             transaction_version BIGINT, event_index BIGINT, transaction_timestamp TIMESTAMP, \
+             \"pool\" VARCHAR(66), \"amount_in\" NUMERIC, \"from\" TEXT) \
+             ON CONFLICT (transaction_version, event_index) DO NOTHING"
+        );
+    }
+
+    #[test]
+    fn test_matches() {
+        let mut table = swaps_table();
+        table.event_type = standardize_event_type(&table.event_type);
+        let address = standardize_address("0xabc");
+        assert!(table.matches("0xabc::pool::SwapEvent"));
+        assert!(table.matches(&format!(
+            "{}::pool::SwapEvent<0x1::aptos_coin::AptosCoin>",
+            address
+        )));
+        assert!(!table.matches(&format!("{}::pool::SwapEventV2", address)));
+        assert!(!table.matches("0xabd::pool::SwapEvent"));
+    }
+
+    #[test]
+    fn test_build_row() {
+        let table = swaps_table();
+        let event = Event {
+            type_str: "0xabc::pool::SwapEvent".to_string(),
+            data: r#"{"pool": {"inner": "0x1"}, "amounts": ["340282366920938463463374607431768211455"], "from": {"a": 1}}"#
+                .to_string(),
+            ..Event::default()
+        };
+        let timestamp = chrono::NaiveDateTime::default();
+        let row = table.build_row(&event, 10, 2, timestamp).unwrap();
+        assert_eq!(row["transaction_version"], Value::from(10));
+        assert_eq!(row["event_index"], Value::from(2));
+        assert_eq!(row["pool"], Value::from(standardize_address("0x1")));
+        assert_eq!(
+            row["amount_in"],
+            Value::from("340282366920938463463374607431768211455")
+        );
+        assert_eq!(row["from"], Value::from(r#"{"a":1}"#));
+
+        let event = Event {
+            data: r#"{"pool": true, "amounts": []}"#.to_string(),
+            ..event
+        };
+        assert!(table.build_row(&event, 10, 2, timestamp).is_err());
+        let event = Event {
+            data: r#"{"amounts": []}"#.to_string(),
+            ..event
+        };
+        let row = table.build_row(&event, 10, 2, timestamp).unwrap();
+        assert_eq!(row["pool"], Value::Null);
+        assert_eq!(row["amount_in"], Value::Null);
+    }
+}
@@ -456,6 +456,12 @@ fn process_transactions(
     if flags.contains(TableFlags::MOVE_MODULES) {
         move_modules.clear();
     }
+    if flags.contains(TableFlags::BLOCK_METADATA_TRANSACTIONS) {
+        block_metadata_transactions.clear();
+    }
+    if flags.contains(TableFlags::CURRENT_TABLE_ITEMS) {
+        current_table_items.clear();
+    }
 
     (
         txns,
@@ -0,0 +1,341 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{DefaultProcessingResult, ProcessorName, ProcessorTrait};
+use crate::{
+    db::common::models::dex_models::{
+        current_dex_pools::{CurrentDexPool, CurrentDexPoolPK},
+        dex_activities::DexActivity,
+        dex_utils::{get_decimals, DexConfig, DexEvent},
+    },
+    gap_detectors::ProcessingResult,
+    schema,
+    utils::{
+        counters::PROCESSOR_UNKNOWN_TYPE_COUNT,
+        database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
+        util::{get_entry_function_from_user_request, parse_timestamp},
+    },
+};
+use ahash::{AHashMap, AHashSet};
+use anyhow::bail;
+use aptos_protos::transaction::v1::{transaction::TxnData, Transaction};
+use async_trait::async_trait;
+use diesel::{
+    pg::{upsert::excluded, Pg},
+    query_builder::QueryFragment,
+    ExpressionMethods,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, sync::Mutex};
+use tracing::error;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DexProcessorConfig {
+    pub dexes: Vec<DexConfig>,
+}
+
+pub struct DexProcessor {
+    connection_pool: ArcDbPool,
+    config: DexProcessorConfig,
+    per_table_chunk_sizes: AHashMap<String, usize>,
+    // Decimals never change once an asset exists, so they're only looked up until found
+    decimals: Mutex<AHashMap<String, i32>>,
+}
+
+impl DexProcessor {
+    pub fn new(
+        connection_pool: ArcDbPool,
+        config: DexProcessorConfig,
+        per_table_chunk_sizes: AHashMap<String, usize>,
+    ) -> Self {
+        Self {
+            connection_pool,
+            config,
+            per_table_chunk_sizes,
+            decimals: Mutex::new(AHashMap::new()),
+        }
+    }
+
+    /// Returns the decimals of the assets that are known, from the cache or the database
+    async fn lookup_decimals(
+        &self,
+        asset_types: AHashSet<String>,
+    ) -> anyhow::Result<AHashMap<String, i32>> {
+        let missing: Vec<String> = {
+            let cache = self.decimals.lock().unwrap();
+            asset_types
+                .iter()
+                .filter(|asset_type| !cache.contains_key(*asset_type))
+                .cloned()
+                .collect()
+        };
+        if !missing.is_empty() {
+            let mut conn = self.get_pool().get().await?;
+            let found = get_decimals(&mut conn, &missing).await?;
+            self.decimals.lock().unwrap().extend(found);
+        }
+        let cache = self.decimals.lock().unwrap();
+        Ok(asset_types
+            .into_iter()
+            .filter_map(|asset_type| {
+                let decimals = *cache.get(&asset_type)?;
+                Some((asset_type, decimals))
+            })
+            .collect())
+    }
+}
+
+impl Debug for DexProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = &self.connection_pool.state();
+        write!(
+            f,
+            "DexProcessor {{ connections: {:?}  idle_connections: {:?} }}",
+            state.connections, state.idle_connections
+        )
+    }
+}
+
+async fn insert_to_db(
+    conn: ArcDbPool,
+    name: &'static str,
+    start_version: u64,
+    end_version: u64,
+    (activities, pools): (&[DexActivity], &[CurrentDexPool]),
+    per_table_chunk_sizes: &AHashMap<String, usize>,
+) -> Result<(), diesel::result::Error> {
+    tracing::trace!(
+        name = name,
+        start_version = start_version,
+        end_version = end_version,
+        "Inserting to db",
+    );
+
+    let a = execute_in_chunks(
+        conn.clone(),
+        insert_dex_activities_query,
+        activities,
+        get_config_table_chunk_size::<DexActivity>("dex_activities", per_table_chunk_sizes),
+    );
+    let p = execute_in_chunks(
+        conn,
+        insert_current_dex_pools_query,
+        pools,
+        get_config_table_chunk_size::<CurrentDexPool>("current_dex_pools", per_table_chunk_sizes),
+    );
+    let (a_res, p_res) = tokio::join!(a, p);
+    for res in [a_res, p_res] {
+        res?;
+    }
+
+    Ok(())
+}
+
+fn insert_dex_activities_query(
+    items_to_insert: Vec<DexActivity>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::dex_activities::dsl::*;
+    (
+        diesel::insert_into(schema::dex_activities::table)
+            .values(items_to_insert)
+            .on_conflict((transaction_version, event_index))
+            .do_nothing(),
+        None,
+    )
+}
+
+fn insert_current_dex_pools_query(
+    items_to_insert: Vec<CurrentDexPool>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::current_dex_pools::dsl::*;
+    (
+        diesel::insert_into(schema::current_dex_pools::table)
+            .values(items_to_insert)
+            .on_conflict((dex, pool_id))
+            .do_update()
+            .set((
+                contract_address.eq(excluded(contract_address)),
+                token_x.eq(excluded(token_x)),
+                token_y.eq(excluded(token_y)),
+                token_x_decimals.eq(excluded(token_x_decimals)),
+                token_y_decimals.eq(excluded(token_y_decimals)),
+                reserve_x.eq(excluded(reserve_x)),
+                reserve_y.eq(excluded(reserve_y)),
+                reserve_x_normalized.eq(excluded(reserve_x_normalized)),
+                reserve_y_normalized.eq(excluded(reserve_y_normalized)),
+                volume_x.eq(excluded(volume_x)),
+                volume_y.eq(excluded(volume_y)),
+                volume_x_normalized.eq(excluded(volume_x_normalized)),
+                volume_y_normalized.eq(excluded(volume_y_normalized)),
+                swap_count.eq(excluded(swap_count)),
+                last_transaction_version.eq(excluded(last_transaction_version)),
+                last_transaction_timestamp.eq(excluded(last_transaction_timestamp)),
+                inserted_at.eq(excluded(inserted_at)),
+            )),
+        Some(" WHERE current_dex_pools.last_transaction_version <= excluded.last_transaction_version "),
+    )
+}
+
+#[async_trait]
+impl ProcessorTrait for DexProcessor {
+    fn name(&self) -> &'static str {
+        ProcessorName::DexProcessor.into()
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+        _: Option<u64>,
+    ) -> anyhow::Result<ProcessingResult> {
+        let processing_start = std::time::Instant::now();
+        let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
+
+        let mut activities_and_events = parse_dex_events(&transactions, &self.config.dexes)?;
+
+        // Pools keep running totals, so events are applied on top of what's stored
+        let pool_pks: Vec<CurrentDexPoolPK> = activities_and_events
+            .iter()
+            .map(|(activity, _)| (activity.dex.clone(), activity.pool_id.clone()))
+            .collect::<AHashSet<_>>()
+            .into_iter()
+            .collect();
+        let existing_pools = if pool_pks.is_empty() {
+            AHashMap::new()
+        } else {
+            let mut conn = self.get_pool().get().await?;
+            CurrentDexPool::get_by_pks(&mut conn, &pool_pks).await?
+        };
+        let mut pools = CurrentDexPool::from_dex_events(&mut activities_and_events, existing_pools);
+
+        let asset_types: AHashSet<String> = pools
+            .iter()
+            .flat_map(|pool| [pool.token_x.clone(), pool.token_y.clone()])
+            .chain(
+                activities_and_events
+                    .iter()
+                    .flat_map(|(activity, _)| [activity.token_x.clone(), activity.token_y.clone()]),
+            )
+            .flatten()
+            .collect();
+        let decimals = self.lookup_decimals(asset_types).await?;
+        for pool in pools.iter_mut() {
+            pool.set_normalized_amounts(&decimals);
+        }
+        let activities: Vec<DexActivity> = activities_and_events
+            .into_iter()
+            .map(|(mut activity, _)| {
+                activity.set_normalized_amounts(&decimals);
+                activity
+            })
+            .collect();
+
+        let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
+        let db_insertion_start = std::time::Instant::now();
+
+        let tx_result = insert_to_db(
+            self.get_pool(),
+            self.name(),
+            start_version,
+            end_version,
+            (&activities, &pools),
+            &self.per_table_chunk_sizes,
+        )
+        .await;
+        let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
+
+        match tx_result {
+            Ok(_) => Ok(ProcessingResult::DefaultProcessingResult(
+                DefaultProcessingResult {
+                    start_version,
+                    end_version,
+                    processing_duration_in_secs,
+                    db_insertion_duration_in_secs,
+                    last_transaction_timestamp,
+                },
+            )),
+            Err(e) => {
+                error!(
+                    start_version = start_version,
+                    end_version = end_version,
+                    processor_name = self.name(),
+                    error = ?e,
+                    "[Parser] Error inserting transactions to db",
+                );
+                bail!(e)
+            },
+        }
+    }
+
+    fn connection_pool(&self) -> &ArcDbPool {
+        &self.connection_pool
+    }
+}
+
+/// Reads the events of the configured DEXes, in the order they were emitted
+pub fn parse_dex_events(
+    transactions: &[Transaction],
+    dexes: &[DexConfig],
+) -> anyhow::Result<Vec<(DexActivity, DexEvent)>> {
+    let mut activities_and_events = vec![];
+
+    for txn in transactions {
+        let txn_version = txn.version as i64;
+        let user_txn = match txn.txn_data.as_ref() {
+            Some(TxnData::User(user_txn)) => user_txn,
+            Some(_) => continue,
+            None => {
+                PROCESSOR_UNKNOWN_TYPE_COUNT
+                    .with_label_values(&["DexProcessor"])
+                    .inc();
+                tracing::warn!(
+                    transaction_version = txn_version,
+                    "Transaction data doesn't exist"
+                );
+                continue;
+            },
+        };
+        let txn_timestamp = parse_timestamp(txn.timestamp.as_ref().unwrap(), txn_version);
+        let request = user_txn.request.as_ref().unwrap();
+        let entry_function_id_str = get_entry_function_from_user_request(request);
+
+        for (index, event) in user_txn.events.iter().enumerate() {
+            let Some((dex, kind)) = dexes
+                .iter()
+                .find_map(|dex| dex.get_event_kind(&event.type_str).map(|kind| (dex, kind)))
+            else {
+                continue;
+            };
+            let parsed = DexEvent::from_event_data(
+                kind,
+                &event.type_str,
+                &event.data,
+                &dex.fields,
+                txn_version,
+            )?;
+            activities_and_events.push((
+                DexActivity::from_dex_event(
+                    dex,
+                    &event.type_str,
+                    &parsed,
+                    txn_version,
+                    index as i64,
+                    &request.sender,
+                    &entry_function_id_str,
+                    txn_timestamp,
+                ),
+                parsed,
+            ));
+        }
+    }
+
+    Ok(activities_and_events)
+}
@@ -6,13 +6,13 @@ use crate::{
     db::common::models::events_models::events::EventModel,
     gap_detectors::ProcessingResult,
     schema,
+    sinks::{write_to_sinks, ProcessorOutput, Sink, SinkConfig, TableRows},
     utils::{
         counters::PROCESSOR_UNKNOWN_TYPE_COUNT,
         database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
     },
 };
 use ahash::AHashMap;
-use anyhow::bail;
 use aptos_protos::transaction::v1::{transaction::TxnData, Transaction};
 use async_trait::async_trait;
 use diesel::{
@@ -20,19 +20,28 @@ use diesel::{
     query_builder::QueryFragment,
     ExpressionMethods,
 };
-use std::fmt::Debug;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, sync::Arc};
 use tracing::error;
 
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventsProcessorConfig {
+    // Where rows are written, Postgres if empty
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
+}
+
 pub struct EventsProcessor {
     connection_pool: ArcDbPool,
-    per_table_chunk_sizes: AHashMap<String, usize>,
+    sinks: Arc<Vec<Sink>>,
 }
 
 impl EventsProcessor {
-    pub fn new(connection_pool: ArcDbPool, per_table_chunk_sizes: AHashMap<String, usize>) -> Self {
+    pub fn new(connection_pool: ArcDbPool, sinks: Arc<Vec<Sink>>) -> Self {
         Self {
             connection_pool,
-            per_table_chunk_sizes,
+            sinks,
         }
     }
 }
@@ -48,28 +57,33 @@ impl Debug for EventsProcessor {
     }
 }
 
-async fn insert_to_db(
-    conn: ArcDbPool,
-    name: &'static str,
-    start_version: u64,
-    end_version: u64,
-    events: &[EventModel],
-    per_table_chunk_sizes: &AHashMap<String, usize>,
-) -> Result<(), diesel::result::Error> {
-    tracing::trace!(
-        name = name,
-        start_version = start_version,
-        end_version = end_version,
-        "Inserting to db",
-    );
-    execute_in_chunks(
-        conn,
-        insert_events_query,
-        events,
-        get_config_table_chunk_size::<EventModel>("events", per_table_chunk_sizes),
-    )
-    .await?;
-    Ok(())
+pub struct EventsProcessorOutput {
+    pub events: Vec<EventModel>,
+}
+
+#[async_trait]
+impl ProcessorOutput for EventsProcessorOutput {
+    async fn insert_to_db(
+        &self,
+        conn: ArcDbPool,
+        per_table_chunk_sizes: &AHashMap<String, usize>,
+    ) -> Result<(), diesel::result::Error> {
+        execute_in_chunks(
+            conn,
+            insert_events_query,
+            &self.events,
+            get_config_table_chunk_size::<EventModel>("events", per_table_chunk_sizes),
+        )
+        .await
+    }
+
+    fn table_rows(&self) -> anyhow::Result<Vec<TableRows>> {
+        Ok(vec![TableRows::new(
+            "events",
+            &["transaction_version", "event_index"],
+            &self.events,
+        )?])
+    }
 }
 
 fn insert_events_query(
@@ -142,15 +156,14 @@ impl ProcessorTrait for EventsProcessor {
         let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
         let db_insertion_start = std::time::Instant::now();
 
-        let tx_result = insert_to_db(
-            self.get_pool(),
-            self.name(),
-            start_version,
-            end_version,
-            &events,
-            &self.per_table_chunk_sizes,
-        )
-        .await;
+        tracing::trace!(
+            name = self.name(),
+            start_version = start_version,
+            end_version = end_version,
+            "Writing to sinks",
+        );
+        let output = EventsProcessorOutput { events };
+        let tx_result = write_to_sinks(&self.sinks, &output, start_version, end_version).await;
 
         let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
         match tx_result {
@@ -171,7 +184,7 @@ impl ProcessorTrait for EventsProcessor {
                     error = ?e,
                     "[Parser] Error inserting transactions to db",
                 );
-                bail!(e)
+                Err(e)
             },
         }
     }
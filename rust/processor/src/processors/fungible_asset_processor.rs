@@ -9,7 +9,8 @@ use crate::{
             v2_fungible_asset_activities::{EventToCoinType, FungibleAssetActivity},
             v2_fungible_asset_balances::{
                 CurrentFungibleAssetBalance, CurrentFungibleAssetMapping,
-                CurrentUnifiedFungibleAssetBalance, FungibleAssetBalance,
+                CurrentUnifiedFungibleAssetBalance, DailyFungibleAssetBalance,
+                FungibleAssetBalance,
             },
             v2_fungible_asset_utils::{
                 ConcurrentFungibleAssetBalance, ConcurrentFungibleAssetSupply, FeeStatement,
@@ -23,6 +24,7 @@ use crate::{
     },
     gap_detectors::ProcessingResult,
     schema,
+    sinks::{write_to_sinks, ProcessorOutput, Sink, SinkConfig, TableRows},
     utils::{
         counters::PROCESSOR_UNKNOWN_TYPE_COUNT,
         database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
@@ -40,24 +42,37 @@ use diesel::{
     query_builder::QueryFragment,
     ExpressionMethods,
 };
-use std::fmt::Debug;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, sync::Arc};
 use tracing::error;
 
+/// The unified balances table keeps this name until the old balances tables are dropped
+const CURRENT_UNIFIED_FUNGIBLE_ASSET_BALANCES_TABLE: &str =
+    "current_unified_fungible_asset_balances_to_be_renamed";
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FungibleAssetProcessorConfig {
+    // Where rows are written, Postgres if empty
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
+}
+
 pub struct FungibleAssetProcessor {
     connection_pool: ArcDbPool,
-    per_table_chunk_sizes: AHashMap<String, usize>,
+    sinks: Arc<Vec<Sink>>,
     deprecated_tables: TableFlags,
 }
 
 impl FungibleAssetProcessor {
     pub fn new(
         connection_pool: ArcDbPool,
-        per_table_chunk_sizes: AHashMap<String, usize>,
+        sinks: Arc<Vec<Sink>>,
         deprecated_tables: TableFlags,
     ) -> Self {
         Self {
             connection_pool,
-            per_table_chunk_sizes,
+            sinks,
             deprecated_tables,
         }
     }
@@ -74,98 +89,154 @@ impl Debug for FungibleAssetProcessor {
     }
 }
 
-async fn insert_to_db(
-    conn: ArcDbPool,
-    name: &'static str,
-    start_version: u64,
-    end_version: u64,
-    fungible_asset_activities: &[FungibleAssetActivity],
-    fungible_asset_metadata: &[FungibleAssetMetadataModel],
-    fungible_asset_balances: &[FungibleAssetBalance],
-    current_fungible_asset_balances: &[CurrentFungibleAssetBalance],
-    current_unified_fungible_asset_balances: (
-        &[CurrentUnifiedFungibleAssetBalance],
-        &[CurrentUnifiedFungibleAssetBalance],
-    ),
-    coin_supply: &[CoinSupply],
-    per_table_chunk_sizes: &AHashMap<String, usize>,
-) -> Result<(), diesel::result::Error> {
-    tracing::trace!(
-        name = name,
-        start_version = start_version,
-        end_version = end_version,
-        "Inserting to db",
-    );
-
-    let faa = execute_in_chunks(
-        conn.clone(),
-        insert_fungible_asset_activities_query,
-        fungible_asset_activities,
-        get_config_table_chunk_size::<FungibleAssetActivity>(
-            "fungible_asset_activities",
-            per_table_chunk_sizes,
-        ),
-    );
-    let fam = execute_in_chunks(
-        conn.clone(),
-        insert_fungible_asset_metadata_query,
-        fungible_asset_metadata,
-        get_config_table_chunk_size::<FungibleAssetMetadataModel>(
-            "fungible_asset_metadata",
-            per_table_chunk_sizes,
-        ),
-    );
-    let fab = execute_in_chunks(
-        conn.clone(),
-        insert_fungible_asset_balances_query,
-        fungible_asset_balances,
-        get_config_table_chunk_size::<FungibleAssetBalance>(
-            "fungible_asset_balances",
-            per_table_chunk_sizes,
-        ),
-    );
-    let cfab = execute_in_chunks(
-        conn.clone(),
-        insert_current_fungible_asset_balances_query,
-        current_fungible_asset_balances,
-        get_config_table_chunk_size::<CurrentFungibleAssetBalance>(
-            "current_fungible_asset_balances",
-            per_table_chunk_sizes,
-        ),
-    );
-    let cufab_v1 = execute_in_chunks(
-        conn.clone(),
-        insert_current_unified_fungible_asset_balances_v1_query,
-        current_unified_fungible_asset_balances.0,
-        get_config_table_chunk_size::<CurrentUnifiedFungibleAssetBalance>(
-            "current_unified_fungible_asset_balances",
-            per_table_chunk_sizes,
-        ),
-    );
-    let cufab_v2 = execute_in_chunks(
-        conn.clone(),
-        insert_current_unified_fungible_asset_balances_v2_query,
-        current_unified_fungible_asset_balances.1,
-        get_config_table_chunk_size::<CurrentUnifiedFungibleAssetBalance>(
-            "current_unified_fungible_asset_balances",
-            per_table_chunk_sizes,
-        ),
-    );
-    let cs = execute_in_chunks(
-        conn,
-        insert_coin_supply_query,
-        coin_supply,
-        get_config_table_chunk_size::<CoinSupply>("coin_supply", per_table_chunk_sizes),
-    );
-    let (faa_res, fam_res, fab_res, cfab_res, cufab1_res, cufab2_res, cs_res) =
-        tokio::join!(faa, fam, fab, cfab, cufab_v1, cufab_v2, cs);
-    for res in [
-        faa_res, fam_res, fab_res, cfab_res, cufab1_res, cufab2_res, cs_res,
-    ] {
-        res?;
+pub struct FungibleAssetProcessorOutput {
+    pub fungible_asset_activities: Vec<FungibleAssetActivity>,
+    pub fungible_asset_metadata: Vec<FungibleAssetMetadataModel>,
+    pub fungible_asset_balances: Vec<FungibleAssetBalance>,
+    pub current_fungible_asset_balances: Vec<CurrentFungibleAssetBalance>,
+    /// Coin balances, which only set the v1 columns of the unified table
+    pub current_unified_fungible_asset_balances_v1: Vec<CurrentUnifiedFungibleAssetBalance>,
+    /// Fungible store balances, which only set the v2 columns of the unified table
+    pub current_unified_fungible_asset_balances_v2: Vec<CurrentUnifiedFungibleAssetBalance>,
+    pub daily_fungible_asset_balances: Vec<DailyFungibleAssetBalance>,
+    pub coin_supply: Vec<CoinSupply>,
+}
+
+#[async_trait]
+impl ProcessorOutput for FungibleAssetProcessorOutput {
+    async fn insert_to_db(
+        &self,
+        conn: ArcDbPool,
+        per_table_chunk_sizes: &AHashMap<String, usize>,
+    ) -> Result<(), diesel::result::Error> {
+        let faa = execute_in_chunks(
+            conn.clone(),
+            insert_fungible_asset_activities_query,
+            &self.fungible_asset_activities,
+            get_config_table_chunk_size::<FungibleAssetActivity>(
+                "fungible_asset_activities",
+                per_table_chunk_sizes,
+            ),
+        );
+        let fam = execute_in_chunks(
+            conn.clone(),
+            insert_fungible_asset_metadata_query,
+            &self.fungible_asset_metadata,
+            get_config_table_chunk_size::<FungibleAssetMetadataModel>(
+                "fungible_asset_metadata",
+                per_table_chunk_sizes,
+            ),
+        );
+        let fab = execute_in_chunks(
+            conn.clone(),
+            insert_fungible_asset_balances_query,
+            &self.fungible_asset_balances,
+            get_config_table_chunk_size::<FungibleAssetBalance>(
+                "fungible_asset_balances",
+                per_table_chunk_sizes,
+            ),
+        );
+        let cfab = execute_in_chunks(
+            conn.clone(),
+            insert_current_fungible_asset_balances_query,
+            &self.current_fungible_asset_balances,
+            get_config_table_chunk_size::<CurrentFungibleAssetBalance>(
+                "current_fungible_asset_balances",
+                per_table_chunk_sizes,
+            ),
+        );
+        let cufab_v1 = execute_in_chunks(
+            conn.clone(),
+            insert_current_unified_fungible_asset_balances_v1_query,
+            &self.current_unified_fungible_asset_balances_v1,
+            get_config_table_chunk_size::<CurrentUnifiedFungibleAssetBalance>(
+                CURRENT_UNIFIED_FUNGIBLE_ASSET_BALANCES_TABLE,
+                per_table_chunk_sizes,
+            ),
+        );
+        let cufab_v2 = execute_in_chunks(
+            conn.clone(),
+            insert_current_unified_fungible_asset_balances_v2_query,
+            &self.current_unified_fungible_asset_balances_v2,
+            get_config_table_chunk_size::<CurrentUnifiedFungibleAssetBalance>(
+                CURRENT_UNIFIED_FUNGIBLE_ASSET_BALANCES_TABLE,
+                per_table_chunk_sizes,
+            ),
+        );
+        let dfab = execute_in_chunks(
+            conn.clone(),
+            insert_daily_fungible_asset_balances_query,
+            &self.daily_fungible_asset_balances,
+            get_config_table_chunk_size::<DailyFungibleAssetBalance>(
+                "daily_fungible_asset_balances",
+                per_table_chunk_sizes,
+            ),
+        );
+        let cs = execute_in_chunks(
+            conn,
+            insert_coin_supply_query,
+            &self.coin_supply,
+            get_config_table_chunk_size::<CoinSupply>("coin_supply", per_table_chunk_sizes),
+        );
+        let (faa_res, fam_res, fab_res, cfab_res, cufab1_res, cufab2_res, dfab_res, cs_res) =
+            tokio::join!(faa, fam, fab, cfab, cufab_v1, cufab_v2, dfab, cs);
+        for res in [
+            faa_res, fam_res, fab_res, cfab_res, cufab1_res, cufab2_res, dfab_res, cs_res,
+        ] {
+            res?;
+        }
+
+        Ok(())
     }
 
-    Ok(())
+    fn table_rows(&self) -> anyhow::Result<Vec<TableRows>> {
+        let mut current_unified_fungible_asset_balances = TableRows::new(
+            CURRENT_UNIFIED_FUNGIBLE_ASSET_BALANCES_TABLE,
+            &["storage_id"],
+            &self.current_unified_fungible_asset_balances_v1,
+        )?;
+        current_unified_fungible_asset_balances.rows.extend(
+            TableRows::new(
+                CURRENT_UNIFIED_FUNGIBLE_ASSET_BALANCES_TABLE,
+                &["storage_id"],
+                &self.current_unified_fungible_asset_balances_v2,
+            )?
+            .rows,
+        );
+        Ok(vec![
+            TableRows::new(
+                "fungible_asset_activities",
+                &["transaction_version", "event_index"],
+                &self.fungible_asset_activities,
+            )?,
+            TableRows::new(
+                "fungible_asset_metadata",
+                &["asset_type"],
+                &self.fungible_asset_metadata,
+            )?,
+            TableRows::new(
+                "fungible_asset_balances",
+                &["transaction_version", "write_set_change_index"],
+                &self.fungible_asset_balances,
+            )?,
+            TableRows::new(
+                "current_fungible_asset_balances",
+                &["storage_id"],
+                &self.current_fungible_asset_balances,
+            )?,
+            current_unified_fungible_asset_balances,
+            TableRows::new(
+                "daily_fungible_asset_balances",
+                &["owner_address", "asset_type", "date"],
+                &self.daily_fungible_asset_balances,
+            )?,
+            TableRows::new(
+                "coin_supply",
+                &["transaction_version", "coin_type_hash"],
+                &self.coin_supply,
+            )?,
+        ])
+    }
 }
 
 fn insert_fungible_asset_activities_query(
@@ -268,6 +339,31 @@ fn insert_current_fungible_asset_balances_query(
     )
 }
 
+fn insert_daily_fungible_asset_balances_query(
+    items_to_insert: Vec<DailyFungibleAssetBalance>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::daily_fungible_asset_balances::dsl::*;
+
+    (
+        diesel::insert_into(schema::daily_fungible_asset_balances::table)
+            .values(items_to_insert)
+            .on_conflict((owner_address, asset_type, date))
+            .do_update()
+            .set((
+                storage_id.eq(excluded(storage_id)),
+                amount.eq(excluded(amount)),
+                token_standard.eq(excluded(token_standard)),
+                last_transaction_version.eq(excluded(last_transaction_version)),
+                last_transaction_timestamp.eq(excluded(last_transaction_timestamp)),
+                inserted_at.eq(excluded(inserted_at)),
+            )),
+        Some(" WHERE daily_fungible_asset_balances.last_transaction_version <= excluded.last_transaction_version "),
+    )
+}
+
 fn insert_current_unified_fungible_asset_balances_v1_query(
     items_to_insert: Vec<CurrentUnifiedFungibleAssetBalance>,
 ) -> (
@@ -360,21 +456,38 @@ impl ProcessorTrait for FungibleAssetProcessor {
         let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
 
         let (
-            fungible_asset_activities,
-            fungible_asset_metadata,
+            mut fungible_asset_activities,
+            mut fungible_asset_metadata,
             mut fungible_asset_balances,
             mut current_fungible_asset_balances,
-            current_unified_fungible_asset_balances,
-            coin_supply,
+            mut current_unified_fungible_asset_balances,
+            mut coin_supply,
         ) = parse_v2_coin(&transactions).await;
 
         let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
         let db_insertion_start = std::time::Instant::now();
 
+        if self
+            .deprecated_tables
+            .contains(TableFlags::CURRENT_UNIFIED_FUNGIBLE_ASSET_BALANCES)
+        {
+            current_unified_fungible_asset_balances.clear();
+        }
+
         let (coin_balance, fa_balance): (Vec<_>, Vec<_>) = current_unified_fungible_asset_balances
             .into_iter()
             .partition(|x| x.is_primary.is_none());
 
+        // Built before fungible_asset_balances is cleared, so it can be written on its own
+        let daily_fungible_asset_balances = if self
+            .deprecated_tables
+            .contains(TableFlags::DAILY_FUNGIBLE_ASSET_BALANCES)
+        {
+            vec![]
+        } else {
+            DailyFungibleAssetBalance::from_balances(&fungible_asset_balances)
+        };
+
         if self
             .deprecated_tables
             .contains(TableFlags::FUNGIBLE_ASSET_BALANCES)
@@ -389,20 +502,41 @@ impl ProcessorTrait for FungibleAssetProcessor {
             current_fungible_asset_balances.clear();
         }
 
-        let tx_result = insert_to_db(
-            self.get_pool(),
-            self.name(),
-            start_version,
-            end_version,
-            &fungible_asset_activities,
-            &fungible_asset_metadata,
-            &fungible_asset_balances,
-            &current_fungible_asset_balances,
-            (&coin_balance, &fa_balance),
-            &coin_supply,
-            &self.per_table_chunk_sizes,
-        )
-        .await;
+        if self
+            .deprecated_tables
+            .contains(TableFlags::FUNGIBLE_ASSET_ACTIVITIES)
+        {
+            fungible_asset_activities.clear();
+        }
+
+        if self
+            .deprecated_tables
+            .contains(TableFlags::FUNGIBLE_ASSET_METADATA)
+        {
+            fungible_asset_metadata.clear();
+        }
+
+        if self.deprecated_tables.contains(TableFlags::COIN_SUPPLY) {
+            coin_supply.clear();
+        }
+
+        tracing::trace!(
+            name = self.name(),
+            start_version = start_version,
+            end_version = end_version,
+            "Writing to sinks",
+        );
+        let output = FungibleAssetProcessorOutput {
+            fungible_asset_activities,
+            fungible_asset_metadata,
+            fungible_asset_balances,
+            current_fungible_asset_balances,
+            current_unified_fungible_asset_balances_v1: coin_balance,
+            current_unified_fungible_asset_balances_v2: fa_balance,
+            daily_fungible_asset_balances,
+            coin_supply,
+        };
+        let tx_result = write_to_sinks(&self.sinks, &output, start_version, end_version).await;
         let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
         match tx_result {
             Ok(_) => Ok(ProcessingResult::DefaultProcessingResult(
@@ -7,50 +7,70 @@
 pub mod account_transactions_processor;
 pub mod ans_processor;
 pub mod coin_processor;
+pub mod custom_event_processor;
 pub mod default_processor;
+pub mod dex_processor;
 pub mod events_processor;
 pub mod fungible_asset_processor;
 pub mod monitoring_processor;
+pub mod nft_marketplace_processor;
 pub mod nft_metadata_processor;
 pub mod objects_processor;
 pub mod parquet_processors;
+pub mod resource_tracking_processor;
 pub mod stake_processor;
 pub mod token_processor;
 pub mod token_v2_processor;
 pub mod transaction_metadata_processor;
 pub mod user_transaction_processor;
+pub mod webhook_processor;
 
 use self::{
     account_transactions_processor::AccountTransactionsProcessor,
     ans_processor::{AnsProcessor, AnsProcessorConfig},
-    coin_processor::CoinProcessor,
+    coin_processor::{CoinProcessor, CoinProcessorConfig},
+    custom_event_processor::{CustomEventProcessor, CustomEventProcessorConfig},
     default_processor::DefaultProcessor,
-    events_processor::EventsProcessor,
-    fungible_asset_processor::FungibleAssetProcessor,
+    dex_processor::{DexProcessor, DexProcessorConfig},
+    events_processor::{EventsProcessor, EventsProcessorConfig},
+    fungible_asset_processor::{FungibleAssetProcessor, FungibleAssetProcessorConfig},
     monitoring_processor::MonitoringProcessor,
+    nft_marketplace_processor::{NftMarketplaceProcessor, NftMarketplaceProcessorConfig},
     nft_metadata_processor::{NftMetadataProcessor, NftMetadataProcessorConfig},
     objects_processor::{ObjectsProcessor, ObjectsProcessorConfig},
+    resource_tracking_processor::{ResourceTrackingProcessor, ResourceTrackingProcessorConfig},
     stake_processor::{StakeProcessor, StakeProcessorConfig},
     token_processor::{TokenProcessor, TokenProcessorConfig},
     token_v2_processor::{TokenV2Processor, TokenV2ProcessorConfig},
     transaction_metadata_processor::TransactionMetadataProcessor,
     user_transaction_processor::UserTransactionProcessor,
+    webhook_processor::{WebhookProcessor, WebhookProcessorConfig},
 };
 use crate::{
-    db::common::models::processor_status::ProcessorStatus,
+    config::BackfillConfig,
+    db::common::models::{
+        backfill_processor_status::{BackfillProcessorStatus, BackfillStatus},
+        processor_status::ProcessorStatus,
+    },
     gap_detectors::ProcessingResult,
     processors::parquet_processors::{
+        parquet_ans_processor::{ParquetAnsProcessor, ParquetAnsProcessorConfig},
         parquet_default_processor::{ParquetDefaultProcessor, ParquetDefaultProcessorConfig},
         parquet_fungible_asset_processor::{
             ParquetFungibleAssetProcessor, ParquetFungibleAssetProcessorConfig,
         },
+        parquet_objects_processor::{ParquetObjectsProcessor, ParquetObjectsProcessorConfig},
+        parquet_stake_processor::{ParquetStakeProcessor, ParquetStakeProcessorConfig},
+        parquet_token_v2_processor::{ParquetTokenV2Processor, ParquetTokenV2ProcessorConfig},
     },
-    schema::processor_status,
+    schema::{backfill_processor_status, processor_status},
+    sinks::SinkConfig,
     utils::{
         counters::{GOT_CONNECTION_COUNT, UNABLE_TO_GET_CONNECTION_COUNT},
         database::{execute_with_better_error, ArcDbPool, DbPoolConnection},
         util::parse_timestamp,
     },
+    worker::TableFlags,
 };
 use aptos_protos::transaction::v1::Transaction as ProtoTransaction;
 use async_trait::async_trait;
@@ -126,32 +146,95 @@ pub trait ProcessorTrait: Send + Sync + Debug {
         version: u64,
         last_transaction_timestamp: Option<aptos_protos::util::timestamp::Timestamp>,
     ) -> anyhow::Result<()> {
-        let timestamp = last_transaction_timestamp.map(|t| parse_timestamp(&t, version as i64));
-        let status = ProcessorStatus {
-            processor: self.name().to_string(),
-            last_success_version: version as i64,
-            last_transaction_timestamp: timestamp,
-        };
-        execute_with_better_error(
+        write_processor_status(
             self.get_pool(),
-            diesel::insert_into(processor_status::table)
-                .values(&status)
-                .on_conflict(processor_status::processor)
-                .do_update()
-                .set((
-                    processor_status::last_success_version
-                        .eq(excluded(processor_status::last_success_version)),
-                    processor_status::last_updated.eq(excluded(processor_status::last_updated)),
-                    processor_status::last_transaction_timestamp
-                        .eq(excluded(processor_status::last_transaction_timestamp)),
-                )),
-            Some(" WHERE processor_status.last_success_version <= EXCLUDED.last_success_version "),
+            self.name(),
+            version,
+            last_transaction_timestamp,
         )
-        .await?;
-        Ok(())
+        .await
     }
 }
 
+/// Writes the processor's row in `processor_status`. The gap detector calls this directly, so it
+/// doesn't need a processor instance of its own.
+pub async fn write_processor_status(
+    db_pool: ArcDbPool,
+    processor_name: &str,
+    version: u64,
+    last_transaction_timestamp: Option<aptos_protos::util::timestamp::Timestamp>,
+) -> anyhow::Result<()> {
+    let timestamp = last_transaction_timestamp.map(|t| parse_timestamp(&t, version as i64));
+    let status = ProcessorStatus {
+        processor: processor_name.to_string(),
+        last_success_version: version as i64,
+        last_transaction_timestamp: timestamp,
+    };
+    execute_with_better_error(
+        db_pool,
+        diesel::insert_into(processor_status::table)
+            .values(&status)
+            .on_conflict(processor_status::processor)
+            .do_update()
+            .set((
+                processor_status::last_success_version
+                    .eq(excluded(processor_status::last_success_version)),
+                processor_status::last_updated.eq(excluded(processor_status::last_updated)),
+                processor_status::last_transaction_timestamp
+                    .eq(excluded(processor_status::last_transaction_timestamp)),
+            )),
+        Some(" WHERE processor_status.last_success_version <= EXCLUDED.last_success_version "),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Like `write_processor_status`, but for a backfill. The backfill is marked complete once it has
+/// processed its end version.
+pub async fn write_backfill_status(
+    db_pool: ArcDbPool,
+    processor_name: &str,
+    backfill: &BackfillConfig,
+    version: u64,
+    last_transaction_timestamp: Option<aptos_protos::util::timestamp::Timestamp>,
+) -> anyhow::Result<()> {
+    let timestamp = last_transaction_timestamp.map(|t| parse_timestamp(&t, version as i64));
+    let backfill_status = if version >= backfill.end_version {
+        BackfillStatus::Complete
+    } else {
+        BackfillStatus::InProgress
+    };
+    let status = BackfillProcessorStatus {
+        backfill_alias: backfill.backfill_alias.clone(),
+        processor: processor_name.to_string(),
+        backfill_status: backfill_status.to_string(),
+        last_success_version: version as i64,
+        last_transaction_timestamp: timestamp,
+        backfill_start_version: backfill.start_version as i64,
+        backfill_end_version: backfill.end_version as i64,
+    };
+    execute_with_better_error(
+        db_pool,
+        diesel::insert_into(backfill_processor_status::table)
+            .values(&status)
+            .on_conflict(backfill_processor_status::backfill_alias)
+            .do_update()
+            .set((
+                backfill_processor_status::backfill_status
+                    .eq(excluded(backfill_processor_status::backfill_status)),
+                backfill_processor_status::last_success_version
+                    .eq(excluded(backfill_processor_status::last_success_version)),
+                backfill_processor_status::last_updated
+                    .eq(excluded(backfill_processor_status::last_updated)),
+                backfill_processor_status::last_transaction_timestamp
+                    .eq(excluded(backfill_processor_status::last_transaction_timestamp)),
+            )),
+        Some(" WHERE backfill_processor_status.last_success_version <= EXCLUDED.last_success_version "),
+    )
+    .await?;
+    Ok(())
+}
+
 /// This enum captures the configs for all the different processors that are defined.
 /// The configs for each processor should only contain configuration specific to that
 /// processor. For configuration that is common to all processors, put it in
@@ -186,20 +269,29 @@ pub trait ProcessorTrait: Send + Sync + Debug {
 pub enum ProcessorConfig {
     AccountTransactionsProcessor,
     AnsProcessor(AnsProcessorConfig),
-    CoinProcessor,
+    CoinProcessor(CoinProcessorConfig),
+    CustomEventProcessor(CustomEventProcessorConfig),
     DefaultProcessor,
-    EventsProcessor,
-    FungibleAssetProcessor,
+    DexProcessor(DexProcessorConfig),
+    EventsProcessor(EventsProcessorConfig),
+    FungibleAssetProcessor(FungibleAssetProcessorConfig),
     MonitoringProcessor,
+    NftMarketplaceProcessor(NftMarketplaceProcessorConfig),
     NftMetadataProcessor(NftMetadataProcessorConfig),
     ObjectsProcessor(ObjectsProcessorConfig),
+    ResourceTrackingProcessor(ResourceTrackingProcessorConfig),
     StakeProcessor(StakeProcessorConfig),
     TokenProcessor(TokenProcessorConfig),
     TokenV2Processor(TokenV2ProcessorConfig),
     TransactionMetadataProcessor,
     UserTransactionProcessor,
+    WebhookProcessor(WebhookProcessorConfig),
+    ParquetAnsProcessor(ParquetAnsProcessorConfig),
     ParquetDefaultProcessor(ParquetDefaultProcessorConfig),
     ParquetFungibleAssetProcessor(ParquetFungibleAssetProcessorConfig),
+    ParquetObjectsProcessor(ParquetObjectsProcessorConfig),
+    ParquetStakeProcessor(ParquetStakeProcessorConfig),
+    ParquetTokenV2Processor(ParquetTokenV2ProcessorConfig),
 }
 
 impl ProcessorConfig {
@@ -212,10 +304,102 @@ impl ProcessorConfig {
     pub fn is_parquet_processor(&self) -> bool {
         matches!(
             self,
-            ProcessorConfig::ParquetDefaultProcessor(_)
+            ProcessorConfig::ParquetAnsProcessor(_)
+                | ProcessorConfig::ParquetDefaultProcessor(_)
                 | ProcessorConfig::ParquetFungibleAssetProcessor(_)
+                | ProcessorConfig::ParquetObjectsProcessor(_)
+                | ProcessorConfig::ParquetStakeProcessor(_)
+                | ProcessorConfig::ParquetTokenV2Processor(_)
         )
     }
+
+    /// Where the processor writes its rows. Empty for processors that only write to Postgres, and
+    /// for processors that use the default Postgres sink.
+    pub fn sinks(&self) -> &[SinkConfig] {
+        match self {
+            ProcessorConfig::CoinProcessor(config) => &config.sinks,
+            ProcessorConfig::EventsProcessor(config) => &config.sinks,
+            ProcessorConfig::FungibleAssetProcessor(config) => &config.sinks,
+            ProcessorConfig::TokenV2Processor(config) => &config.sinks,
+            _ => &[],
+        }
+    }
+
+    /// Whether batches have to be written in version order, because a sink publishes rows as
+    /// they come or the processor builds on what earlier batches wrote.
+    pub fn requires_ordered_batches(&self) -> bool {
+        matches!(self, ProcessorConfig::DexProcessor(_))
+            || self
+                .sinks()
+                .iter()
+                .any(|sink| sink.requires_ordered_batches())
+    }
+
+    /// Checks the parts of the config that serde can't, so that a bad config fails at startup
+    /// rather than when the processor is built.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            ProcessorConfig::CustomEventProcessor(config) => config.validate(),
+            ProcessorConfig::ResourceTrackingProcessor(config) => config.validate(),
+            ProcessorConfig::WebhookProcessor(config) => config.validate(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Tables this processor can be told to skip, through `deprecated_tables` or a backfill.
+    /// Empty for processors that always write every table.
+    pub fn table_flags(&self) -> TableFlags {
+        match self {
+            ProcessorConfig::AnsProcessor(_) => {
+                TableFlags::ANS_LOOKUP
+                    | TableFlags::ANS_LOOKUP_V2
+                    | TableFlags::ANS_PRIMARY_NAME
+                    | TableFlags::ANS_PRIMARY_NAME_V2
+                    | TableFlags::CURRENT_ANS_LOOKUP
+                    | TableFlags::CURRENT_ANS_LOOKUP_V2
+                    | TableFlags::CURRENT_ANS_PRIMARY_NAME
+                    | TableFlags::CURRENT_ANS_PRIMARY_NAME_V2
+            },
+            ProcessorConfig::DefaultProcessor => {
+                TableFlags::BLOCK_METADATA_TRANSACTIONS
+                    | TableFlags::CURRENT_TABLE_ITEMS
+                    | TableFlags::MOVE_MODULES
+                    | TableFlags::MOVE_RESOURCES
+                    | TableFlags::TABLE_ITEMS
+                    | TableFlags::TABLE_METADATAS
+                    | TableFlags::TRANSACTIONS
+                    | TableFlags::WRITE_SET_CHANGES
+            },
+            ProcessorConfig::FungibleAssetProcessor(_) => {
+                TableFlags::COIN_SUPPLY
+                    | TableFlags::CURRENT_FUNGIBLE_ASSET_BALANCES
+                    | TableFlags::CURRENT_UNIFIED_FUNGIBLE_ASSET_BALANCES
+                    | TableFlags::DAILY_FUNGIBLE_ASSET_BALANCES
+                    | TableFlags::FUNGIBLE_ASSET_ACTIVITIES
+                    | TableFlags::FUNGIBLE_ASSET_BALANCES
+                    | TableFlags::FUNGIBLE_ASSET_METADATA
+            },
+            ProcessorConfig::ObjectsProcessor(_) => {
+                TableFlags::CURRENT_OBJECTS | TableFlags::OBJECTS
+            },
+            ProcessorConfig::TokenV2Processor(_) => {
+                TableFlags::COLLECTIONS_V2
+                    | TableFlags::CURRENT_COLLECTIONS_V2
+                    | TableFlags::CURRENT_TOKEN_DATAS_V2
+                    | TableFlags::CURRENT_TOKEN_OWNERSHIPS_V2
+                    | TableFlags::CURRENT_TOKEN_PENDING_CLAIMS
+                    | TableFlags::CURRENT_TOKEN_ROYALTY_V1
+                    | TableFlags::CURRENT_TOKEN_V2_METADATA
+                    | TableFlags::TOKEN_ACTIVITIES_V2
+                    | TableFlags::TOKEN_DATAS_V2
+                    | TableFlags::TOKEN_OWNERSHIPS_V2
+            },
+            ProcessorConfig::UserTransactionProcessor => {
+                TableFlags::SIGNATURES | TableFlags::USER_TRANSACTIONS
+            },
+            _ => TableFlags::empty(),
+        }
+    }
 }
 
 /// This enum contains all the processors defined in this crate. We use enum_dispatch
@@ -239,19 +423,28 @@ pub enum Processor {
     AccountTransactionsProcessor,
     AnsProcessor,
     CoinProcessor,
+    CustomEventProcessor,
     DefaultProcessor,
+    DexProcessor,
     EventsProcessor,
     FungibleAssetProcessor,
     MonitoringProcessor,
+    NftMarketplaceProcessor,
     NftMetadataProcessor,
     ObjectsProcessor,
+    ResourceTrackingProcessor,
     StakeProcessor,
     TokenProcessor,
     TokenV2Processor,
     TransactionMetadataProcessor,
     UserTransactionProcessor,
+    WebhookProcessor,
+    ParquetAnsProcessor,
     ParquetDefaultProcessor,
     ParquetFungibleAssetProcessor,
+    ParquetObjectsProcessor,
+    ParquetStakeProcessor,
+    ParquetTokenV2Processor,
 }
 
 #[cfg(test)]
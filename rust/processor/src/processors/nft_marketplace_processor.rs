@@ -0,0 +1,442 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{DefaultProcessingResult, ProcessorName, ProcessorTrait};
+use crate::{
+    db::common::models::nft_marketplace_models::{
+        current_nft_marketplace_orders::{
+            CurrentNftMarketplaceCollectionOffer, CurrentNftMarketplaceListing,
+            CurrentNftMarketplaceOrderPK, CurrentNftMarketplaceTokenOffer,
+        },
+        marketplace_utils::{MarketplaceConfig, MarketplaceEvent, MarketplaceOrderType},
+        nft_marketplace_activities::NftMarketplaceActivity,
+    },
+    gap_detectors::ProcessingResult,
+    schema,
+    utils::{
+        counters::PROCESSOR_UNKNOWN_TYPE_COUNT,
+        database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
+        util::{get_entry_function_from_user_request, parse_timestamp, standardize_address},
+    },
+};
+use ahash::{AHashMap, AHashSet};
+use anyhow::bail;
+use aptos_protos::transaction::v1::{transaction::TxnData, write_set_change::Change, Transaction};
+use async_trait::async_trait;
+use diesel::{
+    pg::{upsert::excluded, Pg},
+    query_builder::QueryFragment,
+    ExpressionMethods,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use tracing::error;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct NftMarketplaceProcessorConfig {
+    pub marketplaces: Vec<MarketplaceConfig>,
+}
+
+pub struct NftMarketplaceProcessor {
+    connection_pool: ArcDbPool,
+    config: NftMarketplaceProcessorConfig,
+    per_table_chunk_sizes: AHashMap<String, usize>,
+}
+
+impl NftMarketplaceProcessor {
+    pub fn new(
+        connection_pool: ArcDbPool,
+        config: NftMarketplaceProcessorConfig,
+        per_table_chunk_sizes: AHashMap<String, usize>,
+    ) -> Self {
+        Self {
+            connection_pool,
+            config,
+            per_table_chunk_sizes,
+        }
+    }
+}
+
+impl Debug for NftMarketplaceProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = &self.connection_pool.state();
+        write!(
+            f,
+            "NftMarketplaceProcessor {{ connections: {:?}  idle_connections: {:?} }}",
+            state.connections, state.idle_connections
+        )
+    }
+}
+
+async fn insert_to_db(
+    conn: ArcDbPool,
+    name: &'static str,
+    start_version: u64,
+    end_version: u64,
+    (activities, listings, token_offers, collection_offers): (
+        &[NftMarketplaceActivity],
+        &[CurrentNftMarketplaceListing],
+        &[CurrentNftMarketplaceTokenOffer],
+        &[CurrentNftMarketplaceCollectionOffer],
+    ),
+    per_table_chunk_sizes: &AHashMap<String, usize>,
+) -> Result<(), diesel::result::Error> {
+    tracing::trace!(
+        name = name,
+        start_version = start_version,
+        end_version = end_version,
+        "Inserting to db",
+    );
+
+    let a = execute_in_chunks(
+        conn.clone(),
+        insert_nft_marketplace_activities_query,
+        activities,
+        get_config_table_chunk_size::<NftMarketplaceActivity>(
+            "nft_marketplace_activities",
+            per_table_chunk_sizes,
+        ),
+    );
+    let l = execute_in_chunks(
+        conn.clone(),
+        insert_current_nft_marketplace_listings_query,
+        listings,
+        get_config_table_chunk_size::<CurrentNftMarketplaceListing>(
+            "current_nft_marketplace_listings",
+            per_table_chunk_sizes,
+        ),
+    );
+    let to = execute_in_chunks(
+        conn.clone(),
+        insert_current_nft_marketplace_token_offers_query,
+        token_offers,
+        get_config_table_chunk_size::<CurrentNftMarketplaceTokenOffer>(
+            "current_nft_marketplace_token_offers",
+            per_table_chunk_sizes,
+        ),
+    );
+    let co = execute_in_chunks(
+        conn,
+        insert_current_nft_marketplace_collection_offers_query,
+        collection_offers,
+        get_config_table_chunk_size::<CurrentNftMarketplaceCollectionOffer>(
+            "current_nft_marketplace_collection_offers",
+            per_table_chunk_sizes,
+        ),
+    );
+    let (a_res, l_res, to_res, co_res) = tokio::join!(a, l, to, co);
+    for res in [a_res, l_res, to_res, co_res] {
+        res?;
+    }
+
+    Ok(())
+}
+
+fn insert_nft_marketplace_activities_query(
+    items_to_insert: Vec<NftMarketplaceActivity>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::nft_marketplace_activities::dsl::*;
+    (
+        diesel::insert_into(schema::nft_marketplace_activities::table)
+            .values(items_to_insert)
+            .on_conflict((transaction_version, event_index))
+            .do_nothing(),
+        None,
+    )
+}
+
+fn insert_current_nft_marketplace_listings_query(
+    items_to_insert: Vec<CurrentNftMarketplaceListing>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::current_nft_marketplace_listings::dsl::*;
+    (
+        diesel::insert_into(schema::current_nft_marketplace_listings::table)
+            .values(items_to_insert)
+            .on_conflict((contract_address, listing_id))
+            .do_update()
+            .set((
+                marketplace.eq(excluded(marketplace)),
+                collection_id.eq(excluded(collection_id)),
+                token_data_id.eq(excluded(token_data_id)),
+                token_standard.eq(excluded(token_standard)),
+                price.eq(excluded(price)),
+                token_amount.eq(excluded(token_amount)),
+                seller.eq(excluded(seller)),
+                is_deleted.eq(excluded(is_deleted)),
+                entry_function_id_str.eq(excluded(entry_function_id_str)),
+                last_transaction_version.eq(excluded(last_transaction_version)),
+                last_transaction_timestamp.eq(excluded(last_transaction_timestamp)),
+                inserted_at.eq(excluded(inserted_at)),
+            )),
+        Some(" WHERE current_nft_marketplace_listings.last_transaction_version <= excluded.last_transaction_version "),
+    )
+}
+
+fn insert_current_nft_marketplace_token_offers_query(
+    items_to_insert: Vec<CurrentNftMarketplaceTokenOffer>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::current_nft_marketplace_token_offers::dsl::*;
+    (
+        diesel::insert_into(schema::current_nft_marketplace_token_offers::table)
+            .values(items_to_insert)
+            .on_conflict((contract_address, offer_id))
+            .do_update()
+            .set((
+                marketplace.eq(excluded(marketplace)),
+                collection_id.eq(excluded(collection_id)),
+                token_data_id.eq(excluded(token_data_id)),
+                token_standard.eq(excluded(token_standard)),
+                price.eq(excluded(price)),
+                token_amount.eq(excluded(token_amount)),
+                buyer.eq(excluded(buyer)),
+                is_deleted.eq(excluded(is_deleted)),
+                entry_function_id_str.eq(excluded(entry_function_id_str)),
+                last_transaction_version.eq(excluded(last_transaction_version)),
+                last_transaction_timestamp.eq(excluded(last_transaction_timestamp)),
+                inserted_at.eq(excluded(inserted_at)),
+            )),
+        Some(" WHERE current_nft_marketplace_token_offers.last_transaction_version <= excluded.last_transaction_version "),
+    )
+}
+
+fn insert_current_nft_marketplace_collection_offers_query(
+    items_to_insert: Vec<CurrentNftMarketplaceCollectionOffer>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::current_nft_marketplace_collection_offers::dsl::*;
+    (
+        diesel::insert_into(schema::current_nft_marketplace_collection_offers::table)
+            .values(items_to_insert)
+            .on_conflict((contract_address, collection_offer_id))
+            .do_update()
+            .set((
+                marketplace.eq(excluded(marketplace)),
+                collection_id.eq(excluded(collection_id)),
+                token_standard.eq(excluded(token_standard)),
+                item_price.eq(excluded(item_price)),
+                remaining_token_amount.eq(excluded(remaining_token_amount)),
+                buyer.eq(excluded(buyer)),
+                is_deleted.eq(excluded(is_deleted)),
+                entry_function_id_str.eq(excluded(entry_function_id_str)),
+                last_transaction_version.eq(excluded(last_transaction_version)),
+                last_transaction_timestamp.eq(excluded(last_transaction_timestamp)),
+                inserted_at.eq(excluded(inserted_at)),
+            )),
+        Some(" WHERE current_nft_marketplace_collection_offers.last_transaction_version <= excluded.last_transaction_version "),
+    )
+}
+
+#[async_trait]
+impl ProcessorTrait for NftMarketplaceProcessor {
+    fn name(&self) -> &'static str {
+        ProcessorName::NftMarketplaceProcessor.into()
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+        _: Option<u64>,
+    ) -> anyhow::Result<ProcessingResult> {
+        let processing_start = std::time::Instant::now();
+        let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
+
+        let (activities, listings, token_offers, collection_offers) =
+            parse_nft_marketplace_events(&transactions, &self.config.marketplaces);
+
+        let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
+        let db_insertion_start = std::time::Instant::now();
+
+        let tx_result = insert_to_db(
+            self.get_pool(),
+            self.name(),
+            start_version,
+            end_version,
+            (&activities, &listings, &token_offers, &collection_offers),
+            &self.per_table_chunk_sizes,
+        )
+        .await;
+        let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
+
+        match tx_result {
+            Ok(_) => Ok(ProcessingResult::DefaultProcessingResult(
+                DefaultProcessingResult {
+                    start_version,
+                    end_version,
+                    processing_duration_in_secs,
+                    db_insertion_duration_in_secs,
+                    last_transaction_timestamp,
+                },
+            )),
+            Err(e) => {
+                error!(
+                    start_version = start_version,
+                    end_version = end_version,
+                    processor_name = self.name(),
+                    error = ?e,
+                    "[Parser] Error inserting transactions to db",
+                );
+                bail!(e)
+            },
+        }
+    }
+
+    fn connection_pool(&self) -> &ArcDbPool {
+        &self.connection_pool
+    }
+}
+
+pub fn parse_nft_marketplace_events(
+    transactions: &[Transaction],
+    marketplaces: &[MarketplaceConfig],
+) -> (
+    Vec<NftMarketplaceActivity>,
+    Vec<CurrentNftMarketplaceListing>,
+    Vec<CurrentNftMarketplaceTokenOffer>,
+    Vec<CurrentNftMarketplaceCollectionOffer>,
+) {
+    let mut activities = vec![];
+    // Orders touched more than once in the batch keep their latest state
+    let mut listings: AHashMap<CurrentNftMarketplaceOrderPK, CurrentNftMarketplaceListing> =
+        AHashMap::new();
+    let mut token_offers: AHashMap<CurrentNftMarketplaceOrderPK, CurrentNftMarketplaceTokenOffer> =
+        AHashMap::new();
+    let mut collection_offers: AHashMap<
+        CurrentNftMarketplaceOrderPK,
+        CurrentNftMarketplaceCollectionOffer,
+    > = AHashMap::new();
+
+    for txn in transactions {
+        let txn_version = txn.version as i64;
+        let user_txn = match txn.txn_data.as_ref() {
+            Some(TxnData::User(user_txn)) => user_txn,
+            Some(_) => continue,
+            None => {
+                PROCESSOR_UNKNOWN_TYPE_COUNT
+                    .with_label_values(&["NftMarketplaceProcessor"])
+                    .inc();
+                tracing::warn!(
+                    transaction_version = txn_version,
+                    "Transaction data doesn't exist"
+                );
+                continue;
+            },
+        };
+        let txn_timestamp = parse_timestamp(txn.timestamp.as_ref().unwrap(), txn_version);
+        let entry_function_id_str =
+            get_entry_function_from_user_request(user_txn.request.as_ref().unwrap());
+        // Collection offers whose object is deleted have no tokens left to buy
+        let deleted_object_addresses: AHashSet<String> = txn
+            .info
+            .as_ref()
+            .map(|info| info.changes.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|wsc| match wsc.change.as_ref() {
+                Some(Change::DeleteResource(dr)) => Some(standardize_address(&dr.address)),
+                _ => None,
+            })
+            .collect();
+
+        for (index, event) in user_txn.events.iter().enumerate() {
+            let Some((marketplace, kind)) = marketplaces.iter().find_map(|marketplace| {
+                marketplace
+                    .get_event_kind(&event.type_str)
+                    .map(|kind| (marketplace, kind))
+            }) else {
+                continue;
+            };
+            let parsed = match MarketplaceEvent::from_event_data(
+                kind,
+                &event.data,
+                &marketplace.fields,
+                txn_version,
+            ) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    PROCESSOR_UNKNOWN_TYPE_COUNT
+                        .with_label_values(&["NftMarketplaceProcessor"])
+                        .inc();
+                    tracing::warn!(
+                        transaction_version = txn_version,
+                        event_index = index,
+                        event_type = event.type_str.as_str(),
+                        error = ?e,
+                        "Skipping marketplace event that can't be parsed"
+                    );
+                    continue;
+                },
+            };
+            activities.push(NftMarketplaceActivity::from_marketplace_event(
+                marketplace,
+                &event.type_str,
+                &parsed,
+                txn_version,
+                index as i64,
+                &entry_function_id_str,
+                txn_timestamp,
+            ));
+            match kind.get_order_type() {
+                MarketplaceOrderType::Listing => {
+                    if let Some(listing) = CurrentNftMarketplaceListing::from_marketplace_event(
+                        marketplace,
+                        &parsed,
+                        txn_version,
+                        &entry_function_id_str,
+                        txn_timestamp,
+                    ) {
+                        listings.insert(listing.get_pk(), listing);
+                    }
+                },
+                MarketplaceOrderType::TokenOffer => {
+                    if let Some(offer) = CurrentNftMarketplaceTokenOffer::from_marketplace_event(
+                        marketplace,
+                        &parsed,
+                        txn_version,
+                        &entry_function_id_str,
+                        txn_timestamp,
+                    ) {
+                        token_offers.insert(offer.get_pk(), offer);
+                    }
+                },
+                MarketplaceOrderType::CollectionOffer => {
+                    if let Some(offer) =
+                        CurrentNftMarketplaceCollectionOffer::from_marketplace_event(
+                            marketplace,
+                            &parsed,
+                            txn_version,
+                            &entry_function_id_str,
+                            txn_timestamp,
+                            &deleted_object_addresses,
+                        )
+                    {
+                        collection_offers.insert(offer.get_pk(), offer);
+                    }
+                },
+            }
+        }
+    }
+
+    // Sort by PK
+    let mut listings = listings.into_values().collect::<Vec<_>>();
+    listings.sort_by(|a, b| a.get_pk().cmp(&b.get_pk()));
+    let mut token_offers = token_offers.into_values().collect::<Vec<_>>();
+    token_offers.sort_by(|a, b| a.get_pk().cmp(&b.get_pk()));
+    let mut collection_offers = collection_offers.into_values().collect::<Vec<_>>();
+    collection_offers.sort_by(|a, b| a.get_pk().cmp(&b.get_pk()));
+
+    (activities, listings, token_offers, collection_offers)
+}
@@ -10,7 +10,7 @@ use crate::{
     gap_detectors::ProcessingResult,
     schema,
     utils::{
-        database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
+        database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool, DbPoolConnection},
         util::standardize_address,
     },
     worker::TableFlags,
@@ -168,105 +168,20 @@ impl ProcessorTrait for ObjectsProcessor {
         let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
 
         let mut conn = self.get_conn().await;
-        let query_retries = self.config.query_retries;
-        let query_retry_delay_ms = self.config.query_retry_delay_ms;
-
-        // Moving object handling here because we need a single object
-        // map through transactions for lookups
-        let mut all_objects = vec![];
-        let mut all_current_objects = AHashMap::new();
-        let mut object_metadata_helper: ObjectAggregatedDataMapping = AHashMap::new();
-
-        for txn in &transactions {
-            let txn_version = txn.version as i64;
-            let vec = vec![];
-            let changes = &txn.info.as_ref().map(|info| &info.changes).unwrap_or(&vec);
-
-            // First pass to get all the object cores
-            for wsc in changes.iter() {
-                if let Change::WriteResource(wr) = wsc.change.as_ref().unwrap() {
-                    let address = standardize_address(&wr.address.to_string());
-                    if let Some(object_with_metadata) =
-                        ObjectWithMetadata::from_write_resource(wr, txn_version).unwrap()
-                    {
-                        // Object core is the first struct that we need to get
-                        object_metadata_helper.insert(
-                            address.clone(),
-                            ObjectAggregatedData {
-                                object: object_with_metadata,
-                                token: None,
-                                fungible_asset_store: None,
-                                // The following structs are unused in this processor
-                                fungible_asset_metadata: None,
-                                aptos_collection: None,
-                                fixed_supply: None,
-                                unlimited_supply: None,
-                                concurrent_supply: None,
-                                property_map: None,
-                                transfer_events: vec![],
-                                untransferable: None,
-                                fungible_asset_supply: None,
-                                concurrent_fungible_asset_supply: None,
-                                concurrent_fungible_asset_balance: None,
-                                token_identifier: None,
-                            },
-                        );
-                    }
-                }
-            }
-
-            // Second pass to construct the object data
-            for (index, wsc) in changes.iter().enumerate() {
-                let index: i64 = index as i64;
-                match wsc.change.as_ref().unwrap() {
-                    Change::WriteResource(inner) => {
-                        if let Some((object, current_object)) = &Object::from_write_resource(
-                            inner,
-                            txn_version,
-                            index,
-                            &object_metadata_helper,
-                        )
-                        .unwrap()
-                        {
-                            all_objects.push(object.clone());
-                            all_current_objects
-                                .insert(object.object_address.clone(), current_object.clone());
-                        }
-                    },
-                    Change::DeleteResource(inner) => {
-                        // Passing all_current_objects into the function so that we can get the owner of the deleted
-                        // resource if it was handled in the same batch
-                        if let Some((object, current_object)) = Object::from_delete_resource(
-                            inner,
-                            txn_version,
-                            index,
-                            &all_current_objects,
-                            &mut conn,
-                            query_retries,
-                            query_retry_delay_ms,
-                        )
-                        .await
-                        .unwrap()
-                        {
-                            all_objects.push(object.clone());
-                            all_current_objects
-                                .insert(object.object_address.clone(), current_object.clone());
-                        }
-                    },
-                    _ => {},
-                };
-            }
-        }
-
-        // Sort by PK
-        let mut all_current_objects = all_current_objects
-            .into_values()
-            .collect::<Vec<CurrentObject>>();
-        all_current_objects.sort_by(|a, b| a.object_address.cmp(&b.object_address));
+        let (mut all_objects, mut all_current_objects) = parse_objects(
+            &transactions,
+            &mut conn,
+            self.config.query_retries,
+            self.config.query_retry_delay_ms,
+        )
+        .await;
 
         if self.deprecated_tables.contains(TableFlags::OBJECTS) {
             all_objects.clear();
         }
+        if self.deprecated_tables.contains(TableFlags::CURRENT_OBJECTS) {
+            all_current_objects.clear();
+        }
 
         let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
         let db_insertion_start = std::time::Instant::now();
@@ -309,3 +224,105 @@ impl ProcessorTrait for ObjectsProcessor {
         &self.connection_pool
     }
 }
+
+pub async fn parse_objects(
+    transactions: &[Transaction],
+    conn: &mut DbPoolConnection<'_>,
+    query_retries: u32,
+    query_retry_delay_ms: u64,
+) -> (Vec<Object>, Vec<CurrentObject>) {
+    // Moving object handling here because we need a single object
+    // map through transactions for lookups
+    let mut all_objects = vec![];
+    let mut all_current_objects = AHashMap::new();
+    let mut object_metadata_helper: ObjectAggregatedDataMapping = AHashMap::new();
+
+    for txn in transactions {
+        let txn_version = txn.version as i64;
+        let vec = vec![];
+        let changes = &txn.info.as_ref().map(|info| &info.changes).unwrap_or(&vec);
+
+        // First pass to get all the object cores
+        for wsc in changes.iter() {
+            if let Change::WriteResource(wr) = wsc.change.as_ref().unwrap() {
+                let address = standardize_address(&wr.address.to_string());
+                if let Some(object_with_metadata) =
+                    ObjectWithMetadata::from_write_resource(wr, txn_version).unwrap()
+                {
+                    // Object core is the first struct that we need to get
+                    object_metadata_helper.insert(
+                        address.clone(),
+                        ObjectAggregatedData {
+                            object: object_with_metadata,
+                            token: None,
+                            fungible_asset_store: None,
+                            // The following structs are unused in this processor
+                            fungible_asset_metadata: None,
+                            aptos_collection: None,
+                            fixed_supply: None,
+                            unlimited_supply: None,
+                            concurrent_supply: None,
+                            property_map: None,
+                            transfer_events: vec![],
+                            untransferable: None,
+                            fungible_asset_supply: None,
+                            concurrent_fungible_asset_supply: None,
+                            concurrent_fungible_asset_balance: None,
+                            token_identifier: None,
+                        },
+                    );
+                }
+            }
+        }
+
+        // Second pass to construct the object data
+        for (index, wsc) in changes.iter().enumerate() {
+            let index: i64 = index as i64;
+            match wsc.change.as_ref().unwrap() {
+                Change::WriteResource(inner) => {
+                    if let Some((object, current_object)) = &Object::from_write_resource(
+                        inner,
+                        txn_version,
+                        index,
+                        &object_metadata_helper,
+                    )
+                    .unwrap()
+                    {
+                        all_objects.push(object.clone());
+                        all_current_objects
+                            .insert(object.object_address.clone(), current_object.clone());
+                    }
+                },
+                Change::DeleteResource(inner) => {
+                    // Passing all_current_objects into the function so that we can get the owner of the deleted
+                    // resource if it was handled in the same batch
+                    if let Some((object, current_object)) = Object::from_delete_resource(
+                        inner,
+                        txn_version,
+                        index,
+                        &all_current_objects,
+                        conn,
+                        query_retries,
+                        query_retry_delay_ms,
+                    )
+                    .await
+                    .unwrap()
+                    {
+                        all_objects.push(object.clone());
+                        all_current_objects
+                            .insert(object.object_address.clone(), current_object.clone());
+                    }
+                },
+                _ => {},
+            };
+        }
+    }
+
+    // Sort by PK
+    let mut all_current_objects = all_current_objects
+        .into_values()
+        .collect::<Vec<CurrentObject>>();
+    all_current_objects.sort_by(|a, b| a.object_address.cmp(&b.object_address));
+
+    (all_objects, all_current_objects)
+}
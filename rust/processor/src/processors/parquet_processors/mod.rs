@@ -1,11 +1,74 @@
+use crate::{
+    bq_analytics::generic_parquet_processor::{HasVersion, ParquetDataGeneric},
+    utils::util::parse_timestamp,
+};
+use ahash::AHashMap;
+use anyhow::anyhow;
+use aptos_protos::transaction::v1::Transaction;
+use chrono::NaiveDateTime;
+use kanal::AsyncSender;
 use std::time::Duration;
 
+pub mod parquet_ans_processor;
+
 pub mod parquet_default_processor;
 
 pub mod parquet_fungible_asset_processor;
 
+pub mod parquet_objects_processor;
+
+pub mod parquet_stake_processor;
+
+pub mod parquet_token_v2_processor;
+
 pub const GOOGLE_APPLICATION_CREDENTIALS: &str = "GOOGLE_APPLICATION_CREDENTIALS";
 
 pub trait UploadIntervalConfig {
     fn parquet_upload_interval_in_secs(&self) -> Duration;
 }
+
+/// Block timestamp of every transaction in the batch, for parquet rows built from models that
+/// don't carry one themselves.
+pub fn get_version_to_block_timestamp(
+    transactions: &[Transaction],
+) -> AHashMap<i64, NaiveDateTime> {
+    transactions
+        .iter()
+        .map(|txn| {
+            let txn_version = txn.version as i64;
+            let timestamp = txn
+                .timestamp
+                .as_ref()
+                .expect("Transaction timestamp doesn't exist!");
+            (txn_version, parse_timestamp(timestamp, txn_version))
+        })
+        .collect()
+}
+
+/// Adds one to the struct count of each row's version. The gap detector only moves past a
+/// version once this many rows from it have been uploaded.
+pub fn count_structs<ParquetType: HasVersion>(
+    transaction_version_to_struct_count: &mut AHashMap<i64, i64>,
+    data: &[ParquetType],
+) {
+    for row in data {
+        transaction_version_to_struct_count
+            .entry(row.version())
+            .and_modify(|e| *e += 1)
+            .or_insert(1);
+    }
+}
+
+pub async fn send_to_parquet_handler<ParquetType>(
+    sender: &AsyncSender<ParquetDataGeneric<ParquetType>>,
+    data: Vec<ParquetType>,
+    transaction_version_to_struct_count: &AHashMap<i64, i64>,
+) -> anyhow::Result<()> {
+    sender
+        .send(ParquetDataGeneric {
+            data,
+            transaction_version_to_struct_count: transaction_version_to_struct_count.clone(),
+        })
+        .await
+        .map_err(|e| anyhow!("Failed to send to parquet manager: {}", e))
+}
@@ -0,0 +1,182 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    count_structs, get_version_to_block_timestamp, send_to_parquet_handler, UploadIntervalConfig,
+    GOOGLE_APPLICATION_CREDENTIALS,
+};
+use crate::{
+    bq_analytics::{
+        create_parquet_handler_loop, generic_parquet_processor::ParquetDataGeneric,
+        storage::ParquetStorageConfig, ParquetProcessingResult,
+    },
+    db::common::models::ans_models::parquet_ans_lookup_v2::{AnsLookupV2, AnsPrimaryNameV2},
+    gap_detectors::ProcessingResult,
+    processors::{ans_processor::parse_ans, ProcessorName, ProcessorTrait},
+    utils::database::ArcDbPool,
+};
+use ahash::AHashMap;
+use aptos_protos::transaction::v1::Transaction;
+use async_trait::async_trait;
+use kanal::AsyncSender;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, time::Duration};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParquetAnsProcessorConfig {
+    pub google_application_credentials: Option<String>,
+    pub bucket_name: String,
+    pub bucket_root: String,
+    #[serde(default)]
+    pub storage: ParquetStorageConfig,
+    pub parquet_handler_response_channel_size: usize,
+    pub max_buffer_size: usize,
+    pub parquet_upload_interval: u64,
+    pub ans_v1_primary_names_table_handle: String,
+    pub ans_v1_name_records_table_handle: String,
+    pub ans_v2_contract_address: String,
+}
+
+impl UploadIntervalConfig for ParquetAnsProcessorConfig {
+    fn parquet_upload_interval_in_secs(&self) -> Duration {
+        Duration::from_secs(self.parquet_upload_interval)
+    }
+}
+
+pub struct ParquetAnsProcessor {
+    connection_pool: ArcDbPool,
+    config: ParquetAnsProcessorConfig,
+    ans_lookup_v2_sender: AsyncSender<ParquetDataGeneric<AnsLookupV2>>,
+    ans_primary_name_v2_sender: AsyncSender<ParquetDataGeneric<AnsPrimaryNameV2>>,
+}
+
+impl ParquetAnsProcessor {
+    pub fn new(
+        connection_pool: ArcDbPool,
+        config: ParquetAnsProcessorConfig,
+        new_gap_detector_sender: AsyncSender<ProcessingResult>,
+    ) -> Self {
+        if let Some(credentials) = config.google_application_credentials.clone() {
+            std::env::set_var(GOOGLE_APPLICATION_CREDENTIALS, credentials);
+        }
+
+        let ans_lookup_v2_sender = create_parquet_handler_loop::<AnsLookupV2>(
+            new_gap_detector_sender.clone(),
+            ProcessorName::ParquetAnsProcessor.into(),
+            config.bucket_name.clone(),
+            config.bucket_root.clone(),
+            config.storage.clone(),
+            config.parquet_handler_response_channel_size,
+            config.max_buffer_size,
+            config.parquet_upload_interval_in_secs(),
+        );
+
+        let ans_primary_name_v2_sender = create_parquet_handler_loop::<AnsPrimaryNameV2>(
+            new_gap_detector_sender.clone(),
+            ProcessorName::ParquetAnsProcessor.into(),
+            config.bucket_name.clone(),
+            config.bucket_root.clone(),
+            config.storage.clone(),
+            config.parquet_handler_response_channel_size,
+            config.max_buffer_size,
+            config.parquet_upload_interval_in_secs(),
+        );
+
+        Self {
+            connection_pool,
+            config,
+            ans_lookup_v2_sender,
+            ans_primary_name_v2_sender,
+        }
+    }
+}
+
+impl Debug for ParquetAnsProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ParquetAnsProcessor {{ capacity of ans lookup channel: {:?}, capacity of ans primary name channel: {:?} }}",
+            &self.ans_lookup_v2_sender.capacity(),
+            &self.ans_primary_name_v2_sender.capacity(),
+        )
+    }
+}
+
+#[async_trait]
+impl ProcessorTrait for ParquetAnsProcessor {
+    fn name(&self) -> &'static str {
+        ProcessorName::ParquetAnsProcessor.into()
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+        _: Option<u64>,
+    ) -> anyhow::Result<ProcessingResult> {
+        let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
+        let version_to_block_timestamp = get_version_to_block_timestamp(&transactions);
+
+        // Only the v2 tables are exported, they already include the v1 names
+        let (_, _, _, _, _, all_ans_lookups_v2, _, all_ans_primary_names_v2) = parse_ans(
+            &transactions,
+            self.config.ans_v1_primary_names_table_handle.clone(),
+            self.config.ans_v1_name_records_table_handle.clone(),
+            self.config.ans_v2_contract_address.clone(),
+        );
+
+        let ans_lookups_v2: Vec<AnsLookupV2> = all_ans_lookups_v2
+            .iter()
+            .map(|lookup| {
+                AnsLookupV2::from_model(
+                    lookup,
+                    version_to_block_timestamp[&lookup.transaction_version],
+                )
+            })
+            .collect();
+        let ans_primary_names_v2: Vec<AnsPrimaryNameV2> = all_ans_primary_names_v2
+            .iter()
+            .map(|primary_name| {
+                AnsPrimaryNameV2::from_model(
+                    primary_name,
+                    version_to_block_timestamp[&primary_name.transaction_version],
+                )
+            })
+            .collect();
+
+        let mut transaction_version_to_struct_count: AHashMap<i64, i64> = AHashMap::new();
+        count_structs(&mut transaction_version_to_struct_count, &ans_lookups_v2);
+        count_structs(
+            &mut transaction_version_to_struct_count,
+            &ans_primary_names_v2,
+        );
+
+        send_to_parquet_handler(
+            &self.ans_lookup_v2_sender,
+            ans_lookups_v2,
+            &transaction_version_to_struct_count,
+        )
+        .await?;
+        send_to_parquet_handler(
+            &self.ans_primary_name_v2_sender,
+            ans_primary_names_v2,
+            &transaction_version_to_struct_count,
+        )
+        .await?;
+
+        Ok(ProcessingResult::ParquetProcessingResult(
+            ParquetProcessingResult {
+                start_version: start_version as i64,
+                end_version: end_version as i64,
+                last_transaction_timestamp,
+                txn_version_to_struct_count: AHashMap::new(),
+            },
+        ))
+    }
+
+    fn connection_pool(&self) -> &ArcDbPool {
+        &self.connection_pool
+    }
+}
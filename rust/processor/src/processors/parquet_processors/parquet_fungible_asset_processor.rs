@@ -5,7 +5,7 @@ use super::{UploadIntervalConfig, GOOGLE_APPLICATION_CREDENTIALS};
 use crate::{
     bq_analytics::{
         create_parquet_handler_loop, generic_parquet_processor::ParquetDataGeneric,
-        ParquetProcessingResult,
+        storage::ParquetStorageConfig, ParquetProcessingResult,
     },
     db::common::models::{
         fungible_asset_models::{
@@ -35,6 +35,8 @@ pub struct ParquetFungibleAssetProcessorConfig {
     pub google_application_credentials: Option<String>,
     pub bucket_name: String,
     pub bucket_root: String,
+    #[serde(default)]
+    pub storage: ParquetStorageConfig,
     pub parquet_handler_response_channel_size: usize,
     pub max_buffer_size: usize,
     pub parquet_upload_interval: u64,
@@ -67,6 +69,7 @@ impl ParquetFungibleAssetProcessor {
             ProcessorName::ParquetFungibleAssetProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.storage.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -77,6 +80,7 @@ impl ParquetFungibleAssetProcessor {
             ProcessorName::ParquetFungibleAssetProcessor.into(),
             config.bucket_name.clone(),
             config.bucket_root.clone(),
+            config.storage.clone(),
             config.parquet_handler_response_channel_size,
             config.max_buffer_size,
             config.parquet_upload_interval_in_secs(),
@@ -0,0 +1,156 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    count_structs, get_version_to_block_timestamp, send_to_parquet_handler, UploadIntervalConfig,
+    GOOGLE_APPLICATION_CREDENTIALS,
+};
+use crate::{
+    bq_analytics::{
+        create_parquet_handler_loop, generic_parquet_processor::ParquetDataGeneric,
+        storage::ParquetStorageConfig, ParquetProcessingResult,
+    },
+    db::common::models::object_models::parquet_v2_objects::Object,
+    gap_detectors::ProcessingResult,
+    processors::{objects_processor::parse_objects, ProcessorName, ProcessorTrait},
+    utils::database::ArcDbPool,
+    IndexerGrpcProcessorConfig,
+};
+use ahash::AHashMap;
+use aptos_protos::transaction::v1::Transaction;
+use async_trait::async_trait;
+use kanal::AsyncSender;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, time::Duration};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParquetObjectsProcessorConfig {
+    pub google_application_credentials: Option<String>,
+    pub bucket_name: String,
+    pub bucket_root: String,
+    #[serde(default)]
+    pub storage: ParquetStorageConfig,
+    pub parquet_handler_response_channel_size: usize,
+    pub max_buffer_size: usize,
+    pub parquet_upload_interval: u64,
+    #[serde(default = "IndexerGrpcProcessorConfig::default_query_retries")]
+    pub query_retries: u32,
+    #[serde(default = "IndexerGrpcProcessorConfig::default_query_retry_delay_ms")]
+    pub query_retry_delay_ms: u64,
+}
+
+impl UploadIntervalConfig for ParquetObjectsProcessorConfig {
+    fn parquet_upload_interval_in_secs(&self) -> Duration {
+        Duration::from_secs(self.parquet_upload_interval)
+    }
+}
+
+/// Writes `objects` to parquet. The owner of a deleted object is taken from the batch when the
+/// object was written earlier in it, and otherwise looked up in `current_objects`, so the Postgres
+/// `objects_processor` has to run against the same database and be ahead of this processor.
+pub struct ParquetObjectsProcessor {
+    connection_pool: ArcDbPool,
+    config: ParquetObjectsProcessorConfig,
+    objects_sender: AsyncSender<ParquetDataGeneric<Object>>,
+}
+
+impl ParquetObjectsProcessor {
+    pub fn new(
+        connection_pool: ArcDbPool,
+        config: ParquetObjectsProcessorConfig,
+        new_gap_detector_sender: AsyncSender<ProcessingResult>,
+    ) -> Self {
+        if let Some(credentials) = config.google_application_credentials.clone() {
+            std::env::set_var(GOOGLE_APPLICATION_CREDENTIALS, credentials);
+        }
+
+        let objects_sender = create_parquet_handler_loop::<Object>(
+            new_gap_detector_sender.clone(),
+            ProcessorName::ParquetObjectsProcessor.into(),
+            config.bucket_name.clone(),
+            config.bucket_root.clone(),
+            config.storage.clone(),
+            config.parquet_handler_response_channel_size,
+            config.max_buffer_size,
+            config.parquet_upload_interval_in_secs(),
+        );
+
+        Self {
+            connection_pool,
+            config,
+            objects_sender,
+        }
+    }
+}
+
+impl Debug for ParquetObjectsProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ParquetObjectsProcessor {{ capacity of objects channel: {:?} }}",
+            &self.objects_sender.capacity(),
+        )
+    }
+}
+
+#[async_trait]
+impl ProcessorTrait for ParquetObjectsProcessor {
+    fn name(&self) -> &'static str {
+        ProcessorName::ParquetObjectsProcessor.into()
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+        _: Option<u64>,
+    ) -> anyhow::Result<ProcessingResult> {
+        let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
+        let version_to_block_timestamp = get_version_to_block_timestamp(&transactions);
+
+        // Deleted objects that aren't in the batch are looked up in current_objects for the owner
+        let mut conn = self.get_conn().await;
+        let (objects, _) = parse_objects(
+            &transactions,
+            &mut conn,
+            self.config.query_retries,
+            self.config.query_retry_delay_ms,
+        )
+        .await;
+
+        let objects: Vec<Object> = objects
+            .iter()
+            .map(|object| {
+                Object::from_model(
+                    object,
+                    version_to_block_timestamp[&object.transaction_version],
+                )
+            })
+            .collect();
+
+        let mut transaction_version_to_struct_count: AHashMap<i64, i64> = AHashMap::new();
+        count_structs(&mut transaction_version_to_struct_count, &objects);
+
+        send_to_parquet_handler(
+            &self.objects_sender,
+            objects,
+            &transaction_version_to_struct_count,
+        )
+        .await?;
+
+        Ok(ProcessingResult::ParquetProcessingResult(
+            ParquetProcessingResult {
+                start_version: start_version as i64,
+                end_version: end_version as i64,
+                last_transaction_timestamp,
+                txn_version_to_struct_count: AHashMap::new(),
+            },
+        ))
+    }
+
+    fn connection_pool(&self) -> &ArcDbPool {
+        &self.connection_pool
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    count_structs, get_version_to_block_timestamp, send_to_parquet_handler, UploadIntervalConfig,
+    GOOGLE_APPLICATION_CREDENTIALS,
+};
+use crate::{
+    bq_analytics::{
+        create_parquet_handler_loop, generic_parquet_processor::ParquetDataGeneric,
+        storage::ParquetStorageConfig, ParquetProcessingResult,
+    },
+    db::common::models::stake_models::{
+        parquet_delegator_activities::DelegatedStakingActivity as ParquetDelegatedStakingActivity,
+        parquet_delegator_balances::DelegatorBalance as ParquetDelegatorBalance,
+        parquet_delegator_pools::DelegatorPoolBalance as ParquetDelegatorPoolBalance,
+        parquet_proposal_votes::ProposalVote as ParquetProposalVote,
+    },
+    gap_detectors::ProcessingResult,
+    processors::{stake_processor::parse_stake_data, ProcessorName, ProcessorTrait},
+    utils::database::ArcDbPool,
+    IndexerGrpcProcessorConfig,
+};
+use ahash::AHashMap;
+use aptos_protos::transaction::v1::Transaction;
+use async_trait::async_trait;
+use kanal::AsyncSender;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, time::Duration};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParquetStakeProcessorConfig {
+    pub google_application_credentials: Option<String>,
+    pub bucket_name: String,
+    pub bucket_root: String,
+    #[serde(default)]
+    pub storage: ParquetStorageConfig,
+    pub parquet_handler_response_channel_size: usize,
+    pub max_buffer_size: usize,
+    pub parquet_upload_interval: u64,
+    #[serde(default = "IndexerGrpcProcessorConfig::default_query_retries")]
+    pub query_retries: u32,
+    #[serde(default = "IndexerGrpcProcessorConfig::default_query_retry_delay_ms")]
+    pub query_retry_delay_ms: u64,
+}
+
+impl UploadIntervalConfig for ParquetStakeProcessorConfig {
+    fn parquet_upload_interval_in_secs(&self) -> Duration {
+        Duration::from_secs(self.parquet_upload_interval)
+    }
+}
+
+/// Writes the stake tables to parquet. Inactive shares that aren't in the batch are looked up in
+/// `current_delegator_balances`, so the Postgres `stake_processor` has to run against the same
+/// database and be ahead of this processor.
+pub struct ParquetStakeProcessor {
+    connection_pool: ArcDbPool,
+    config: ParquetStakeProcessorConfig,
+    proposal_votes_sender: AsyncSender<ParquetDataGeneric<ParquetProposalVote>>,
+    delegator_activities_sender: AsyncSender<ParquetDataGeneric<ParquetDelegatedStakingActivity>>,
+    delegator_balances_sender: AsyncSender<ParquetDataGeneric<ParquetDelegatorBalance>>,
+    delegator_pool_balances_sender: AsyncSender<ParquetDataGeneric<ParquetDelegatorPoolBalance>>,
+}
+
+impl ParquetStakeProcessor {
+    pub fn new(
+        connection_pool: ArcDbPool,
+        config: ParquetStakeProcessorConfig,
+        new_gap_detector_sender: AsyncSender<ProcessingResult>,
+    ) -> Self {
+        if let Some(credentials) = config.google_application_credentials.clone() {
+            std::env::set_var(GOOGLE_APPLICATION_CREDENTIALS, credentials);
+        }
+
+        let proposal_votes_sender = create_parquet_handler_loop::<ParquetProposalVote>(
+            new_gap_detector_sender.clone(),
+            ProcessorName::ParquetStakeProcessor.into(),
+            config.bucket_name.clone(),
+            config.bucket_root.clone(),
+            config.storage.clone(),
+            config.parquet_handler_response_channel_size,
+            config.max_buffer_size,
+            config.parquet_upload_interval_in_secs(),
+        );
+
+        let delegator_activities_sender =
+            create_parquet_handler_loop::<ParquetDelegatedStakingActivity>(
+                new_gap_detector_sender.clone(),
+                ProcessorName::ParquetStakeProcessor.into(),
+                config.bucket_name.clone(),
+                config.bucket_root.clone(),
+                config.storage.clone(),
+                config.parquet_handler_response_channel_size,
+                config.max_buffer_size,
+                config.parquet_upload_interval_in_secs(),
+            );
+
+        let delegator_balances_sender = create_parquet_handler_loop::<ParquetDelegatorBalance>(
+            new_gap_detector_sender.clone(),
+            ProcessorName::ParquetStakeProcessor.into(),
+            config.bucket_name.clone(),
+            config.bucket_root.clone(),
+            config.storage.clone(),
+            config.parquet_handler_response_channel_size,
+            config.max_buffer_size,
+            config.parquet_upload_interval_in_secs(),
+        );
+
+        let delegator_pool_balances_sender =
+            create_parquet_handler_loop::<ParquetDelegatorPoolBalance>(
+                new_gap_detector_sender.clone(),
+                ProcessorName::ParquetStakeProcessor.into(),
+                config.bucket_name.clone(),
+                config.bucket_root.clone(),
+                config.storage.clone(),
+                config.parquet_handler_response_channel_size,
+                config.max_buffer_size,
+                config.parquet_upload_interval_in_secs(),
+            );
+
+        Self {
+            connection_pool,
+            config,
+            proposal_votes_sender,
+            delegator_activities_sender,
+            delegator_balances_sender,
+            delegator_pool_balances_sender,
+        }
+    }
+}
+
+impl Debug for ParquetStakeProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ParquetStakeProcessor {{ capacity of proposal votes channel: {:?}, capacity of delegator activities channel: {:?}, capacity of delegator balances channel: {:?}, capacity of delegator pool balances channel: {:?} }}",
+            &self.proposal_votes_sender.capacity(),
+            &self.delegator_activities_sender.capacity(),
+            &self.delegator_balances_sender.capacity(),
+            &self.delegator_pool_balances_sender.capacity(),
+        )
+    }
+}
+
+#[async_trait]
+impl ProcessorTrait for ParquetStakeProcessor {
+    fn name(&self) -> &'static str {
+        ProcessorName::ParquetStakeProcessor.into()
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+        _: Option<u64>,
+    ) -> anyhow::Result<ProcessingResult> {
+        let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
+
+        let mut conn = self.get_conn().await;
+        let query_retries = self.config.query_retries;
+        let query_retry_delay_ms = self.config.query_retry_delay_ms;
+
+        let version_to_block_timestamp = get_version_to_block_timestamp(&transactions);
+
+        let (
+            proposal_votes,
+            delegator_activities,
+            delegator_balances,
+            _,
+            _,
+            delegator_pool_balances,
+            _,
+        ) = parse_stake_data(
+            &transactions,
+            &mut conn,
+            query_retries,
+            query_retry_delay_ms,
+        )
+        .await?;
+
+        let all_proposal_votes: Vec<ParquetProposalVote> = proposal_votes
+            .iter()
+            .map(ParquetProposalVote::from)
+            .collect();
+        let all_delegator_activities: Vec<ParquetDelegatedStakingActivity> = delegator_activities
+            .iter()
+            .map(|activity| {
+                ParquetDelegatedStakingActivity::from_model(
+                    activity,
+                    version_to_block_timestamp[&activity.transaction_version],
+                )
+            })
+            .collect();
+        let all_delegator_balances: Vec<ParquetDelegatorBalance> = delegator_balances
+            .iter()
+            .map(|balance| {
+                ParquetDelegatorBalance::from_model(
+                    balance,
+                    version_to_block_timestamp[&balance.transaction_version],
+                )
+            })
+            .collect();
+        let all_delegator_pool_balances: Vec<ParquetDelegatorPoolBalance> = delegator_pool_balances
+            .iter()
+            .map(|pool_balance| {
+                ParquetDelegatorPoolBalance::from_model(
+                    pool_balance,
+                    version_to_block_timestamp[&pool_balance.transaction_version],
+                )
+            })
+            .collect();
+
+        let mut transaction_version_to_struct_count: AHashMap<i64, i64> = AHashMap::new();
+        count_structs(
+            &mut transaction_version_to_struct_count,
+            &all_proposal_votes,
+        );
+        count_structs(
+            &mut transaction_version_to_struct_count,
+            &all_delegator_activities,
+        );
+        count_structs(
+            &mut transaction_version_to_struct_count,
+            &all_delegator_balances,
+        );
+        count_structs(
+            &mut transaction_version_to_struct_count,
+            &all_delegator_pool_balances,
+        );
+
+        send_to_parquet_handler(
+            &self.proposal_votes_sender,
+            all_proposal_votes,
+            &transaction_version_to_struct_count,
+        )
+        .await?;
+        send_to_parquet_handler(
+            &self.delegator_activities_sender,
+            all_delegator_activities,
+            &transaction_version_to_struct_count,
+        )
+        .await?;
+        send_to_parquet_handler(
+            &self.delegator_balances_sender,
+            all_delegator_balances,
+            &transaction_version_to_struct_count,
+        )
+        .await?;
+        send_to_parquet_handler(
+            &self.delegator_pool_balances_sender,
+            all_delegator_pool_balances,
+            &transaction_version_to_struct_count,
+        )
+        .await?;
+
+        Ok(ProcessingResult::ParquetProcessingResult(
+            ParquetProcessingResult {
+                start_version: start_version as i64,
+                end_version: end_version as i64,
+                last_transaction_timestamp,
+                txn_version_to_struct_count: AHashMap::new(),
+            },
+        ))
+    }
+
+    fn connection_pool(&self) -> &ArcDbPool {
+        &self.connection_pool
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    count_structs, send_to_parquet_handler, UploadIntervalConfig, GOOGLE_APPLICATION_CREDENTIALS,
+};
+use crate::{
+    bq_analytics::{
+        create_parquet_handler_loop, generic_parquet_processor::ParquetDataGeneric,
+        storage::ParquetStorageConfig, ParquetProcessingResult,
+    },
+    db::common::models::{
+        token_models::tokens::TableMetadataForToken,
+        token_v2_models::{
+            parquet_v2_collections::CollectionV2, parquet_v2_token_activities::TokenActivityV2,
+            parquet_v2_token_datas::TokenDataV2, parquet_v2_token_ownerships::TokenOwnershipV2,
+        },
+    },
+    gap_detectors::ProcessingResult,
+    processors::{token_v2_processor::parse_v2_token, ProcessorName, ProcessorTrait},
+    utils::database::ArcDbPool,
+    IndexerGrpcProcessorConfig,
+};
+use ahash::AHashMap;
+use aptos_protos::transaction::v1::Transaction;
+use async_trait::async_trait;
+use kanal::AsyncSender;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, time::Duration};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParquetTokenV2ProcessorConfig {
+    pub google_application_credentials: Option<String>,
+    pub bucket_name: String,
+    pub bucket_root: String,
+    #[serde(default)]
+    pub storage: ParquetStorageConfig,
+    pub parquet_handler_response_channel_size: usize,
+    pub max_buffer_size: usize,
+    pub parquet_upload_interval: u64,
+    #[serde(default = "IndexerGrpcProcessorConfig::default_query_retries")]
+    pub query_retries: u32,
+    #[serde(default = "IndexerGrpcProcessorConfig::default_query_retry_delay_ms")]
+    pub query_retry_delay_ms: u64,
+}
+
+impl UploadIntervalConfig for ParquetTokenV2ProcessorConfig {
+    fn parquet_upload_interval_in_secs(&self) -> Duration {
+        Duration::from_secs(self.parquet_upload_interval)
+    }
+}
+
+/// Writes the token v2 tables to parquet. Burned tokens and v1 collections that aren't in the batch
+/// are looked up in `current_token_ownerships_v2` and `current_collections_v2`, so the Postgres
+/// `token_v2_processor` has to run against the same database and be ahead of this processor.
+pub struct ParquetTokenV2Processor {
+    connection_pool: ArcDbPool,
+    config: ParquetTokenV2ProcessorConfig,
+    collections_v2_sender: AsyncSender<ParquetDataGeneric<CollectionV2>>,
+    token_datas_v2_sender: AsyncSender<ParquetDataGeneric<TokenDataV2>>,
+    token_ownerships_v2_sender: AsyncSender<ParquetDataGeneric<TokenOwnershipV2>>,
+    token_activities_v2_sender: AsyncSender<ParquetDataGeneric<TokenActivityV2>>,
+}
+
+impl ParquetTokenV2Processor {
+    pub fn new(
+        connection_pool: ArcDbPool,
+        config: ParquetTokenV2ProcessorConfig,
+        new_gap_detector_sender: AsyncSender<ProcessingResult>,
+    ) -> Self {
+        if let Some(credentials) = config.google_application_credentials.clone() {
+            std::env::set_var(GOOGLE_APPLICATION_CREDENTIALS, credentials);
+        }
+
+        let collections_v2_sender = create_parquet_handler_loop::<CollectionV2>(
+            new_gap_detector_sender.clone(),
+            ProcessorName::ParquetTokenV2Processor.into(),
+            config.bucket_name.clone(),
+            config.bucket_root.clone(),
+            config.storage.clone(),
+            config.parquet_handler_response_channel_size,
+            config.max_buffer_size,
+            config.parquet_upload_interval_in_secs(),
+        );
+
+        let token_datas_v2_sender = create_parquet_handler_loop::<TokenDataV2>(
+            new_gap_detector_sender.clone(),
+            ProcessorName::ParquetTokenV2Processor.into(),
+            config.bucket_name.clone(),
+            config.bucket_root.clone(),
+            config.storage.clone(),
+            config.parquet_handler_response_channel_size,
+            config.max_buffer_size,
+            config.parquet_upload_interval_in_secs(),
+        );
+
+        let token_ownerships_v2_sender = create_parquet_handler_loop::<TokenOwnershipV2>(
+            new_gap_detector_sender.clone(),
+            ProcessorName::ParquetTokenV2Processor.into(),
+            config.bucket_name.clone(),
+            config.bucket_root.clone(),
+            config.storage.clone(),
+            config.parquet_handler_response_channel_size,
+            config.max_buffer_size,
+            config.parquet_upload_interval_in_secs(),
+        );
+
+        let token_activities_v2_sender = create_parquet_handler_loop::<TokenActivityV2>(
+            new_gap_detector_sender.clone(),
+            ProcessorName::ParquetTokenV2Processor.into(),
+            config.bucket_name.clone(),
+            config.bucket_root.clone(),
+            config.storage.clone(),
+            config.parquet_handler_response_channel_size,
+            config.max_buffer_size,
+            config.parquet_upload_interval_in_secs(),
+        );
+
+        Self {
+            connection_pool,
+            config,
+            collections_v2_sender,
+            token_datas_v2_sender,
+            token_ownerships_v2_sender,
+            token_activities_v2_sender,
+        }
+    }
+}
+
+impl Debug for ParquetTokenV2Processor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ParquetTokenV2Processor {{ capacity of collections channel: {:?}, capacity of token datas channel: {:?}, capacity of token ownerships channel: {:?}, capacity of token activities channel: {:?} }}",
+            &self.collections_v2_sender.capacity(),
+            &self.token_datas_v2_sender.capacity(),
+            &self.token_ownerships_v2_sender.capacity(),
+            &self.token_activities_v2_sender.capacity(),
+        )
+    }
+}
+
+#[async_trait]
+impl ProcessorTrait for ParquetTokenV2Processor {
+    fn name(&self) -> &'static str {
+        ProcessorName::ParquetTokenV2Processor.into()
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+        _: Option<u64>,
+    ) -> anyhow::Result<ProcessingResult> {
+        let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
+
+        // Token v1 ownerships still need the owner from the table handle
+        let table_handle_to_owner =
+            TableMetadataForToken::get_table_handle_to_owner_from_transactions(&transactions);
+
+        let mut conn = self.get_conn().await;
+        let (
+            collections_v2,
+            token_datas_v2,
+            token_ownerships_v2,
+            _,
+            _,
+            _,
+            _,
+            _,
+            token_activities_v2,
+            _,
+            _,
+            _,
+        ) = parse_v2_token(
+            &transactions,
+            &table_handle_to_owner,
+            &mut conn,
+            self.config.query_retries,
+            self.config.query_retry_delay_ms,
+        )
+        .await;
+
+        let collections_v2: Vec<CollectionV2> =
+            collections_v2.iter().map(CollectionV2::from).collect();
+        let token_datas_v2: Vec<TokenDataV2> =
+            token_datas_v2.iter().map(TokenDataV2::from).collect();
+        let token_ownerships_v2: Vec<TokenOwnershipV2> = token_ownerships_v2
+            .iter()
+            .map(TokenOwnershipV2::from)
+            .collect();
+        let token_activities_v2: Vec<TokenActivityV2> = token_activities_v2
+            .iter()
+            .map(TokenActivityV2::from)
+            .collect();
+
+        let mut transaction_version_to_struct_count: AHashMap<i64, i64> = AHashMap::new();
+        count_structs(&mut transaction_version_to_struct_count, &collections_v2);
+        count_structs(&mut transaction_version_to_struct_count, &token_datas_v2);
+        count_structs(
+            &mut transaction_version_to_struct_count,
+            &token_ownerships_v2,
+        );
+        count_structs(
+            &mut transaction_version_to_struct_count,
+            &token_activities_v2,
+        );
+
+        send_to_parquet_handler(
+            &self.collections_v2_sender,
+            collections_v2,
+            &transaction_version_to_struct_count,
+        )
+        .await?;
+        send_to_parquet_handler(
+            &self.token_datas_v2_sender,
+            token_datas_v2,
+            &transaction_version_to_struct_count,
+        )
+        .await?;
+        send_to_parquet_handler(
+            &self.token_ownerships_v2_sender,
+            token_ownerships_v2,
+            &transaction_version_to_struct_count,
+        )
+        .await?;
+        send_to_parquet_handler(
+            &self.token_activities_v2_sender,
+            token_activities_v2,
+            &transaction_version_to_struct_count,
+        )
+        .await?;
+
+        Ok(ProcessingResult::ParquetProcessingResult(
+            ParquetProcessingResult {
+                start_version: start_version as i64,
+                end_version: end_version as i64,
+                last_transaction_timestamp,
+                txn_version_to_struct_count: AHashMap::new(),
+            },
+        ))
+    }
+
+    fn connection_pool(&self) -> &ArcDbPool {
+        &self.connection_pool
+    }
+}
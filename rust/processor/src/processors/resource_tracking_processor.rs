@@ -0,0 +1,353 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{DefaultProcessingResult, ProcessorName, ProcessorTrait};
+use crate::{
+    db::common::models::{
+        default_models::move_resources::MoveResource,
+        tracked_resource_models::tracked_resources::{
+            CurrentTrackedResource, CurrentTrackedResourcePK, ResourceTypePattern, TrackedResource,
+        },
+    },
+    gap_detectors::ProcessingResult,
+    schema,
+    utils::{
+        database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
+        util::{get_json_path, parse_timestamp},
+    },
+};
+use ahash::AHashMap;
+use anyhow::{bail, ensure, Context};
+use aptos_protos::transaction::v1::{write_set_change::Change, MoveStructTag, Transaction};
+use async_trait::async_trait;
+use diesel::{
+    pg::{upsert::excluded, Pg},
+    query_builder::QueryFragment,
+    ExpressionMethods,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt::Debug;
+use tracing::error;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceTrackingProcessorConfig {
+    pub resources: Vec<TrackedResourceConfig>,
+}
+
+impl ResourceTrackingProcessorConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            !self.resources.is_empty(),
+            "Resource tracking processor needs at least one resource"
+        );
+        for resource in &self.resources {
+            resource
+                .resource_type
+                .parse::<ResourceTypePattern>()
+                .with_context(|| format!("Invalid resource {}", resource.name))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrackedResourceConfig {
+    /// Written to the tracker column, so rows of different resources can be told apart
+    pub name: String,
+    pub resource_type: String,
+    // Field name to dot separated path in the resource, e.g. `reserve_x: reserves.0.value`.
+    // Without any, the whole resource is stored.
+    #[serde(default)]
+    pub fields: AHashMap<String, String>,
+}
+
+impl TrackedResourceConfig {
+    fn extract_fields(&self, data: &Value) -> Value {
+        if self.fields.is_empty() {
+            return data.clone();
+        }
+        let fields: Map<String, Value> = self
+            .fields
+            .iter()
+            .map(|(name, path)| {
+                let value = get_json_path(data, path).cloned().unwrap_or(Value::Null);
+                (name.clone(), value)
+            })
+            .collect();
+        Value::Object(fields)
+    }
+}
+
+pub struct ResourceTrackingProcessor {
+    connection_pool: ArcDbPool,
+    // Resources are checked against the patterns in order, and the first match tracks them
+    trackers: Vec<(ResourceTypePattern, TrackedResourceConfig)>,
+    per_table_chunk_sizes: AHashMap<String, usize>,
+}
+
+impl ResourceTrackingProcessor {
+    /// Expects a config that passed `ResourceTrackingProcessorConfig::validate`.
+    pub fn new(
+        connection_pool: ArcDbPool,
+        config: ResourceTrackingProcessorConfig,
+        per_table_chunk_sizes: AHashMap<String, usize>,
+    ) -> Self {
+        let trackers = config
+            .resources
+            .into_iter()
+            .map(|resource| {
+                let pattern = resource
+                    .resource_type
+                    .parse::<ResourceTypePattern>()
+                    .expect("Resource types are checked by validate");
+                (pattern, resource)
+            })
+            .collect();
+        Self {
+            connection_pool,
+            trackers,
+            per_table_chunk_sizes,
+        }
+    }
+
+    fn get_tracker(
+        &self,
+        struct_tag: Option<&MoveStructTag>,
+        type_str: &str,
+    ) -> Option<&TrackedResourceConfig> {
+        let struct_tag = struct_tag?;
+        self.trackers
+            .iter()
+            .find(|(pattern, _)| pattern.matches(struct_tag, type_str))
+            .map(|(_, tracker)| tracker)
+    }
+
+    fn parse_tracked_resources(
+        &self,
+        transactions: &[Transaction],
+    ) -> (Vec<TrackedResource>, Vec<CurrentTrackedResource>) {
+        let mut tracked_resources = vec![];
+        let mut current_tracked_resources: AHashMap<
+            CurrentTrackedResourcePK,
+            CurrentTrackedResource,
+        > = AHashMap::new();
+
+        for txn in transactions {
+            let txn_version = txn.version as i64;
+            let block_height = txn.block_height as i64;
+            let txn_timestamp = parse_timestamp(txn.timestamp.as_ref().unwrap(), txn_version);
+            let Some(info) = txn.info.as_ref() else {
+                continue;
+            };
+            for (index, wsc) in info.changes.iter().enumerate() {
+                let index = index as i64;
+                let (resource, tracker) = match wsc.change.as_ref() {
+                    Some(Change::WriteResource(inner)) => {
+                        match self.get_tracker(inner.r#type.as_ref(), &inner.type_str) {
+                            Some(tracker) => (
+                                MoveResource::from_write_resource(
+                                    inner,
+                                    index,
+                                    txn_version,
+                                    block_height,
+                                ),
+                                tracker,
+                            ),
+                            None => continue,
+                        }
+                    },
+                    Some(Change::DeleteResource(inner)) => {
+                        match self.get_tracker(inner.r#type.as_ref(), &inner.type_str) {
+                            Some(tracker) => (
+                                MoveResource::from_delete_resource(
+                                    inner,
+                                    index,
+                                    txn_version,
+                                    block_height,
+                                ),
+                                tracker,
+                            ),
+                            None => continue,
+                        }
+                    },
+                    _ => continue,
+                };
+                let fields = resource
+                    .data
+                    .as_ref()
+                    .map(|data| tracker.extract_fields(data));
+                let (tracked_resource, current_tracked_resource) =
+                    TrackedResource::from_move_resource(
+                        &resource,
+                        &tracker.name,
+                        fields,
+                        txn_timestamp,
+                    );
+                tracked_resources.push(tracked_resource);
+                current_tracked_resources
+                    .insert(current_tracked_resource.get_pk(), current_tracked_resource);
+            }
+        }
+
+        // Sort by PK
+        let mut current_tracked_resources = current_tracked_resources
+            .into_values()
+            .collect::<Vec<CurrentTrackedResource>>();
+        current_tracked_resources.sort_by(|a, b| a.get_pk().cmp(&b.get_pk()));
+
+        (tracked_resources, current_tracked_resources)
+    }
+}
+
+impl Debug for ResourceTrackingProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = &self.connection_pool.state();
+        write!(
+            f,
+            "ResourceTrackingProcessor {{ connections: {:?}  idle_connections: {:?} }}",
+            state.connections, state.idle_connections
+        )
+    }
+}
+
+async fn insert_to_db(
+    conn: ArcDbPool,
+    name: &'static str,
+    start_version: u64,
+    end_version: u64,
+    (tracked_resources, current_tracked_resources): (&[TrackedResource], &[CurrentTrackedResource]),
+    per_table_chunk_sizes: &AHashMap<String, usize>,
+) -> Result<(), diesel::result::Error> {
+    tracing::trace!(
+        name = name,
+        start_version = start_version,
+        end_version = end_version,
+        "Inserting to db",
+    );
+
+    let tr = execute_in_chunks(
+        conn.clone(),
+        insert_tracked_resources_query,
+        tracked_resources,
+        get_config_table_chunk_size::<TrackedResource>("tracked_resources", per_table_chunk_sizes),
+    );
+    let ctr = execute_in_chunks(
+        conn,
+        insert_current_tracked_resources_query,
+        current_tracked_resources,
+        get_config_table_chunk_size::<CurrentTrackedResource>(
+            "current_tracked_resources",
+            per_table_chunk_sizes,
+        ),
+    );
+    let (tr_res, ctr_res) = tokio::join!(tr, ctr);
+    for res in [tr_res, ctr_res] {
+        res?;
+    }
+
+    Ok(())
+}
+
+fn insert_tracked_resources_query(
+    items_to_insert: Vec<TrackedResource>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::tracked_resources::dsl::*;
+    (
+        diesel::insert_into(schema::tracked_resources::table)
+            .values(items_to_insert)
+            .on_conflict((transaction_version, write_set_change_index))
+            .do_nothing(),
+        None,
+    )
+}
+
+fn insert_current_tracked_resources_query(
+    items_to_insert: Vec<CurrentTrackedResource>,
+) -> (
+    impl QueryFragment<Pg> + diesel::query_builder::QueryId + Send,
+    Option<&'static str>,
+) {
+    use schema::current_tracked_resources::dsl::*;
+    (
+        diesel::insert_into(schema::current_tracked_resources::table)
+            .values(items_to_insert)
+            .on_conflict((address, resource_type))
+            .do_update()
+            .set((
+                tracker.eq(excluded(tracker)),
+                fields.eq(excluded(fields)),
+                is_deleted.eq(excluded(is_deleted)),
+                last_transaction_version.eq(excluded(last_transaction_version)),
+                last_transaction_timestamp.eq(excluded(last_transaction_timestamp)),
+                inserted_at.eq(excluded(inserted_at)),
+            )),
+        Some(" WHERE current_tracked_resources.last_transaction_version <= excluded.last_transaction_version "),
+    )
+}
+
+#[async_trait]
+impl ProcessorTrait for ResourceTrackingProcessor {
+    fn name(&self) -> &'static str {
+        ProcessorName::ResourceTrackingProcessor.into()
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+        _: Option<u64>,
+    ) -> anyhow::Result<ProcessingResult> {
+        let processing_start = std::time::Instant::now();
+        let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
+
+        let (tracked_resources, current_tracked_resources) =
+            self.parse_tracked_resources(&transactions);
+
+        let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
+        let db_insertion_start = std::time::Instant::now();
+
+        let tx_result = insert_to_db(
+            self.get_pool(),
+            self.name(),
+            start_version,
+            end_version,
+            (&tracked_resources, &current_tracked_resources),
+            &self.per_table_chunk_sizes,
+        )
+        .await;
+        let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
+
+        match tx_result {
+            Ok(_) => Ok(ProcessingResult::DefaultProcessingResult(
+                DefaultProcessingResult {
+                    start_version,
+                    end_version,
+                    processing_duration_in_secs,
+                    db_insertion_duration_in_secs,
+                    last_transaction_timestamp,
+                },
+            )),
+            Err(e) => {
+                error!(
+                    start_version = start_version,
+                    end_version = end_version,
+                    processor_name = self.name(),
+                    error = ?e,
+                    "[Parser] Error inserting transactions to db",
+                );
+                bail!(e)
+            },
+        }
+    }
+
+    fn connection_pool(&self) -> &ArcDbPool {
+        &self.connection_pool
+    }
+}
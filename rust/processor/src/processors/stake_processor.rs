@@ -19,7 +19,7 @@ use crate::{
     gap_detectors::ProcessingResult,
     schema,
     utils::{
-        database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool},
+        database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool, DbPoolConnection},
         util::{parse_timestamp, standardize_address},
     },
     IndexerGrpcProcessorConfig,
@@ -402,15 +402,23 @@ impl ProcessorTrait for StakeProcessor {
         let query_retries = self.config.query_retries;
         let query_retry_delay_ms = self.config.query_retry_delay_ms;
 
-        let mut all_current_stake_pool_voters: StakingPoolVoterMap = AHashMap::new();
-        let mut all_proposal_votes = vec![];
-        let mut all_delegator_activities = vec![];
-        let mut all_delegator_balances = vec![];
-        let mut all_current_delegator_balances: CurrentDelegatorBalanceMap = AHashMap::new();
-        let mut all_delegator_pools: DelegatorPoolMap = AHashMap::new();
-        let mut all_delegator_pool_balances = vec![];
-        let mut all_current_delegator_pool_balances = AHashMap::new();
+        let (
+            all_proposal_votes,
+            all_delegator_activities,
+            all_delegator_balances,
+            all_current_delegator_balances,
+            all_delegator_pools,
+            all_delegator_pool_balances,
+            all_current_delegator_pool_balances,
+        ) = parse_stake_data(
+            &transactions,
+            &mut conn,
+            query_retries,
+            query_retry_delay_ms,
+        )
+        .await?;
 
+        let mut all_current_stake_pool_voters: StakingPoolVoterMap = AHashMap::new();
         let mut active_pool_to_staking_pool = AHashMap::new();
         // structs needed to get delegated voters
         let mut all_current_delegated_voter = AHashMap::new();
@@ -420,19 +428,6 @@ impl ProcessorTrait for StakeProcessor {
             // Add votes data
             let current_stake_pool_voter = CurrentStakingPoolVoter::from_transaction(txn).unwrap();
             all_current_stake_pool_voters.extend(current_stake_pool_voter);
-            let mut proposal_votes = ProposalVote::from_transaction(txn).unwrap();
-            all_proposal_votes.append(&mut proposal_votes);
-
-            // Add delegator activities
-            let mut delegator_activities = DelegatedStakingActivity::from_transaction(txn).unwrap();
-            all_delegator_activities.append(&mut delegator_activities);
-
-            // Add delegator pools
-            let (delegator_pools, mut delegator_pool_balances, current_delegator_pool_balances) =
-                DelegatorPool::from_transaction(txn).unwrap();
-            all_delegator_pools.extend(delegator_pools);
-            all_delegator_pool_balances.append(&mut delegator_pool_balances);
-            all_current_delegator_pool_balances.extend(current_delegator_pool_balances);
 
             // Moving the transaction code here is the new paradigm to avoid redoing a lot of the duplicate work
             // Currently only delegator voting follows this paradigm
@@ -473,20 +468,6 @@ impl ProcessorTrait for StakeProcessor {
                 }
             }
 
-            // Add delegator balances
-            let (mut delegator_balances, current_delegator_balances) =
-                CurrentDelegatorBalance::from_transaction(
-                    txn,
-                    &active_pool_to_staking_pool,
-                    &mut conn,
-                    query_retries,
-                    query_retry_delay_ms,
-                )
-                .await
-                .unwrap();
-            all_delegator_balances.append(&mut delegator_balances);
-            all_current_delegator_balances.extend(current_delegator_balances);
-
             // this write table item indexing is to get delegator address, table handle, and voter & pending voter
             for wsc in transaction_info
                 .changes
@@ -541,15 +522,6 @@ impl ProcessorTrait for StakeProcessor {
         let mut all_current_stake_pool_voters = all_current_stake_pool_voters
             .into_values()
             .collect::<Vec<CurrentStakingPoolVoter>>();
-        let mut all_current_delegator_balances = all_current_delegator_balances
-            .into_values()
-            .collect::<Vec<CurrentDelegatorBalance>>();
-        let mut all_delegator_pools = all_delegator_pools
-            .into_values()
-            .collect::<Vec<DelegatorPool>>();
-        let mut all_current_delegator_pool_balances = all_current_delegator_pool_balances
-            .into_values()
-            .collect::<Vec<CurrentDelegatorPoolBalance>>();
         let mut all_current_delegated_voter = all_current_delegated_voter
             .into_values()
             .collect::<Vec<CurrentDelegatedVoter>>();
@@ -557,16 +529,6 @@ impl ProcessorTrait for StakeProcessor {
         // Sort by PK
         all_current_stake_pool_voters
             .sort_by(|a, b| a.staking_pool_address.cmp(&b.staking_pool_address));
-        all_current_delegator_balances.sort_by(|a, b| {
-            (&a.delegator_address, &a.pool_address, &a.pool_type).cmp(&(
-                &b.delegator_address,
-                &b.pool_address,
-                &b.pool_type,
-            ))
-        });
-        all_delegator_pools.sort_by(|a, b| a.staking_pool_address.cmp(&b.staking_pool_address));
-        all_current_delegator_pool_balances
-            .sort_by(|a, b| a.staking_pool_address.cmp(&b.staking_pool_address));
         all_current_delegated_voter.sort();
 
         let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
@@ -617,3 +579,106 @@ impl ProcessorTrait for StakeProcessor {
         &self.connection_pool
     }
 }
+
+/// Parses the proposal votes, delegator activities, delegator balances and delegation pools of a
+/// batch. Shared with the parquet stake processor. Balances of inactive shares that were created
+/// before this batch are looked up in `current_delegator_balances`.
+pub async fn parse_stake_data(
+    transactions: &[Transaction],
+    conn: &mut DbPoolConnection<'_>,
+    query_retries: u32,
+    query_retry_delay_ms: u64,
+) -> anyhow::Result<(
+    Vec<ProposalVote>,
+    Vec<DelegatedStakingActivity>,
+    Vec<DelegatorBalance>,
+    Vec<CurrentDelegatorBalance>,
+    Vec<DelegatorPool>,
+    Vec<DelegatorPoolBalance>,
+    Vec<CurrentDelegatorPoolBalance>,
+)> {
+    let mut all_proposal_votes = vec![];
+    let mut all_delegator_activities = vec![];
+    let mut all_delegator_balances = vec![];
+    let mut all_current_delegator_balances: CurrentDelegatorBalanceMap = AHashMap::new();
+    let mut all_delegator_pools: DelegatorPoolMap = AHashMap::new();
+    let mut all_delegator_pool_balances = vec![];
+    let mut all_current_delegator_pool_balances = AHashMap::new();
+
+    // The active share pool of a delegation pool can be created earlier in the same batch
+    let mut active_pool_to_staking_pool = AHashMap::new();
+
+    for txn in transactions {
+        let mut proposal_votes = ProposalVote::from_transaction(txn)?;
+        all_proposal_votes.append(&mut proposal_votes);
+
+        // Add delegator activities
+        let mut delegator_activities = DelegatedStakingActivity::from_transaction(txn)?;
+        all_delegator_activities.append(&mut delegator_activities);
+
+        // Add delegator pools
+        let (delegator_pools, mut delegator_pool_balances, current_delegator_pool_balances) =
+            DelegatorPool::from_transaction(txn)?;
+        all_delegator_pools.extend(delegator_pools);
+        all_delegator_pool_balances.append(&mut delegator_pool_balances);
+        all_current_delegator_pool_balances.extend(current_delegator_pool_balances);
+
+        let txn_version = txn.version as i64;
+        let transaction_info = txn.info.as_ref().expect("Transaction info doesn't exist!");
+        for wsc in transaction_info.changes.iter() {
+            if let Some(Change::WriteResource(write_resource)) = wsc.change.as_ref() {
+                if let Some(map) = CurrentDelegatorBalance::get_active_pool_to_staking_pool_mapping(
+                    write_resource,
+                    txn_version,
+                )? {
+                    active_pool_to_staking_pool.extend(map);
+                }
+            }
+        }
+
+        // Add delegator balances
+        let (mut delegator_balances, current_delegator_balances) =
+            CurrentDelegatorBalance::from_transaction(
+                txn,
+                &active_pool_to_staking_pool,
+                conn,
+                query_retries,
+                query_retry_delay_ms,
+            )
+            .await?;
+        all_delegator_balances.append(&mut delegator_balances);
+        all_current_delegator_balances.extend(current_delegator_balances);
+    }
+
+    let mut all_current_delegator_balances = all_current_delegator_balances
+        .into_values()
+        .collect::<Vec<CurrentDelegatorBalance>>();
+    let mut all_delegator_pools = all_delegator_pools
+        .into_values()
+        .collect::<Vec<DelegatorPool>>();
+    let mut all_current_delegator_pool_balances = all_current_delegator_pool_balances
+        .into_values()
+        .collect::<Vec<CurrentDelegatorPoolBalance>>();
+
+    // Sort by PK
+    all_current_delegator_balances.sort_by(|a, b| {
+        (&a.delegator_address, &a.pool_address, &a.pool_type).cmp(&(
+            &b.delegator_address,
+            &b.pool_address,
+            &b.pool_type,
+        ))
+    });
+    all_delegator_pools.sort_by(|a, b| a.staking_pool_address.cmp(&b.staking_pool_address));
+    all_current_delegator_pool_balances
+        .sort_by(|a, b| a.staking_pool_address.cmp(&b.staking_pool_address));
+
+    Ok((
+        all_proposal_votes,
+        all_delegator_activities,
+        all_delegator_balances,
+        all_current_delegator_balances,
+        all_delegator_pools,
+        all_delegator_pool_balances,
+        all_current_delegator_pool_balances,
+    ))
+}
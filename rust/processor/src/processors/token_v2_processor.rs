@@ -31,6 +31,7 @@ use crate::{
     },
     gap_detectors::ProcessingResult,
     schema,
+    sinks::{write_to_sinks, ProcessorOutput, Sink, SinkConfig, TableRows},
     utils::{
         counters::PROCESSOR_UNKNOWN_TYPE_COUNT,
         database::{execute_in_chunks, get_config_table_chunk_size, ArcDbPool, DbPoolConnection},
@@ -49,7 +50,7 @@ use diesel::{
     ExpressionMethods,
 };
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 use tracing::error;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -59,12 +60,15 @@ pub struct TokenV2ProcessorConfig {
     pub query_retries: u32,
     #[serde(default = "IndexerGrpcProcessorConfig::default_query_retry_delay_ms")]
     pub query_retry_delay_ms: u64,
+    // Where rows are written, Postgres if empty
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
 }
 
 pub struct TokenV2Processor {
     connection_pool: ArcDbPool,
     config: TokenV2ProcessorConfig,
-    per_table_chunk_sizes: AHashMap<String, usize>,
+    sinks: Arc<Vec<Sink>>,
     deprecated_tables: TableFlags,
 }
 
@@ -72,13 +76,13 @@ impl TokenV2Processor {
     pub fn new(
         connection_pool: ArcDbPool,
         config: TokenV2ProcessorConfig,
-        per_table_chunk_sizes: AHashMap<String, usize>,
+        sinks: Arc<Vec<Sink>>,
         deprecated_tables: TableFlags,
     ) -> Self {
         Self {
             connection_pool,
             config,
-            per_table_chunk_sizes,
+            sinks,
             deprecated_tables,
         }
     }
@@ -95,176 +99,254 @@ impl Debug for TokenV2Processor {
     }
 }
 
-#[allow(clippy::too_many_arguments)]
-async fn insert_to_db(
-    conn: ArcDbPool,
-    name: &'static str,
-    start_version: u64,
-    end_version: u64,
-    collections_v2: &[CollectionV2],
-    token_datas_v2: &[TokenDataV2],
-    token_ownerships_v2: &[TokenOwnershipV2],
-    current_collections_v2: &[CurrentCollectionV2],
-    (current_token_datas_v2, current_deleted_token_datas_v2): (
-        &[CurrentTokenDataV2],
-        &[CurrentTokenDataV2],
-    ),
-    (current_token_ownerships_v2, current_deleted_token_ownerships_v2): (
-        &[CurrentTokenOwnershipV2],
-        &[CurrentTokenOwnershipV2],
-    ),
-    token_activities_v2: &[TokenActivityV2],
-    current_token_v2_metadata: &[CurrentTokenV2Metadata],
-    current_token_royalties_v1: &[CurrentTokenRoyaltyV1],
-    current_token_claims: &[CurrentTokenPendingClaim],
-    per_table_chunk_sizes: &AHashMap<String, usize>,
-) -> Result<(), diesel::result::Error> {
-    tracing::trace!(
-        name = name,
-        start_version = start_version,
-        end_version = end_version,
-        "Inserting to db",
-    );
+pub struct TokenV2ProcessorOutput {
+    pub collections_v2: Vec<CollectionV2>,
+    pub token_datas_v2: Vec<TokenDataV2>,
+    pub token_ownerships_v2: Vec<TokenOwnershipV2>,
+    pub current_collections_v2: Vec<CurrentCollectionV2>,
+    pub current_token_datas_v2: Vec<CurrentTokenDataV2>,
+    /// Burned tokens, which only update the deletion columns
+    pub current_deleted_token_datas_v2: Vec<CurrentTokenDataV2>,
+    pub current_token_ownerships_v2: Vec<CurrentTokenOwnershipV2>,
+    /// Ownerships that ended, which only update the amount and deletion columns
+    pub current_deleted_token_ownerships_v2: Vec<CurrentTokenOwnershipV2>,
+    pub token_activities_v2: Vec<TokenActivityV2>,
+    pub current_token_v2_metadata: Vec<CurrentTokenV2Metadata>,
+    pub current_token_royalties_v1: Vec<CurrentTokenRoyaltyV1>,
+    pub current_token_claims: Vec<CurrentTokenPendingClaim>,
+}
 
-    let coll_v2 = execute_in_chunks(
-        conn.clone(),
-        insert_collections_v2_query,
-        collections_v2,
-        get_config_table_chunk_size::<CollectionV2>("collections_v2", per_table_chunk_sizes),
-    );
-    let td_v2 = execute_in_chunks(
-        conn.clone(),
-        insert_token_datas_v2_query,
-        token_datas_v2,
-        get_config_table_chunk_size::<TokenDataV2>("token_datas_v2", per_table_chunk_sizes),
-    );
-    let to_v2 = execute_in_chunks(
-        conn.clone(),
-        insert_token_ownerships_v2_query,
-        token_ownerships_v2,
-        get_config_table_chunk_size::<TokenOwnershipV2>(
-            "token_ownerships_v2",
-            per_table_chunk_sizes,
-        ),
-    );
-    let cc_v2 = execute_in_chunks(
-        conn.clone(),
-        insert_current_collections_v2_query,
-        current_collections_v2,
-        get_config_table_chunk_size::<CurrentCollectionV2>(
-            "current_collections_v2",
-            per_table_chunk_sizes,
-        ),
-    );
-    let ctd_v2 = execute_in_chunks(
-        conn.clone(),
-        insert_current_token_datas_v2_query,
-        current_token_datas_v2,
-        get_config_table_chunk_size::<CurrentTokenDataV2>(
-            "current_token_datas_v2",
-            per_table_chunk_sizes,
-        ),
-    );
-    let cdtd_v2 = execute_in_chunks(
-        conn.clone(),
-        insert_current_deleted_token_datas_v2_query,
-        current_deleted_token_datas_v2,
-        get_config_table_chunk_size::<CurrentTokenDataV2>(
-            "current_token_datas_v2",
-            per_table_chunk_sizes,
-        ),
-    );
-    let cto_v2 = execute_in_chunks(
-        conn.clone(),
-        insert_current_token_ownerships_v2_query,
-        current_token_ownerships_v2,
-        get_config_table_chunk_size::<CurrentTokenOwnershipV2>(
-            "current_token_ownerships_v2",
-            per_table_chunk_sizes,
-        ),
-    );
-    let cdto_v2 = execute_in_chunks(
-        conn.clone(),
-        insert_current_deleted_token_ownerships_v2_query,
-        current_deleted_token_ownerships_v2,
-        get_config_table_chunk_size::<CurrentTokenOwnershipV2>(
-            "current_token_ownerships_v2",
-            per_table_chunk_sizes,
-        ),
-    );
-    let ta_v2 = execute_in_chunks(
-        conn.clone(),
-        insert_token_activities_v2_query,
-        token_activities_v2,
-        get_config_table_chunk_size::<TokenActivityV2>(
-            "token_activities_v2",
-            per_table_chunk_sizes,
-        ),
-    );
-    let ct_v2 = execute_in_chunks(
-        conn.clone(),
-        insert_current_token_v2_metadatas_query,
-        current_token_v2_metadata,
-        get_config_table_chunk_size::<CurrentTokenV2Metadata>(
-            "current_token_v2_metadata",
-            per_table_chunk_sizes,
-        ),
-    );
-    let ctr_v1 = execute_in_chunks(
-        conn.clone(),
-        insert_current_token_royalties_v1_query,
-        current_token_royalties_v1,
-        get_config_table_chunk_size::<CurrentTokenRoyaltyV1>(
-            "current_token_royalty_v1",
-            per_table_chunk_sizes,
-        ),
-    );
-    let ctc_v1 = execute_in_chunks(
-        conn,
-        insert_current_token_claims_query,
-        current_token_claims,
-        get_config_table_chunk_size::<CurrentTokenPendingClaim>(
-            "current_token_pending_claims",
-            per_table_chunk_sizes,
-        ),
-    );
+#[async_trait]
+impl ProcessorOutput for TokenV2ProcessorOutput {
+    async fn insert_to_db(
+        &self,
+        conn: ArcDbPool,
+        per_table_chunk_sizes: &AHashMap<String, usize>,
+    ) -> Result<(), diesel::result::Error> {
+        let coll_v2 = execute_in_chunks(
+            conn.clone(),
+            insert_collections_v2_query,
+            &self.collections_v2,
+            get_config_table_chunk_size::<CollectionV2>("collections_v2", per_table_chunk_sizes),
+        );
+        let td_v2 = execute_in_chunks(
+            conn.clone(),
+            insert_token_datas_v2_query,
+            &self.token_datas_v2,
+            get_config_table_chunk_size::<TokenDataV2>("token_datas_v2", per_table_chunk_sizes),
+        );
+        let to_v2 = execute_in_chunks(
+            conn.clone(),
+            insert_token_ownerships_v2_query,
+            &self.token_ownerships_v2,
+            get_config_table_chunk_size::<TokenOwnershipV2>(
+                "token_ownerships_v2",
+                per_table_chunk_sizes,
+            ),
+        );
+        let cc_v2 = execute_in_chunks(
+            conn.clone(),
+            insert_current_collections_v2_query,
+            &self.current_collections_v2,
+            get_config_table_chunk_size::<CurrentCollectionV2>(
+                "current_collections_v2",
+                per_table_chunk_sizes,
+            ),
+        );
+        let ctd_v2 = execute_in_chunks(
+            conn.clone(),
+            insert_current_token_datas_v2_query,
+            &self.current_token_datas_v2,
+            get_config_table_chunk_size::<CurrentTokenDataV2>(
+                "current_token_datas_v2",
+                per_table_chunk_sizes,
+            ),
+        );
+        let cdtd_v2 = execute_in_chunks(
+            conn.clone(),
+            insert_current_deleted_token_datas_v2_query,
+            &self.current_deleted_token_datas_v2,
+            get_config_table_chunk_size::<CurrentTokenDataV2>(
+                "current_token_datas_v2",
+                per_table_chunk_sizes,
+            ),
+        );
+        let cto_v2 = execute_in_chunks(
+            conn.clone(),
+            insert_current_token_ownerships_v2_query,
+            &self.current_token_ownerships_v2,
+            get_config_table_chunk_size::<CurrentTokenOwnershipV2>(
+                "current_token_ownerships_v2",
+                per_table_chunk_sizes,
+            ),
+        );
+        let cdto_v2 = execute_in_chunks(
+            conn.clone(),
+            insert_current_deleted_token_ownerships_v2_query,
+            &self.current_deleted_token_ownerships_v2,
+            get_config_table_chunk_size::<CurrentTokenOwnershipV2>(
+                "current_token_ownerships_v2",
+                per_table_chunk_sizes,
+            ),
+        );
+        let ta_v2 = execute_in_chunks(
+            conn.clone(),
+            insert_token_activities_v2_query,
+            &self.token_activities_v2,
+            get_config_table_chunk_size::<TokenActivityV2>(
+                "token_activities_v2",
+                per_table_chunk_sizes,
+            ),
+        );
+        let ct_v2 = execute_in_chunks(
+            conn.clone(),
+            insert_current_token_v2_metadatas_query,
+            &self.current_token_v2_metadata,
+            get_config_table_chunk_size::<CurrentTokenV2Metadata>(
+                "current_token_v2_metadata",
+                per_table_chunk_sizes,
+            ),
+        );
+        let ctr_v1 = execute_in_chunks(
+            conn.clone(),
+            insert_current_token_royalties_v1_query,
+            &self.current_token_royalties_v1,
+            get_config_table_chunk_size::<CurrentTokenRoyaltyV1>(
+                "current_token_royalty_v1",
+                per_table_chunk_sizes,
+            ),
+        );
+        let ctc_v1 = execute_in_chunks(
+            conn,
+            insert_current_token_claims_query,
+            &self.current_token_claims,
+            get_config_table_chunk_size::<CurrentTokenPendingClaim>(
+                "current_token_pending_claims",
+                per_table_chunk_sizes,
+            ),
+        );
+
+        let (
+            coll_v2_res,
+            td_v2_res,
+            to_v2_res,
+            cc_v2_res,
+            ctd_v2_res,
+            cdtd_v2_res,
+            cto_v2_res,
+            cdto_v2_res,
+            ta_v2_res,
+            ct_v2_res,
+            ctr_v1_res,
+            ctc_v1_res,
+        ) = tokio::join!(
+            coll_v2, td_v2, to_v2, cc_v2, ctd_v2, cdtd_v2, cto_v2, cdto_v2, ta_v2, ct_v2, ctr_v1,
+            ctc_v1
+        );
 
-    let (
-        coll_v2_res,
-        td_v2_res,
-        to_v2_res,
-        cc_v2_res,
-        ctd_v2_res,
-        cdtd_v2_res,
-        cto_v2_res,
-        cdto_v2_res,
-        ta_v2_res,
-        ct_v2_res,
-        ctr_v1_res,
-        ctc_v1_res,
-    ) = tokio::join!(
-        coll_v2, td_v2, to_v2, cc_v2, ctd_v2, cdtd_v2, cto_v2, cdto_v2, ta_v2, ct_v2, ctr_v1,
-        ctc_v1
-    );
+        for res in [
+            coll_v2_res,
+            td_v2_res,
+            to_v2_res,
+            cc_v2_res,
+            ctd_v2_res,
+            cdtd_v2_res,
+            cto_v2_res,
+            cdto_v2_res,
+            ta_v2_res,
+            ct_v2_res,
+            ctr_v1_res,
+            ctc_v1_res,
+        ] {
+            res?;
+        }
 
-    for res in [
-        coll_v2_res,
-        td_v2_res,
-        to_v2_res,
-        cc_v2_res,
-        ctd_v2_res,
-        cdtd_v2_res,
-        cto_v2_res,
-        cdto_v2_res,
-        ta_v2_res,
-        ct_v2_res,
-        ctr_v1_res,
-        ctc_v1_res,
-    ] {
-        res?;
+        Ok(())
     }
 
-    Ok(())
+    fn table_rows(&self) -> anyhow::Result<Vec<TableRows>> {
+        let mut current_token_datas_v2 = TableRows::new(
+            "current_token_datas_v2",
+            &["token_data_id"],
+            &self.current_token_datas_v2,
+        )?;
+        current_token_datas_v2.rows.extend(
+            TableRows::new(
+                "current_token_datas_v2",
+                &["token_data_id"],
+                &self.current_deleted_token_datas_v2,
+            )?
+            .rows,
+        );
+        let ownership_key = &[
+            "token_data_id",
+            "property_version_v1",
+            "owner_address",
+            "storage_id",
+        ];
+        let mut current_token_ownerships_v2 = TableRows::new(
+            "current_token_ownerships_v2",
+            ownership_key,
+            &self.current_token_ownerships_v2,
+        )?;
+        current_token_ownerships_v2.rows.extend(
+            TableRows::new(
+                "current_token_ownerships_v2",
+                ownership_key,
+                &self.current_deleted_token_ownerships_v2,
+            )?
+            .rows,
+        );
+        Ok(vec![
+            TableRows::new(
+                "collections_v2",
+                &["transaction_version", "write_set_change_index"],
+                &self.collections_v2,
+            )?,
+            TableRows::new(
+                "token_datas_v2",
+                &["transaction_version", "write_set_change_index"],
+                &self.token_datas_v2,
+            )?,
+            TableRows::new(
+                "token_ownerships_v2",
+                &["transaction_version", "write_set_change_index"],
+                &self.token_ownerships_v2,
+            )?,
+            TableRows::new(
+                "current_collections_v2",
+                &["collection_id"],
+                &self.current_collections_v2,
+            )?,
+            current_token_datas_v2,
+            current_token_ownerships_v2,
+            TableRows::new(
+                "token_activities_v2",
+                &["transaction_version", "event_index"],
+                &self.token_activities_v2,
+            )?,
+            TableRows::new(
+                "current_token_v2_metadata",
+                &["object_address", "resource_type"],
+                &self.current_token_v2_metadata,
+            )?,
+            TableRows::new(
+                "current_token_royalty_v1",
+                &["token_data_id"],
+                &self.current_token_royalties_v1,
+            )?,
+            TableRows::new(
+                "current_token_pending_claims",
+                &[
+                    "token_data_id_hash",
+                    "property_version",
+                    "from_address",
+                    "to_address",
+                ],
+                &self.current_token_claims,
+            )?,
+        ])
+    }
 }
 
 fn insert_collections_v2_query(
@@ -606,15 +688,15 @@ impl ProcessorTrait for TokenV2Processor {
             mut collections_v2,
             mut token_datas_v2,
             mut token_ownerships_v2,
-            current_collections_v2,
-            current_token_datas_v2,
-            current_deleted_token_datas_v2,
-            current_token_ownerships_v2,
-            current_deleted_token_ownerships_v2,
-            token_activities_v2,
+            mut current_collections_v2,
+            mut current_token_datas_v2,
+            mut current_deleted_token_datas_v2,
+            mut current_token_ownerships_v2,
+            mut current_deleted_token_ownerships_v2,
+            mut token_activities_v2,
             mut current_token_v2_metadata,
-            current_token_royalties_v1,
-            current_token_claims,
+            mut current_token_royalties_v1,
+            mut current_token_claims,
         ) = parse_v2_token(
             &transactions,
             &table_handle_to_owner,
@@ -645,28 +727,66 @@ impl ProcessorTrait for TokenV2Processor {
         {
             current_token_v2_metadata.clear();
         }
+        if self
+            .deprecated_tables
+            .contains(TableFlags::CURRENT_COLLECTIONS_V2)
+        {
+            current_collections_v2.clear();
+        }
+        if self
+            .deprecated_tables
+            .contains(TableFlags::CURRENT_TOKEN_DATAS_V2)
+        {
+            current_token_datas_v2.clear();
+            current_deleted_token_datas_v2.clear();
+        }
+        if self
+            .deprecated_tables
+            .contains(TableFlags::CURRENT_TOKEN_OWNERSHIPS_V2)
+        {
+            current_token_ownerships_v2.clear();
+            current_deleted_token_ownerships_v2.clear();
+        }
+        if self
+            .deprecated_tables
+            .contains(TableFlags::TOKEN_ACTIVITIES_V2)
+        {
+            token_activities_v2.clear();
+        }
+        if self
+            .deprecated_tables
+            .contains(TableFlags::CURRENT_TOKEN_ROYALTY_V1)
+        {
+            current_token_royalties_v1.clear();
+        }
+        if self
+            .deprecated_tables
+            .contains(TableFlags::CURRENT_TOKEN_PENDING_CLAIMS)
+        {
+            current_token_claims.clear();
+        }
 
-        let tx_result = insert_to_db(
-            self.get_pool(),
-            self.name(),
-            start_version,
-            end_version,
-            &collections_v2,
-            &token_datas_v2,
-            &token_ownerships_v2,
-            &current_collections_v2,
-            (&current_token_datas_v2, &current_deleted_token_datas_v2),
-            (
-                &current_token_ownerships_v2,
-                &current_deleted_token_ownerships_v2,
-            ),
-            &token_activities_v2,
-            &current_token_v2_metadata,
-            &current_token_royalties_v1,
-            &current_token_claims,
-            &self.per_table_chunk_sizes,
-        )
-        .await;
+        tracing::trace!(
+            name = self.name(),
+            start_version = start_version,
+            end_version = end_version,
+            "Writing to sinks",
+        );
+        let output = TokenV2ProcessorOutput {
+            collections_v2,
+            token_datas_v2,
+            token_ownerships_v2,
+            current_collections_v2,
+            current_token_datas_v2,
+            current_deleted_token_datas_v2,
+            current_token_ownerships_v2,
+            current_deleted_token_ownerships_v2,
+            token_activities_v2,
+            current_token_v2_metadata,
+            current_token_royalties_v1,
+            current_token_claims,
+        };
+        let tx_result = write_to_sinks(&self.sinks, &output, start_version, end_version).await;
 
         let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
         match tx_result {
@@ -697,7 +817,7 @@ impl ProcessorTrait for TokenV2Processor {
     }
 }
 
-async fn parse_v2_token(
+pub async fn parse_v2_token(
     transactions: &[Transaction],
     table_handle_to_owner: &TableHandleToOwner,
     conn: &mut DbPoolConnection<'_>,
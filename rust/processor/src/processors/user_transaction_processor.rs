@@ -187,6 +187,9 @@ impl ProcessorTrait for UserTransactionProcessor {
         if self.deprecated_tables.contains(TableFlags::SIGNATURES) {
             signatures.clear();
         }
+        if self.deprecated_tables.contains(TableFlags::USER_TRANSACTIONS) {
+            user_transactions.clear();
+        }
 
         let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
         let db_insertion_start = std::time::Instant::now();
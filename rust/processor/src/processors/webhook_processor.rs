@@ -0,0 +1,583 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Notifies a webhook of transactions that touch watched addresses or emit watched event types.
+//! Each matching transaction is POSTed as one JSON payload, signed with HMAC-SHA256 so the
+//! receiver can check it came from us. Every delivery is logged in `webhook_deliveries`, which is
+//! also used to skip transactions that were already delivered when a batch is processed again.
+
+use super::{DefaultProcessingResult, ProcessorName, ProcessorTrait};
+use crate::{
+    db::common::models::{
+        events_models::events::EventModel,
+        object_models::v2_object_utils::ObjectWithMetadata,
+        webhook_deliveries::{WebhookDelivery, DELIVERED, FAILED},
+    },
+    gap_detectors::ProcessingResult,
+    schema,
+    utils::{
+        counters::WEBHOOK_DELIVERY_COUNT,
+        database::{execute_with_better_error, ArcDbPool},
+        util::{parse_timestamp, standardize_address, standardize_event_type},
+    },
+};
+use ahash::{AHashMap, AHashSet};
+use anyhow::ensure;
+use aptos_protos::transaction::v1::{transaction::TxnData, write_set_change::Change, Transaction};
+use async_trait::async_trait;
+use diesel::{pg::upsert::excluded, ExpressionMethods};
+use hmac::{Hmac, Mac, NewMac};
+use reqwest::{header::CONTENT_TYPE, Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::{fmt::Debug, time::Duration};
+use tracing::{error, warn};
+use url::Url;
+
+/// `sha256=` followed by the hex encoded HMAC-SHA256 of the body, keyed with the secret
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// The transaction version, which stays the same across retries
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookProcessorConfig {
+    pub url: Url,
+    // Key of the payload signature
+    pub secret: String,
+    pub rules: Vec<WatchRule>,
+    // Attempts per transaction before the delivery is logged as failed
+    #[serde(default = "WebhookProcessorConfig::default_max_attempts")]
+    pub max_attempts: u32,
+    // Delay before the first retry, doubled for every retry after that
+    #[serde(default = "WebhookProcessorConfig::default_retry_delay_ms")]
+    pub retry_delay_ms: u64,
+    #[serde(default = "WebhookProcessorConfig::default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+}
+
+impl WebhookProcessorConfig {
+    pub const fn default_max_attempts() -> u32 {
+        5
+    }
+
+    pub const fn default_retry_delay_ms() -> u64 {
+        1000
+    }
+
+    pub const fn default_request_timeout_secs() -> u64 {
+        10
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            !self.rules.is_empty(),
+            "Webhook processor needs at least one rule"
+        );
+        for rule in &self.rules {
+            ensure!(
+                !rule.addresses.is_empty() || !rule.event_types.is_empty(),
+                "Webhook rule {} needs addresses or event_types",
+                rule.name
+            );
+            for address in &rule.addresses {
+                ensure!(
+                    is_address(address),
+                    "Webhook rule {} has an invalid address {}",
+                    rule.name,
+                    address
+                );
+            }
+            for event_type in &rule.event_types {
+                ensure!(
+                    event_type
+                        .split_once("::")
+                        .is_some_and(|(address, _)| is_address(address)),
+                    "Webhook rule {} needs event types like 0x1::module::Event, got {}",
+                    rule.name,
+                    event_type
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `address` is a hex address of at most 32 bytes, with or without the `0x` prefix
+fn is_address(address: &str) -> bool {
+    let hex_address = address.strip_prefix("0x").unwrap_or(address);
+    !hex_address.is_empty()
+        && hex_address.len() <= 64
+        && hex_address.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Matches events of any of `event_types` that involve any of `addresses`. An empty list matches
+/// everything, but a rule needs at least one of them.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchRule {
+    pub name: String,
+    // An event involves an address if it's emitted under the account, e.g. a coin DepositEvent,
+    // names it in a top level field, e.g. the `to` of a token transfer, or names an object the
+    // address owns in the same transaction, e.g. the `store` of a fungible asset deposit
+    #[serde(default)]
+    pub addresses: Vec<String>,
+    // Full types, or types without generic parameters to match all instantiations
+    #[serde(default)]
+    pub event_types: Vec<String>,
+}
+
+impl WatchRule {
+    /// Expects the addresses and event types of the rule to be standardized already
+    fn matches(&self, event: &EventModel, involved_addresses: &AHashSet<String>) -> bool {
+        let event_type = standardize_event_type(&event.type_);
+        let type_matches = self.event_types.is_empty()
+            || self.event_types.iter().any(|rule_type| {
+                event_type == *rule_type
+                    || event_type
+                        .strip_prefix(rule_type.as_str())
+                        .is_some_and(|rest| rest.starts_with('<'))
+            });
+        let address_matches = self.addresses.is_empty()
+            || self
+                .addresses
+                .iter()
+                .any(|address| involved_addresses.contains(address));
+        type_matches && address_matches
+    }
+}
+
+pub struct WebhookProcessor {
+    connection_pool: ArcDbPool,
+    config: WebhookProcessorConfig,
+    client: Client,
+}
+
+impl WebhookProcessor {
+    /// Expects a config that passed `WebhookProcessorConfig::validate`.
+    pub fn new(connection_pool: ArcDbPool, mut config: WebhookProcessorConfig) -> Self {
+        for rule in config.rules.iter_mut() {
+            rule.addresses = rule
+                .addresses
+                .iter()
+                .map(|address| standardize_address(&address.to_lowercase()))
+                .collect();
+            rule.event_types = rule
+                .event_types
+                .iter()
+                .map(|event_type| standardize_event_type(event_type))
+                .collect();
+        }
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self {
+            connection_pool,
+            config,
+            client,
+        }
+    }
+
+    /// Builds the payload of a transaction, or None if no event matches a rule.
+    fn build_payload(&self, txn: &Transaction, chain_id: Option<u64>) -> Option<Value> {
+        let txn_version = txn.version as i64;
+        let raw_events = match txn.txn_data.as_ref()? {
+            TxnData::BlockMetadata(tx_inner) => &tx_inner.events,
+            TxnData::Genesis(tx_inner) => &tx_inner.events,
+            TxnData::User(tx_inner) => &tx_inner.events,
+            _ => return None,
+        };
+        let events = EventModel::from_events(raw_events, txn_version, txn.block_height as i64);
+        let object_owners = get_object_owners(txn);
+
+        let mut matched_events = vec![];
+        for event in &events {
+            let involved_addresses = get_involved_addresses(event, &object_owners);
+            let rules: Vec<&str> = self
+                .config
+                .rules
+                .iter()
+                .filter(|rule| rule.matches(event, &involved_addresses))
+                .map(|rule| rule.name.as_str())
+                .collect();
+            if !rules.is_empty() {
+                matched_events.push(json!({
+                    "rules": rules,
+                    "event_index": event.event_index,
+                    "account_address": event.account_address,
+                    "type": event.type_,
+                    "data": event.data,
+                }));
+            }
+        }
+        if matched_events.is_empty() {
+            return None;
+        }
+        let timestamp = parse_timestamp(txn.timestamp.as_ref().unwrap(), txn_version);
+        Some(json!({
+            "chain_id": chain_id,
+            "transaction_version": txn_version,
+            "transaction_timestamp": timestamp,
+            "events": matched_events,
+        }))
+    }
+
+    /// Posts the payload until it's accepted, the webhook rejects it or we run out of attempts.
+    async fn deliver(&self, transaction_version: i64, payload: Value) -> WebhookDelivery {
+        let body = serde_json::to_vec(&payload).expect("JSON values always serialize");
+        let signature = format!("sha256={}", sign(self.config.secret.as_bytes(), &body));
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let result = self
+                .client
+                .post(self.config.url.clone())
+                .header(CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .header(DELIVERY_HEADER, transaction_version.to_string())
+                .body(body.clone())
+                .send()
+                .await;
+            let (response_status, error_message, retryable) = match result {
+                Ok(response) if response.status().is_success() => {
+                    return WebhookDelivery {
+                        transaction_version,
+                        payload,
+                        status: DELIVERED.to_string(),
+                        attempts,
+                        response_status: Some(response.status().as_u16() as i32),
+                        error_message: None,
+                        last_attempted_at: chrono::Utc::now().naive_utc(),
+                    };
+                },
+                Ok(response) => {
+                    let status = response.status();
+                    let text = response.text().await.unwrap_or_default();
+                    (
+                        Some(status.as_u16() as i32),
+                        format!("Webhook returned {}: {}", status, text),
+                        status.is_server_error()
+                            || status == StatusCode::TOO_MANY_REQUESTS
+                            || status == StatusCode::REQUEST_TIMEOUT,
+                    )
+                },
+                Err(err) => (None, format!("{:?}", err), true),
+            };
+            if !retryable || attempts >= self.config.max_attempts as i32 {
+                return WebhookDelivery {
+                    transaction_version,
+                    payload,
+                    status: FAILED.to_string(),
+                    attempts,
+                    response_status,
+                    error_message: Some(error_message),
+                    last_attempted_at: chrono::Utc::now().naive_utc(),
+                };
+            }
+            warn!(
+                transaction_version = transaction_version,
+                attempts = attempts,
+                error = error_message.as_str(),
+                "[Webhook] Delivery failed, retrying"
+            );
+            let delay = self.config.retry_delay_ms << (attempts - 1).min(16);
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+        }
+    }
+}
+
+impl Debug for WebhookProcessor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = &self.connection_pool.state();
+        write!(
+            f,
+            "WebhookProcessor {{ connections: {:?}  idle_connections: {:?} }}",
+            state.connections, state.idle_connections
+        )
+    }
+}
+
+/// Owners of the objects written in the transaction, e.g. of fungible stores.
+fn get_object_owners(txn: &Transaction) -> AHashMap<String, String> {
+    let mut object_owners = AHashMap::new();
+    let Some(info) = txn.info.as_ref() else {
+        return object_owners;
+    };
+    for wsc in &info.changes {
+        if let Some(Change::WriteResource(write_resource)) = wsc.change.as_ref() {
+            if let Ok(Some(object)) =
+                ObjectWithMetadata::from_write_resource(write_resource, txn.version as i64)
+            {
+                object_owners.insert(
+                    standardize_address(&write_resource.address),
+                    object.object_core.get_owner_address(),
+                );
+            }
+        }
+    }
+    object_owners
+}
+
+fn get_involved_addresses(
+    event: &EventModel,
+    object_owners: &AHashMap<String, String>,
+) -> AHashSet<String> {
+    let mut addresses = AHashSet::from([event.account_address.clone()]);
+    if let Some(fields) = event.data.as_object() {
+        for value in fields.values() {
+            let Some(address) = value.as_str().filter(|value| value.starts_with("0x")) else {
+                continue;
+            };
+            let address = standardize_address(address);
+            if let Some(owner) = object_owners.get(&address) {
+                addresses.insert(owner.clone());
+            }
+            addresses.insert(address);
+        }
+    }
+    addresses
+}
+
+fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+async fn log_delivery(
+    conn: ArcDbPool,
+    delivery: &WebhookDelivery,
+) -> Result<usize, diesel::result::Error> {
+    use schema::webhook_deliveries::dsl::*;
+
+    execute_with_better_error(
+        conn,
+        diesel::insert_into(schema::webhook_deliveries::table)
+            .values(delivery)
+            .on_conflict(transaction_version)
+            .do_update()
+            .set((
+                payload.eq(excluded(payload)),
+                status.eq(excluded(status)),
+                attempts.eq(excluded(attempts)),
+                response_status.eq(excluded(response_status)),
+                error_message.eq(excluded(error_message)),
+                last_attempted_at.eq(excluded(last_attempted_at)),
+                inserted_at.eq(excluded(inserted_at)),
+            )),
+        None,
+    )
+    .await
+}
+
+#[async_trait]
+impl ProcessorTrait for WebhookProcessor {
+    fn name(&self) -> &'static str {
+        ProcessorName::WebhookProcessor.into()
+    }
+
+    async fn process_transactions(
+        &self,
+        transactions: Vec<Transaction>,
+        start_version: u64,
+        end_version: u64,
+        db_chain_id: Option<u64>,
+    ) -> anyhow::Result<ProcessingResult> {
+        let processing_start = std::time::Instant::now();
+        let last_transaction_timestamp = transactions.last().unwrap().timestamp.clone();
+
+        let payloads: Vec<(i64, Value)> = transactions
+            .iter()
+            .filter_map(|txn| {
+                self.build_payload(txn, db_chain_id)
+                    .map(|payload| (txn.version as i64, payload))
+            })
+            .collect();
+
+        let processing_duration_in_secs = processing_start.elapsed().as_secs_f64();
+        let db_insertion_start = std::time::Instant::now();
+
+        if !payloads.is_empty() {
+            let mut conn = self.get_conn().await;
+            let delivered: AHashSet<i64> = WebhookDelivery::get_delivered_versions(
+                start_version as i64,
+                end_version as i64,
+                &mut conn,
+            )
+            .await?
+            .into_iter()
+            .collect();
+            drop(conn);
+
+            // Sent one at a time so the webhook sees transactions of a batch in order
+            for (version, payload) in payloads {
+                if delivered.contains(&version) {
+                    continue;
+                }
+                let delivery = self.deliver(version, payload).await;
+                WEBHOOK_DELIVERY_COUNT
+                    .with_label_values(&[delivery.status.as_str()])
+                    .inc();
+                // Failed deliveries aren't retried by later batches, they stay in
+                // `webhook_deliveries` until the version is reprocessed
+                if delivery.status == FAILED {
+                    error!(
+                        processor_name = self.name(),
+                        transaction_version = version,
+                        attempts = delivery.attempts,
+                        error = delivery.error_message.as_deref(),
+                        "[Webhook] Giving up on delivery"
+                    );
+                }
+                if let Err(err) = log_delivery(self.get_pool(), &delivery).await {
+                    anyhow::bail!(format!(
+                        "Error logging webhook delivery. Processor {}. Version {}. Error {:?}",
+                        self.name(),
+                        version,
+                        err
+                    ));
+                }
+            }
+        }
+
+        let db_insertion_duration_in_secs = db_insertion_start.elapsed().as_secs_f64();
+        Ok(ProcessingResult::DefaultProcessingResult(
+            DefaultProcessingResult {
+                start_version,
+                end_version,
+                processing_duration_in_secs,
+                db_insertion_duration_in_secs,
+                last_transaction_timestamp,
+            },
+        ))
+    }
+
+    fn connection_pool(&self) -> &ArcDbPool {
+        &self.connection_pool
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(account_address: &str, type_: &str, data: Value) -> EventModel {
+        EventModel {
+            sequence_number: 0,
+            creation_number: 0,
+            account_address: standardize_address(account_address),
+            transaction_version: 1,
+            transaction_block_height: 1,
+            type_: type_.to_string(),
+            data,
+            event_index: 0,
+            indexed_type: type_.to_string(),
+        }
+    }
+
+    fn rule(addresses: &[&str], event_types: &[&str]) -> WatchRule {
+        WatchRule {
+            name: "test".to_string(),
+            addresses: addresses.iter().map(|a| standardize_address(a)).collect(),
+            event_types: event_types
+                .iter()
+                .map(|t| standardize_event_type(t))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign(b"Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = |rules: Vec<WatchRule>| WebhookProcessorConfig {
+            url: Url::parse("https://example.com/hooks").unwrap(),
+            secret: "secret".to_string(),
+            rules,
+            max_attempts: WebhookProcessorConfig::default_max_attempts(),
+            retry_delay_ms: WebhookProcessorConfig::default_retry_delay_ms(),
+            request_timeout_secs: WebhookProcessorConfig::default_request_timeout_secs(),
+        };
+        assert!(config(vec![rule(&["0xa"], &[])]).validate().is_ok());
+        assert!(config(vec![]).validate().is_err());
+        assert!(config(vec![rule(&[], &[])]).validate().is_err());
+
+        let unstandardized = |addresses: &[&str], event_types: &[&str]| WatchRule {
+            name: "test".to_string(),
+            addresses: addresses.iter().map(|a| a.to_string()).collect(),
+            event_types: event_types.iter().map(|t| t.to_string()).collect(),
+        };
+        assert!(
+            config(vec![unstandardized(&["0xA"], &["0x1::coin::CoinDeposit"])])
+                .validate()
+                .is_ok()
+        );
+        assert!(config(vec![unstandardized(&["0xzz"], &[])])
+            .validate()
+            .is_err());
+        let long_address = "a".repeat(65);
+        assert!(config(vec![unstandardized(&[long_address.as_str()], &[])])
+            .validate()
+            .is_err());
+        assert!(config(vec![unstandardized(&[], &["coin::CoinDeposit"])])
+            .validate()
+            .is_err());
+        assert!(
+            config(vec![unstandardized(&[], &["0xzz::coin::CoinDeposit"])])
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_rule_matches_event_types() {
+        let deposit = event(
+            "0xa",
+            "0x1::coin::CoinDeposit<0x1::aptos_coin::AptosCoin>",
+            json!({}),
+        );
+        let involved = get_involved_addresses(&deposit, &AHashMap::new());
+        assert!(rule(&[], &["0x1::coin::CoinDeposit"]).matches(&deposit, &involved));
+        assert!(
+            rule(&[], &["0x1::coin::CoinDeposit<0x1::aptos_coin::AptosCoin>"])
+                .matches(&deposit, &involved)
+        );
+        assert!(!rule(&[], &["0x1::coin::Coin"]).matches(&deposit, &involved));
+        // Either form of the address matches
+        let long_type = format!("{}::coin::CoinDeposit", standardize_address("0x1"));
+        assert!(rule(&[], &[long_type.as_str()]).matches(&deposit, &involved));
+        assert!(rule(&["0xa"], &["0x1::coin::CoinDeposit"]).matches(&deposit, &involved));
+        assert!(!rule(&["0xb"], &["0x1::coin::CoinDeposit"]).matches(&deposit, &involved));
+    }
+
+    #[test]
+    fn test_rule_matches_addresses() {
+        let transfer = event(
+            "0x0",
+            "0x1::object::Transfer",
+            json!({ "object": "0xc", "from": "0xa", "to": "0xb" }),
+        );
+        let involved = get_involved_addresses(&transfer, &AHashMap::new());
+        assert!(rule(&["0xb"], &[]).matches(&transfer, &involved));
+        assert!(!rule(&["0xd"], &[]).matches(&transfer, &involved));
+
+        // Deposits into a store count for the store's owner
+        let deposit = event(
+            "0x0",
+            "0x1::fungible_asset::Deposit",
+            json!({ "store": "0xe", "amount": "100" }),
+        );
+        let owners = AHashMap::from([(standardize_address("0xe"), standardize_address("0xd"))]);
+        let involved = get_involved_addresses(&deposit, &owners);
+        assert!(rule(&["0xd"], &["0x1::fungible_asset::Deposit"]).matches(&deposit, &involved));
+    }
+}
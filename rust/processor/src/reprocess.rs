@@ -0,0 +1,267 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reruns a processor on a list of transaction versions, e.g. the handful of versions a parser fix
+//! affects, without streaming the range in between. Writes go through the processor's usual
+//! inserts, so `current_*` rows are only overwritten where the reprocessed version is at least as
+//! new as the stored one. `processor_status` isn't touched.
+
+use crate::{
+    config::TransactionSourceConfig,
+    db::common::models::ledger_info::LedgerInfo,
+    file_stream::read_transactions_from_dir,
+    grpc_stream::get_stream,
+    processors::{ProcessorConfig, ProcessorName, ProcessorTrait},
+    sinks::build_sinks,
+    utils::database::new_db_pool,
+    worker::{build_processor, TableFlags},
+    IndexerGrpcProcessorConfig,
+};
+use ahash::AHashMap;
+use anyhow::{bail, Context, Result};
+use aptos_protos::transaction::v1::Transaction;
+use clap::Args;
+use futures_util::StreamExt;
+use std::{collections::BTreeSet, path::PathBuf, sync::Arc};
+use tracing::{error, info, warn};
+
+#[derive(Args, Clone, Debug)]
+pub struct ReprocessArgs {
+    /// Processor to rerun. Defaults to `processor_config`, otherwise it must be one of the
+    /// `additional_processor_configs`
+    #[clap(long, value_enum)]
+    pub processor: Option<ProcessorName>,
+    /// Versions to reprocess, comma separated
+    #[clap(long, value_delimiter = ',')]
+    pub versions: Vec<u64>,
+    /// File with more versions to reprocess, separated by whitespace or commas. Lines starting
+    /// with `#` are ignored
+    #[clap(long)]
+    pub versions_file: Option<PathBuf>,
+}
+
+/// What happened to a single version.
+#[derive(Debug)]
+pub enum ReprocessOutcome {
+    Processed,
+    /// The source didn't return the version, e.g. because it's past the end of the files
+    NotFound,
+    Failed(anyhow::Error),
+}
+
+impl ReprocessArgs {
+    pub async fn run(&self, config: &IndexerGrpcProcessorConfig) -> Result<()> {
+        let processor_config = self.processor_config(config)?;
+        let processor_name = processor_config.name();
+        anyhow::ensure!(
+            !processor_config.is_parquet_processor(),
+            "{} writes parquet files and can't be reprocessed by version",
+            processor_name
+        );
+
+        processor_config.validate()?;
+
+        let mut versions: BTreeSet<u64> = self.versions.iter().copied().collect();
+        if let Some(path) = &self.versions_file {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            versions.extend(parse_versions(&contents)?);
+        }
+        if versions.is_empty() {
+            bail!("No versions to reprocess, pass --versions or --versions-file");
+        }
+
+        let pool = new_db_pool(&config.postgres_connection_string, config.db_pool_size)
+            .await
+            .context("Failed to create connection pool")?;
+        let sinks = build_sinks(
+            processor_config.sinks(),
+            pool.clone(),
+            config.per_table_chunk_sizes.clone(),
+        )?;
+        let processor = build_processor(
+            processor_config,
+            config.per_table_chunk_sizes.clone(),
+            TableFlags::from_names(&config.deprecated_tables),
+            pool.clone(),
+            Arc::new(sinks),
+            None,
+        );
+
+        let (chain_id, mut transactions) =
+            fetch_transactions(config, processor_name, &versions).await?;
+        let mut conn = pool.get().await.context("Failed to get a connection")?;
+        match LedgerInfo::get(&mut conn).await? {
+            Some(ledger_info) if ledger_info.chain_id as u64 != chain_id => bail!(
+                "Transactions are from chain {} but the database has chain {}",
+                chain_id,
+                ledger_info.chain_id
+            ),
+            Some(_) => {},
+            None => bail!("The database has no chain id yet, run the processor first"),
+        }
+        drop(conn);
+
+        info!(
+            processor_name = processor_name,
+            num_versions = versions.len(),
+            "[Reprocess] Reprocessing versions"
+        );
+        let mut num_failed = 0;
+        for version in versions.iter().copied() {
+            let outcome = match transactions.remove(&version) {
+                None => ReprocessOutcome::NotFound,
+                Some(txn) => match processor
+                    .process_transactions(vec![txn], version, version, Some(chain_id))
+                    .await
+                {
+                    Ok(_) => ReprocessOutcome::Processed,
+                    Err(e) => ReprocessOutcome::Failed(e),
+                },
+            };
+            match &outcome {
+                ReprocessOutcome::Processed => {
+                    info!(processor_name, version, "[Reprocess] Processed version");
+                },
+                ReprocessOutcome::NotFound => {
+                    num_failed += 1;
+                    warn!(processor_name, version, "[Reprocess] Version not found");
+                },
+                ReprocessOutcome::Failed(e) => {
+                    num_failed += 1;
+                    error!(processor_name, version, error = ?e, "[Reprocess] Version failed");
+                },
+            }
+        }
+
+        if num_failed > 0 {
+            bail!(
+                "{} of {} versions could not be reprocessed",
+                num_failed,
+                versions.len()
+            );
+        }
+        info!(
+            processor_name = processor_name,
+            num_versions = versions.len(),
+            "[Reprocess] All versions reprocessed"
+        );
+        Ok(())
+    }
+
+    fn processor_config<'a>(
+        &self,
+        config: &'a IndexerGrpcProcessorConfig,
+    ) -> Result<&'a ProcessorConfig> {
+        let Some(processor) = self.processor else {
+            return Ok(&config.processor_config);
+        };
+        let name: &'static str = processor.into();
+        std::iter::once(&config.processor_config)
+            .chain(&config.additional_processor_configs)
+            .find(|processor_config| processor_config.name() == name)
+            .with_context(|| format!("{} isn't configured in the config file", name))
+    }
+}
+
+/// Fetches the transactions at the given versions, keyed by version, along with their chain id.
+async fn fetch_transactions(
+    config: &IndexerGrpcProcessorConfig,
+    processor_name: &str,
+    versions: &BTreeSet<u64>,
+) -> Result<(u64, AHashMap<u64, Transaction>)> {
+    let mut transactions = AHashMap::new();
+    match &config.transaction_source {
+        TransactionSourceConfig::Grpc => {
+            let mut chain_id = None;
+            // Neighbouring versions are fetched with one request
+            for (start_version, end_version) in contiguous_ranges(versions) {
+                let mut stream = get_stream(
+                    config.indexer_grpc_data_service_address.clone(),
+                    config.grpc_http2_config.grpc_http2_ping_interval_in_secs(),
+                    config.grpc_http2_config.grpc_http2_ping_timeout_in_secs(),
+                    config.grpc_http2_config.grpc_connection_timeout_secs(),
+                    start_version,
+                    Some(end_version),
+                    config.auth_token.clone(),
+                    processor_name.to_string(),
+                )
+                .await?
+                .into_inner();
+                while let Some(response) = stream.next().await {
+                    let response = response.context("Failed to read from the GRPC stream")?;
+                    chain_id = chain_id.or(response.chain_id);
+                    let mut reached_end = false;
+                    for txn in response.transactions {
+                        reached_end |= txn.version >= end_version;
+                        if versions.contains(&txn.version) {
+                            transactions.insert(txn.version, txn);
+                        }
+                    }
+                    if reached_end {
+                        break;
+                    }
+                }
+            }
+            let chain_id = chain_id.context("The data service didn't return a chain id")?;
+            Ok((chain_id, transactions))
+        },
+        TransactionSourceConfig::LocalFile(source) => {
+            // The whole directory is read, which is fine for the fixture sized sets this is for
+            for txn in read_transactions_from_dir(&source.path, &source.format).await? {
+                if versions.contains(&txn.version) {
+                    transactions.insert(txn.version, txn);
+                }
+            }
+            Ok((source.chain_id, transactions))
+        },
+    }
+}
+
+/// Parses versions separated by whitespace or commas, skipping comment lines.
+fn parse_versions(contents: &str) -> Result<Vec<u64>> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(|line| line.split(|c: char| c == ',' || c.is_whitespace()))
+        .filter(|version| !version.is_empty())
+        .map(|version| {
+            version
+                .parse::<u64>()
+                .with_context(|| format!("Invalid version {}", version))
+        })
+        .collect()
+}
+
+/// Groups sorted versions into inclusive ranges of consecutive versions.
+fn contiguous_ranges(versions: &BTreeSet<u64>) -> Vec<(u64, u64)> {
+    let mut ranges: Vec<(u64, u64)> = vec![];
+    for version in versions.iter().copied() {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == version => *end = version,
+            _ => ranges.push((version, version)),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_versions() {
+        assert_eq!(
+            parse_versions("# affected versions\n1, 2\n3 4\n\n  5\n").unwrap(),
+            vec![1, 2, 3, 4, 5]
+        );
+        assert!(parse_versions("1\nabc\n").is_err());
+    }
+
+    #[test]
+    fn test_contiguous_ranges() {
+        let versions = BTreeSet::from([1, 2, 3, 7, 9, 10]);
+        assert_eq!(contiguous_ranges(&versions), vec![(1, 3), (7, 7), (9, 10)]);
+    }
+}
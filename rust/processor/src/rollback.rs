@@ -0,0 +1,933 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rewinds a processor to an earlier version so a range can be reprocessed after a parsing bug.
+//! Everything the processor wrote after the target version is removed, its `current_*` tables are
+//! rebuilt from history as of the target version and `processor_status` is moved back, all in one
+//! database transaction. The processor must be stopped first, otherwise it will move its status
+//! forward again.
+
+use crate::{
+    processors::ProcessorName,
+    schema::processor_status,
+    utils::database::{new_db_pool, MyDbConnection},
+    IndexerGrpcProcessorConfig,
+};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use diesel::{
+    sql_types::{BigInt, Timestamp},
+    ExpressionMethods, OptionalExtension, QueryDsl, QueryableByName,
+};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection, RunQueryDsl};
+use tracing::{info, warn};
+
+#[derive(Args, Clone, Debug)]
+pub struct RollbackArgs {
+    /// Processor to roll back
+    #[clap(long, value_enum)]
+    pub processor: ProcessorName,
+    /// Last version to keep. Everything the processor wrote after it is removed
+    #[clap(long)]
+    pub target_version: u64,
+    /// Leave `current_*` tables that can't be rebuilt from history as they are. Their rows from
+    /// after the target version are overwritten once reprocessing catches up with them
+    #[clap(long)]
+    pub allow_partial: bool,
+}
+
+/// A table written by a processor, and how to undo its writes after a version.
+#[derive(Clone, Copy, Debug)]
+pub enum OwnedTable {
+    /// One row per change. Rows written after the target version are deleted.
+    Versioned {
+        table: &'static str,
+        version_column: &'static str,
+    },
+    /// Latest state per key, which is rebuilt from a history table.
+    Current(CurrentTable),
+    /// Latest state per key that a `CurrentTable` can't describe, e.g. running totals. The keys
+    /// changed after the target version are recomputed from history by `rebuild_query`, which
+    /// takes the target version as `$1`, and keys first written after it are deleted.
+    Rebuilt {
+        table: &'static str,
+        rebuild_query: &'static str,
+    },
+    /// Latest state per key with no history to rebuild it from.
+    CurrentWithoutHistory { table: &'static str },
+    /// Data that doesn't change once written, such as table metadata, so it's left alone.
+    Unversioned { table: &'static str },
+}
+
+const fn versioned(table: &'static str) -> OwnedTable {
+    OwnedTable::Versioned {
+        table,
+        version_column: "transaction_version",
+    }
+}
+
+/// A `current_*` table whose rows are the latest row per key of a history table.
+#[derive(Clone, Copy, Debug)]
+pub struct CurrentTable {
+    pub table: &'static str,
+    pub history_table: &'static str,
+    pub key_columns: &'static [&'static str],
+    /// Columns with the same name in both tables
+    pub columns: &'static [&'static str],
+    /// Columns of the current table, including key columns, that are computed from the history
+    /// table. The history table's `transaction_version` always becomes `last_transaction_version`.
+    pub renamed_columns: &'static [(&'static str, &'static str)],
+    /// Orders history rows written in the same version, newest last
+    pub order_columns: &'static [&'static str],
+}
+
+impl CurrentTable {
+    /// The expression computing `column` of the current table from a history row.
+    pub fn history_expression(&self, column: &'static str) -> &'static str {
+        self.renamed_columns
+            .iter()
+            .find(|(name, _)| *name == column)
+            .map(|(_, expression)| *expression)
+            .unwrap_or(column)
+    }
+
+    /// The columns of the current table that are computed from history, key columns first. This
+    /// excludes `last_transaction_version`.
+    pub fn current_columns(&self) -> Vec<&'static str> {
+        self.key_columns
+            .iter()
+            .chain(self.columns)
+            .chain(
+                self.renamed_columns
+                    .iter()
+                    .map(|(name, _)| name)
+                    .filter(|name| !self.key_columns.contains(*name)),
+            )
+            .copied()
+            .collect()
+    }
+
+    /// Deletes the rows last changed after the target version.
+    pub fn delete_query(&self) -> String {
+        format!(
+            "DELETE FROM {} WHERE last_transaction_version > $1",
+            self.table
+        )
+    }
+
+    /// Writes, for every key changed after the target version, its latest history row at or
+    /// before the target version. Keys first written after the target version stay deleted. Rows
+    /// that weren't deleted, e.g. because a flag stopped the processor from writing this table, are
+    /// overwritten. This has to run before the history rows after the target version are deleted.
+    pub fn rebuild_query(&self) -> String {
+        let columns = self.current_columns();
+        let keys = self
+            .key_columns
+            .iter()
+            .map(|column| self.history_expression(column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let values = columns
+            .iter()
+            .map(|column| self.history_expression(column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let order = std::iter::once("transaction_version")
+            .chain(self.order_columns.iter().copied())
+            .map(|column| format!("{} DESC", column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let updates = columns
+            .iter()
+            .filter(|column| !self.key_columns.contains(*column))
+            .chain(std::iter::once(&"last_transaction_version"))
+            .map(|column| format!("{0} = EXCLUDED.{0}", column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "INSERT INTO {table} ({columns}, last_transaction_version) \
+             SELECT DISTINCT ON ({keys}) {values}, transaction_version FROM {history} \
+             WHERE transaction_version <= $1 \
+             AND ({keys}) IN (SELECT {keys} FROM {history} WHERE transaction_version > $1) \
+             ORDER BY {keys}, {order} \
+             ON CONFLICT ({key_columns}) DO UPDATE SET {updates}",
+            table = self.table,
+            columns = columns.join(", "),
+            history = self.history_table,
+            key_columns = self.key_columns.join(", "),
+        )
+    }
+
+    /// The timestamp of the newest history row at or before the target version, if the history
+    /// table has one.
+    pub fn last_timestamp_query(&self) -> Option<String> {
+        let timestamp = self
+            .renamed_columns
+            .iter()
+            .find(|(name, _)| *name == "last_transaction_timestamp")
+            .map(|(_, expression)| *expression)?;
+        Some(format!(
+            "SELECT {} AS timestamp FROM {} WHERE transaction_version <= $1 \
+             ORDER BY transaction_version DESC LIMIT 1",
+            timestamp, self.history_table
+        ))
+    }
+}
+
+/// Sums up the activities of each pool up to the target version, the way the dex processor applies
+/// them to the pool. A reserve is the last one an event reported plus the amounts after it, or
+/// the sum of all amounts if no event reported it. Activities are numbered by how many reserves
+/// were reported up to them, so the last reported reserve and the amounts after it share the
+/// highest number. Tokens and decimals don't change and are kept, and amounts are normalized by
+/// moving the decimal point like `normalize_amount`.
+const CURRENT_DEX_POOLS_REBUILD_QUERY: &str = "UPDATE current_dex_pools AS pools SET \
+     reserve_x = totals.reserve_x, \
+     reserve_y = totals.reserve_y, \
+     reserve_x_normalized = (totals.reserve_x::TEXT || 'e-' || pools.token_x_decimals)::NUMERIC, \
+     reserve_y_normalized = (totals.reserve_y::TEXT || 'e-' || pools.token_y_decimals)::NUMERIC, \
+     volume_x = totals.volume_x, \
+     volume_y = totals.volume_y, \
+     volume_x_normalized = (totals.volume_x::TEXT || 'e-' || pools.token_x_decimals)::NUMERIC, \
+     volume_y_normalized = (totals.volume_y::TEXT || 'e-' || pools.token_y_decimals)::NUMERIC, \
+     swap_count = totals.swap_count, \
+     last_transaction_version = totals.last_transaction_version, \
+     last_transaction_timestamp = totals.last_transaction_timestamp \
+     FROM (SELECT dex, pool_id, \
+     SUM(COALESCE(reserve_x, amount_x)) FILTER (WHERE reserve_x_number = reserve_x_count) \
+     AS reserve_x, \
+     SUM(COALESCE(reserve_y, amount_y)) FILTER (WHERE reserve_y_number = reserve_y_count) \
+     AS reserve_y, \
+     COALESCE(SUM(ABS(amount_x)) FILTER (WHERE activity_type = 'swap'), 0) AS volume_x, \
+     COALESCE(SUM(ABS(amount_y)) FILTER (WHERE activity_type = 'swap'), 0) AS volume_y, \
+     COUNT(*) FILTER (WHERE activity_type = 'swap') AS swap_count, \
+     MAX(transaction_version) AS last_transaction_version, \
+     MAX(transaction_timestamp) AS last_transaction_timestamp \
+     FROM (SELECT *, \
+     COUNT(reserve_x) OVER (PARTITION BY dex, pool_id ORDER BY transaction_version, event_index) \
+     AS reserve_x_number, \
+     COUNT(reserve_y) OVER (PARTITION BY dex, pool_id ORDER BY transaction_version, event_index) \
+     AS reserve_y_number, \
+     COUNT(reserve_x) OVER (PARTITION BY dex, pool_id) AS reserve_x_count, \
+     COUNT(reserve_y) OVER (PARTITION BY dex, pool_id) AS reserve_y_count \
+     FROM dex_activities WHERE transaction_version <= $1 \
+     AND (dex, pool_id) IN (SELECT dex, pool_id FROM dex_activities WHERE transaction_version > $1)) \
+     AS activities \
+     GROUP BY dex, pool_id) AS totals \
+     WHERE pools.dex = totals.dex AND pools.pool_id = totals.pool_id";
+
+/// Writes, for every day changed after the target version, the last primary store balance of the
+/// day at or before the target version. This is `CurrentTable::rebuild_query` with the day as part
+/// of the key and only primary stores, like `DailyFungibleAssetBalance::from_balances`.
+const DAILY_FUNGIBLE_ASSET_BALANCES_REBUILD_QUERY: &str =
+    "INSERT INTO daily_fungible_asset_balances \
+     (owner_address, asset_type, date, storage_id, amount, token_standard, \
+     last_transaction_version, last_transaction_timestamp) \
+     SELECT DISTINCT ON (owner_address, asset_type, transaction_timestamp::DATE) \
+     owner_address, asset_type, transaction_timestamp::DATE, storage_id, amount, token_standard, \
+     transaction_version, transaction_timestamp FROM fungible_asset_balances \
+     WHERE is_primary AND transaction_version <= $1 \
+     AND (owner_address, asset_type, transaction_timestamp::DATE) IN \
+     (SELECT owner_address, asset_type, transaction_timestamp::DATE FROM fungible_asset_balances \
+     WHERE is_primary AND transaction_version > $1) \
+     ORDER BY owner_address, asset_type, transaction_timestamp::DATE, \
+     transaction_version DESC, write_set_change_index DESC \
+     ON CONFLICT (owner_address, asset_type, date) DO UPDATE SET \
+     storage_id = EXCLUDED.storage_id, amount = EXCLUDED.amount, \
+     token_standard = EXCLUDED.token_standard, \
+     last_transaction_version = EXCLUDED.last_transaction_version, \
+     last_transaction_timestamp = EXCLUDED.last_transaction_timestamp";
+
+/// The postgres tables each processor writes to.
+pub fn owned_tables(processor: ProcessorName) -> Result<Vec<OwnedTable>> {
+    let tables = match processor {
+        ProcessorName::AccountTransactionsProcessor => vec![versioned("account_transactions")],
+        ProcessorName::AnsProcessor => vec![
+            versioned("ans_lookup"),
+            versioned("ans_lookup_v2"),
+            versioned("ans_primary_name"),
+            versioned("ans_primary_name_v2"),
+            OwnedTable::Current(CurrentTable {
+                table: "current_ans_lookup",
+                history_table: "ans_lookup",
+                key_columns: &["domain", "subdomain"],
+                columns: &[
+                    "registered_address",
+                    "expiration_timestamp",
+                    "token_name",
+                    "is_deleted",
+                ],
+                renamed_columns: &[],
+                order_columns: &["write_set_change_index"],
+            }),
+            OwnedTable::Current(CurrentTable {
+                table: "current_ans_lookup_v2",
+                history_table: "ans_lookup_v2",
+                key_columns: &["domain", "subdomain", "token_standard"],
+                columns: &[
+                    "token_name",
+                    "registered_address",
+                    "expiration_timestamp",
+                    "is_deleted",
+                    "subdomain_expiration_policy",
+                ],
+                renamed_columns: &[],
+                order_columns: &["write_set_change_index"],
+            }),
+            OwnedTable::Current(CurrentTable {
+                table: "current_ans_primary_name",
+                history_table: "ans_primary_name",
+                key_columns: &["registered_address"],
+                columns: &["domain", "subdomain", "token_name", "is_deleted"],
+                renamed_columns: &[],
+                order_columns: &["write_set_change_index"],
+            }),
+            OwnedTable::Current(CurrentTable {
+                table: "current_ans_primary_name_v2",
+                history_table: "ans_primary_name_v2",
+                key_columns: &["registered_address", "token_standard"],
+                columns: &["domain", "subdomain", "token_name", "is_deleted"],
+                renamed_columns: &[],
+                order_columns: &["write_set_change_index"],
+            }),
+        ],
+        ProcessorName::CoinProcessor => vec![
+            versioned("coin_activities"),
+            versioned("coin_balances"),
+            OwnedTable::CurrentWithoutHistory {
+                table: "coin_infos",
+            },
+            OwnedTable::Current(CurrentTable {
+                table: "current_coin_balances",
+                history_table: "coin_balances",
+                key_columns: &["owner_address", "coin_type_hash"],
+                columns: &["coin_type", "amount"],
+                renamed_columns: &[("last_transaction_timestamp", "transaction_timestamp")],
+                order_columns: &[],
+            }),
+        ],
+        ProcessorName::CustomEventProcessor => bail!(
+            "{} writes the tables named in its config, which rollback doesn't read, so it can't be \
+             rolled back",
+            processor
+        ),
+        ProcessorName::DefaultProcessor => vec![
+            OwnedTable::Versioned {
+                table: "block_metadata_transactions",
+                version_column: "version",
+            },
+            versioned("move_modules"),
+            versioned("move_resources"),
+            versioned("table_items"),
+            OwnedTable::Versioned {
+                table: "transactions",
+                version_column: "version",
+            },
+            versioned("write_set_changes"),
+            OwnedTable::Current(CurrentTable {
+                table: "current_table_items",
+                history_table: "table_items",
+                key_columns: &["table_handle", "key_hash"],
+                columns: &["key", "decoded_key", "decoded_value", "is_deleted"],
+                // Same as `hash_str` on the key
+                renamed_columns: &[("key_hash", "encode(sha256(convert_to(key, 'UTF8')), 'hex')")],
+                order_columns: &["write_set_change_index"],
+            }),
+            OwnedTable::Unversioned {
+                table: "table_metadatas",
+            },
+        ],
+        ProcessorName::DexProcessor => vec![
+            versioned("dex_activities"),
+            OwnedTable::Rebuilt {
+                table: "current_dex_pools",
+                rebuild_query: CURRENT_DEX_POOLS_REBUILD_QUERY,
+            },
+        ],
+        ProcessorName::EventsProcessor => vec![versioned("events")],
+        ProcessorName::FungibleAssetProcessor => vec![
+            versioned("coin_supply"),
+            versioned("fungible_asset_activities"),
+            versioned("fungible_asset_balances"),
+            OwnedTable::Current(CurrentTable {
+                table: "current_fungible_asset_balances",
+                history_table: "fungible_asset_balances",
+                key_columns: &["storage_id"],
+                columns: &[
+                    "owner_address",
+                    "asset_type",
+                    "is_primary",
+                    "is_frozen",
+                    "amount",
+                    "token_standard",
+                ],
+                renamed_columns: &[("last_transaction_timestamp", "transaction_timestamp")],
+                order_columns: &["write_set_change_index"],
+            }),
+            OwnedTable::CurrentWithoutHistory {
+                table: "current_unified_fungible_asset_balances_to_be_renamed",
+            },
+            OwnedTable::Rebuilt {
+                table: "daily_fungible_asset_balances",
+                rebuild_query: DAILY_FUNGIBLE_ASSET_BALANCES_REBUILD_QUERY,
+            },
+            OwnedTable::CurrentWithoutHistory {
+                table: "fungible_asset_metadata",
+            },
+        ],
+        ProcessorName::NftMarketplaceProcessor => vec![
+            versioned("nft_marketplace_activities"),
+            OwnedTable::CurrentWithoutHistory {
+                table: "current_nft_marketplace_collection_offers",
+            },
+            OwnedTable::CurrentWithoutHistory {
+                table: "current_nft_marketplace_listings",
+            },
+            OwnedTable::CurrentWithoutHistory {
+                table: "current_nft_marketplace_token_offers",
+            },
+        ],
+        // Webhook deliveries are kept, so transactions that were delivered aren't sent again
+        ProcessorName::MonitoringProcessor
+        | ProcessorName::NftMetadataProcessor
+        | ProcessorName::WebhookProcessor => vec![],
+        ProcessorName::ObjectsProcessor => vec![
+            versioned("objects"),
+            OwnedTable::Current(CurrentTable {
+                table: "current_objects",
+                history_table: "objects",
+                key_columns: &["object_address"],
+                columns: &[
+                    "owner_address",
+                    "state_key_hash",
+                    "allow_ungated_transfer",
+                    "is_deleted",
+                    "untransferrable",
+                ],
+                renamed_columns: &[("last_guid_creation_num", "guid_creation_num")],
+                order_columns: &["write_set_change_index"],
+            }),
+        ],
+        ProcessorName::ResourceTrackingProcessor => vec![
+            versioned("tracked_resources"),
+            OwnedTable::Current(CurrentTable {
+                table: "current_tracked_resources",
+                history_table: "tracked_resources",
+                key_columns: &["address", "resource_type"],
+                columns: &["tracker", "fields", "is_deleted"],
+                renamed_columns: &[("last_transaction_timestamp", "transaction_timestamp")],
+                order_columns: &["write_set_change_index"],
+            }),
+        ],
+        ProcessorName::StakeProcessor => vec![
+            versioned("delegated_staking_activities"),
+            versioned("delegated_staking_pool_balances"),
+            OwnedTable::Versioned {
+                table: "delegated_staking_pools",
+                version_column: "first_transaction_version",
+            },
+            versioned("delegator_balances"),
+            versioned("proposal_votes"),
+            OwnedTable::Current(CurrentTable {
+                table: "current_delegated_staking_pool_balances",
+                history_table: "delegated_staking_pool_balances",
+                key_columns: &["staking_pool_address"],
+                columns: &[
+                    "total_coins",
+                    "total_shares",
+                    "operator_commission_percentage",
+                    "inactive_table_handle",
+                    "active_table_handle",
+                ],
+                renamed_columns: &[],
+                order_columns: &[],
+            }),
+            OwnedTable::Current(CurrentTable {
+                table: "current_delegator_balances",
+                history_table: "delegator_balances",
+                key_columns: &[
+                    "delegator_address",
+                    "pool_address",
+                    "pool_type",
+                    "table_handle",
+                ],
+                columns: &["shares", "parent_table_handle"],
+                renamed_columns: &[],
+                order_columns: &["write_set_change_index"],
+            }),
+            OwnedTable::CurrentWithoutHistory {
+                table: "current_delegated_voter",
+            },
+            OwnedTable::CurrentWithoutHistory {
+                table: "current_staking_pool_voter",
+            },
+        ],
+        ProcessorName::TokenProcessor => vec![
+            versioned("collection_datas"),
+            versioned("nft_points"),
+            versioned("token_activities"),
+            versioned("token_datas"),
+            versioned("token_ownerships"),
+            versioned("tokens"),
+            OwnedTable::Current(CurrentTable {
+                table: "current_collection_datas",
+                history_table: "collection_datas",
+                key_columns: &["collection_data_id_hash"],
+                columns: &[
+                    "creator_address",
+                    "collection_name",
+                    "description",
+                    "metadata_uri",
+                    "supply",
+                    "maximum",
+                    "maximum_mutable",
+                    "uri_mutable",
+                    "description_mutable",
+                    "table_handle",
+                ],
+                renamed_columns: &[("last_transaction_timestamp", "transaction_timestamp")],
+                order_columns: &[],
+            }),
+            OwnedTable::Current(CurrentTable {
+                table: "current_token_datas",
+                history_table: "token_datas",
+                key_columns: &["token_data_id_hash"],
+                columns: &[
+                    "creator_address",
+                    "collection_name",
+                    "name",
+                    "maximum",
+                    "supply",
+                    "largest_property_version",
+                    "metadata_uri",
+                    "payee_address",
+                    "royalty_points_numerator",
+                    "royalty_points_denominator",
+                    "maximum_mutable",
+                    "uri_mutable",
+                    "description_mutable",
+                    "properties_mutable",
+                    "royalty_mutable",
+                    "default_properties",
+                    "collection_data_id_hash",
+                    "description",
+                ],
+                renamed_columns: &[("last_transaction_timestamp", "transaction_timestamp")],
+                order_columns: &[],
+            }),
+            // The token properties live in `tokens`, not `token_ownerships`
+            OwnedTable::CurrentWithoutHistory {
+                table: "current_token_ownerships",
+            },
+        ],
+        ProcessorName::TokenV2Processor => vec![
+            versioned("collections_v2"),
+            versioned("token_activities_v2"),
+            versioned("token_datas_v2"),
+            versioned("token_ownerships_v2"),
+            OwnedTable::Current(CurrentTable {
+                table: "current_collections_v2",
+                history_table: "collections_v2",
+                key_columns: &["collection_id"],
+                columns: &[
+                    "creator_address",
+                    "collection_name",
+                    "description",
+                    "uri",
+                    "current_supply",
+                    "max_supply",
+                    "total_minted_v2",
+                    "mutable_description",
+                    "mutable_uri",
+                    "table_handle_v1",
+                    "token_standard",
+                    "collection_properties",
+                ],
+                renamed_columns: &[("last_transaction_timestamp", "transaction_timestamp")],
+                order_columns: &["write_set_change_index"],
+            }),
+            OwnedTable::Current(CurrentTable {
+                table: "current_token_datas_v2",
+                history_table: "token_datas_v2",
+                key_columns: &["token_data_id"],
+                columns: &[
+                    "collection_id",
+                    "token_name",
+                    "maximum",
+                    "supply",
+                    "largest_property_version_v1",
+                    "token_uri",
+                    "description",
+                    "token_properties",
+                    "token_standard",
+                    "is_fungible_v2",
+                    "decimals",
+                    "is_deleted_v2",
+                ],
+                renamed_columns: &[("last_transaction_timestamp", "transaction_timestamp")],
+                order_columns: &["write_set_change_index"],
+            }),
+            OwnedTable::Current(CurrentTable {
+                table: "current_token_ownerships_v2",
+                history_table: "token_ownerships_v2",
+                key_columns: &[
+                    "token_data_id",
+                    "property_version_v1",
+                    "owner_address",
+                    "storage_id",
+                ],
+                columns: &[
+                    "amount",
+                    "table_type_v1",
+                    "token_properties_mutated_v1",
+                    "is_soulbound_v2",
+                    "token_standard",
+                    "is_fungible_v2",
+                    "non_transferrable_by_owner",
+                ],
+                renamed_columns: &[("last_transaction_timestamp", "transaction_timestamp")],
+                order_columns: &["write_set_change_index"],
+            }),
+            OwnedTable::CurrentWithoutHistory {
+                table: "current_token_pending_claims",
+            },
+            OwnedTable::CurrentWithoutHistory {
+                table: "current_token_royalty_v1",
+            },
+            OwnedTable::CurrentWithoutHistory {
+                table: "current_token_v2_metadata",
+            },
+        ],
+        ProcessorName::TransactionMetadataProcessor => vec![
+            versioned("event_size_info"),
+            versioned("transaction_size_info"),
+            versioned("write_set_size_info"),
+        ],
+        ProcessorName::UserTransactionProcessor => vec![
+            versioned("signatures"),
+            OwnedTable::Versioned {
+                table: "user_transactions",
+                version_column: "version",
+            },
+        ],
+        ProcessorName::ParquetAnsProcessor
+        | ProcessorName::ParquetDefaultProcessor
+        | ProcessorName::ParquetFungibleAssetProcessor
+        | ProcessorName::ParquetObjectsProcessor
+        | ProcessorName::ParquetStakeProcessor
+        | ProcessorName::ParquetTokenV2Processor => bail!(
+            "{} writes parquet files, not postgres tables, so it can't be rolled back",
+            processor
+        ),
+    };
+    Ok(tables)
+}
+
+impl RollbackArgs {
+    pub async fn run(&self, config: &IndexerGrpcProcessorConfig) -> Result<()> {
+        let processor_name: &'static str = self.processor.into();
+        let tables = owned_tables(self.processor)?;
+
+        let without_history: Vec<&str> = tables
+            .iter()
+            .filter_map(|table| match table {
+                OwnedTable::CurrentWithoutHistory { table } => Some(*table),
+                _ => None,
+            })
+            .collect();
+        if !without_history.is_empty() {
+            if !self.allow_partial {
+                bail!(
+                    "{} can't be rolled back exactly: {} have no history to rebuild from. \
+                     Pass --allow-partial to leave them as they are",
+                    processor_name,
+                    without_history.join(", ")
+                );
+            }
+            warn!(
+                processor_name = processor_name,
+                tables = without_history.join(", "),
+                "[Rollback] Leaving current tables without history as they are"
+            );
+        }
+
+        let pool = new_db_pool(&config.postgres_connection_string, Some(1))
+            .await
+            .context("Failed to create connection pool")?;
+        let mut conn = pool.get().await.context("Failed to get a connection")?;
+        let target_version = self.target_version as i64;
+
+        conn.transaction::<_, anyhow::Error, _>(|conn| {
+            async move {
+                let last_success_version = processor_status::table
+                    .filter(processor_status::processor.eq(processor_name))
+                    .select(processor_status::last_success_version)
+                    .for_update()
+                    .first::<i64>(conn)
+                    .await
+                    .optional()?;
+                match last_success_version {
+                    None => bail!("{} has no processor status", processor_name),
+                    Some(version) if version < target_version => bail!(
+                        "{} has only processed up to version {}, which is before {}",
+                        processor_name,
+                        version,
+                        target_version
+                    ),
+                    Some(_) => {},
+                }
+
+                // Current tables are rebuilt first since that reads the history being removed
+                for table in &tables {
+                    if let OwnedTable::Rebuilt {
+                        table,
+                        rebuild_query,
+                    } = table
+                    {
+                        let rebuilt =
+                            execute_with_version(conn, rebuild_query, target_version).await?;
+                        let query =
+                            format!("DELETE FROM {} WHERE last_transaction_version > $1", table);
+                        let deleted = execute_with_version(conn, &query, target_version).await?;
+                        info!(
+                            processor_name = processor_name,
+                            table = table,
+                            deleted,
+                            rebuilt,
+                            "[Rollback] Rebuilt current table"
+                        );
+                    }
+                    if let OwnedTable::Current(current) = table {
+                        let deleted =
+                            execute_with_version(conn, &current.delete_query(), target_version)
+                                .await?;
+                        let rebuilt =
+                            execute_with_version(conn, &current.rebuild_query(), target_version)
+                                .await?;
+                        info!(
+                            processor_name = processor_name,
+                            table = current.table,
+                            deleted,
+                            rebuilt,
+                            "[Rollback] Rebuilt current table"
+                        );
+                    }
+                }
+                for table in &tables {
+                    if let OwnedTable::Versioned {
+                        table,
+                        version_column,
+                    } = table
+                    {
+                        let query = format!("DELETE FROM {} WHERE {} > $1", table, version_column);
+                        let deleted = execute_with_version(conn, &query, target_version).await?;
+                        info!(
+                            processor_name = processor_name,
+                            table = table,
+                            deleted,
+                            "[Rollback] Deleted rows after target version"
+                        );
+                    }
+                }
+
+                // The timestamp of the target version itself isn't stored anywhere, so the newest
+                // one in history at or before it is used. The next batch sets the exact one.
+                let mut last_transaction_timestamp = None;
+                for table in &tables {
+                    if let OwnedTable::Current(current) = table {
+                        if let Some(query) = current.last_timestamp_query() {
+                            let timestamp = diesel::sql_query(query)
+                                .bind::<BigInt, _>(target_version)
+                                .get_result::<TimestampRow>(conn)
+                                .await
+                                .optional()?
+                                .map(|row| row.timestamp);
+                            last_transaction_timestamp = last_transaction_timestamp.max(timestamp);
+                        }
+                    }
+                }
+                let status =
+                    processor_status::table.filter(processor_status::processor.eq(processor_name));
+                if let Some(last_transaction_timestamp) = last_transaction_timestamp {
+                    diesel::update(status)
+                        .set((
+                            processor_status::last_success_version.eq(target_version),
+                            processor_status::last_updated.eq(diesel::dsl::now),
+                            processor_status::last_transaction_timestamp
+                                .eq(last_transaction_timestamp),
+                        ))
+                        .execute(conn)
+                        .await?;
+                } else {
+                    warn!(
+                        processor_name = processor_name,
+                        "[Rollback] No timestamp found at or before the target version, keeping \
+                         the last transaction timestamp until the next batch"
+                    );
+                    diesel::update(status)
+                        .set((
+                            processor_status::last_success_version.eq(target_version),
+                            processor_status::last_updated.eq(diesel::dsl::now),
+                        ))
+                        .execute(conn)
+                        .await?;
+                }
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await?;
+
+        info!(
+            processor_name = processor_name,
+            target_version = self.target_version,
+            "[Rollback] Rolled back processor"
+        );
+        Ok(())
+    }
+}
+
+#[derive(QueryableByName)]
+struct TimestampRow {
+    #[diesel(sql_type = Timestamp)]
+    timestamp: chrono::NaiveDateTime,
+}
+
+async fn execute_with_version(
+    conn: &mut MyDbConnection,
+    query: &str,
+    target_version: i64,
+) -> diesel::QueryResult<usize> {
+    diesel::sql_query(query)
+        .bind::<BigInt, _>(target_version)
+        .execute(conn)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebuild_query() {
+        let current = CurrentTable {
+            table: "current_coin_balances",
+            history_table: "coin_balances",
+            key_columns: &["owner_address", "coin_type_hash"],
+            columns: &["coin_type", "amount"],
+            renamed_columns: &[("last_transaction_timestamp", "transaction_timestamp")],
+            order_columns: &[],
+        };
+        assert_eq!(
+            current.rebuild_query(),
+            "INSERT INTO current_coin_balances (owner_address, coin_type_hash, coin_type, amount, \
+             last_transaction_timestamp, last_transaction_version) \
+             SELECT DISTINCT ON (owner_address, coin_type_hash) owner_address, coin_type_hash, \
+             coin_type, amount, transaction_timestamp, transaction_version FROM coin_balances \
+             WHERE transaction_version <= $1 \
+             AND (owner_address, coin_type_hash) IN (SELECT owner_address, coin_type_hash \
+             FROM coin_balances WHERE transaction_version > $1) \
+             ORDER BY owner_address, coin_type_hash, transaction_version DESC \
+             ON CONFLICT (owner_address, coin_type_hash) DO UPDATE SET \
+             coin_type = EXCLUDED.coin_type, amount = EXCLUDED.amount, \
+             last_transaction_timestamp = EXCLUDED.last_transaction_timestamp, \
+             last_transaction_version = EXCLUDED.last_transaction_version"
+        );
+        assert_eq!(
+            current.last_timestamp_query().unwrap(),
+            "SELECT transaction_timestamp AS timestamp FROM coin_balances \
+             WHERE transaction_version <= $1 ORDER BY transaction_version DESC LIMIT 1"
+        );
+    }
+
+    #[test]
+    fn test_key_columns_can_be_computed() {
+        let tables = owned_tables(ProcessorName::DefaultProcessor).unwrap();
+        let current_table_items = tables
+            .iter()
+            .find_map(|table| match table {
+                OwnedTable::Current(current) => Some(current),
+                _ => None,
+            })
+            .unwrap();
+        let query = current_table_items.rebuild_query();
+        assert!(query.contains(
+            "SELECT DISTINCT ON (table_handle, encode(sha256(convert_to(key, 'UTF8')), 'hex'))"
+        ));
+        assert!(query.contains("ORDER BY table_handle, encode(sha256(convert_to(key, 'UTF8')), 'hex'), transaction_version DESC, write_set_change_index DESC ON CONFLICT (table_handle, key_hash)"));
+        // current_table_items has no timestamp
+        assert!(current_table_items.last_timestamp_query().is_none());
+    }
+
+    #[test]
+    fn test_tables_without_history_are_documented() {
+        let readme = include_str!("../README.md");
+        for processor in <ProcessorName as clap::ValueEnum>::value_variants() {
+            let Ok(tables) = owned_tables(*processor) else {
+                continue;
+            };
+            let mut without_history: Vec<&str> = tables
+                .iter()
+                .filter_map(|table| match table {
+                    OwnedTable::CurrentWithoutHistory { table } => Some(*table),
+                    _ => None,
+                })
+                .collect();
+            without_history.sort();
+            // Processors whose tables can all be rolled back have no row
+            let row = format!("| `{}` |", processor);
+            let mut documented: Vec<&str> = readme
+                .lines()
+                .find_map(|line| line.strip_prefix(&row))
+                .map(|tables| {
+                    tables
+                        .trim_end_matches('|')
+                        .split(',')
+                        .map(|table| table.trim().trim_matches('`'))
+                        .collect()
+                })
+                .unwrap_or_default();
+            documented.sort();
+            assert_eq!(
+                documented, without_history,
+                "The README lists the wrong tables without history for {}",
+                processor
+            );
+        }
+    }
+
+    #[test]
+    fn test_dex_pools_are_rebuilt() {
+        let tables = owned_tables(ProcessorName::DexProcessor).unwrap();
+        assert!(tables.iter().any(|table| matches!(
+            table,
+            OwnedTable::Rebuilt {
+                table: "current_dex_pools",
+                ..
+            }
+        )));
+        // Only swaps add to the volume
+        assert!(CURRENT_DEX_POOLS_REBUILD_QUERY
+            .contains("COALESCE(SUM(ABS(amount_x)) FILTER (WHERE activity_type = 'swap'), 0)"));
+        // Reserves start from the last reported one
+        assert!(CURRENT_DEX_POOLS_REBUILD_QUERY.contains(
+            "SUM(COALESCE(reserve_x, amount_x)) FILTER (WHERE reserve_x_number = reserve_x_count)"
+        ));
+    }
+
+    #[test]
+    fn test_daily_fungible_asset_balances_are_rebuilt() {
+        let tables = owned_tables(ProcessorName::FungibleAssetProcessor).unwrap();
+        assert!(tables.iter().any(|table| matches!(
+            table,
+            OwnedTable::Rebuilt {
+                table: "daily_fungible_asset_balances",
+                ..
+            }
+        )));
+        // Only primary stores have a daily balance
+        assert!(DAILY_FUNGIBLE_ASSET_BALANCES_REBUILD_QUERY
+            .contains("WHERE is_primary AND transaction_version <= $1"));
+        assert!(DAILY_FUNGIBLE_ASSET_BALANCES_REBUILD_QUERY
+            .contains("WHERE is_primary AND transaction_version > $1"));
+    }
+}
@@ -0,0 +1,431 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Backfills a version range by splitting it into shards that are processed concurrently. Each
+//! shard is leased through the `backfill_shards` table and processed by a copy of the `Worker`
+//! that streams only that shard's range, so several pods can share one backfill. A worker that
+//! stops renewing its lease loses the shard, which is picked up again from its last recorded
+//! version, and stops as soon as it notices. `processor_status` is only moved over complete shards
+//! that directly follow it, so a restarted live processor never skips a range.
+
+use crate::{
+    config::ShardedBackfillConfig,
+    db::common::models::{
+        backfill_processor_status::BackfillStatus,
+        backfill_shards::{BackfillShard, BackfillShardQuery},
+    },
+    errors::PipelineError,
+    schema::{backfill_shards, processor_status},
+    utils::database::ArcDbPool,
+    worker::{shutdown_signal, Worker, PROCESSOR_SERVICE_TYPE},
+};
+use anyhow::{Context, Result};
+use diesel::{ExpressionMethods, QueryDsl};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection, RunQueryDsl};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tracing::{info, warn};
+
+/// Shards are inserted with four bind parameters each, well below Postgres' limit of 65535
+const MAX_SHARDS_PER_INSERT: usize = 10_000;
+
+/// A shard leased by one worker task.
+#[derive(Clone, Debug)]
+pub struct ShardLease {
+    pub shard_start_version: u64,
+    pub shard_end_version: u64,
+    /// First version that hasn't been processed yet, which is later than the start of the shard
+    /// if an earlier lease made progress
+    pub next_version: u64,
+    pub lease_owner: String,
+    pub lease_duration_secs: u64,
+}
+
+pub struct ShardedBackfill {
+    config: ShardedBackfillConfig,
+    worker: Worker,
+}
+
+impl ShardedBackfill {
+    pub fn new(config: ShardedBackfillConfig, worker: Worker) -> Result<Self> {
+        anyhow::ensure!(
+            config.start_version <= config.end_version,
+            "[Backfill] Start version {} is after end version {}",
+            config.start_version,
+            config.end_version
+        );
+        anyhow::ensure!(
+            config.shard_size > 0 && config.concurrent_shards > 0,
+            "[Backfill] shard_size and concurrent_shards must be positive"
+        );
+        anyhow::ensure!(
+            worker.backfill.is_none() && worker.additional_processor_configs.is_empty(),
+            "[Backfill] A sharded backfill can't be combined with backfill or additional processors"
+        );
+        anyhow::ensure!(
+            worker.starting_version.is_none() && worker.ending_version.is_none(),
+            "[Backfill] A sharded backfill takes its version range from its own config, unset starting_version and ending_version"
+        );
+        anyhow::ensure!(
+            !worker.processor_config.requires_ordered_batches(),
+            "[Backfill] {} needs batches in version order, but shards are processed out of order",
+            worker.processor_config.name()
+        );
+        Ok(Self { config, worker })
+    }
+
+    pub async fn run(self) -> Result<()> {
+        let processor_name = self.worker.processor_config.name();
+        self.worker.prepare_database().await?;
+
+        let pool = self.worker.db_pool.clone();
+        create_shards(
+            &pool,
+            processor_name,
+            &plan_shards(
+                self.config.start_version,
+                self.config.end_version,
+                self.config.shard_size,
+            ),
+        )
+        .await?;
+
+        // Once asked to shut down, tasks finish their current shard but don't claim another one
+        let stopping = Arc::new(AtomicBool::new(false));
+        {
+            let stopping = stopping.clone();
+            tokio::spawn(async move {
+                shutdown_signal().await;
+                stopping.store(true, Ordering::SeqCst);
+            });
+        }
+
+        // Pods running the same backfill need distinct lease owners
+        let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string());
+        let tasks = (0..self.config.concurrent_shards).map(|task_index| {
+            tokio::spawn(run_shards(
+                self.worker.clone(),
+                self.config.lease_duration_secs,
+                format!("{}-{}-{}", hostname, std::process::id(), task_index),
+                stopping.clone(),
+            ))
+        });
+        let results = futures::future::try_join_all(tasks)
+            .await
+            .expect("[Backfill] Shard tasks have died");
+        results.into_iter().collect::<Result<Vec<_>>>()?;
+
+        advance_processor_status(&pool, processor_name).await?;
+        info!(
+            processor_name = processor_name,
+            service_type = PROCESSOR_SERVICE_TYPE,
+            "[Backfill] No shards left to claim, exiting"
+        );
+        Ok(())
+    }
+}
+
+/// Claims and processes shards until none are left or the worker is shutting down.
+async fn run_shards(
+    worker: Worker,
+    lease_duration_secs: u64,
+    lease_owner: String,
+    stopping: Arc<AtomicBool>,
+) -> Result<()> {
+    let processor_name = worker.processor_config.name();
+    let pool = worker.db_pool.clone();
+    while !stopping.load(Ordering::SeqCst) {
+        let claimed = {
+            let mut conn = pool.get().await?;
+            BackfillShardQuery::claim(
+                processor_name,
+                &lease_owner,
+                lease_duration_secs as i64,
+                &mut conn,
+            )
+            .await?
+        };
+        let Some(shard) = claimed else {
+            return Ok(());
+        };
+        let lease = ShardLease {
+            shard_start_version: shard.shard_start_version as u64,
+            shard_end_version: shard.shard_end_version as u64,
+            next_version: shard
+                .last_success_version
+                .map_or(shard.shard_start_version, |version| version + 1)
+                as u64,
+            lease_owner: lease_owner.clone(),
+            lease_duration_secs,
+        };
+        info!(
+            processor_name = processor_name,
+            service_type = PROCESSOR_SERVICE_TYPE,
+            lease_owner = lease_owner.as_str(),
+            shard_start_version = lease.shard_start_version,
+            shard_end_version = lease.shard_end_version,
+            start_version = lease.next_version,
+            "[Backfill] Claimed shard"
+        );
+
+        let shard_start_version = lease.shard_start_version;
+        let mut shard_worker = worker.clone();
+        shard_worker.starting_version = Some(lease.next_version);
+        shard_worker.ending_version = Some(lease.shard_end_version);
+        shard_worker.shard = Some(lease);
+        if let Err(e) = shard_worker.run().await {
+            // The worker that took over the shard finishes it, this task moves on to another one
+            if !matches!(
+                e.downcast_ref::<PipelineError>(),
+                Some(PipelineError::LeaseLost { .. })
+            ) {
+                return Err(e);
+            }
+            warn!(
+                processor_name = processor_name,
+                service_type = PROCESSOR_SERVICE_TYPE,
+                lease_owner = lease_owner.as_str(),
+                shard_start_version,
+                "[Backfill] Lost the lease on the shard, claiming another one"
+            );
+        }
+
+        advance_processor_status(&pool, processor_name).await?;
+    }
+    Ok(())
+}
+
+/// Renews the lease on a shard every third of its duration, independently of how fast batches
+/// are processed. Returns once the lease has been lost to another worker. A failed renewal is
+/// retried, and if the lease expires in the meantime the next renewal finds it lost.
+pub async fn keep_shard_leased(
+    pool: ArcDbPool,
+    processor_name: &'static str,
+    lease: ShardLease,
+) -> PipelineError {
+    let interval = Duration::from_secs((lease.lease_duration_secs / 3).max(1));
+    loop {
+        tokio::time::sleep(interval).await;
+        let renewed = match pool.get().await {
+            Ok(mut conn) => BackfillShardQuery::renew_lease(
+                processor_name,
+                lease.shard_start_version as i64,
+                &lease.lease_owner,
+                lease.lease_duration_secs as i64,
+                &mut conn,
+            )
+            .await
+            .map_err(anyhow::Error::from),
+            Err(e) => Err(anyhow::Error::from(e)),
+        };
+        match renewed {
+            Ok(true) => {},
+            Ok(false) => {
+                return PipelineError::LeaseLost {
+                    shard_start_version: lease.shard_start_version,
+                }
+            },
+            Err(e) => {
+                warn!(
+                    processor_name = processor_name,
+                    service_type = PROCESSOR_SERVICE_TYPE,
+                    shard_start_version = lease.shard_start_version,
+                    error = ?e,
+                    "[Backfill] Failed to renew the shard's lease, retrying"
+                );
+            },
+        }
+    }
+}
+
+/// Splits `start_version..=end_version` into inclusive ranges of at most `shard_size` versions.
+pub fn plan_shards(start_version: u64, end_version: u64, shard_size: u64) -> Vec<(u64, u64)> {
+    (start_version..=end_version)
+        .step_by(shard_size as usize)
+        .map(|shard_start| {
+            (
+                shard_start,
+                shard_start.saturating_add(shard_size - 1).min(end_version),
+            )
+        })
+        .collect()
+}
+
+/// Inserts the planned shards in one transaction, unless an earlier run already did. Refuses to
+/// mix shards of different plans, since their ranges would overlap.
+async fn create_shards(
+    pool: &ArcDbPool,
+    processor_name: &str,
+    shards: &[(u64, u64)],
+) -> Result<()> {
+    let rows: Vec<BackfillShard> = shards
+        .iter()
+        .map(|(shard_start, shard_end)| BackfillShard {
+            processor: processor_name.to_string(),
+            shard_start_version: *shard_start as i64,
+            shard_end_version: *shard_end as i64,
+            shard_status: BackfillStatus::Pending.to_string(),
+        })
+        .collect();
+    let mut conn = pool.get().await?;
+    conn.transaction::<_, anyhow::Error, _>(|conn| {
+        async move {
+            // Pods starting the same backfill at once must not both see an empty plan
+            diesel::sql_query("LOCK TABLE backfill_shards IN SHARE ROW EXCLUSIVE MODE")
+                .execute(conn)
+                .await?;
+            let existing = BackfillShardQuery::get_by_processor(processor_name, conn).await?;
+            if !existing.is_empty() {
+                let existing_ranges: Vec<(u64, u64)> = existing
+                    .iter()
+                    .map(|shard| {
+                        (
+                            shard.shard_start_version as u64,
+                            shard.shard_end_version as u64,
+                        )
+                    })
+                    .collect();
+                anyhow::ensure!(
+                    existing_ranges == shards,
+                    "[Backfill] backfill_shards already has a different plan for {}. Delete its rows to start over",
+                    processor_name
+                );
+                return Ok(());
+            }
+            // Bind parameters are limited, so large plans take several statements
+            for chunk in rows.chunks(MAX_SHARDS_PER_INSERT) {
+                diesel::insert_into(backfill_shards::table)
+                    .values(chunk)
+                    .execute(conn)
+                    .await?;
+            }
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await
+    .context("[Backfill] Failed to create shards")
+}
+
+/// The end of the complete shards at the start of the plan, with its timestamp. Progress inside
+/// an incomplete shard doesn't count, since the rows it wrote can be redone by another lease.
+pub fn contiguous_progress(
+    shards: &[BackfillShardQuery],
+) -> Option<(i64, Option<chrono::NaiveDateTime>)> {
+    shards
+        .iter()
+        .take_while(|shard| shard.is_complete())
+        .last()
+        .map(|shard| (shard.shard_end_version, shard.last_transaction_timestamp))
+}
+
+/// Moves `processor_status` up to the end of the complete shards at the start of the plan, but
+/// only if it has already reached the start of the plan, so no versions before it are skipped.
+/// It never moves back and never creates the row, so this is safe to run from every task and
+/// every pod.
+async fn advance_processor_status(pool: &ArcDbPool, processor_name: &str) -> Result<()> {
+    let mut conn = pool.get().await?;
+    let shards = BackfillShardQuery::get_by_processor(processor_name, &mut conn).await?;
+    let (Some(first_shard), Some((version, last_transaction_timestamp))) =
+        (shards.first(), contiguous_progress(&shards))
+    else {
+        return Ok(());
+    };
+    let updated = diesel::update(
+        processor_status::table
+            .filter(processor_status::processor.eq(processor_name))
+            .filter(processor_status::last_success_version.ge(first_shard.shard_start_version - 1))
+            .filter(processor_status::last_success_version.lt(version)),
+    )
+    .set((
+        processor_status::last_success_version.eq(version),
+        processor_status::last_updated.eq(diesel::dsl::now),
+        processor_status::last_transaction_timestamp.eq(last_transaction_timestamp),
+    ))
+    .execute(&mut conn)
+    .await
+    .context("[Backfill] Failed to advance processor status")?;
+    if updated > 0 {
+        info!(
+            processor_name = processor_name,
+            service_type = PROCESSOR_SERVICE_TYPE,
+            last_success_version = version,
+            "[Backfill] Advanced processor status"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shard(
+        shard_start_version: i64,
+        shard_end_version: i64,
+        status: BackfillStatus,
+        last_success_version: Option<i64>,
+    ) -> BackfillShardQuery {
+        BackfillShardQuery {
+            processor: "default_processor".to_string(),
+            shard_start_version,
+            shard_end_version,
+            shard_status: status.to_string(),
+            last_success_version,
+            last_transaction_timestamp: None,
+            lease_owner: None,
+            lease_expires_at: None,
+            last_updated: chrono::NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn test_plan_shards() {
+        assert_eq!(plan_shards(0, 9, 5), vec![(0, 4), (5, 9)]);
+        assert_eq!(plan_shards(10, 22, 5), vec![(10, 14), (15, 19), (20, 22)]);
+        assert_eq!(plan_shards(7, 7, 100), vec![(7, 7)]);
+    }
+
+    #[test]
+    fn test_contiguous_progress() {
+        assert_eq!(
+            contiguous_progress(&[shard(0, 9, BackfillStatus::Pending, None)]),
+            None
+        );
+        // Neither progress inside an unfinished shard nor a complete shard after it counts
+        assert_eq!(
+            contiguous_progress(&[
+                shard(0, 9, BackfillStatus::Complete, Some(9)),
+                shard(10, 19, BackfillStatus::InProgress, Some(14)),
+                shard(20, 29, BackfillStatus::Complete, Some(29)),
+            ]),
+            Some((9, None))
+        );
+        assert_eq!(
+            contiguous_progress(&[
+                shard(0, 9, BackfillStatus::InProgress, Some(5)),
+                shard(10, 19, BackfillStatus::Complete, Some(19)),
+            ]),
+            None
+        );
+        assert_eq!(
+            contiguous_progress(&[
+                shard(0, 9, BackfillStatus::Complete, Some(9)),
+                shard(10, 19, BackfillStatus::Pending, None),
+            ]),
+            Some((9, None))
+        );
+        assert_eq!(
+            contiguous_progress(&[
+                shard(0, 9, BackfillStatus::Complete, Some(9)),
+                shard(10, 19, BackfillStatus::Complete, Some(19)),
+            ]),
+            Some((19, None))
+        );
+    }
+}
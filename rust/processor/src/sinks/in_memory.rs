@@ -0,0 +1,39 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{ProcessorOutput, SinkTrait};
+use ahash::AHashMap;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+
+/// Collects rows by table. Clones share the same rows, so a test can keep a clone to inspect what
+/// the processor wrote.
+#[derive(Clone, Debug, Default)]
+pub struct InMemorySink {
+    rows: Arc<Mutex<AHashMap<&'static str, Vec<serde_json::Value>>>>,
+}
+
+impl InMemorySink {
+    /// The rows written to the table so far, in the order they were written.
+    pub fn rows(&self, table: &str) -> Vec<serde_json::Value> {
+        self.rows
+            .lock()
+            .unwrap()
+            .get(table)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl SinkTrait for InMemorySink {
+    async fn write(&self, output: &dyn ProcessorOutput, _: u64, _: u64) -> Result<()> {
+        let tables = output.table_rows()?;
+        let mut rows = self.rows.lock().unwrap();
+        for table in tables {
+            rows.entry(table.table).or_default().extend(table.rows);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{ProcessorOutput, SinkTrait};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::path::PathBuf;
+use tokio::{io::AsyncWriteExt, sync::Mutex};
+
+/// Appends rows to one file per table. Batches are processed concurrently, so the order of rows
+/// across batches isn't guaranteed.
+pub struct JsonLinesSink {
+    path: PathBuf,
+    // Keeps concurrent batches from interleaving their lines
+    write_lock: Mutex<()>,
+}
+
+impl JsonLinesSink {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            write_lock: Mutex::new(()),
+        }
+    }
+}
+
+#[async_trait]
+impl SinkTrait for JsonLinesSink {
+    async fn write(&self, output: &dyn ProcessorOutput, _: u64, _: u64) -> Result<()> {
+        let tables = output.table_rows()?;
+        let _guard = self.write_lock.lock().await;
+        tokio::fs::create_dir_all(&self.path)
+            .await
+            .with_context(|| format!("Failed to create {}", self.path.display()))?;
+        for table in tables.iter().filter(|table| !table.rows.is_empty()) {
+            let mut buffer = vec![];
+            for row in &table.rows {
+                serde_json::to_writer(&mut buffer, row)?;
+                buffer.push(b'\n');
+            }
+            let path = self.path.join(format!("{}.jsonl", table.table));
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .await
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+            file.write_all(&buffer).await?;
+            file.flush().await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::stream::{StreamMessage, StreamPublisher};
+use ahash::AHashMap;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use rdkafka::{
+    config::ClientConfig,
+    error::{KafkaError, RDKafkaErrorCode},
+    producer::{FutureProducer, FutureRecord},
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct KafkaConfig {
+    pub bootstrap_servers: String,
+    #[serde(default = "KafkaConfig::default_message_timeout_ms")]
+    pub message_timeout_ms: u64,
+    // Any other librdkafka producer settings, e.g. security.protocol
+    #[serde(default)]
+    pub producer_config: AHashMap<String, String>,
+}
+
+impl KafkaConfig {
+    pub const fn default_message_timeout_ms() -> u64 {
+        30_000
+    }
+}
+
+pub struct KafkaPublisher {
+    producer: FutureProducer,
+}
+
+impl KafkaPublisher {
+    pub fn new(config: KafkaConfig) -> Result<Self> {
+        let mut client_config = ClientConfig::new();
+        client_config
+            .set("bootstrap.servers", &config.bootstrap_servers)
+            .set("message.timeout.ms", config.message_timeout_ms.to_string());
+        for (key, value) in &config.producer_config {
+            client_config.set(key, value);
+        }
+        // Retries must not reorder messages of a partition, and a message only counts as
+        // delivered once every in-sync replica has it
+        client_config
+            .set("enable.idempotence", "true")
+            .set("acks", "all");
+        let producer = client_config
+            .create()
+            .context("Failed to create Kafka producer")?;
+        Ok(Self { producer })
+    }
+}
+
+#[async_trait]
+impl StreamPublisher for KafkaPublisher {
+    async fn publish(&self, messages: Vec<StreamMessage>) -> Result<()> {
+        // Messages are enqueued in order before waiting on any of them, so a key's messages reach
+        // its partition in order
+        let mut deliveries = Vec::with_capacity(messages.len());
+        for message in &messages {
+            let mut record = FutureRecord::to(&message.topic)
+                .key(&message.key)
+                .payload(&message.payload);
+            loop {
+                match self.producer.send_result(record) {
+                    Ok(delivery) => {
+                        deliveries.push(delivery);
+                        break;
+                    },
+                    Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned)) => {
+                        record = returned;
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    },
+                    Err((err, _)) => bail!("Failed to enqueue Kafka message: {}", err),
+                }
+            }
+        }
+        for delivery in futures::future::join_all(deliveries).await {
+            match delivery {
+                Ok(Ok(_)) => {},
+                Ok(Err((err, _))) => bail!("Kafka message wasn't delivered: {}", err),
+                Err(_) => bail!("Kafka producer dropped a message"),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Where processors write their rows. A processor collects the rows of a batch into a
+//! `ProcessorOutput` and hands it to each of its sinks. Postgres is the default sink and keeps the
+//! processor's upsert rules, the other sinks receive the same rows as JSON objects per table.
+//! `processor_status` is always kept in Postgres, and a batch only counts as processed once every
+//! sink has written it, so sinks other than Postgres can see a batch again after a retry.
+
+// Note: For enum_dispatch to work nicely, the trait and the enum are kept in this file.
+
+pub mod in_memory;
+pub mod json_lines;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod parquet;
+pub mod postgres;
+pub mod stream;
+
+use self::{
+    in_memory::InMemorySink,
+    json_lines::JsonLinesSink,
+    parquet::{ParquetSink, ParquetSinkConfig},
+    postgres::PostgresSink,
+    stream::{StreamSink, StreamSinkConfig},
+};
+use crate::utils::database::ArcDbPool;
+use ahash::AHashMap;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use enum_dispatch::enum_dispatch;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// The processor's tables in `postgres_connection_string`.
+    Postgres,
+    /// Appends rows to `{path}/{table}.jsonl`, one JSON object per line.
+    JsonLines { path: PathBuf },
+    /// Uploads a parquet file per table and batch, with column types inferred from the rows.
+    Parquet(ParquetSinkConfig),
+    /// Publishes every row to a message stream, keyed by table and primary key.
+    Stream(StreamSinkConfig),
+    /// Keeps rows in memory, for tests.
+    InMemory,
+}
+
+impl SinkConfig {
+    /// Whether the sink relies on batches being written in version order.
+    pub fn requires_ordered_batches(&self) -> bool {
+        matches!(self, SinkConfig::Stream(_))
+    }
+}
+
+/// The rows of one table in a batch.
+#[derive(Clone, Debug)]
+pub struct TableRows {
+    pub table: &'static str,
+    pub primary_key: &'static [&'static str],
+    pub rows: Vec<serde_json::Value>,
+}
+
+impl TableRows {
+    pub fn new<T: Serialize>(
+        table: &'static str,
+        primary_key: &'static [&'static str],
+        rows: &[T],
+    ) -> Result<Self> {
+        let rows = rows
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Failed to serialize {} rows", table))?;
+        Ok(Self {
+            table,
+            primary_key,
+            rows,
+        })
+    }
+
+    /// Identifies the row across tables, e.g. `current_coin_balances:0x1:abcd`.
+    pub fn key(&self, row: &serde_json::Value) -> String {
+        std::iter::once(self.table.to_string())
+            .chain(self.primary_key.iter().map(|column| match row.get(column) {
+                Some(serde_json::Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+                None => String::new(),
+            }))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+}
+
+/// The rows a processor produced for a batch of transactions.
+#[async_trait]
+pub trait ProcessorOutput: Send + Sync {
+    /// Writes the rows to Postgres with the processor's conflict handling.
+    async fn insert_to_db(
+        &self,
+        conn: ArcDbPool,
+        per_table_chunk_sizes: &AHashMap<String, usize>,
+    ) -> Result<(), diesel::result::Error>;
+
+    /// The rows of every table, including empty ones.
+    fn table_rows(&self) -> Result<Vec<TableRows>>;
+}
+
+#[async_trait]
+#[enum_dispatch]
+pub trait SinkTrait: Send + Sync {
+    async fn write(
+        &self,
+        output: &dyn ProcessorOutput,
+        start_version: u64,
+        end_version: u64,
+    ) -> Result<()>;
+}
+
+#[enum_dispatch(SinkTrait)]
+pub enum Sink {
+    PostgresSink,
+    JsonLinesSink,
+    ParquetSink,
+    StreamSink,
+    InMemorySink,
+}
+
+/// Builds the configured sinks, or a Postgres sink if there are none. Fails if a sink can't be
+/// created, e.g. a Kafka producer with invalid settings.
+pub fn build_sinks(
+    configs: &[SinkConfig],
+    db_pool: ArcDbPool,
+    per_table_chunk_sizes: AHashMap<String, usize>,
+) -> Result<Vec<Sink>> {
+    if configs.is_empty() {
+        return Ok(vec![Sink::from(PostgresSink::new(
+            db_pool,
+            per_table_chunk_sizes,
+        ))]);
+    }
+    configs
+        .iter()
+        .map(|config| {
+            Ok(match config {
+                SinkConfig::Postgres => Sink::from(PostgresSink::new(
+                    db_pool.clone(),
+                    per_table_chunk_sizes.clone(),
+                )),
+                SinkConfig::JsonLines { path } => Sink::from(JsonLinesSink::new(path.clone())),
+                SinkConfig::Parquet(config) => Sink::from(ParquetSink::new(config.clone())),
+                SinkConfig::Stream(config) => Sink::from(StreamSink::new(config.clone())?),
+                SinkConfig::InMemory => Sink::from(InMemorySink::default()),
+            })
+        })
+        .collect()
+}
+
+/// Writes the output to all sinks concurrently. Fails if any of them fails.
+pub async fn write_to_sinks(
+    sinks: &[Sink],
+    output: &dyn ProcessorOutput,
+    start_version: u64,
+    end_version: u64,
+) -> Result<()> {
+    futures::future::try_join_all(
+        sinks
+            .iter()
+            .map(|sink| sink.write(output, start_version, end_version)),
+    )
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::ProcessorConfig;
+
+    struct TestOutput {
+        rows: Vec<serde_json::Value>,
+    }
+
+    #[async_trait]
+    impl ProcessorOutput for TestOutput {
+        async fn insert_to_db(
+            &self,
+            _: ArcDbPool,
+            _: &AHashMap<String, usize>,
+        ) -> Result<(), diesel::result::Error> {
+            unreachable!("Not written to postgres")
+        }
+
+        fn table_rows(&self) -> Result<Vec<TableRows>> {
+            Ok(vec![TableRows::new(
+                "events",
+                &["transaction_version", "event_index"],
+                &self.rows,
+            )?])
+        }
+    }
+
+    #[test]
+    fn test_parse_sinks() {
+        let config: ProcessorConfig = serde_yaml::from_str("type: coin_processor").unwrap();
+        match config {
+            ProcessorConfig::CoinProcessor(config) => assert!(config.sinks.is_empty()),
+            _ => panic!("Expected the coin processor"),
+        }
+
+        let config: ProcessorConfig = serde_yaml::from_str(
+            "type: coin_processor\nsinks:\n  - type: postgres\n  - type: json_lines\n    path: /tmp/rows\n",
+        )
+        .unwrap();
+        match config {
+            ProcessorConfig::CoinProcessor(config) => {
+                assert!(matches!(config.sinks[0], SinkConfig::Postgres));
+                assert!(matches!(config.sinks[1], SinkConfig::JsonLines { .. }));
+            },
+            _ => panic!("Expected the coin processor"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_sink() {
+        let sink = InMemorySink::default();
+        let sinks = vec![Sink::from(sink.clone())];
+        for version in [1, 2] {
+            let output = TestOutput {
+                rows: vec![serde_json::json!({ "transaction_version": version })],
+            };
+            write_to_sinks(&sinks, &output, version, version)
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            sink.rows("events"),
+            vec![
+                serde_json::json!({ "transaction_version": 1 }),
+                serde_json::json!({ "transaction_version": 2 }),
+            ]
+        );
+        assert!(sink.rows("coin_activities").is_empty());
+    }
+}
@@ -0,0 +1,341 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{ProcessorOutput, SinkTrait};
+use crate::{
+    bq_analytics::storage::{upload_parquet, ParquetStorage, ParquetStorageConfig},
+    processors::parquet_processors::GOOGLE_APPLICATION_CREDENTIALS,
+};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use parquet::{
+    basic::{Compression, LogicalType, Repetition, Type as PhysicalType},
+    data_type::{BoolType, ByteArray, ByteArrayType, DataType, DoubleType, Int64Type},
+    file::{
+        properties::WriterProperties,
+        writer::{SerializedColumnWriter, SerializedFileWriter},
+    },
+    format::{MilliSeconds, TimeUnit},
+    schema::types::Type,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{path::PathBuf, sync::Arc};
+use tokio::sync::OnceCell;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParquetSinkConfig {
+    pub google_application_credentials: Option<String>,
+    pub bucket_name: String,
+    pub bucket_root: String,
+    #[serde(default)]
+    pub storage: ParquetStorageConfig,
+}
+
+/// Uploads a parquet file per table and batch, named like the files of the parquet processors.
+/// Rows don't have a parquet schema of their own, so every column is optional and typed by its
+/// values in the batch, see `ColumnType`.
+pub struct ParquetSink {
+    config: ParquetSinkConfig,
+    // Created on the first write, since creating it is async
+    storage: OnceCell<Box<dyn ParquetStorage>>,
+}
+
+impl ParquetSink {
+    pub fn new(config: ParquetSinkConfig) -> Self {
+        if let Some(credentials) = config.google_application_credentials.clone() {
+            std::env::set_var(GOOGLE_APPLICATION_CREDENTIALS, credentials);
+        }
+        Self {
+            config,
+            storage: OnceCell::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl SinkTrait for ParquetSink {
+    async fn write(
+        &self,
+        output: &dyn ProcessorOutput,
+        start_version: u64,
+        end_version: u64,
+    ) -> Result<()> {
+        let storage = self
+            .storage
+            .get_or_try_init(|| {
+                self.config
+                    .storage
+                    .create_storage(self.config.bucket_name.clone())
+            })
+            .await?;
+        for table in output.table_rows()? {
+            if table.rows.is_empty() {
+                continue;
+            }
+            let buffer = rows_to_parquet(&table.rows)
+                .with_context(|| format!("Failed to write {} to parquet", table.table))?;
+            upload_parquet(
+                storage.as_ref(),
+                buffer,
+                table.table,
+                &PathBuf::from(&self.config.bucket_root),
+                start_version as i64,
+                end_version as i64,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// The parquet type of a column, inferred from its JSON values in the batch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ColumnType {
+    Boolean,
+    Int64,
+    Double,
+    /// Strings in the format serde writes `NaiveDateTime` in, stored in milliseconds like the
+    /// timestamps of the parquet processors
+    Timestamp,
+    /// Other strings, numbers that don't fit an i64, e.g. u64 and `BigDecimal` values, which serde
+    /// writes as strings, and JSON objects and arrays as JSON text
+    String,
+}
+
+impl ColumnType {
+    fn of_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => None,
+            Value::Bool(_) => Some(Self::Boolean),
+            Value::Number(number) if number.is_i64() => Some(Self::Int64),
+            Value::Number(number) if number.is_f64() => Some(Self::Double),
+            Value::String(value) if parse_timestamp(value).is_some() => Some(Self::Timestamp),
+            _ => Some(Self::String),
+        }
+    }
+
+    /// The type of a column whose values have both types
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Self::Int64, Self::Double) | (Self::Double, Self::Int64) => Self::Double,
+            _ => Self::String,
+        }
+    }
+
+    /// A column without any values, e.g. an optional field that is null in every row, is a string.
+    fn of_column(rows: &[Value], name: &str) -> Self {
+        rows.iter()
+            .filter_map(|row| row.get(name).and_then(Self::of_value))
+            .reduce(Self::merge)
+            .unwrap_or(Self::String)
+    }
+
+    fn to_parquet(self, name: &str) -> Result<Type> {
+        let (physical_type, logical_type) = match self {
+            Self::Boolean => (PhysicalType::BOOLEAN, None),
+            Self::Int64 => (PhysicalType::INT64, None),
+            Self::Double => (PhysicalType::DOUBLE, None),
+            Self::Timestamp => (
+                PhysicalType::INT64,
+                Some(LogicalType::Timestamp {
+                    is_adjusted_to_utc: false,
+                    unit: TimeUnit::MILLIS(MilliSeconds {}),
+                }),
+            ),
+            Self::String => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+        };
+        Ok(Type::primitive_type_builder(name, physical_type)
+            .with_repetition(Repetition::OPTIONAL)
+            .with_logical_type(logical_type)
+            .build()?)
+    }
+}
+
+fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    value.parse::<NaiveDateTime>().ok()
+}
+
+/// Writes JSON objects to a parquet file with a column per field of the first row, typed by the
+/// values of the column.
+fn rows_to_parquet(rows: &[Value]) -> Result<Vec<u8>> {
+    // serde_json keeps the field order of the model, so the columns follow the struct
+    let columns: Vec<(String, ColumnType)> = rows
+        .first()
+        .and_then(Value::as_object)
+        .map(|row| {
+            row.keys()
+                .map(|name| (name.clone(), ColumnType::of_column(rows, name)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let fields = columns
+        .iter()
+        .map(|(name, column_type)| column_type.to_parquet(name).map(Arc::new))
+        .collect::<Result<Vec<_>>>()?;
+    let schema = Arc::new(
+        Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()?,
+    );
+    let props = WriterProperties::builder()
+        .set_compression(Compression::LZ4)
+        .build();
+    let mut writer = SerializedFileWriter::new(Vec::new(), schema, Arc::new(props))?;
+
+    let mut row_group = writer.next_row_group()?;
+    for (name, column_type) in &columns {
+        let mut column = row_group
+            .next_column()?
+            .context("Parquet writer has fewer columns than the schema")?;
+        match column_type {
+            ColumnType::Boolean => {
+                write_column::<BoolType>(&mut column, rows, name, Value::as_bool)
+            },
+            ColumnType::Int64 => write_column::<Int64Type>(&mut column, rows, name, Value::as_i64),
+            ColumnType::Double => {
+                write_column::<DoubleType>(&mut column, rows, name, Value::as_f64)
+            },
+            ColumnType::Timestamp => write_column::<Int64Type>(&mut column, rows, name, |value| {
+                value
+                    .as_str()
+                    .and_then(parse_timestamp)
+                    .map(|timestamp| timestamp.and_utc().timestamp_millis())
+            }),
+            ColumnType::String => {
+                write_column::<ByteArrayType>(&mut column, rows, name, |value| match value {
+                    Value::Null => None,
+                    Value::String(value) => Some(ByteArray::from(value.as_str())),
+                    value => Some(ByteArray::from(value.to_string().as_str())),
+                })
+            },
+        }
+        .with_context(|| format!("Failed to write column {}", name))?;
+        column.close()?;
+    }
+    row_group.close()?;
+    Ok(writer.into_inner()?)
+}
+
+/// Writes the column's values of every row, with missing and null values as nulls.
+fn write_column<T: DataType>(
+    column: &mut SerializedColumnWriter<'_>,
+    rows: &[Value],
+    name: &str,
+    to_parquet: impl Fn(&Value) -> Option<T::T>,
+) -> Result<()> {
+    let mut values = vec![];
+    let mut definition_levels = vec![];
+    for row in rows {
+        match row.get(name).and_then(&to_parquet) {
+            Some(value) => {
+                values.push(value);
+                definition_levels.push(1);
+            },
+            None => definition_levels.push(0),
+        }
+    }
+    column
+        .typed::<T>()
+        .write_batch(&values, Some(&definition_levels), None)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    #[test]
+    fn test_rows_to_parquet() {
+        let rows = vec![
+            serde_json::json!({
+                "owner_address": "0x1",
+                "amount": "100000000000000000000",
+                "transaction_version": 10,
+                "is_frozen": false,
+                "gas_unit_price": 1,
+                "transaction_timestamp": "2024-05-01T12:30:45.123",
+                "metadata": {"name": "coin"},
+                "token_standard": null,
+            }),
+            serde_json::json!({
+                "owner_address": "0x2",
+                "amount": null,
+                "transaction_version": 11,
+                "is_frozen": true,
+                "gas_unit_price": 1.5,
+                "transaction_timestamp": "2024-05-01T12:30:46",
+                "metadata": null,
+                "token_standard": null,
+            }),
+        ];
+        let path = std::env::temp_dir().join(format!("sink_test_{}.parquet", std::process::id()));
+        std::fs::write(&path, rows_to_parquet(&rows).unwrap()).unwrap();
+        let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.file_metadata().num_rows(), 2);
+        let schema = metadata.file_metadata().schema_descr();
+        let columns: Vec<_> = (0..schema.num_columns())
+            .map(|i| {
+                let column = schema.column(i);
+                (
+                    column.name().to_string(),
+                    column.physical_type(),
+                    column.logical_type(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                (
+                    "owner_address".to_string(),
+                    PhysicalType::BYTE_ARRAY,
+                    Some(LogicalType::String)
+                ),
+                (
+                    "amount".to_string(),
+                    PhysicalType::BYTE_ARRAY,
+                    Some(LogicalType::String)
+                ),
+                ("transaction_version".to_string(), PhysicalType::INT64, None),
+                ("is_frozen".to_string(), PhysicalType::BOOLEAN, None),
+                ("gas_unit_price".to_string(), PhysicalType::DOUBLE, None),
+                (
+                    "transaction_timestamp".to_string(),
+                    PhysicalType::INT64,
+                    Some(LogicalType::Timestamp {
+                        is_adjusted_to_utc: false,
+                        unit: TimeUnit::MILLIS(MilliSeconds {}),
+                    })
+                ),
+                (
+                    "metadata".to_string(),
+                    PhysicalType::BYTE_ARRAY,
+                    Some(LogicalType::String)
+                ),
+                (
+                    "token_standard".to_string(),
+                    PhysicalType::BYTE_ARRAY,
+                    Some(LogicalType::String)
+                ),
+            ]
+        );
+
+        let values: Vec<_> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().to_json_value())
+            .collect();
+        assert_eq!(values[0]["transaction_version"], 10);
+        assert_eq!(values[0]["is_frozen"], false);
+        assert_eq!(values[0]["metadata"], r#"{"name":"coin"}"#);
+        assert_eq!(values[1]["amount"], Value::Null);
+        assert_eq!(values[1]["gas_unit_price"], 1.5);
+        std::fs::remove_file(path).unwrap();
+    }
+}
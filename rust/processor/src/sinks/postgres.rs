@@ -0,0 +1,33 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{ProcessorOutput, SinkTrait};
+use crate::utils::database::ArcDbPool;
+use ahash::AHashMap;
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Inserts rows with the processor's own queries, chunked by `per_table_chunk_sizes`.
+pub struct PostgresSink {
+    db_pool: ArcDbPool,
+    per_table_chunk_sizes: AHashMap<String, usize>,
+}
+
+impl PostgresSink {
+    pub fn new(db_pool: ArcDbPool, per_table_chunk_sizes: AHashMap<String, usize>) -> Self {
+        Self {
+            db_pool,
+            per_table_chunk_sizes,
+        }
+    }
+}
+
+#[async_trait]
+impl SinkTrait for PostgresSink {
+    async fn write(&self, output: &dyn ProcessorOutput, _: u64, _: u64) -> Result<()> {
+        output
+            .insert_to_db(self.db_pool.clone(), &self.per_table_chunk_sizes)
+            .await?;
+        Ok(())
+    }
+}
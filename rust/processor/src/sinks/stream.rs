@@ -0,0 +1,178 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Publishes every row to a message stream, keyed by table and primary key, so downstream
+//! services can react to new rows without polling Postgres. A batch is only done once the broker
+//! has acknowledged all of its messages, and `processor_status` only moves past a batch once it's
+//! done, so every row is delivered at least once: after a restart the batches after the last
+//! processed version are published again.
+//!
+//! Rows with the same key are published in version order as long as batches are processed one at
+//! a time, which is why stream sinks require `number_concurrent_processing_tasks: 1`. Within a
+//! batch, current tables have one row per key and history tables include the version in the key.
+
+use super::{ProcessorOutput, SinkTrait};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StreamSinkConfig {
+    pub topic: String,
+    pub broker: StreamBrokerConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamBrokerConfig {
+    /// A Kafka cluster. Only available when built with the `kafka` feature.
+    #[cfg(feature = "kafka")]
+    Kafka(super::kafka::KafkaConfig),
+    /// Keeps messages in memory, standing in for a broker in tests.
+    InMemory,
+}
+
+/// A row as published to the stream.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamMessage {
+    pub topic: String,
+    /// `{table}:{primary key columns}`, which brokers use to keep a key's messages in order
+    pub key: String,
+    /// `{"table": ..., "row": {...}}` as JSON
+    pub payload: Vec<u8>,
+}
+
+#[async_trait]
+pub trait StreamPublisher: Send + Sync {
+    /// Publishes the messages in order and returns once the broker has acknowledged all of them.
+    async fn publish(&self, messages: Vec<StreamMessage>) -> Result<()>;
+}
+
+pub struct StreamSink {
+    topic: String,
+    publisher: Box<dyn StreamPublisher>,
+}
+
+impl StreamSink {
+    pub fn new(config: StreamSinkConfig) -> Result<Self> {
+        let publisher: Box<dyn StreamPublisher> = match config.broker {
+            #[cfg(feature = "kafka")]
+            StreamBrokerConfig::Kafka(kafka) => Box::new(super::kafka::KafkaPublisher::new(kafka)?),
+            StreamBrokerConfig::InMemory => Box::<InMemoryPublisher>::default(),
+        };
+        Ok(Self::with_publisher(config.topic, publisher))
+    }
+
+    pub fn with_publisher(topic: String, publisher: Box<dyn StreamPublisher>) -> Self {
+        Self { topic, publisher }
+    }
+}
+
+#[async_trait]
+impl SinkTrait for StreamSink {
+    async fn write(&self, output: &dyn ProcessorOutput, _: u64, _: u64) -> Result<()> {
+        let mut messages = vec![];
+        for table in output.table_rows()? {
+            for row in &table.rows {
+                messages.push(StreamMessage {
+                    topic: self.topic.clone(),
+                    key: table.key(row),
+                    payload: serde_json::to_vec(&serde_json::json!({
+                        "table": table.table,
+                        "row": row,
+                    }))?,
+                });
+            }
+        }
+        if messages.is_empty() {
+            return Ok(());
+        }
+        self.publisher.publish(messages).await
+    }
+}
+
+/// Records published messages in order. Clones share the same messages.
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryPublisher {
+    messages: Arc<Mutex<Vec<StreamMessage>>>,
+}
+
+impl InMemoryPublisher {
+    pub fn messages(&self) -> Vec<StreamMessage> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl StreamPublisher for InMemoryPublisher {
+    async fn publish(&self, messages: Vec<StreamMessage>) -> Result<()> {
+        self.messages.lock().unwrap().extend(messages);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sinks::TableRows, utils::database::ArcDbPool};
+    use ahash::AHashMap;
+    use serde_json::json;
+
+    struct TestOutput {
+        balances: Vec<serde_json::Value>,
+    }
+
+    #[async_trait]
+    impl ProcessorOutput for TestOutput {
+        async fn insert_to_db(
+            &self,
+            _: ArcDbPool,
+            _: &AHashMap<String, usize>,
+        ) -> Result<(), diesel::result::Error> {
+            unreachable!("Not written to postgres")
+        }
+
+        fn table_rows(&self) -> Result<Vec<TableRows>> {
+            Ok(vec![
+                TableRows::new(
+                    "current_fungible_asset_balances",
+                    &["storage_id"],
+                    &self.balances,
+                )?,
+                TableRows::new::<serde_json::Value>("coin_supply", &["transaction_version"], &[])?,
+            ])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_sink_keys_and_order() {
+        let publisher = InMemoryPublisher::default();
+        let sink = StreamSink::with_publisher("rows".to_string(), Box::new(publisher.clone()));
+        for (version, amount) in [(1, 10), (2, 20)] {
+            let output = TestOutput {
+                balances: vec![json!({ "storage_id": "0xa", "amount": amount })],
+            };
+            sink.write(&output, version, version).await.unwrap();
+        }
+        sink.write(&TestOutput { balances: vec![] }, 3, 3)
+            .await
+            .unwrap();
+
+        let messages = publisher.messages();
+        assert_eq!(messages.len(), 2);
+        for (message, amount) in messages.iter().zip([10, 20]) {
+            assert_eq!(message.topic, "rows");
+            assert_eq!(message.key, "current_fungible_asset_balances:0xa");
+            let payload: serde_json::Value = serde_json::from_slice(&message.payload).unwrap();
+            assert_eq!(
+                payload,
+                json!({
+                    "table": "current_fungible_asset_balances",
+                    "row": { "storage_id": "0xa", "amount": amount },
+                })
+            );
+        }
+    }
+}
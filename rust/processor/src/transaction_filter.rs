@@ -1,24 +1,62 @@
+use crate::utils::util::{get_entry_function_from_user_request, standardize_address};
+use anyhow::Result;
 use aptos_protos::transaction::v1::{
     transaction::{TransactionType, TxnData},
     transaction_payload::Payload,
-    Transaction,
+    write_set_change::Change,
+    Event, Transaction,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 /// Allows filtering transactions based on various criteria
 /// The criteria are combined with `AND`
 /// If a criteria is not set, it is ignored
 /// Criteria will be loaded from the config file
+/// Addresses, including those in entry functions and types, are standardized when the config is
+/// loaded, so `0x1` and `0x0000...01` are equivalent. Malformed addresses fail deserialization
+///
+/// Filters can be nested with `any_of`, `all_of` and `not`, e.g. to only keep successful
+/// transactions that either call a given entry function or emit a given event:
+/// ```yaml
+/// transaction_filter:
+///   success: true
+///   any_of:
+///     - focus_entry_functions: ["0x1::coin::transfer"]
+///     - focus_event_types: ["0x1::coin::DepositEvent"]
+/// ```
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 #[serde(default)]
 pub struct TransactionFilter {
     // Only allow transactions from these contract addresses
+    #[serde(deserialize_with = "deserialize_addresses")]
     focus_contract_addresses: Option<ahash::HashSet<String>>,
     // Skip transactions from these sender addresses
+    #[serde(deserialize_with = "deserialize_addresses")]
     skip_sender_addresses: Option<ahash::HashSet<String>>,
     // Skip all transactions that aren't user transactions
     focus_user_transactions: bool,
+    // Only allow user transactions calling one of these entry functions, e.g. `0x1::coin::transfer`
+    #[serde(deserialize_with = "deserialize_module_paths")]
+    focus_entry_functions: Option<ahash::HashSet<String>>,
+    // Only allow transactions emitting an event of one of these types. A type without generic
+    // parameters matches all of its instantiations
+    #[serde(deserialize_with = "deserialize_module_paths")]
+    focus_event_types: Option<ahash::HashSet<String>>,
+    // Only allow transactions writing or deleting a resource of one of these types. A type
+    // without generic parameters matches all of its instantiations
+    #[serde(deserialize_with = "deserialize_module_paths")]
+    focus_resource_types: Option<ahash::HashSet<String>>,
+    // Only allow successful (true) or failed (false) transactions
+    success: Option<bool>,
+    // Only allow these transaction types, e.g. `user`, `genesis` or `block_metadata`
+    focus_transaction_types: Option<ahash::HashSet<String>>,
+    // Only allow transactions included by at least one of these filters
+    any_of: Option<Vec<TransactionFilter>>,
+    // Only allow transactions included by all of these filters
+    all_of: Option<Vec<TransactionFilter>>,
+    // Skip transactions included by this filter
+    not: Option<Box<TransactionFilter>>,
 }
 
 impl TransactionFilter {
@@ -26,17 +64,29 @@ impl TransactionFilter {
         focus_contract_addresses: Option<ahash::HashSet<String>>,
         skip_sender_addresses: Option<ahash::HashSet<String>>,
         focus_user_transactions: bool,
-    ) -> Self {
-        // TODO: normalize addresses
-        Self {
-            focus_contract_addresses,
-            skip_sender_addresses,
+    ) -> Result<Self> {
+        Ok(Self {
+            focus_contract_addresses: focus_contract_addresses
+                .map(|addresses| addresses.iter().map(|a| normalize_address(a)).collect())
+                .transpose()?,
+            skip_sender_addresses: skip_sender_addresses
+                .map(|addresses| addresses.iter().map(|a| normalize_address(a)).collect())
+                .transpose()?,
             focus_user_transactions,
-        }
+            ..Self::default()
+        })
     }
 
     /// Returns true if the transaction should be included
     pub fn include(&self, transaction: &Transaction) -> bool {
+        self.include_user_transaction(transaction)
+            && self.include_transaction_details(transaction)
+            && self.include_composed(transaction)
+    }
+
+    /// Checks the criteria that only look at user transactions. Other transactions pass unless
+    /// we're only focusing on user transactions or entry functions.
+    fn include_user_transaction(&self, transaction: &Transaction) -> bool {
         // If we're only focusing on user transactions, skip if it's not a user transaction
 
         let is_user_txn = transaction.r#type == TransactionType::User as i32;
@@ -44,6 +94,22 @@ impl TransactionFilter {
             return false;
         }
 
+        // Only user transactions call entry functions
+        let entry_function = match transaction.txn_data.as_ref() {
+            Some(TxnData::User(user_transaction)) => user_transaction
+                .request
+                .as_ref()
+                .and_then(get_entry_function_from_user_request),
+            _ => None,
+        };
+        if let Some(focus_entry_functions) = &self.focus_entry_functions {
+            if !entry_function.map_or(false, |f| {
+                focus_entry_functions.contains(&standardize_module_path(&f))
+            }) {
+                return false;
+            }
+        }
+
         // If it's not a user transaction, we can skip the rest of the checks
         if !is_user_txn {
             return true;
@@ -53,7 +119,7 @@ impl TransactionFilter {
             if let Some(utr) = user_transaction.request.as_ref() {
                 // Skip if sender is in the skip list
                 if let Some(skip_sender_addresses) = &self.skip_sender_addresses {
-                    if skip_sender_addresses.contains(&utr.sender) {
+                    if skip_sender_addresses.contains(&standardize_address(&utr.sender)) {
                         return false;
                     }
                 }
@@ -64,7 +130,9 @@ impl TransactionFilter {
                         if let Some(Payload::EntryFunctionPayload(efp)) = payload.payload.as_ref() {
                             if let Some(function) = efp.function.as_ref() {
                                 if let Some(module) = function.module.as_ref() {
-                                    if !focus_contract_addresses.contains(&module.address) {
+                                    if !focus_contract_addresses
+                                        .contains(&standardize_address(&module.address))
+                                    {
                                         return false;
                                     }
                                 }
@@ -77,4 +145,333 @@ impl TransactionFilter {
 
         true
     }
+
+    /// Checks the criteria that apply to every transaction type.
+    fn include_transaction_details(&self, transaction: &Transaction) -> bool {
+        if let Some(success) = self.success {
+            if transaction.info.as_ref().map(|info| info.success) != Some(success) {
+                return false;
+            }
+        }
+
+        if let Some(focus_transaction_types) = &self.focus_transaction_types {
+            if !transaction_type_name(transaction)
+                .map_or(false, |name| focus_transaction_types.contains(&name))
+            {
+                return false;
+            }
+        }
+
+        if let Some(focus_event_types) = &self.focus_event_types {
+            if !get_events(transaction)
+                .iter()
+                .any(|event| type_matches(focus_event_types, &event.type_str))
+            {
+                return false;
+            }
+        }
+
+        if let Some(focus_resource_types) = &self.focus_resource_types {
+            let changes = transaction
+                .info
+                .as_ref()
+                .map(|info| info.changes.as_slice())
+                .unwrap_or_default();
+            if !changes.iter().any(|wsc| match wsc.change.as_ref() {
+                Some(Change::WriteResource(resource)) => {
+                    type_matches(focus_resource_types, &resource.type_str)
+                },
+                Some(Change::DeleteResource(resource)) => {
+                    type_matches(focus_resource_types, &resource.type_str)
+                },
+                _ => false,
+            }) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Checks the nested filters.
+    fn include_composed(&self, transaction: &Transaction) -> bool {
+        if let Some(any_of) = &self.any_of {
+            if !any_of.iter().any(|filter| filter.include(transaction)) {
+                return false;
+            }
+        }
+
+        if let Some(all_of) = &self.all_of {
+            if !all_of.iter().all(|filter| filter.include(transaction)) {
+                return false;
+            }
+        }
+
+        if let Some(not) = &self.not {
+            if not.include(transaction) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Returns the transaction type as it's written in the config, e.g. `block_metadata` for
+/// `TRANSACTION_TYPE_BLOCK_METADATA`.
+fn transaction_type_name(transaction: &Transaction) -> Option<String> {
+    let transaction_type = TransactionType::try_from(transaction.r#type).ok()?;
+    let name = transaction_type.as_str_name();
+    Some(
+        name.strip_prefix("TRANSACTION_TYPE_")
+            .unwrap_or(name)
+            .to_lowercase(),
+    )
+}
+
+fn get_events(transaction: &Transaction) -> &[Event] {
+    match transaction.txn_data.as_ref() {
+        Some(TxnData::BlockMetadata(tx_inner)) => &tx_inner.events,
+        Some(TxnData::Genesis(tx_inner)) => &tx_inner.events,
+        Some(TxnData::User(tx_inner)) => &tx_inner.events,
+        // No events in Movement protobuf Validator Tx.
+        _ => &[],
+    }
+}
+
+/// A configured type matches either the exact type, or the type without its generic parameters.
+fn type_matches(types: &ahash::HashSet<String>, type_str: &str) -> bool {
+    let type_str = standardize_module_path(type_str);
+    types.contains(&type_str)
+        || type_str
+            .split_once('<')
+            .map_or(false, |(base_type, _)| types.contains(base_type))
+}
+
+/// Standardizes an address from the config, e.g. `0x1` to `0x0000...01`. Unlike
+/// `standardize_address` this rejects anything that isn't a hex address of at most 32 bytes.
+fn normalize_address(address: &str) -> Result<String> {
+    let hex_address = address.strip_prefix("0x").unwrap_or(address);
+    anyhow::ensure!(
+        !hex_address.is_empty()
+            && hex_address.len() <= 64
+            && hex_address.chars().all(|c| c.is_ascii_hexdigit()),
+        "Invalid address in transaction filter: {}",
+        address
+    );
+    Ok(standardize_address(&hex_address.to_lowercase()))
+}
+
+/// Standardizes the address of a config entry like `0x1::coin::transfer` or
+/// `0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>`. Only the leading address is standardized,
+/// so generic type parameters have to be written the way the chain formats them.
+fn normalize_module_path(path: &str) -> Result<String> {
+    let (address, rest) = path.split_once("::").ok_or_else(|| {
+        anyhow::anyhow!(
+            "Invalid entry function or type in transaction filter, expected `<address>::<module>::<name>`: {}",
+            path
+        )
+    })?;
+    Ok(format!("{}::{}", normalize_address(address)?, rest))
+}
+
+/// Standardizes the leading address of an entry function or type from a transaction so it can
+/// be compared with the normalized config.
+fn standardize_module_path(path: &str) -> String {
+    match path.split_once("::") {
+        Some((address, rest)) => format!("{}::{}", standardize_address(address), rest),
+        None => path.to_string(),
+    }
+}
+
+fn deserialize_addresses<'de, D>(
+    deserializer: D,
+) -> Result<Option<ahash::HashSet<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    let addresses = <Option<Vec<String>>>::deserialize(deserializer)?;
+    addresses
+        .map(|addresses| addresses.iter().map(|a| normalize_address(a)).collect())
+        .transpose()
+        .map_err(D::Error::custom)
+}
+
+fn deserialize_module_paths<'de, D>(
+    deserializer: D,
+) -> Result<Option<ahash::HashSet<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    let paths = <Option<Vec<String>>>::deserialize(deserializer)?;
+    paths
+        .map(|paths| paths.iter().map(|p| normalize_module_path(p)).collect())
+        .transpose()
+        .map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::transaction::v1::{
+        EntryFunctionId, EntryFunctionPayload, MoveModuleId, TransactionInfo, TransactionPayload,
+        UserTransaction, UserTransactionRequest, WriteResource, WriteSetChange,
+    };
+
+    fn user_transaction(entry_function: &str, event_types: &[&str], success: bool) -> Transaction {
+        let (module, name) = entry_function.rsplit_once("::").unwrap();
+        let (address, module_name) = module.split_once("::").unwrap();
+        Transaction {
+            r#type: TransactionType::User as i32,
+            info: Some(TransactionInfo {
+                success,
+                changes: vec![WriteSetChange {
+                    change: Some(Change::WriteResource(WriteResource {
+                        type_str: "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>".to_string(),
+                        ..WriteResource::default()
+                    })),
+                    ..WriteSetChange::default()
+                }],
+                ..TransactionInfo::default()
+            }),
+            txn_data: Some(TxnData::User(UserTransaction {
+                request: Some(UserTransactionRequest {
+                    sender: "0xa".to_string(),
+                    payload: Some(TransactionPayload {
+                        payload: Some(Payload::EntryFunctionPayload(EntryFunctionPayload {
+                            function: Some(EntryFunctionId {
+                                module: Some(MoveModuleId {
+                                    address: address.to_string(),
+                                    name: module_name.to_string(),
+                                }),
+                                name: name.to_string(),
+                            }),
+                            entry_function_id_str: entry_function.to_string(),
+                            ..EntryFunctionPayload::default()
+                        })),
+                        ..TransactionPayload::default()
+                    }),
+                    ..UserTransactionRequest::default()
+                }),
+                events: event_types
+                    .iter()
+                    .map(|type_str| Event {
+                        type_str: type_str.to_string(),
+                        ..Event::default()
+                    })
+                    .collect(),
+                ..UserTransaction::default()
+            })),
+            ..Transaction::default()
+        }
+    }
+
+    fn filter(yaml: &str) -> TransactionFilter {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_entry_function_and_event_filters() {
+        let transfer = user_transaction(
+            "0x1::coin::transfer",
+            &["0x1::coin::WithdrawEvent", "0x1::coin::DepositEvent"],
+            true,
+        );
+        let swap = user_transaction(
+            "0xbeef::router::swap",
+            &["0xbeef::pool::SwapEvent<0x1::aptos_coin::AptosCoin>"],
+            true,
+        );
+
+        let entry_function_filter = filter("focus_entry_functions: ['0x1::coin::transfer']");
+        assert!(entry_function_filter.include(&transfer));
+        assert!(!entry_function_filter.include(&swap));
+
+        // Event types match with or without their generic parameters
+        let event_filter = filter("focus_event_types: ['0xbeef::pool::SwapEvent']");
+        assert!(!event_filter.include(&transfer));
+        assert!(event_filter.include(&swap));
+
+        let resource_filter = filter("focus_resource_types: ['0x1::coin::CoinStore']");
+        assert!(resource_filter.include(&transfer));
+    }
+
+    #[test]
+    fn test_success_and_transaction_type_filters() {
+        let failed = user_transaction("0x1::coin::transfer", &[], false);
+        let block_metadata = Transaction {
+            r#type: TransactionType::BlockMetadata as i32,
+            ..Transaction::default()
+        };
+
+        assert!(!filter("success: true").include(&failed));
+        assert!(filter("success: false").include(&failed));
+        assert!(filter("focus_transaction_types: ['block_metadata']").include(&block_metadata));
+        assert!(!filter("focus_transaction_types: ['user']").include(&block_metadata));
+    }
+
+    #[test]
+    fn test_addresses_are_normalized() {
+        let transfer = user_transaction(
+            "0x0000000000000000000000000000000000000000000000000000000000000001::coin::transfer",
+            &["0x1::coin::DepositEvent"],
+            true,
+        );
+
+        assert!(filter("focus_contract_addresses: ['0x1']").include(&transfer));
+        assert!(filter("focus_entry_functions: ['0x01::coin::transfer']").include(&transfer));
+        assert!(filter(
+            "focus_event_types: \
+             ['0x0000000000000000000000000000000000000000000000000000000000000001::coin::DepositEvent']"
+        )
+        .include(&transfer));
+        assert!(!filter("skip_sender_addresses: ['0x00A']").include(&transfer));
+
+        assert!(
+            serde_yaml::from_str::<TransactionFilter>("skip_sender_addresses: ['0xzz']").is_err()
+        );
+        assert!(
+            serde_yaml::from_str::<TransactionFilter>("focus_entry_functions: ['transfer']")
+                .is_err()
+        );
+        assert!(
+            TransactionFilter::new(None, Some(["1".repeat(65)].into_iter().collect()), false)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_composed_filters() {
+        let transfer = user_transaction("0x1::coin::transfer", &["0x1::coin::DepositEvent"], true);
+        let swap = user_transaction("0xbeef::router::swap", &[], true);
+        let failed_swap = user_transaction("0xbeef::router::swap", &[], false);
+
+        let any_of = filter(
+            "
+            any_of:
+              - focus_entry_functions: ['0xbeef::router::swap']
+              - focus_event_types: ['0x1::coin::DepositEvent']
+            not:
+              success: false
+            ",
+        );
+        assert!(any_of.include(&transfer));
+        assert!(any_of.include(&swap));
+        assert!(!any_of.include(&failed_swap));
+
+        let all_of = filter(
+            "
+            all_of:
+              - focus_entry_functions: ['0xbeef::router::swap']
+              - success: true
+            ",
+        );
+        assert!(!all_of.include(&transfer));
+        assert!(all_of.include(&swap));
+        assert!(!all_of.include(&failed_swap));
+    }
 }
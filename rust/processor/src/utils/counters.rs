@@ -89,6 +89,26 @@ pub static PROCESSOR_SUCCESSES_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of transactions skipped and recorded in processor_errors because they failed to process
+pub static PROCESSOR_DEAD_LETTER_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "indexer_processor_dead_letter_count",
+        "Number of transactions a processor skipped because they failed to process",
+        &["processor_name"]
+    )
+    .unwrap()
+});
+
+/// Number of times a failed pipeline component was retried by the worker's supervisor
+pub static PIPELINE_RESTART_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "indexer_processor_pipeline_restart_count",
+        "Number of times a failed pipeline component was retried",
+        &["processor_name", "component"]
+    )
+    .unwrap()
+});
+
 /// Number of times the connection pool has timed out when trying to get a connection
 pub static UNABLE_TO_GET_CONNECTION_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     register_int_counter!(
@@ -282,3 +302,23 @@ pub static PARQUET_HANDLER_BUFFER_SIZE: Lazy<IntGaugeVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+/// Keys compared by the last consistency check of a current table, by outcome
+pub static CONSISTENCY_CHECK_KEYS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "indexer_consistency_check_keys",
+        "Keys compared by the last consistency check of a current table against its history table",
+        &["table", "outcome"]
+    )
+    .unwrap()
+});
+
+/// Webhook notifications by the status of their last attempt
+pub static WEBHOOK_DELIVERY_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "indexer_webhook_delivery_count",
+        "Webhook notifications sent, by whether they were delivered or failed after all attempts",
+        &["status"]
+    )
+    .unwrap()
+});
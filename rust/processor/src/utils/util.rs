@@ -75,6 +75,15 @@ pub fn standardize_address(handle: &str) -> String {
     }
 }
 
+/// Standardizes the leading address of an event type, e.g. `0x1::coin::CoinDeposit`. Event types
+/// carry short addresses for special accounts like 0x1, so they're compared in the long form.
+pub fn standardize_event_type(event_type: &str) -> String {
+    match event_type.split_once("::") {
+        Some((address, rest)) => format!("{}::{}", standardize_address(address), rest),
+        None => event_type.to_string(),
+    }
+}
+
 /// Standardizes all addresses and table handles to be length 66 (0x-64 length hash) that takes in a slice.
 pub fn standardize_address_from_bytes(bytes: &[u8]) -> String {
     let encdoed_bytes = hex::encode(bytes);
@@ -446,6 +455,15 @@ pub fn convert_bcs_token_object_propertymap(s: Value) -> Option<Value> {
     }
 }
 
+/// Looks up a dot separated path in a JSON value, with numbers indexing into arrays, e.g.
+/// `metadata.inner` or `amounts.0`
+pub fn get_json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, field| match value {
+        Value::Array(items) => items.get(field.parse::<usize>().ok()?),
+        _ => value.get(field),
+    })
+}
+
 /// Convert from hex string to raw byte string
 pub fn hex_to_raw_bytes(val: &str) -> anyhow::Result<Vec<u8>> {
     Ok(hex::decode(val.strip_prefix("0x").unwrap_or(val))?)
@@ -687,4 +705,18 @@ mod tests {
         let d: TokenObjectDataMock = serde_json::from_str(val.as_str()).unwrap();
         assert_eq!(d.default_properties, Value::Object(serde_json::Map::new()));
     }
+
+    #[test]
+    fn test_get_json_path() {
+        let value: Value =
+            serde_json::from_str(r#"{"pool": {"inner": "0x1"}, "amounts": ["10", "20"]}"#).unwrap();
+        assert_eq!(
+            get_json_path(&value, "pool.inner"),
+            Some(&Value::from("0x1"))
+        );
+        assert_eq!(get_json_path(&value, "amounts.1"), Some(&Value::from("20")));
+        assert_eq!(get_json_path(&value, "amounts.2"), None);
+        assert_eq!(get_json_path(&value, "amounts.first"), None);
+        assert_eq!(get_json_path(&value, "pool.missing"), None);
+    }
 }
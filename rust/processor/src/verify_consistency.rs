@@ -0,0 +1,298 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Checks that `current_*` tables still agree with the history tables they're derived from. For
+//! every key, the latest history row is compared with the current row, using the same mapping
+//! rollback rebuilds current tables with. Only versions up to the processor's
+//! `last_success_version` are compared, so the processor can keep running meanwhile. Keys whose
+//! current row is newer than that are skipped.
+
+use crate::{
+    db::common::models::processor_status::ProcessorStatusQuery,
+    processors::ProcessorName,
+    rollback::{owned_tables, CurrentTable, OwnedTable},
+    utils::{counters::CONSISTENCY_CHECK_KEYS, database::new_db_pool},
+    IndexerGrpcProcessorConfig,
+};
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use diesel::sql_types::BigInt;
+use diesel_async::RunQueryDsl;
+use prometheus::{Encoder, TextEncoder};
+use std::path::PathBuf;
+use tracing::{error, info};
+
+#[derive(Args, Clone, Debug)]
+pub struct VerifyConsistencyArgs {
+    /// Processors whose current tables are checked, comma separated. Defaults to the processors in
+    /// the config file
+    #[clap(long, value_enum, value_delimiter = ',')]
+    pub processor: Vec<ProcessorName>,
+    /// Only check these current tables, comma separated
+    #[clap(long, value_delimiter = ',')]
+    pub tables: Vec<String>,
+    /// Only check the keys found in roughly this percentage of history table blocks, instead of
+    /// every key
+    #[clap(long)]
+    pub sample_percent: Option<f64>,
+    /// Write the results in the Prometheus text format to this file, e.g. for node exporter's
+    /// textfile collector
+    #[clap(long)]
+    pub metrics_file: Option<PathBuf>,
+}
+
+/// Key counts of one current table.
+#[derive(Debug, Default, QueryableByName)]
+pub struct ConsistencyCounts {
+    #[diesel(sql_type = BigInt)]
+    pub checked: i64,
+    /// Keys in history without a current row
+    #[diesel(sql_type = BigInt)]
+    pub missing: i64,
+    /// Current rows without history
+    #[diesel(sql_type = BigInt)]
+    pub extra: i64,
+    /// Current rows that differ from the latest history row
+    #[diesel(sql_type = BigInt)]
+    pub mismatched: i64,
+}
+
+impl ConsistencyCounts {
+    pub fn inconsistent(&self) -> i64 {
+        self.missing + self.extra + self.mismatched
+    }
+}
+
+impl VerifyConsistencyArgs {
+    pub async fn run(&self, config: &IndexerGrpcProcessorConfig) -> Result<()> {
+        if let Some(percent) = self.sample_percent {
+            anyhow::ensure!(
+                percent > 0.0 && percent <= 100.0,
+                "--sample-percent must be in (0, 100]"
+            );
+        }
+        let processors: Vec<ProcessorName> = if self.processor.is_empty() {
+            std::iter::once(&config.processor_config)
+                .chain(&config.additional_processor_configs)
+                .filter(|processor_config| !processor_config.is_parquet_processor())
+                .map(ProcessorName::from)
+                .collect()
+        } else {
+            self.processor.clone()
+        };
+
+        let pool = new_db_pool(&config.postgres_connection_string, Some(1))
+            .await
+            .context("Failed to create connection pool")?;
+        let mut conn = pool.get().await.context("Failed to get a connection")?;
+
+        let mut num_checked_tables = 0;
+        let mut num_inconsistent_tables = 0;
+        for processor in processors {
+            let processor_name: &'static str = processor.into();
+            let current_tables: Vec<CurrentTable> = owned_tables(processor)?
+                .into_iter()
+                .filter_map(|table| match table {
+                    OwnedTable::Current(current) => Some(current),
+                    _ => None,
+                })
+                .filter(|current| {
+                    self.tables.is_empty() || self.tables.iter().any(|t| t == current.table)
+                })
+                .collect();
+            if current_tables.is_empty() {
+                continue;
+            }
+            let Some(status) =
+                ProcessorStatusQuery::get_by_processor(processor_name, &mut conn).await?
+            else {
+                bail!("{} has no processor status", processor_name);
+            };
+
+            for current in current_tables {
+                let counts = diesel::sql_query(consistency_query(&current, self.sample_percent))
+                    .bind::<BigInt, _>(status.last_success_version)
+                    .get_result::<ConsistencyCounts>(&mut conn)
+                    .await
+                    .with_context(|| format!("Failed to check {}", current.table))?;
+                for (outcome, count) in [
+                    ("checked", counts.checked),
+                    ("missing", counts.missing),
+                    ("extra", counts.extra),
+                    ("mismatched", counts.mismatched),
+                ] {
+                    CONSISTENCY_CHECK_KEYS
+                        .with_label_values(&[current.table, outcome])
+                        .set(count);
+                }
+                num_checked_tables += 1;
+                if counts.inconsistent() > 0 {
+                    num_inconsistent_tables += 1;
+                    error!(
+                        processor_name = processor_name,
+                        table = current.table,
+                        max_version = status.last_success_version,
+                        checked = counts.checked,
+                        missing = counts.missing,
+                        extra = counts.extra,
+                        mismatched = counts.mismatched,
+                        "[Verify] Current table doesn't match its history"
+                    );
+                } else {
+                    info!(
+                        processor_name = processor_name,
+                        table = current.table,
+                        max_version = status.last_success_version,
+                        checked = counts.checked,
+                        "[Verify] Current table matches its history"
+                    );
+                }
+            }
+        }
+
+        if let Some(path) = &self.metrics_file {
+            let mut buffer = vec![];
+            TextEncoder::new().encode(&prometheus::gather(), &mut buffer)?;
+            std::fs::write(path, buffer)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        if num_checked_tables == 0 {
+            bail!("No current tables to check");
+        }
+        if num_inconsistent_tables > 0 {
+            bail!(
+                "{} of {} current tables don't match their history",
+                num_inconsistent_tables,
+                num_checked_tables
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Counts the keys of a current table that disagree with the latest history row at or before
+/// version `$1`. With a sample, only keys seen in the sampled history blocks are compared.
+pub fn consistency_query(current: &CurrentTable, sample_percent: Option<f64>) -> String {
+    let columns = current.current_columns();
+    let key_expressions = current
+        .key_columns
+        .iter()
+        .map(|column| current.history_expression(column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let keys = current.key_columns.join(", ");
+    let values = columns
+        .iter()
+        .map(|column| format!("{} AS {}", current.history_expression(column), column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let order = std::iter::once("transaction_version")
+        .chain(current.order_columns.iter().copied())
+        .map(|column| format!("{} DESC", column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let join_condition = current
+        .key_columns
+        .iter()
+        .map(|column| format!("l.{column} = c.{column}"))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let compared = |alias: &str| {
+        columns
+            .iter()
+            .chain(std::iter::once(&"last_transaction_version"))
+            .map(|column| format!("{}.{}", alias, column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let (sampled, history_filter, current_filter) = match sample_percent {
+        Some(percent) => (
+            format!(
+                "sampled AS (SELECT DISTINCT {key_expressions} FROM {history} TABLESAMPLE SYSTEM ({percent})), ",
+                history = current.history_table,
+            ),
+            format!(" AND ({key_expressions}) IN (SELECT * FROM sampled)"),
+            format!(" WHERE ({keys}) IN (SELECT * FROM sampled)"),
+        ),
+        None => (String::new(), String::new(), String::new()),
+    };
+    format!(
+        "WITH {sampled}\
+         latest AS (SELECT DISTINCT ON ({key_expressions}) {values}, \
+         transaction_version AS last_transaction_version FROM {history} \
+         WHERE transaction_version <= $1{history_filter} \
+         ORDER BY {key_expressions}, {order}), \
+         current_rows AS (SELECT {columns}, last_transaction_version FROM {table}{current_filter}) \
+         SELECT COUNT(*) AS checked, \
+         COUNT(*) FILTER (WHERE c.last_transaction_version IS NULL) AS missing, \
+         COUNT(*) FILTER (WHERE l.last_transaction_version IS NULL) AS extra, \
+         COUNT(*) FILTER (WHERE l.last_transaction_version IS NOT NULL \
+         AND c.last_transaction_version IS NOT NULL \
+         AND ({latest_values}) IS DISTINCT FROM ({current_values})) AS mismatched \
+         FROM latest l FULL OUTER JOIN current_rows c ON {join_condition} \
+         WHERE c.last_transaction_version IS NULL OR c.last_transaction_version <= $1",
+        history = current.history_table,
+        columns = columns.join(", "),
+        table = current.table,
+        latest_values = compared("l"),
+        current_values = compared("c"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CURRENT_COIN_BALANCES: CurrentTable = CurrentTable {
+        table: "current_coin_balances",
+        history_table: "coin_balances",
+        key_columns: &["owner_address", "coin_type_hash"],
+        columns: &["coin_type", "amount"],
+        renamed_columns: &[("last_transaction_timestamp", "transaction_timestamp")],
+        order_columns: &[],
+    };
+
+    #[test]
+    fn test_consistency_query() {
+        assert_eq!(
+            consistency_query(&CURRENT_COIN_BALANCES, None),
+            "WITH latest AS (SELECT DISTINCT ON (owner_address, coin_type_hash) \
+             owner_address AS owner_address, coin_type_hash AS coin_type_hash, \
+             coin_type AS coin_type, amount AS amount, \
+             transaction_timestamp AS last_transaction_timestamp, \
+             transaction_version AS last_transaction_version FROM coin_balances \
+             WHERE transaction_version <= $1 \
+             ORDER BY owner_address, coin_type_hash, transaction_version DESC), \
+             current_rows AS (SELECT owner_address, coin_type_hash, coin_type, amount, \
+             last_transaction_timestamp, last_transaction_version FROM current_coin_balances) \
+             SELECT COUNT(*) AS checked, \
+             COUNT(*) FILTER (WHERE c.last_transaction_version IS NULL) AS missing, \
+             COUNT(*) FILTER (WHERE l.last_transaction_version IS NULL) AS extra, \
+             COUNT(*) FILTER (WHERE l.last_transaction_version IS NOT NULL \
+             AND c.last_transaction_version IS NOT NULL \
+             AND (l.owner_address, l.coin_type_hash, l.coin_type, l.amount, \
+             l.last_transaction_timestamp, l.last_transaction_version) IS DISTINCT FROM \
+             (c.owner_address, c.coin_type_hash, c.coin_type, c.amount, \
+             c.last_transaction_timestamp, c.last_transaction_version)) AS mismatched \
+             FROM latest l FULL OUTER JOIN current_rows c \
+             ON l.owner_address = c.owner_address AND l.coin_type_hash = c.coin_type_hash \
+             WHERE c.last_transaction_version IS NULL OR c.last_transaction_version <= $1"
+        );
+    }
+
+    #[test]
+    fn test_sampled_consistency_query() {
+        let query = consistency_query(&CURRENT_COIN_BALANCES, Some(1.5));
+        assert!(query.starts_with(
+            "WITH sampled AS (SELECT DISTINCT owner_address, coin_type_hash FROM coin_balances \
+             TABLESAMPLE SYSTEM (1.5)), latest AS"
+        ));
+        assert!(query.contains(
+            "WHERE transaction_version <= $1 AND (owner_address, coin_type_hash) IN (SELECT * FROM sampled)"
+        ));
+        assert!(query.contains(
+            "FROM current_coin_balances WHERE (owner_address, coin_type_hash) IN (SELECT * FROM sampled))"
+        ));
+    }
+}
@@ -2,48 +2,65 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    config::IndexerGrpcHttp2Config,
-    db::common::models::{ledger_info::LedgerInfo, processor_status::ProcessorStatusQuery},
+    config::{BackfillConfig, IndexerGrpcHttp2Config, TransactionSourceConfig},
+    db::common::models::{
+        backfill_processor_status::BackfillProcessorStatusQuery, ledger_info::LedgerInfo,
+        processor_errors::ProcessorError, processor_status::ProcessorStatusQuery,
+    },
+    errors::PipelineError,
     gap_detectors::{
         create_gap_detector_status_tracker_loop, gap_detector::DefaultGapDetector,
-        parquet_gap_detector::ParquetFileGapDetector, GapDetector, ProcessingResult,
+        parquet_gap_detector::ParquetFileGapDetector, GapDetector, ProcessingResult, StatusTarget,
     },
     grpc_stream::TransactionsPBResponse,
     processors::{
         account_transactions_processor::AccountTransactionsProcessor,
         ans_processor::AnsProcessor,
         coin_processor::CoinProcessor,
+        custom_event_processor::CustomEventProcessor,
         default_processor::DefaultProcessor,
+        dex_processor::DexProcessor,
         events_processor::EventsProcessor,
         fungible_asset_processor::FungibleAssetProcessor,
         monitoring_processor::MonitoringProcessor,
+        nft_marketplace_processor::NftMarketplaceProcessor,
         nft_metadata_processor::NftMetadataProcessor,
         objects_processor::ObjectsProcessor,
         parquet_processors::{
+            parquet_ans_processor::ParquetAnsProcessor,
             parquet_default_processor::ParquetDefaultProcessor,
             parquet_fungible_asset_processor::ParquetFungibleAssetProcessor,
+            parquet_objects_processor::ParquetObjectsProcessor,
+            parquet_stake_processor::ParquetStakeProcessor,
+            parquet_token_v2_processor::ParquetTokenV2Processor,
         },
+        resource_tracking_processor::ResourceTrackingProcessor,
         stake_processor::StakeProcessor,
         token_processor::TokenProcessor,
         token_v2_processor::TokenV2Processor,
         transaction_metadata_processor::TransactionMetadataProcessor,
         user_transaction_processor::UserTransactionProcessor,
+        webhook_processor::WebhookProcessor,
         DefaultProcessingResult, Processor, ProcessorConfig, ProcessorTrait,
     },
-    schema::ledger_infos,
+    schema::{ledger_infos, processor_errors},
+    sharded_backfill::{keep_shard_leased, ShardLease},
+    sinks::{build_sinks, Sink},
     transaction_filter::TransactionFilter,
     utils::{
         counters::{
             ProcessorStep, GRPC_LATENCY_BY_PROCESSOR_IN_SECS, LATEST_PROCESSED_VERSION,
             NUM_TRANSACTIONS_PROCESSED_COUNT, PB_CHANNEL_FETCH_WAIT_TIME_SECS,
-            PROCESSED_BYTES_COUNT, PROCESSOR_DATA_PROCESSED_LATENCY_IN_SECS,
-            PROCESSOR_DATA_RECEIVED_LATENCY_IN_SECS, PROCESSOR_ERRORS_COUNT,
-            PROCESSOR_INVOCATIONS_COUNT, PROCESSOR_SUCCESSES_COUNT,
-            SINGLE_BATCH_DB_INSERTION_TIME_IN_SECS, SINGLE_BATCH_PARSING_TIME_IN_SECS,
-            SINGLE_BATCH_PROCESSING_TIME_IN_SECS, TRANSACTION_UNIX_TIMESTAMP,
+            PIPELINE_RESTART_COUNT, PROCESSED_BYTES_COUNT,
+            PROCESSOR_DATA_PROCESSED_LATENCY_IN_SECS, PROCESSOR_DATA_RECEIVED_LATENCY_IN_SECS,
+            PROCESSOR_DEAD_LETTER_COUNT, PROCESSOR_ERRORS_COUNT, PROCESSOR_INVOCATIONS_COUNT,
+            PROCESSOR_SUCCESSES_COUNT, SINGLE_BATCH_DB_INSERTION_TIME_IN_SECS,
+            SINGLE_BATCH_PARSING_TIME_IN_SECS, SINGLE_BATCH_PROCESSING_TIME_IN_SECS,
+            TRANSACTION_UNIX_TIMESTAMP,
         },
         database::{
-            execute_with_better_error_conn, new_db_pool, run_pending_migrations, ArcDbPool,
+            execute_with_better_error, execute_with_better_error_conn, new_db_pool,
+            run_pending_migrations, ArcDbPool,
         },
         util::{time_diff_since_pb_timestamp_in_secs, timestamp_to_iso, timestamp_to_unixtime},
     },
@@ -51,11 +68,22 @@ use crate::{
 use ahash::AHashMap;
 use anyhow::{Context, Result};
 use aptos_moving_average::MovingAverage;
+use aptos_protos::transaction::v1::Transaction;
 use bitflags::bitflags;
+use futures::FutureExt;
 use kanal::AsyncSender;
-use std::collections::HashSet;
+use std::{
+    any::Any,
+    collections::HashSet,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use tokio::task::JoinHandle;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 use url::Url;
 
 // this is how large the fetch queue should be. Each bucket should have a max of 80MB or so, so a batch
@@ -64,6 +92,7 @@ use url::Url;
 
 pub const BUFFER_SIZE: usize = 300;
 pub const PROCESSOR_SERVICE_TYPE: &str = "processor";
+pub const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
 
 bitflags! {
     #[derive(Debug, Clone, Copy)]
@@ -104,9 +133,92 @@ bitflags! {
 
         // User transaction
         const SIGNATURES = 1 << 23;
+
+        // Token_v2 processor flags
+        const CURRENT_COLLECTIONS_V2 = 1 << 24;
+        const CURRENT_TOKEN_DATAS_V2 = 1 << 25;
+        const CURRENT_TOKEN_OWNERSHIPS_V2 = 1 << 26;
+        const TOKEN_ACTIVITIES_V2 = 1 << 27;
+        const CURRENT_TOKEN_ROYALTY_V1 = 1 << 28;
+        const CURRENT_TOKEN_PENDING_CLAIMS = 1 << 29;
+
+        // Default
+        const BLOCK_METADATA_TRANSACTIONS = 1 << 30;
+        const CURRENT_TABLE_ITEMS = 1 << 31;
+
+        // Fungible asset
+        const FUNGIBLE_ASSET_ACTIVITIES = 1 << 32;
+        const FUNGIBLE_ASSET_METADATA = 1 << 33;
+        const CURRENT_UNIFIED_FUNGIBLE_ASSET_BALANCES = 1 << 34;
+
+        // Objects
+        const CURRENT_OBJECTS = 1 << 35;
+
+        // Ans
+        const ANS_LOOKUP_V2 = 1 << 36;
+        const CURRENT_ANS_LOOKUP_V2 = 1 << 37;
+        const CURRENT_ANS_PRIMARY_NAME_V2 = 1 << 38;
+
+        // User transaction
+        const USER_TRANSACTIONS = 1 << 39;
+
+        // Fungible asset
+        const DAILY_FUNGIBLE_ASSET_BALANCES = 1 << 40;
     }
 }
 
+impl TableFlags {
+    /// Tables that `deprecated_tables` turns off. The other flags are only skipped by a backfill,
+    /// so that names which `deprecated_tables` used to ignore don't start dropping writes.
+    pub const DEPRECATABLE: Self = Self::TRANSACTIONS
+        .union(Self::WRITE_SET_CHANGES)
+        .union(Self::MOVE_RESOURCES)
+        .union(Self::TABLE_ITEMS)
+        .union(Self::TABLE_METADATAS)
+        .union(Self::MOVE_MODULES)
+        .union(Self::FUNGIBLE_ASSET_BALANCES)
+        .union(Self::CURRENT_FUNGIBLE_ASSET_BALANCES)
+        .union(Self::OBJECTS)
+        .union(Self::CURRENT_ANS_LOOKUP)
+        .union(Self::CURRENT_ANS_PRIMARY_NAME)
+        .union(Self::ANS_PRIMARY_NAME_V2)
+        .union(Self::ANS_LOOKUP)
+        .union(Self::ANS_PRIMARY_NAME)
+        .union(Self::TOKEN_OWNERSHIPS_V2)
+        .union(Self::TOKEN_DATAS_V2)
+        .union(Self::COLLECTIONS_V2)
+        .union(Self::CURRENT_TOKEN_V2_METADATA)
+        .union(Self::SIGNATURES)
+        .union(Self::DAILY_FUNGIBLE_ASSET_BALANCES);
+
+    /// Flags for the given table names. Unknown names are ignored.
+    pub fn from_names(names: &HashSet<String>) -> Self {
+        names
+            .iter()
+            .filter_map(|name| TableFlags::from_name(name))
+            .fold(TableFlags::empty(), |flags, flag| flags | flag)
+    }
+}
+
+/// How the worker retries failed parts of the pipeline before giving up.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// Failures in a row, without any progress in between, before the worker exits
+    pub max_consecutive_failures: u32,
+    /// Backoff after the first failure, doubled for every consecutive failure
+    pub initial_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn backoff(&self, consecutive_failures: u32) -> Duration {
+        let multiplier = 2u32.saturating_pow(consecutive_failures.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(multiplier)
+            .min(MAX_RETRY_BACKOFF)
+    }
+}
+
+#[derive(Clone)]
 pub struct Worker {
     pub db_pool: ArcDbPool,
     pub processor_config: ProcessorConfig,
@@ -127,6 +239,15 @@ pub struct Worker {
     pub grpc_response_item_timeout_in_secs: u64,
     pub deprecated_tables: TableFlags,
     pub sleep_time_between_request: u64,
+    pub transaction_source: TransactionSourceConfig,
+    pub additional_processor_configs: Vec<ProcessorConfig>,
+    pub retry_policy: RetryPolicy,
+    pub backfill: Option<BackfillConfig>,
+    /// Set by `ShardedBackfill` on the copy of the worker that processes a leased shard
+    pub shard: Option<ShardLease>,
+    pub enable_dead_letter: bool,
+    /// The sinks of each processor by name, shared by all of its processing tasks
+    pub sinks: AHashMap<&'static str, Arc<Vec<Sink>>>,
 }
 
 impl Worker {
@@ -151,10 +272,25 @@ impl Worker {
         grpc_response_item_timeout_in_secs: u64,
         deprecated_tables: HashSet<String>,
         sleep_time_between_request: u64,
+        transaction_source: TransactionSourceConfig,
+        additional_processor_configs: Vec<ProcessorConfig>,
+        retry_policy: RetryPolicy,
+        backfill: Option<BackfillConfig>,
+        enable_dead_letter: bool,
     ) -> Result<Self> {
         let processor_name = processor_config.name();
         info!(processor_name = processor_name, "[Parser] Kicking off");
 
+        // The processor name is the key of its status row, so each processor can only run once
+        let mut processor_names = HashSet::from([processor_name]);
+        for config in additional_processor_configs.iter() {
+            anyhow::ensure!(
+                processor_names.insert(config.name()),
+                "[Parser] Processor {} is configured more than once",
+                config.name()
+            );
+        }
+
         info!(
             processor_name = processor_name,
             service_type = PROCESSOR_SERVICE_TYPE,
@@ -170,13 +306,90 @@ impl Worker {
         );
         let number_concurrent_processing_tasks = number_concurrent_processing_tasks.unwrap_or(10);
 
-        let mut deprecated_tables_flags = TableFlags::empty();
-        for table in deprecated_tables.iter() {
-            if let Some(flags) = TableFlags::from_name(table) {
-                deprecated_tables_flags |= flags;
+        let configured_tables_flags = TableFlags::from_names(&deprecated_tables);
+        let backfill_only_flags = configured_tables_flags.difference(TableFlags::DEPRECATABLE);
+        if !backfill_only_flags.is_empty() {
+            warn!(
+                processor_name = processor_name,
+                service_type = PROCESSOR_SERVICE_TYPE,
+                tables = ?backfill_only_flags,
+                "[Parser] Ignoring deprecated tables that can only be skipped by a backfill"
+            );
+        }
+        let mut deprecated_tables_flags =
+            configured_tables_flags.intersection(TableFlags::DEPRECATABLE);
+
+        // Failed transactions are isolated by processing them one at a time, which doesn't fit
+        // the buffered uploads of parquet processors
+        if enable_dead_letter {
+            for config in std::iter::once(&processor_config).chain(&additional_processor_configs) {
+                anyhow::ensure!(
+                    !config.is_parquet_processor(),
+                    "[Parser] Dead-letter mode isn't supported by {}",
+                    config.name()
+                );
             }
         }
 
+        // Stream sinks publish rows of a key in version order, and the dex processor adds to what
+        // earlier batches wrote, so both only work if batches finish in order
+        for config in std::iter::once(&processor_config).chain(&additional_processor_configs) {
+            anyhow::ensure!(
+                !config.requires_ordered_batches() || number_concurrent_processing_tasks == 1,
+                "[Parser] {} writes batches in version order, which requires number_concurrent_processing_tasks: 1",
+                config.name()
+            );
+        }
+
+        // E.g. custom event tables are named in the config and created before anything is
+        // processed
+        for config in std::iter::once(&processor_config).chain(&additional_processor_configs) {
+            config
+                .validate()
+                .with_context(|| format!("[Parser] Invalid {} config", config.name()))?;
+        }
+
+        // A backfill runs a single processor over its own version range, writing only the
+        // requested tables on top of the ones that are deprecated anyway
+        let mut ending_version = ending_version;
+        if let Some(backfill) = &backfill {
+            anyhow::ensure!(
+                additional_processor_configs.is_empty(),
+                "[Parser] A backfill can't run additional processors"
+            );
+            anyhow::ensure!(
+                starting_version.is_none() && ending_version.is_none(),
+                "[Parser] A backfill takes its version range from the backfill config, unset starting_version and ending_version"
+            );
+            deprecated_tables_flags |= backfill_skipped_tables(&processor_config, backfill)?;
+            ending_version = Some(backfill.end_version);
+            info!(
+                processor_name = processor_name,
+                service_type = PROCESSOR_SERVICE_TYPE,
+                backfill_alias = backfill.backfill_alias.as_str(),
+                start_version = backfill.start_version,
+                end_version = backfill.end_version,
+                "[Parser] Running in backfill mode"
+            );
+        }
+
+        // Built once, so that concurrent batches write through the same sinks, e.g. the same
+        // write lock of a json lines sink
+        let sinks = std::iter::once(&processor_config)
+            .chain(&additional_processor_configs)
+            .map(|config| {
+                let sinks = build_sinks(
+                    config.sinks(),
+                    conn_pool.clone(),
+                    per_table_chunk_sizes.clone(),
+                )
+                .with_context(|| {
+                    format!("[Parser] Failed to create the sinks of {}", config.name())
+                })?;
+                Ok((config.name(), Arc::new(sinks)))
+            })
+            .collect::<Result<_>>()?;
+
         Ok(Self {
             db_pool: conn_pool,
             processor_config,
@@ -197,6 +410,13 @@ impl Worker {
             grpc_response_item_timeout_in_secs,
             deprecated_tables: deprecated_tables_flags,
             sleep_time_between_request,
+            transaction_source,
+            additional_processor_configs,
+            retry_policy,
+            backfill,
+            shard: None,
+            enable_dead_letter,
+            sinks,
         })
     }
 
@@ -206,63 +426,86 @@ impl Worker {
     /// 3. Start a loop to consume from the buffer. We will have Y threads to process the transactions in parallel. (Y should be less than X for obvious reasons)
     ///   * Note that the batches will be sequential so we won't have problems with gaps
     /// 4. We will keep track of the last processed version and monitoring things like TPS
-    pub async fn run(&mut self) {
+    /// 5. On SIGTERM/SIGINT we stop fetching, drain what was already fetched and write a final processor status
+    pub async fn run(&mut self) -> Result<()> {
         let processor_name = self.processor_config.name();
-        info!(
-            processor_name = processor_name,
-            service_type = PROCESSOR_SERVICE_TYPE,
-            "[Parser] Running migrations"
-        );
-        let migration_time = std::time::Instant::now();
-        self.run_migrations().await;
-        info!(
-            processor_name = processor_name,
-            service_type = PROCESSOR_SERVICE_TYPE,
-            duration_in_secs = migration_time.elapsed().as_secs_f64(),
-            "[Parser] Finished migrations"
-        );
+        // Shards run concurrently, so the coordinator prepares the database once up front
+        if self.shard.is_none() {
+            self.prepare_database().await?;
+        }
 
-        let starting_version_from_db = self
-            .get_start_version()
-            .await
-            .expect("[Parser] Database error when getting starting version")
-            .unwrap_or_else(|| {
-                info!(
-                    processor_name = processor_name,
-                    service_type = PROCESSOR_SERVICE_TYPE,
-                    "[Parser] No starting version from db so starting from version 0"
-                );
-                0
-            });
+        // A backfill resumes from its own status row, which overrides anything in processor_status
+        if let Some(backfill) = self.backfill.clone() {
+            match self.get_backfill_start_version(&backfill).await? {
+                Some(starting_version) => self.starting_version = Some(starting_version),
+                None => {
+                    info!(
+                        processor_name = processor_name,
+                        service_type = PROCESSOR_SERVICE_TYPE,
+                        backfill_alias = backfill.backfill_alias.as_str(),
+                        "[Parser] Backfill is already complete, nothing to do"
+                    );
+                    return Ok(());
+                },
+            }
+        }
 
-        let starting_version = self.starting_version.unwrap_or(starting_version_from_db);
+        // Every processor keeps its own status row, so each one resumes from its own version.
+        // The stream starts from the lowest of these and batches are trimmed per processor.
+        let processor_configs = self.processor_configs();
+        let mut processor_starting_versions = Vec::with_capacity(processor_configs.len());
+        for processor_config in &processor_configs {
+            let processor_name = processor_config.name();
+            let starting_version_from_db = self
+                .get_start_version(processor_name)
+                .await
+                .expect("[Parser] Database error when getting starting version")
+                .unwrap_or_else(|| {
+                    info!(
+                        processor_name = processor_name,
+                        service_type = PROCESSOR_SERVICE_TYPE,
+                        "[Parser] No starting version from db so starting from version 0"
+                    );
+                    0
+                });
+
+            let starting_version = self.starting_version.unwrap_or(starting_version_from_db);
 
-        info!(
-            processor_name = processor_name,
-            service_type = PROCESSOR_SERVICE_TYPE,
-            stream_address = self.indexer_grpc_data_service_address.to_string(),
-            final_start_version = starting_version,
-            start_version_from_config = self.starting_version,
-            start_version_from_db = starting_version_from_db,
-            "[Parser] Building processor",
-        );
+            info!(
+                processor_name = processor_name,
+                service_type = PROCESSOR_SERVICE_TYPE,
+                stream_address = self.indexer_grpc_data_service_address.to_string(),
+                final_start_version = starting_version,
+                start_version_from_config = self.starting_version,
+                start_version_from_db = starting_version_from_db,
+                "[Parser] Building processor",
+            );
+            processor_starting_versions.push(starting_version);
+        }
+        let starting_version = processor_starting_versions
+            .iter()
+            .copied()
+            .min()
+            .expect("[Parser] At least one processor must be configured");
 
         let concurrent_tasks = self.number_concurrent_processing_tasks;
 
         // get the chain id
-        let chain_id = crate::grpc_stream::get_chain_id(
-            self.indexer_grpc_data_service_address.clone(),
-            self.grpc_http2_config.grpc_http2_ping_interval_in_secs(),
-            self.grpc_http2_config.grpc_http2_ping_timeout_in_secs(),
-            self.grpc_http2_config.grpc_connection_timeout_secs(),
-            self.auth_token.clone(),
-            processor_name.to_string(),
-            starting_version_from_db,
-        )
-        .await;
-        self.check_or_update_chain_id(chain_id as i64)
+        let chain_id = match &self.transaction_source {
+            TransactionSourceConfig::Grpc => crate::grpc_stream::get_chain_id(
+                self.indexer_grpc_data_service_address.clone(),
+                self.grpc_http2_config.grpc_http2_ping_interval_in_secs(),
+                self.grpc_http2_config.grpc_http2_ping_timeout_in_secs(),
+                self.grpc_http2_config.grpc_connection_timeout_secs(),
+                self.auth_token.clone(),
+                processor_name.to_string(),
+                starting_version,
+            )
             .await
-            .unwrap();
+            .context("[Parser] Failed to get chain id")?,
+            TransactionSourceConfig::LocalFile(config) => config.chain_id,
+        };
+        self.check_or_update_chain_id(chain_id as i64).await?;
 
         self.grpc_chain_id = Some(chain_id);
 
@@ -286,7 +529,9 @@ impl Worker {
         let grpc_response_item_timeout =
             std::time::Duration::from_secs(self.grpc_response_item_timeout_in_secs);
         let sleep_time_between_request = self.sleep_time_between_request;
-        let fetcher_task = tokio::spawn(async move {
+        let transaction_source = self.transaction_source.clone();
+        let retry_policy = self.retry_policy;
+        let mut fetcher_task = tokio::spawn(async move {
             info!(
                 processor_name = processor_name,
                 service_type = PROCESSOR_SERVICE_TYPE,
@@ -295,129 +540,322 @@ impl Worker {
                 "[Parser] Starting fetcher thread"
             );
 
-            crate::grpc_stream::create_fetcher_loop(
-                tx.clone(),
-                indexer_grpc_data_service_address.clone(),
-                indexer_grpc_http2_ping_interval,
-                indexer_grpc_http2_ping_timeout,
-                indexer_grpc_reconnection_timeout_secs,
-                grpc_response_item_timeout,
-                starting_version,
-                request_ending_version,
-                auth_token.clone(),
-                processor_name.to_string(),
-                transaction_filter,
-                pb_channel_txn_chunk_size,
-                sleep_time_between_request,
-            )
-            .await
+            // The fetcher advances this after every batch it sends, so a restart picks up from there
+            let next_version_to_send = AtomicU64::new(starting_version);
+            let mut consecutive_failures = 0;
+            loop {
+                let attempt_starting_version = next_version_to_send.load(Ordering::SeqCst);
+                let res = match &transaction_source {
+                    TransactionSourceConfig::Grpc => {
+                        crate::grpc_stream::create_fetcher_loop(
+                            tx.clone(),
+                            indexer_grpc_data_service_address.clone(),
+                            indexer_grpc_http2_ping_interval,
+                            indexer_grpc_http2_ping_timeout,
+                            indexer_grpc_reconnection_timeout_secs,
+                            grpc_response_item_timeout,
+                            attempt_starting_version,
+                            request_ending_version,
+                            auth_token.clone(),
+                            processor_name.to_string(),
+                            transaction_filter.clone(),
+                            pb_channel_txn_chunk_size,
+                            sleep_time_between_request,
+                            &next_version_to_send,
+                        )
+                        .await
+                    },
+                    TransactionSourceConfig::LocalFile(config) => {
+                        crate::file_stream::create_file_fetcher_loop(
+                            tx.clone(),
+                            config.clone(),
+                            attempt_starting_version,
+                            request_ending_version,
+                            processor_name.to_string(),
+                            transaction_filter.clone(),
+                            pb_channel_txn_chunk_size,
+                            &next_version_to_send,
+                        )
+                        .await
+                    },
+                };
+                let Err(e) = res else {
+                    return Ok(());
+                };
+
+                // Only consecutive failures without any progress count against the budget
+                if next_version_to_send.load(Ordering::SeqCst) > attempt_starting_version {
+                    consecutive_failures = 0;
+                }
+                consecutive_failures += 1;
+                if !e.is_retryable() || consecutive_failures > retry_policy.max_consecutive_failures
+                {
+                    error!(
+                        processor_name = processor_name,
+                        service_type = PROCESSOR_SERVICE_TYPE,
+                        consecutive_failures,
+                        error = ?e,
+                        "[Parser] Fetcher failed, giving up"
+                    );
+                    return Err(e);
+                }
+                let backoff = retry_policy.backoff(consecutive_failures);
+                warn!(
+                    processor_name = processor_name,
+                    service_type = PROCESSOR_SERVICE_TYPE,
+                    consecutive_failures,
+                    backoff_in_secs = backoff.as_secs_f64(),
+                    next_version_to_send = next_version_to_send.load(Ordering::SeqCst),
+                    error = ?e,
+                    "[Parser] Fetcher failed, restarting"
+                );
+                PIPELINE_RESTART_COUNT
+                    .with_label_values(&[processor_name, "fetcher"])
+                    .inc();
+                tokio::time::sleep(backoff).await;
+            }
         });
-
-        // Create a gap detector task that will panic if there is a gap in the processing
-        let (gap_detector_sender, gap_detector_receiver) =
-            kanal::bounded_async::<ProcessingResult>(BUFFER_SIZE);
-
-        let is_parquet_processor = self.processor_config.is_parquet_processor();
-        let (maybe_gap_detector_sender, gap_detection_batch_size) = if is_parquet_processor {
-            let gap_detection_batch_size: u64 = self.parquet_gap_detection_batch_size;
-            (Some(gap_detector_sender.clone()), gap_detection_batch_size)
+        let mut processor_tasks = vec![];
+        let mut gap_detector_tasks = vec![];
+
+        // With a single processor the tasks consume the fetcher channel directly. Otherwise a
+        // fan out task copies every batch into a channel per processor. These channels are kept
+        // small so that the slowest processor applies backpressure to the shared stream.
+        let (processor_receivers, fan_out_task) = if processor_configs.len() == 1 {
+            (vec![receiver], None)
         } else {
-            let gap_detection_batch_size = self.gap_detection_batch_size;
-            (None, gap_detection_batch_size)
+            let (senders, receivers): (Vec<_>, Vec<_>) = processor_configs
+                .iter()
+                .map(|_| kanal::bounded_async::<TransactionsPBResponse>(concurrent_tasks))
+                .unzip();
+            let processor_senders = processor_configs
+                .iter()
+                .zip(processor_starting_versions.iter().copied())
+                .zip(senders)
+                .map(|((config, starting_version), sender)| {
+                    (config.name(), starting_version, sender)
+                })
+                .collect();
+            // This exits on its own once the fetcher channel closes, which in turn lets the
+            // processor tasks we wait on below finish
+            let fan_out_task = tokio::spawn(fan_out_transactions(receiver, processor_senders));
+            (receivers, Some(fan_out_task))
         };
 
-        let processor = build_processor(
-            &self.processor_config,
-            self.per_table_chunk_sizes.clone(),
-            self.deprecated_tables,
-            self.db_pool.clone(),
-            maybe_gap_detector_sender,
-        );
+        // Closed to stop the processor tasks early, e.g. when the shard's lease is lost
+        let receivers_to_close = processor_receivers.clone();
 
-        let gap_detector = if is_parquet_processor {
-            GapDetector::ParquetFileGapDetector(ParquetFileGapDetector::new(starting_version))
-        } else {
-            GapDetector::DefaultGapDetector(DefaultGapDetector::new(starting_version))
-        };
-
-        tokio::spawn(async move {
-            create_gap_detector_status_tracker_loop(
+        for ((processor_config, starting_version), receiver) in processor_configs
+            .iter()
+            .zip(processor_starting_versions)
+            .zip(processor_receivers)
+        {
+            let processor_name = processor_config.name();
+
+            // Create a gap detector task that will panic if there is a gap in the processing
+            let (gap_detector_sender, gap_detector_receiver) =
+                kanal::bounded_async::<ProcessingResult>(BUFFER_SIZE);
+
+            let is_parquet_processor = processor_config.is_parquet_processor();
+            let gap_detection_batch_size = if is_parquet_processor {
+                self.parquet_gap_detection_batch_size
+            } else {
+                self.gap_detection_batch_size
+            };
+
+            let gap_detector = if is_parquet_processor {
+                GapDetector::ParquetFileGapDetector(ParquetFileGapDetector::new(starting_version))
+            } else {
+                GapDetector::DefaultGapDetector(DefaultGapDetector::new(starting_version))
+            };
+
+            gap_detector_tasks.push(tokio::spawn(create_gap_detector_status_tracker_loop(
                 gap_detector,
                 gap_detector_receiver,
-                processor,
+                processor_name,
+                self.db_pool.clone(),
                 gap_detection_batch_size,
-            )
-            .await;
+                self.status_target(),
+            )));
+
+            // This is the consumer side of the channel. These are the major states:
+            // 1. We're backfilling so we should expect many concurrent threads to process transactions
+            // 2. We're caught up so we should expect a single thread to process transactions
+            // 3. We have received either an empty batch or a batch with a gap. We should panic.
+            // 4. We have not received anything in X seconds, we should panic.
+            // 5. If it's the wrong chain, panic.
+
+            info!(
+                processor_name = processor_name,
+                service_type = PROCESSOR_SERVICE_TYPE,
+                stream_address = self.indexer_grpc_data_service_address.as_str(),
+                concurrent_tasks,
+                "[Parser] Spawning concurrent parallel processor tasks",
+            );
+
+            for task_index in 0..concurrent_tasks {
+                let join_handle: JoinHandle<Result<(), PipelineError>> = self
+                    .launch_processor_task(
+                        processor_config,
+                        task_index,
+                        receiver.clone(),
+                        gap_detector_sender.clone(),
+                    )
+                    .await;
+                processor_tasks.push(join_handle);
+            }
+
+            info!(
+                processor_name = processor_name,
+                service_type = PROCESSOR_SERVICE_TYPE,
+                stream_address = self.indexer_grpc_data_service_address.as_str(),
+                concurrent_tasks,
+                "[Parser] Processor tasks spawned",
+            );
+        }
+
+        // A shard's lease is renewed on its own schedule, so slow batches don't lose it
+        let mut lease_task = self.shard.clone().map(|lease| {
+            tokio::spawn(keep_shard_leased(
+                self.db_pool.clone(),
+                processor_name,
+                lease,
+            ))
         });
+        let lease_lost = async {
+            match lease_task.as_mut() {
+                Some(task) => task.await.expect("[Backfill] Lease renewal task has died"),
+                None => std::future::pending().await,
+            }
+        };
+
+        // Run until the fetcher reaches the ending version, fails for good, we're asked to shut
+        // down or the shard's lease is lost. Either way the fetcher channel then closes, and the
+        // processor tasks drain the batches that were already fetched before exiting.
+        let mut lease_result = Ok(());
+        let fetcher_result = tokio::select! {
+            res = &mut fetcher_task => {
+                res.expect("[Processor] Fetcher task has died")
+            },
+            _ = shutdown_signal() => {
+                info!(
+                    processor_name = processor_name,
+                    service_type = PROCESSOR_SERVICE_TYPE,
+                    "[Parser] Received shutdown signal, stopping fetcher and draining in-flight batches",
+                );
+                fetcher_task.abort();
+                Ok(())
+            },
+            e = lease_lost => {
+                error!(
+                    processor_name = processor_name,
+                    service_type = PROCESSOR_SERVICE_TYPE,
+                    error = ?e,
+                    "[Parser] Lost the shard's lease, stopping without processing fetched batches",
+                );
+                fetcher_task.abort();
+                // Another worker owns the shard now, so batches that weren't started are dropped
+                for receiver in &receivers_to_close {
+                    receiver.close();
+                }
+                lease_result = Err(e);
+                Ok(())
+            },
+        };
 
-        // This is the consumer side of the channel. These are the major states:
-        // 1. We're backfilling so we should expect many concurrent threads to process transactions
-        // 2. We're caught up so we should expect a single thread to process transactions
-        // 3. We have received either an empty batch or a batch with a gap. We should panic.
-        // 4. We have not received anything in X seconds, we should panic.
-        // 5. If it's the wrong chain, panic.
+        let processor_results = futures::future::try_join_all(processor_tasks)
+            .await
+            .expect("[Processor] Processor tasks have died");
 
-        info!(
-            processor_name = processor_name,
-            service_type = PROCESSOR_SERVICE_TYPE,
-            stream_address = self.indexer_grpc_data_service_address.as_str(),
-            concurrent_tasks,
-            "[Parser] Spawning concurrent parallel processor tasks",
-        );
+        // The processor channels are closed by now, so the fan out task has returned or is about to
+        let fan_out_result = match fan_out_task {
+            Some(fan_out_task) => fan_out_task
+                .await
+                .expect("[Processor] Fan out task has died"),
+            None => Ok(()),
+        };
 
-        let mut processor_tasks = vec![fetcher_task];
-        for task_index in 0..concurrent_tasks {
-            let join_handle: JoinHandle<()> = self
-                .launch_processor_task(task_index, receiver.clone(), gap_detector_sender.clone())
-                .await;
-            processor_tasks.push(join_handle);
+        // With the processor tasks gone the gap detector channels close once any parquet handlers
+        // have flushed, and each gap detector writes its final processor status.
+        let gap_detector_results = futures::future::try_join_all(gap_detector_tasks)
+            .await
+            .expect("[Processor] Gap detector tasks have died");
+
+        if let Some(lease_task) = lease_task {
+            lease_task.abort();
         }
 
+        // A processor or gap detector failure closes the channels, so the fan out and fetcher
+        // errors that follow are only symptoms. A gap detector failure, e.g. a lost lease, also
+        // closes the processors' channel. Report the root cause first.
+        std::iter::once(lease_result)
+            .chain(gap_detector_results)
+            .chain(processor_results)
+            .chain(std::iter::once(fan_out_result))
+            .chain(std::iter::once(fetcher_result))
+            .collect::<Result<Vec<_>, _>>()
+            .context("[Parser] Pipeline failed")?;
+
         info!(
             processor_name = processor_name,
             service_type = PROCESSOR_SERVICE_TYPE,
-            stream_address = self.indexer_grpc_data_service_address.as_str(),
-            concurrent_tasks,
-            "[Parser] Processor tasks spawned",
+            "[Parser] All batches drained, worker exiting",
         );
+        Ok(())
+    }
 
-        // Await the processor tasks: this is forever
-        futures::future::try_join_all(processor_tasks)
-            .await
-            .expect("[Processor] Processor tasks have died");
+    fn status_target(&self) -> StatusTarget {
+        match (&self.shard, &self.backfill) {
+            (Some(lease), _) => StatusTarget::Shard(lease.clone()),
+            (None, Some(backfill)) => StatusTarget::Backfill(backfill.clone()),
+            (None, None) => StatusTarget::ProcessorStatus,
+        }
+    }
+
+    /// All processors run by this worker, starting with the main `processor_config`.
+    pub fn processor_configs(&self) -> Vec<ProcessorConfig> {
+        std::iter::once(self.processor_config.clone())
+            .chain(self.additional_processor_configs.iter().cloned())
+            .collect()
     }
 
     async fn launch_processor_task(
         &self,
+        processor_config: &ProcessorConfig,
         task_index: usize,
         receiver: kanal::AsyncReceiver<TransactionsPBResponse>,
         gap_detector_sender: AsyncSender<ProcessingResult>,
-    ) -> JoinHandle<()> {
-        let processor_name = self.processor_config.name();
+    ) -> JoinHandle<Result<(), PipelineError>> {
+        let processor_name = processor_config.name();
         let stream_address = self.indexer_grpc_data_service_address.to_string();
         let receiver_clone = receiver.clone();
         let auth_token = self.auth_token.clone();
 
         // Build the processor based on the config.
-        let processor = if self.processor_config.is_parquet_processor() {
+        let sinks = self.sinks[processor_name].clone();
+        let processor = if processor_config.is_parquet_processor() {
             build_processor(
-                &self.processor_config,
+                processor_config,
                 self.per_table_chunk_sizes.clone(),
                 self.deprecated_tables,
                 self.db_pool.clone(),
+                sinks,
                 Some(gap_detector_sender.clone()),
             )
         } else {
             build_processor(
-                &self.processor_config,
+                processor_config,
                 self.per_table_chunk_sizes.clone(),
                 self.deprecated_tables,
                 self.db_pool.clone(),
+                sinks,
                 None,
             )
         };
 
         let concurrent_tasks = self.number_concurrent_processing_tasks;
+        let retry_policy = self.retry_policy;
+        let enable_dead_letter = self.enable_dead_letter;
 
         let chain_id = self
             .grpc_chain_id
@@ -501,45 +939,63 @@ impl Worker {
                                 "[Parser][T#{}] Stream somehow changed chain id!",
                                 task_index
                             );
-                            break;
+                            receiver_clone.close();
+                            return Err(PipelineError::ChainIdMismatch {
+                                expected: chain_id,
+                                received: transactions_pb.chain_id,
+                            });
                         }
 
                         let processing_time = std::time::Instant::now();
 
-                        let res = do_processor(
-                            transactions_pb,
-                            &processor,
-                            chain_id,
-                            processor_name,
-                            &auth_token,
-                            false, // enable_verbose_logging
-                        )
-                        .await;
-
-                        let processing_result = match res {
-                            Ok(versions) => {
-                                PROCESSOR_SUCCESSES_COUNT
-                                    .with_label_values(&[processor_name])
-                                    .inc();
-                                versions
-                            },
-                            Err(e) => {
-                                error!(
-                                    processor_name = processor_name,
-                                    stream_address = stream_address.as_str(),
-                                    error = ?e,
-                                    task_index,
-                                    "[Parser][T#{}] Error processing transactions", task_index
-                                );
-                                PROCESSOR_ERRORS_COUNT
-                                    .with_label_values(&[processor_name])
-                                    .inc();
-                                // panic!(
-                                //     "[Parser][T#{}] Error processing '{:}' transactions: {:?}",
-                                //     task_index, processor_name, e
-                                // );
-                                continue;
-                            },
+                        // Skipping a failed batch would leave a gap that stalls the processor
+                        // status, so retry it with backoff until the failure budget runs out.
+                        let mut consecutive_failures = 0;
+                        let processing_result = loop {
+                            let res = do_processor(
+                                &transactions_pb,
+                                &processor,
+                                chain_id,
+                                processor_name,
+                                &auth_token,
+                                false, // enable_verbose_logging
+                                enable_dead_letter,
+                            )
+                            .await;
+                            match res {
+                                Ok(versions) => {
+                                    PROCESSOR_SUCCESSES_COUNT
+                                        .with_label_values(&[processor_name])
+                                        .inc();
+                                    break versions;
+                                },
+                                Err(e) => {
+                                    error!(
+                                        processor_name = processor_name,
+                                        stream_address = stream_address.as_str(),
+                                        error = ?e,
+                                        task_index,
+                                        consecutive_failures,
+                                        "[Parser][T#{}] Error processing transactions", task_index
+                                    );
+                                    PROCESSOR_ERRORS_COUNT
+                                        .with_label_values(&[processor_name])
+                                        .inc();
+                                    consecutive_failures += 1;
+                                    if consecutive_failures > retry_policy.max_consecutive_failures
+                                    {
+                                        // Closing the channel stops the fetcher and the other
+                                        // tasks so that the worker can exit
+                                        receiver_clone.close();
+                                        return Err(PipelineError::ProcessingError(e));
+                                    }
+                                    PIPELINE_RESTART_COUNT
+                                        .with_label_values(&[processor_name, "processor"])
+                                        .inc();
+                                    tokio::time::sleep(retry_policy.backoff(consecutive_failures))
+                                        .await;
+                                },
+                            }
                         };
 
                         match processing_result {
@@ -628,14 +1084,19 @@ impl Worker {
                                     .with_label_values(&[processor_name, &task_index_str])
                                     .set(processing_result.db_insertion_duration_in_secs);
 
-                                gap_detector_sender
+                                if gap_detector_sender
                                     .send(ProcessingResult::DefaultProcessingResult(
                                         processing_result,
                                     ))
                                     .await
-                                    .expect("[Parser] Failed to send versions to gap detector");
+                                    .is_err()
+                                {
+                                    receiver_clone.close();
+                                    return Err(PipelineError::ChannelClosed("Gap detector"));
+                                }
                             },
-                            ProcessingResult::ParquetProcessingResult(_) => {
+                            ProcessingResult::ParquetProcessingResult(_)
+                            | ProcessingResult::ParquetHandlerError(_) => {
                                 debug!("parquet processing result doesn't need to be handled here");
                             },
                         }
@@ -654,14 +1115,43 @@ impl Worker {
                     },
                 }
             }
+            Ok(())
         })
     }
 
+    /// Runs the migrations and creates the tables that processors define in their config, e.g.
+    /// custom event tables.
+    pub async fn prepare_database(&self) -> Result<()> {
+        let processor_name = self.processor_config.name();
+        info!(
+            processor_name = processor_name,
+            service_type = PROCESSOR_SERVICE_TYPE,
+            "[Parser] Running migrations"
+        );
+        let migration_time = std::time::Instant::now();
+        self.run_migrations().await;
+        info!(
+            processor_name = processor_name,
+            service_type = PROCESSOR_SERVICE_TYPE,
+            duration_in_secs = migration_time.elapsed().as_secs_f64(),
+            "[Parser] Finished migrations"
+        );
+        for processor_config in self.processor_configs() {
+            if let ProcessorConfig::CustomEventProcessor(config) = processor_config {
+                config
+                    .create_tables(&self.db_pool)
+                    .await
+                    .context("[Parser] Failed to create custom event tables")?;
+            }
+        }
+        Ok(())
+    }
+
     // For the normal processor build we just use standard Diesel with the postgres
     // feature enabled (which uses libpq under the hood, hence why we named the feature
     // this way).
     #[cfg(feature = "libpq")]
-    async fn run_migrations(&self) {
+    pub async fn run_migrations(&self) {
         use crate::diesel::Connection;
         use diesel::pg::PgConnection;
 
@@ -674,7 +1164,7 @@ impl Worker {
     // If the libpq feature isn't enabled, we use diesel async instead. This is used by
     // the CLI for the local testnet, where we cannot tolerate the libpq dependency.
     #[cfg(not(feature = "libpq"))]
-    async fn run_migrations(&self) {
+    pub async fn run_migrations(&self) {
         use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
 
         info!("Running migrations: {:?}", self.postgres_connection_string);
@@ -698,17 +1188,43 @@ impl Worker {
     }
 
     /// Gets the start version for the processor. If not found, start from 0.
-    pub async fn get_start_version(&self) -> Result<Option<u64>> {
+    pub async fn get_start_version(&self, processor_name: &str) -> Result<Option<u64>> {
         let mut conn = self.db_pool.get().await?;
 
-        match ProcessorStatusQuery::get_by_processor(self.processor_config.name(), &mut conn)
-            .await?
-        {
+        match ProcessorStatusQuery::get_by_processor(processor_name, &mut conn).await? {
             Some(status) => Ok(Some(status.last_success_version as u64 + 1)),
             None => Ok(None),
         }
     }
 
+    /// Gets the next version to process for the backfill, or None if it has already finished.
+    pub async fn get_backfill_start_version(
+        &self,
+        backfill: &BackfillConfig,
+    ) -> Result<Option<u64>> {
+        let mut conn = self.db_pool.get().await?;
+
+        match BackfillProcessorStatusQuery::get_by_alias(&backfill.backfill_alias, &mut conn)
+            .await?
+        {
+            Some(status) => {
+                let processor_name = self.processor_config.name();
+                anyhow::ensure!(
+                    status.processor == processor_name,
+                    "[Parser] Backfill {} belongs to {}, not {}",
+                    backfill.backfill_alias,
+                    status.processor,
+                    processor_name
+                );
+                if status.is_complete() {
+                    return Ok(None);
+                }
+                Ok(Some(status.last_success_version as u64 + 1))
+            },
+            None => Ok(Some(backfill.start_version)),
+        }
+    }
+
     /// Verify the chain id from GRPC against the database.
     pub async fn check_or_update_chain_id(&self, grpc_chain_id: i64) -> Result<u64> {
         let processor_name = self.processor_config.name();
@@ -753,6 +1269,119 @@ impl Worker {
     }
 }
 
+/// Tables to skip during a backfill: everything the processor can skip except the requested tables.
+fn backfill_skipped_tables(
+    processor_config: &ProcessorConfig,
+    backfill: &BackfillConfig,
+) -> Result<TableFlags> {
+    anyhow::ensure!(
+        backfill.start_version <= backfill.end_version,
+        "[Parser] Backfill start version {} is after its end version {}",
+        backfill.start_version,
+        backfill.end_version
+    );
+    anyhow::ensure!(
+        !backfill.tables.is_empty(),
+        "[Parser] A backfill needs at least one table"
+    );
+    let supported_tables = processor_config.table_flags();
+    let mut backfill_tables = TableFlags::empty();
+    for table in backfill.tables.iter() {
+        let flags = TableFlags::from_name(table)
+            .with_context(|| format!("[Parser] Unknown backfill table {}", table))?;
+        anyhow::ensure!(
+            supported_tables.contains(flags),
+            "[Parser] {} can't backfill {} on its own",
+            processor_config.name(),
+            table
+        );
+        backfill_tables |= flags;
+    }
+    Ok(supported_tables.difference(backfill_tables))
+}
+
+/// Resolves once the process receives SIGTERM or SIGINT.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("[Parser] Failed to install SIGTERM handler");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {},
+            _ = sigterm.recv() => {},
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Copies every batch from the shared fetcher channel into the channel of each processor, trimming
+/// the batch to the versions that processor still needs. Exits once the fetcher channel is closed
+/// and drained, which closes the processor channels in turn. Fails if a processor channel closed
+/// early, which only happens when that processor's tasks have exited.
+async fn fan_out_transactions(
+    receiver: kanal::AsyncReceiver<TransactionsPBResponse>,
+    processor_senders: Vec<(&'static str, u64, AsyncSender<TransactionsPBResponse>)>,
+) -> Result<(), PipelineError> {
+    while let Ok(transactions_pb) = receiver.recv().await {
+        for (processor_name, starting_version, sender) in processor_senders.iter() {
+            let Some(batch) = trim_batch_to_start_version(&transactions_pb, *starting_version)
+            else {
+                continue;
+            };
+            if let Err(e) = sender.send(batch).await {
+                error!(
+                    processor_name = processor_name,
+                    service_type = PROCESSOR_SERVICE_TYPE,
+                    error = ?e,
+                    "[Parser] Failed to send transactions to processor channel"
+                );
+                return Err(PipelineError::ChannelClosed("Processor"));
+            }
+        }
+    }
+    info!(
+        service_type = PROCESSOR_SERVICE_TYPE,
+        "[Parser] Fetcher channel closed, fan out task exiting"
+    );
+    Ok(())
+}
+
+/// Returns the part of the batch at or after `starting_version`, or None if the processor has
+/// already processed the whole batch.
+fn trim_batch_to_start_version(
+    transactions_pb: &TransactionsPBResponse,
+    starting_version: u64,
+) -> Option<TransactionsPBResponse> {
+    if transactions_pb.end_version < starting_version {
+        return None;
+    }
+    if transactions_pb.start_version >= starting_version {
+        return Some(transactions_pb.clone());
+    }
+    let transactions: Vec<_> = transactions_pb
+        .transactions
+        .iter()
+        .filter(|txn| txn.version >= starting_version)
+        .cloned()
+        .collect();
+    let start_txn_timestamp = transactions
+        .first()
+        .and_then(|txn| txn.timestamp.clone())
+        .or_else(|| transactions_pb.start_txn_timestamp.clone());
+    Some(TransactionsPBResponse {
+        transactions,
+        chain_id: transactions_pb.chain_id,
+        start_version: starting_version,
+        end_version: transactions_pb.end_version,
+        start_txn_timestamp,
+        end_txn_timestamp: transactions_pb.end_txn_timestamp.clone(),
+        size_in_bytes: transactions_pb.size_in_bytes,
+    })
+}
+
 async fn fetch_transactions(
     processor_name: &str,
     stream_address: &str,
@@ -785,12 +1414,13 @@ async fn fetch_transactions(
 }
 
 pub async fn do_processor(
-    transactions_pb: TransactionsPBResponse,
+    transactions_pb: &TransactionsPBResponse,
     processor: &Processor,
     db_chain_id: u64,
     processor_name: &str,
     auth_token: &str,
     enable_verbose_logging: bool,
+    enable_dead_letter: bool,
 ) -> Result<ProcessingResult> {
     // We use the value passed from the `transactions_pb` as it may have been filtered
     let start_version = transactions_pb.start_version;
@@ -804,12 +1434,12 @@ pub async fn do_processor(
                 end_version,
                 processing_duration_in_secs: 0.0,
                 db_insertion_duration_in_secs: 0.0,
-                last_transaction_timestamp: transactions_pb.end_txn_timestamp,
+                last_transaction_timestamp: transactions_pb.end_txn_timestamp.clone(),
             },
         ));
     }
 
-    let txn_time = transactions_pb.start_txn_timestamp;
+    let txn_time = transactions_pb.start_txn_timestamp.clone();
 
     if let Some(ref t) = txn_time {
         PROCESSOR_DATA_RECEIVED_LATENCY_IN_SECS
@@ -831,14 +1461,25 @@ pub async fn do_processor(
         );
     }
 
-    let processed_result = processor
-        .process_transactions(
+    let processed_result = if enable_dead_letter {
+        process_catching_panics(
+            processor,
             transactions_pb.transactions.clone(),
             start_version,
             end_version,
-            Some(db_chain_id),
+            db_chain_id,
         )
-        .await;
+        .await
+    } else {
+        processor
+            .process_transactions(
+                transactions_pb.transactions.clone(),
+                start_version,
+                end_version,
+                Some(db_chain_id),
+            )
+            .await
+    };
     let processed_result = if let Err(err) = processed_result {
         // If the Tx processing abort because of KKey violation, skip the Tx.
         // As several Tx has been processed replay all the Tx one by one to
@@ -851,7 +1492,7 @@ pub async fn do_processor(
             tracing::warn!("Unique Constraint violation replay the Tx: msg: {msg:?}");
             //replay all Tx one by one
             let mut last_transaction_timestamp = None;
-            for tx in transactions_pb.transactions {
+            for tx in transactions_pb.transactions.iter().cloned() {
                 let timestamp = tx.timestamp.clone();
                 if let Err(err) = processor
                     .process_transactions(vec![tx], start_version, end_version, Some(db_chain_id))
@@ -873,6 +1514,15 @@ pub async fn do_processor(
                     last_transaction_timestamp,
                 },
             ))
+        } else if enable_dead_letter {
+            tracing::warn!(
+                processor_name = processor_name,
+                start_version,
+                end_version,
+                error = ?err,
+                "[Parser] Batch failed, processing it one transaction at a time"
+            );
+            process_with_dead_letter(transactions_pb, processor, db_chain_id, processor_name).await
         } else {
             Err(err)
         }
@@ -889,7 +1539,112 @@ pub async fn do_processor(
     processed_result
 }
 
-/// Given a config and a db pool, build a concrete instance of a processor.
+/// Processes a failed batch one transaction at a time. Transactions that still fail are recorded
+/// in `processor_errors` and skipped. If a failure can't be recorded, e.g. because the database is
+/// down, the error is returned and the batch is retried as usual.
+async fn process_with_dead_letter(
+    transactions_pb: &TransactionsPBResponse,
+    processor: &Processor,
+    db_chain_id: u64,
+    processor_name: &str,
+) -> Result<ProcessingResult> {
+    let mut last_transaction_timestamp = None;
+    for txn in transactions_pb.transactions.iter() {
+        let version = txn.version;
+        if let Err(err) =
+            process_catching_panics(processor, vec![txn.clone()], version, version, db_chain_id)
+                .await
+        {
+            error!(
+                processor_name = processor_name,
+                service_type = PROCESSOR_SERVICE_TYPE,
+                transaction_version = version,
+                error = ?err,
+                "[Parser] Skipping transaction that failed to process"
+            );
+            record_processor_error(processor.get_pool(), processor_name, txn, &err)
+                .await
+                .context("[Parser] Failed to record transaction in processor_errors")?;
+            PROCESSOR_DEAD_LETTER_COUNT
+                .with_label_values(&[processor_name])
+                .inc();
+        }
+        if txn.timestamp.is_some() {
+            last_transaction_timestamp = txn.timestamp.clone();
+        }
+    }
+    Ok(ProcessingResult::DefaultProcessingResult(
+        DefaultProcessingResult {
+            start_version: transactions_pb.start_version,
+            end_version: transactions_pb.end_version,
+            processing_duration_in_secs: 0.0,
+            db_insertion_duration_in_secs: 0.0,
+            last_transaction_timestamp,
+        },
+    ))
+}
+
+/// Many models unwrap on data they don't expect, so a panic is treated like any other failure.
+async fn process_catching_panics(
+    processor: &Processor,
+    transactions: Vec<Transaction>,
+    start_version: u64,
+    end_version: u64,
+    db_chain_id: u64,
+) -> Result<ProcessingResult> {
+    AssertUnwindSafe(processor.process_transactions(
+        transactions,
+        start_version,
+        end_version,
+        Some(db_chain_id),
+    ))
+    .catch_unwind()
+    .await
+    .unwrap_or_else(|panic| {
+        Err(anyhow::anyhow!(
+            "Processor panicked: {}",
+            panic_message(&*panic)
+        ))
+    })
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+async fn record_processor_error(
+    pool: ArcDbPool,
+    processor_name: &str,
+    txn: &Transaction,
+    err: &anyhow::Error,
+) -> Result<()> {
+    let processor_error = ProcessorError {
+        processor: processor_name.to_string(),
+        transaction_version: txn.version as i64,
+        error_message: format!("{:?}", err),
+        raw_transaction: serde_json::to_value(txn)?,
+    };
+    execute_with_better_error(
+        pool,
+        diesel::insert_into(processor_errors::table)
+            .values(&processor_error)
+            .on_conflict((
+                processor_errors::processor,
+                processor_errors::transaction_version,
+            ))
+            .do_nothing(),
+        None,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Given a config and a db pool, build a concrete instance of a processor. `sinks` are the ones
+/// built from `config.sinks()`, and are ignored by processors that don't write through sinks.
 // As time goes on there might be other things that we need to provide to certain
 // processors. As that happens we can revist whether this function (which tends to
 // couple processors together based on their args) makes sense.
@@ -899,6 +1654,7 @@ pub fn build_processor(
     per_table_chunk_sizes: AHashMap<String, usize>,
     deprecated_tables: TableFlags,
     db_pool: ArcDbPool,
+    sinks: Arc<Vec<Sink>>,
     gap_detector_sender: Option<AsyncSender<ProcessingResult>>, // Parquet only
 ) -> Processor {
     match config {
@@ -911,23 +1667,32 @@ pub fn build_processor(
             per_table_chunk_sizes,
             deprecated_tables,
         )),
-        ProcessorConfig::CoinProcessor => {
-            Processor::from(CoinProcessor::new(db_pool, per_table_chunk_sizes))
+        ProcessorConfig::CoinProcessor(_) => Processor::from(CoinProcessor::new(db_pool, sinks)),
+        ProcessorConfig::CustomEventProcessor(config) => {
+            Processor::from(CustomEventProcessor::new(db_pool, config.clone()))
         },
         ProcessorConfig::DefaultProcessor => Processor::from(DefaultProcessor::new(
             db_pool,
             per_table_chunk_sizes,
             deprecated_tables,
         )),
-        ProcessorConfig::EventsProcessor => {
-            Processor::from(EventsProcessor::new(db_pool, per_table_chunk_sizes))
-        },
-        ProcessorConfig::FungibleAssetProcessor => Processor::from(FungibleAssetProcessor::new(
+        ProcessorConfig::DexProcessor(config) => Processor::from(DexProcessor::new(
             db_pool,
+            config.clone(),
             per_table_chunk_sizes,
+        )),
+        ProcessorConfig::EventsProcessor(_) => {
+            Processor::from(EventsProcessor::new(db_pool, sinks))
+        },
+        ProcessorConfig::FungibleAssetProcessor(_) => Processor::from(FungibleAssetProcessor::new(
+            db_pool,
+            sinks,
             deprecated_tables,
         )),
         ProcessorConfig::MonitoringProcessor => Processor::from(MonitoringProcessor::new(db_pool)),
+        ProcessorConfig::NftMarketplaceProcessor(config) => Processor::from(
+            NftMarketplaceProcessor::new(db_pool, config.clone(), per_table_chunk_sizes),
+        ),
         ProcessorConfig::NftMetadataProcessor(config) => {
             Processor::from(NftMetadataProcessor::new(db_pool, config.clone()))
         },
@@ -937,6 +1702,9 @@ pub fn build_processor(
             per_table_chunk_sizes,
             deprecated_tables,
         )),
+        ProcessorConfig::ResourceTrackingProcessor(config) => Processor::from(
+            ResourceTrackingProcessor::new(db_pool, config.clone(), per_table_chunk_sizes),
+        ),
         ProcessorConfig::StakeProcessor(config) => Processor::from(StakeProcessor::new(
             db_pool,
             config.clone(),
@@ -950,7 +1718,7 @@ pub fn build_processor(
         ProcessorConfig::TokenV2Processor(config) => Processor::from(TokenV2Processor::new(
             db_pool,
             config.clone(),
-            per_table_chunk_sizes,
+            sinks,
             deprecated_tables,
         )),
         ProcessorConfig::TransactionMetadataProcessor => Processor::from(
@@ -959,6 +1727,14 @@ pub fn build_processor(
         ProcessorConfig::UserTransactionProcessor => Processor::from(
             UserTransactionProcessor::new(db_pool, per_table_chunk_sizes, deprecated_tables),
         ),
+        ProcessorConfig::WebhookProcessor(config) => {
+            Processor::from(WebhookProcessor::new(db_pool, config.clone()))
+        },
+        ProcessorConfig::ParquetAnsProcessor(config) => Processor::from(ParquetAnsProcessor::new(
+            db_pool,
+            config.clone(),
+            gap_detector_sender.expect("Parquet processor requires a gap detector sender"),
+        )),
         ProcessorConfig::ParquetDefaultProcessor(config) => {
             Processor::from(ParquetDefaultProcessor::new(
                 db_pool,
@@ -973,5 +1749,117 @@ pub fn build_processor(
                 gap_detector_sender.expect("Parquet processor requires a gap detector sender"),
             ))
         },
+        ProcessorConfig::ParquetObjectsProcessor(config) => {
+            Processor::from(ParquetObjectsProcessor::new(
+                db_pool,
+                config.clone(),
+                gap_detector_sender.expect("Parquet processor requires a gap detector sender"),
+            ))
+        },
+        ProcessorConfig::ParquetStakeProcessor(config) => {
+            Processor::from(ParquetStakeProcessor::new(
+                db_pool,
+                config.clone(),
+                gap_detector_sender.expect("Parquet processor requires a gap detector sender"),
+            ))
+        },
+        ProcessorConfig::ParquetTokenV2Processor(config) => {
+            Processor::from(ParquetTokenV2Processor::new(
+                db_pool,
+                config.clone(),
+                gap_detector_sender.expect("Parquet processor requires a gap detector sender"),
+            ))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_protos::transaction::v1::Transaction;
+
+    fn batch(start_version: u64, end_version: u64) -> TransactionsPBResponse {
+        TransactionsPBResponse {
+            transactions: (start_version..=end_version)
+                .map(|version| Transaction {
+                    version,
+                    ..Transaction::default()
+                })
+                .collect(),
+            chain_id: 1,
+            start_version,
+            end_version,
+            start_txn_timestamp: None,
+            end_txn_timestamp: None,
+            size_in_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_trim_batch_to_start_version() {
+        assert!(trim_batch_to_start_version(&batch(10, 19), 20).is_none());
+
+        let untouched = trim_batch_to_start_version(&batch(10, 19), 5).unwrap();
+        assert_eq!(untouched.start_version, 10);
+        assert_eq!(untouched.transactions.len(), 10);
+
+        let trimmed = trim_batch_to_start_version(&batch(10, 19), 15).unwrap();
+        assert_eq!(trimmed.start_version, 15);
+        assert_eq!(trimmed.end_version, 19);
+        assert_eq!(
+            trimmed
+                .transactions
+                .iter()
+                .map(|t| t.version)
+                .collect::<Vec<_>>(),
+            vec![15, 16, 17, 18, 19]
+        );
+    }
+
+    fn backfill(tables: &[&str]) -> BackfillConfig {
+        BackfillConfig {
+            backfill_alias: "royalty_backfill".to_string(),
+            start_version: 0,
+            end_version: 100,
+            tables: tables.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_only_deprecatable_tables_are_skipped_outside_backfills() {
+        let names = [
+            "MOVE_RESOURCES",
+            "BLOCK_METADATA_TRANSACTIONS",
+            "USER_TRANSACTIONS",
+        ]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+        assert_eq!(
+            TableFlags::from_names(&names).intersection(TableFlags::DEPRECATABLE),
+            TableFlags::MOVE_RESOURCES
+        );
+    }
+
+    #[test]
+    fn test_backfill_skipped_tables() {
+        let token_v2 = ProcessorConfig::TokenV2Processor(
+            serde_json::from_str("{}").expect("Token v2 config has defaults"),
+        );
+        let skipped =
+            backfill_skipped_tables(&token_v2, &backfill(&["CURRENT_TOKEN_ROYALTY_V1"])).unwrap();
+        assert!(!skipped.contains(TableFlags::CURRENT_TOKEN_ROYALTY_V1));
+        assert!(skipped.contains(TableFlags::CURRENT_TOKEN_OWNERSHIPS_V2));
+        assert!(skipped.contains(TableFlags::TOKEN_ACTIVITIES_V2));
+
+        // Tables of other processors, unknown tables and processors without flags are rejected
+        assert!(backfill_skipped_tables(&token_v2, &backfill(&["OBJECTS"])).is_err());
+        assert!(backfill_skipped_tables(&token_v2, &backfill(&["NOT_A_TABLE"])).is_err());
+        assert!(backfill_skipped_tables(&token_v2, &backfill(&[])).is_err());
+        assert!(backfill_skipped_tables(
+            &ProcessorConfig::CoinProcessor(Default::default()),
+            &backfill(&["COIN_ACTIVITIES"])
+        )
+        .is_err());
     }
 }
@@ -0,0 +1,253 @@
+// Copyright © Aptos Foundation
+// SPDX-License-Identifier: Apache-2.0
+
+//! Golden output regression tests. See `tests/golden/README.md` for the fixture layout and how
+//! to record new golden files.
+
+use ahash::AHashMap;
+use anyhow::{Context, Result};
+use diesel::{pg::PgConnection, sql_types::Text, Connection, QueryableByName};
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use processor::{
+    file_stream::{read_transactions_from_dir, LocalFileFormat},
+    processors::{ProcessorConfig, ProcessorTrait},
+    sinks::build_sinks,
+    utils::database::{new_db_pool, run_pending_migrations},
+    worker::{build_processor, TableFlags},
+};
+use serde::Deserialize;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+const DATABASE_URL_ENV: &str = "GOLDEN_TEST_DATABASE_URL";
+const UPDATE_GOLDEN_ENV: &str = "UPDATE_GOLDEN";
+/// Tables that track processor state rather than processed data
+const SKIPPED_TABLES: [&str; 3] = [
+    "__diesel_schema_migrations",
+    "processor_status",
+    "ledger_infos",
+];
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FixtureConfig {
+    processor_config: ProcessorConfig,
+    #[serde(default)]
+    format: LocalFileFormat,
+    chain_id: u64,
+}
+
+#[derive(QueryableByName)]
+struct TableName {
+    #[diesel(sql_type = Text)]
+    table_name: String,
+}
+
+#[derive(QueryableByName)]
+struct TableRows {
+    #[diesel(sql_type = Text)]
+    rows: String,
+}
+
+#[tokio::test]
+#[ignore = "needs a Postgres instance, see tests/golden/README.md"]
+async fn test_golden_outputs() {
+    let database_url = std::env::var(DATABASE_URL_ENV)
+        .unwrap_or_else(|_| panic!("{DATABASE_URL_ENV} must be set to run the golden tests"));
+    let update_golden = std::env::var(UPDATE_GOLDEN_ENV).is_ok();
+
+    let mut failures = vec![];
+    for fixture_dir in list_fixtures() {
+        if let Err(e) = run_fixture(&database_url, &fixture_dir, update_golden).await {
+            failures.push(format!("{}: {:?}", fixture_dir.display(), e));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "Golden output mismatches:\n{}",
+        failures.join("\n")
+    );
+}
+
+fn list_fixtures() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut fixtures: Vec<PathBuf> = std::fs::read_dir(root)
+        .expect("Failed to read golden fixture directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.join("fixture.yaml").exists())
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+async fn run_fixture(
+    admin_database_url: &str,
+    fixture_dir: &Path,
+    update_golden: bool,
+) -> Result<()> {
+    let config: FixtureConfig = serde_yaml::from_str(
+        &std::fs::read_to_string(fixture_dir.join("fixture.yaml"))
+            .context("Failed to read fixture.yaml")?,
+    )
+    .context("Failed to parse fixture.yaml")?;
+    anyhow::ensure!(
+        !config.processor_config.is_parquet_processor(),
+        "Parquet processors are not supported by the golden tests"
+    );
+
+    let transactions =
+        read_transactions_from_dir(&fixture_dir.join("transactions"), &config.format).await?;
+    let start_version = transactions
+        .first()
+        .context("Fixture has no transactions")?
+        .version;
+    let end_version = transactions.last().unwrap().version;
+
+    let fixture_name = fixture_dir
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    let database_name = format!("golden_{}_{}", fixture_name, std::process::id());
+    let database_url = create_database(admin_database_url, &database_name).await?;
+
+    let result = async {
+        let migration_url = database_url.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = PgConnection::establish(&migration_url)?;
+            run_pending_migrations(&mut conn);
+            anyhow::Ok(())
+        })
+        .await??;
+
+        let pool = new_db_pool(&database_url, Some(10)).await?;
+        let sinks = build_sinks(
+            config.processor_config.sinks(),
+            pool.clone(),
+            AHashMap::new(),
+        )?;
+        let processor = build_processor(
+            &config.processor_config,
+            AHashMap::new(),
+            TableFlags::empty(),
+            pool.clone(),
+            Arc::new(sinks),
+            None,
+        );
+        processor
+            .process_transactions(
+                transactions,
+                start_version,
+                end_version,
+                Some(config.chain_id),
+            )
+            .await?;
+
+        let mut conn = pool.get().await?;
+        let actual = dump_tables(&mut conn).await?;
+        compare_or_update(&fixture_dir.join("expected"), actual, update_golden)
+    }
+    .await;
+
+    drop_database(admin_database_url, &database_name).await?;
+    result
+}
+
+async fn create_database(admin_database_url: &str, database_name: &str) -> Result<String> {
+    let mut conn = AsyncPgConnection::establish(admin_database_url).await?;
+    diesel::sql_query(format!("DROP DATABASE IF EXISTS {database_name}"))
+        .execute(&mut conn)
+        .await?;
+    diesel::sql_query(format!("CREATE DATABASE {database_name}"))
+        .execute(&mut conn)
+        .await?;
+
+    let mut url = url::Url::parse(admin_database_url)?;
+    url.set_path(database_name);
+    Ok(url.to_string())
+}
+
+async fn drop_database(admin_database_url: &str, database_name: &str) -> Result<()> {
+    let mut conn = AsyncPgConnection::establish(admin_database_url).await?;
+    diesel::sql_query(format!(
+        "DROP DATABASE IF EXISTS {database_name} WITH (FORCE)"
+    ))
+    .execute(&mut conn)
+    .await?;
+    Ok(())
+}
+
+/// Returns the rows of every non-empty table as a pretty printed, sorted JSON array.
+async fn dump_tables(conn: &mut AsyncPgConnection) -> Result<Vec<(String, String)>> {
+    let tables: Vec<TableName> = diesel::sql_query(
+        "SELECT table_name::text AS table_name FROM information_schema.tables \
+         WHERE table_schema = 'public' AND table_type = 'BASE TABLE' ORDER BY table_name",
+    )
+    .load(conn)
+    .await?;
+
+    let mut dumps = vec![];
+    for TableName { table_name } in tables {
+        if SKIPPED_TABLES.contains(&table_name.as_str()) {
+            continue;
+        }
+        let rows: TableRows = diesel::sql_query(format!(
+            "SELECT COALESCE(jsonb_agg(r ORDER BY r::text), '[]'::jsonb)::text AS rows \
+             FROM (SELECT to_jsonb(t) - 'inserted_at' - 'last_updated' AS r FROM {table_name} t) s"
+        ))
+        .get_result(conn)
+        .await?;
+        let rows: serde_json::Value = serde_json::from_str(&rows.rows)?;
+        if rows.as_array().map_or(false, |rows| !rows.is_empty()) {
+            dumps.push((table_name, serde_json::to_string_pretty(&rows)? + "\n"));
+        }
+    }
+    Ok(dumps)
+}
+
+fn compare_or_update(
+    expected_dir: &Path,
+    actual: Vec<(String, String)>,
+    update_golden: bool,
+) -> Result<()> {
+    if update_golden {
+        if expected_dir.exists() {
+            std::fs::remove_dir_all(expected_dir)?;
+        }
+        std::fs::create_dir_all(expected_dir)?;
+        for (table_name, rows) in actual {
+            std::fs::write(expected_dir.join(format!("{table_name}.json")), rows)?;
+        }
+        return Ok(());
+    }
+
+    let mut mismatches = vec![];
+    let mut expected_tables = vec![];
+    if expected_dir.exists() {
+        for entry in std::fs::read_dir(expected_dir)? {
+            let path = entry?.path();
+            if let Some(table_name) = path.file_stem() {
+                expected_tables.push(table_name.to_string_lossy().to_string());
+            }
+        }
+    }
+    for (table_name, rows) in &actual {
+        let expected_path = expected_dir.join(format!("{table_name}.json"));
+        match std::fs::read_to_string(&expected_path) {
+            Ok(expected) if &expected == rows => {},
+            Ok(_) => mismatches.push(format!("{table_name}: rows differ from golden file")),
+            Err(_) => mismatches.push(format!("{table_name}: unexpected rows, no golden file")),
+        }
+    }
+    for table_name in expected_tables {
+        if !actual.iter().any(|(name, _)| name == &table_name) {
+            mismatches.push(format!(
+                "{table_name}: golden file exists but table is empty"
+            ));
+        }
+    }
+    anyhow::ensure!(mismatches.is_empty(), mismatches.join(", "));
+    Ok(())
+}